@@ -0,0 +1,82 @@
+//! Free-text assessment notes on drug-event pairs, appended to `data/annotations.jsonl`.
+
+use std::{
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Write},
+    sync::Mutex,
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::config::Settings;
+
+const ANNOTATION_FILE: &str = "annotations.jsonl";
+
+/// Keeps concurrent appends from interleaving.
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Annotation {
+    pub drug_id: String,
+    pub event_id: String,
+    /// Name given in the request body; self-reported, not checked against the key.
+    pub author: String,
+    /// Fingerprint of the API key that added the note, absent when no keys are configured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub text: String,
+}
+
+/// Append a note on the pair and return it.
+pub fn add(
+    settings: &Settings,
+    drug_id: &str,
+    event_id: &str,
+    author: &str,
+    key_id: Option<&str>,
+    text: &str,
+) -> Result<Annotation> {
+    let annotation = Annotation {
+        drug_id: drug_id.to_string(),
+        event_id: event_id.to_string(),
+        author: author.to_string(),
+        key_id: key_id.map(str::to_string),
+        created_at: Utc::now(),
+        text: text.to_string(),
+    };
+    let path = settings.join_data(ANNOTATION_FILE);
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("open {path:?}"))?;
+    writeln!(file, "{}", serde_json::to_string(&annotation)?)?;
+    Ok(annotation)
+}
+
+/// Notes on the pair, oldest first.
+pub fn for_pair(settings: &Settings, drug_id: &str, event_id: &str) -> Result<Vec<Annotation>> {
+    let path = settings.join_data(ANNOTATION_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file = std::fs::File::open(&path).with_context(|| format!("open {path:?}"))?;
+    let mut notes = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.with_context(|| format!("read {path:?}"))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Annotation>(&line) {
+            Ok(note) if note.drug_id == drug_id && note.event_id == event_id => notes.push(note),
+            Ok(_) => {}
+            Err(err) => warn!(%err, "skipping malformed annotation"),
+        }
+    }
+    Ok(notes)
+}
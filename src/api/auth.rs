@@ -0,0 +1,142 @@
+//! API key authentication for every route except `/healthz` and the static UI files.
+
+use std::{path::Path, sync::Arc};
+
+use axum::{
+    extract::{Request, State},
+    http::{header::AUTHORIZATION, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{config::Settings, ui};
+
+use super::{datasets::DATASET_HEADER, error::ApiError};
+
+/// Header accepted as an alternative to `Authorization: Bearer <key>`.
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// Paths reachable without a key, so orchestrators can probe the service.
+const PUBLIC_PATHS: &[&str] = &["/healthz"];
+
+/// Routes that need a key even for reads under `API_ANONYMOUS_READ`, as job logs can name
+/// server paths and upstream requests.
+const PRIVATE_ROUTES: &[&str] = &["jobs"];
+
+/// Fingerprint of the API key a request was authenticated with, added to the request's
+/// extensions so handlers can record who made a change without storing the key itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyId(pub String);
+
+impl KeyId {
+    /// `key-` and the first 8 hex digits of the key's SHA-256.
+    pub fn of(key: &str) -> Self {
+        let digest = Sha256::digest(key.as_bytes());
+        let hex: String = digest[..4]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        Self(format!("key-{hex}"))
+    }
+}
+
+/// Reject requests without a configured key. With no keys configured every request passes;
+/// with `API_ANONYMOUS_READ` set, `GET` and `HEAD` requests pass without a key, except
+/// on [`PRIVATE_ROUTES`].
+///
+/// The UI's pages and assets are always served, but its data requests still need a key or
+/// `API_ANONYMOUS_READ`, as the UI does not send one. The check runs before dataset selection,
+/// so requests naming a dataset, by path prefix or header, get no exemption: a 404 for an
+/// unknown name would tell clients without a key which datasets exist.
+pub async fn require_key(
+    State(settings): State<Arc<Settings>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let read_only = matches!(*request.method(), Method::GET | Method::HEAD);
+    if settings.api_keys.is_empty()
+        || PUBLIC_PATHS.contains(&request.uri().path())
+        || (read_only
+            && !request.headers().contains_key(DATASET_HEADER)
+            && is_static_file(request.uri().path()))
+    {
+        return next.run(request).await;
+    }
+    match presented_key(&request) {
+        Some(key)
+            if settings
+                .api_keys
+                .iter()
+                .any(|known| constant_time_eq(known, key)) =>
+        {
+            let key_id = KeyId::of(key);
+            request.extensions_mut().insert(key_id);
+            next.run(request).await
+        }
+        Some(_) => ApiError::new(StatusCode::UNAUTHORIZED, "invalid API key").into_response(),
+        None if read_only
+            && settings.api_anonymous_read
+            && !is_private_route(request.uri().path()) =>
+        {
+            next.run(request).await
+        }
+        None => (
+            [("www-authenticate", "Bearer")],
+            ApiError::new(StatusCode::UNAUTHORIZED, "API key required"),
+        )
+            .into_response(),
+    }
+}
+
+/// Whether `path` names a file of the static UI, which the server falls back to.
+fn is_static_file(path: &str) -> bool {
+    let relative = match path.trim_start_matches('/') {
+        "" => "index.html",
+        relative => relative,
+    };
+    relative
+        .split('/')
+        .all(|segment| !segment.is_empty() && segment != "..")
+        && Path::new(ui::STATIC_DIR).join(relative).is_file()
+}
+
+/// Whether `path`, with or without a dataset prefix and `/v1`, is under a private route.
+fn is_private_route(path: &str) -> bool {
+    let mut segments = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .peekable();
+    if segments.peek() == Some(&"datasets") {
+        segments.nth(1);
+    }
+    segments.next_if_eq(&"v1");
+    segments
+        .next()
+        .is_some_and(|route| PRIVATE_ROUTES.contains(&route))
+}
+
+fn presented_key(request: &Request) -> Option<&str> {
+    let headers = request.headers();
+    if let Some(bearer) = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    {
+        return Some(bearer.trim());
+    }
+    headers
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+}
+
+/// Compare without short-circuiting on the first differing byte.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
@@ -0,0 +1,130 @@
+//! Several data directories served by one process, selected per request.
+//!
+//! A request picks a dataset with a `/datasets/<name>/` path prefix, which is stripped before
+//! routing, or with the `X-Dataset` header; otherwise it reads the default `DATA_DIR` and
+//! `OUTPUTS_DIR`. Handlers take the selected [`Dataset`] as an extractor.
+
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request, State},
+    http::{header::VARY, request::Parts, HeaderValue, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Redirect, Response},
+    Json,
+};
+
+use crate::{
+    api::{error::ApiError, store::SignalCache},
+    config::Settings,
+};
+
+use super::AppState;
+
+/// Name of the dataset backed by `DATA_DIR` and `OUTPUTS_DIR`.
+pub const DEFAULT_DATASET: &str = "default";
+
+/// Header naming the dataset when the path has no `/datasets/<name>/` prefix.
+pub(crate) const DATASET_HEADER: &str = "x-dataset";
+
+const PREFIX: &str = "/datasets/";
+
+/// Datasets by name, including [`DEFAULT_DATASET`].
+pub type DatasetMap = BTreeMap<String, Dataset>;
+
+/// The default settings plus one copy per `DATASETS` entry, pointed at its directories.
+pub fn from_settings(settings: &Settings) -> DatasetMap {
+    let mut datasets = DatasetMap::new();
+    datasets.insert(
+        DEFAULT_DATASET.to_string(),
+        Dataset::new(DEFAULT_DATASET, settings.clone()),
+    );
+    for (name, root) in &settings.datasets {
+        datasets.insert(name.clone(), Dataset::new(name, settings.for_dataset(root)));
+    }
+    datasets
+}
+
+/// Dataset selected for the request.
+#[derive(Clone)]
+pub struct Dataset {
+    pub name: String,
+    pub settings: Arc<Settings>,
+    /// Vocabulary and signals shared by every request to the dataset.
+    pub cache: Arc<SignalCache>,
+}
+
+impl Dataset {
+    fn new(name: &str, settings: Settings) -> Self {
+        Self {
+            name: name.to_string(),
+            cache: Arc::new(SignalCache::new(Duration::from_secs(
+                settings.signal_cache_ttl_secs,
+            ))),
+            settings: Arc::new(settings),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Dataset {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Dataset>().cloned().ok_or_else(|| {
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "no dataset selected for the request",
+            )
+        })
+    }
+}
+
+/// Resolve the dataset, strip its path prefix and hand the request on. Unknown datasets get
+/// 404; `/datasets/<name>` redirects to `/datasets/<name>/` so the UI's relative links work.
+pub async fn select(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    let (name, rest) = match path.strip_prefix(PREFIX) {
+        Some(tail) => match tail.split_once('/') {
+            Some((name, rest)) => (name.to_string(), Some(format!("/{rest}"))),
+            None => return Redirect::permanent(&format!("{path}/")).into_response(),
+        },
+        None => (
+            request
+                .headers()
+                .get(DATASET_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .unwrap_or(DEFAULT_DATASET)
+                .to_string(),
+            None,
+        ),
+    };
+    let Some(dataset) = state.datasets.get(&name).cloned() else {
+        return ApiError::not_found(format!("unknown dataset {name}")).into_response();
+    };
+    if let Some(rest) = rest {
+        let path_and_query = match request.uri().query() {
+            Some(query) => format!("{rest}?{query}"),
+            None => rest,
+        };
+        match Uri::builder().path_and_query(path_and_query).build() {
+            Ok(uri) => *request.uri_mut() = uri,
+            Err(err) => return ApiError::bad_request(err.to_string()).into_response(),
+        }
+    }
+    request.extensions_mut().insert(dataset);
+    let mut response = next.run(request).await;
+    // The same URL answers differently per header, so shared caches must key on it.
+    response
+        .headers_mut()
+        .append(VARY, HeaderValue::from_static(DATASET_HEADER));
+    response
+}
+
+/// `GET /datasets`: names of the datasets this server hosts.
+pub async fn list(states: State<AppState>) -> Json<Vec<String>> {
+    Json(states.datasets.keys().cloned().collect())
+}
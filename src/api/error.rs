@@ -0,0 +1,134 @@
+//! JSON error body shared by every route: `{"code": "...", "message": "...", "detail": ...}`.
+//!
+//! `code` is a stable snake_case identifier clients can branch on, `message` a one-line
+//! description, and `detail` more context when there is something to add. Internal errors
+//! are logged rather than returned, and `detail` carries the reference of the log line.
+
+use axum::{
+    body::to_bytes,
+    extract::Request,
+    http::{
+        header::{CONTENT_LENGTH, CONTENT_TYPE},
+        HeaderValue, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use tracing::error;
+
+/// Plain-text bodies of extractor rejections and middleware errors are short; anything
+/// longer is not an error message worth forwarding.
+const MAX_PLAIN_BODY: usize = 16 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiError {
+    #[serde(skip)]
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+    pub detail: Option<String>,
+}
+
+impl ApiError {
+    /// An error with the code that goes with `status`.
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code: code_for(status),
+            message: message.into(),
+            detail: None,
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT, message)
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+/// Failures reading artefacts become 500s. The error chain names server paths, so it is
+/// logged under a random reference and only the reference goes back to the client.
+impl From<anyhow::Error> for ApiError {
+    fn from(err: anyhow::Error) -> Self {
+        let reference = reference();
+        error!(%reference, error = format!("{err:#}"), "request failed");
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal error")
+            .with_detail(format!("reference {reference}"))
+    }
+}
+
+/// Random id under which an error chain is logged, for clients to quote.
+pub(crate) fn reference() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self)).into_response()
+    }
+}
+
+/// Stable code for a status.
+fn code_for(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::BAD_REQUEST => "bad_request",
+        StatusCode::UNAUTHORIZED => "unauthorized",
+        StatusCode::FORBIDDEN => "forbidden",
+        StatusCode::NOT_FOUND => "not_found",
+        StatusCode::METHOD_NOT_ALLOWED => "method_not_allowed",
+        StatusCode::REQUEST_TIMEOUT => "timeout",
+        StatusCode::CONFLICT => "conflict",
+        StatusCode::PAYLOAD_TOO_LARGE => "payload_too_large",
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => "unsupported_media_type",
+        StatusCode::UNPROCESSABLE_ENTITY => "unprocessable_entity",
+        StatusCode::TOO_MANY_REQUESTS => "rate_limited",
+        StatusCode::SERVICE_UNAVAILABLE => "unavailable",
+        status if status.is_client_error() => "client_error",
+        _ => "internal",
+    }
+}
+
+/// Wrap error responses that are not JSON yet (extractor rejections, the rate limiter,
+/// missing static files) in the [`ApiError`] body, keeping their text as the message.
+pub async fn envelope(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    let status = response.status();
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !(status.is_client_error() || status.is_server_error()) || is_json {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let text = to_bytes(body, MAX_PLAIN_BODY)
+        .await
+        .map(|bytes| String::from_utf8_lossy(&bytes).trim().to_string())
+        .unwrap_or_default();
+    let message = if text.is_empty() {
+        status.canonical_reason().unwrap_or("error").to_string()
+    } else {
+        text
+    };
+    parts.headers.remove(CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    let body = ApiError::new(status, message).into_response().into_body();
+    Response::from_parts(parts, body)
+}
@@ -0,0 +1,78 @@
+//! Entity tags for responses derived from `signals.csv`, so polling clients can revalidate
+//! with `If-None-Match` instead of downloading unchanged JSON.
+
+use std::{path::PathBuf, time::UNIX_EPOCH};
+
+use axum::{
+    http::{
+        header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+use crate::config::Settings;
+
+/// Strong ETag from the path, size and modification time of `signals.csv`, the vocabularies
+/// that supply names and the watchlists; `None` when `signals.csv` does not exist yet.
+pub fn signals_etag(settings: &Settings) -> Option<String> {
+    let signals = settings.join_output("signals.csv");
+    if !signals.exists() {
+        return None;
+    }
+    let digest = fingerprint(&[
+        signals,
+        settings.join_data("clean/drugs.parquet"),
+        settings.join_data("clean/events.parquet"),
+        // Drives the `watched` flag.
+        settings.join_data("watchlists.json"),
+    ]);
+    Some(format!("\"{digest}\""))
+}
+
+/// Short hex digest of the paths, sizes and modification times of `paths`; missing files
+/// count as absent rather than failing, so creating one changes the digest.
+pub fn fingerprint(paths: &[PathBuf]) -> String {
+    let mut hasher = Sha256::new();
+    for path in paths {
+        // Paths keep tags of different datasets apart.
+        hasher.update(path.to_string_lossy().as_bytes());
+        if let Ok(meta) = std::fs::metadata(path) {
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|age| age.as_nanos())
+                .unwrap_or_default();
+            hasher.update(meta.len().to_le_bytes());
+            hasher.update(modified.to_le_bytes());
+        }
+        hasher.update([0]);
+    }
+    hasher.finalize()[..8]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// `304 Not Modified` when `If-None-Match` lists `etag` (or `*`).
+pub fn not_modified(headers: &HeaderMap, etag: Option<&str>) -> Option<Response> {
+    let etag = etag?;
+    let header = headers.get(IF_NONE_MATCH)?.to_str().ok()?;
+    let matches = header.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    });
+    matches.then(|| tagged(Some(etag.to_string()), StatusCode::NOT_MODIFIED))
+}
+
+/// Attach `etag` and ask caches to revalidate before reuse.
+pub fn tagged(etag: Option<String>, body: impl IntoResponse) -> Response {
+    let mut response = body.into_response();
+    if let Some(value) = etag.and_then(|etag| HeaderValue::from_str(&etag).ok()) {
+        let headers = response.headers_mut();
+        headers.insert(ETAG, value);
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    }
+    response
+}
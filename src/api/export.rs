@@ -0,0 +1,141 @@
+//! Ranked signals encoded as CSV, Parquet, Excel or JSON for download.
+
+use anyhow::Result;
+use polars::prelude::{DataFrame, NamedFrom, ParquetWriter, Series};
+use rust_xlsxwriter::{Format, Workbook};
+use serde::Deserialize;
+
+use crate::api::types::SignalDto;
+
+/// File format of `GET /export/signals`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Parquet,
+    Xlsx,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Parquet => "application/vnd.apache.parquet",
+            Self::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            Self::Json => "application/json",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Parquet => "parquet",
+            Self::Xlsx => "xlsx",
+            Self::Json => "json",
+        }
+    }
+
+    /// Encode `rows` as a complete file.
+    pub fn encode(self, rows: &[SignalDto]) -> Result<Vec<u8>> {
+        match self {
+            Self::Csv => {
+                let mut writer = csv::Writer::from_writer(Vec::new());
+                for row in rows {
+                    writer.serialize(row)?;
+                }
+                Ok(writer.into_inner().map_err(|e| e.into_error())?)
+            }
+            Self::Json => Ok(serde_json::to_vec(rows)?),
+            Self::Parquet => {
+                let mut df = signals_frame(rows)?;
+                let mut buffer = Vec::new();
+                ParquetWriter::new(&mut buffer).finish(&mut df)?;
+                Ok(buffer)
+            }
+            Self::Xlsx => {
+                let mut workbook = Workbook::new();
+                let sheet = workbook.add_worksheet().set_name("signals")?;
+                let header = Format::new().set_bold();
+                sheet.set_freeze_panes(1, 0)?;
+                match rows.first() {
+                    Some(first) => sheet.serialize_headers_with_format(0, 0, first, &header)?,
+                    None => return Ok(workbook.save_to_buffer()?),
+                };
+                for row in rows {
+                    sheet.serialize(row)?;
+                }
+                sheet.autofit();
+                Ok(workbook.save_to_buffer()?)
+            }
+        }
+    }
+}
+
+fn signals_frame(rows: &[SignalDto]) -> Result<DataFrame> {
+    Ok(DataFrame::new(vec![
+        Series::new(
+            "drug_id".into(),
+            rows.iter().map(|r| r.drug_id.as_str()).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "event_id".into(),
+            rows.iter().map(|r| r.event_id.as_str()).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "drug_name".into(),
+            rows.iter()
+                .map(|r| r.drug_name.as_deref())
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "event_term".into(),
+            rows.iter()
+                .map(|r| r.event_term.as_deref())
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "year_quarter".into(),
+            rows.iter()
+                .map(|r| r.year_quarter.as_str())
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "recent_ror".into(),
+            rows.iter().map(|r| r.recent_ror).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "ci_low".into(),
+            rows.iter().map(|r| r.ci_low).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "ci_high".into(),
+            rows.iter().map(|r| r.ci_high).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "lit_support".into(),
+            rows.iter().map(|r| r.lit_support).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "trend_z".into(),
+            rows.iter().map(|r| r.trend_z).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "score".into(),
+            rows.iter().map(|r| r.score).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "labeled".into(),
+            rows.iter().map(|r| r.labeled).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "sider_known".into(),
+            rows.iter().map(|r| r.sider_known).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "smq".into(),
+            rows.iter().map(|r| r.smq.as_str()).collect::<Vec<_>>(),
+        ),
+    ])?)
+}
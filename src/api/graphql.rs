@@ -0,0 +1,417 @@
+//! GraphQL schema over signals, drugs, events and their literature relations.
+//!
+//! Artefacts are read at most once per request, on first use, so a query that only asks
+//! for signal scores never opens `relations.parquet`.
+
+use std::{collections::HashMap, sync::Arc};
+
+use async_graphql::{
+    http::GraphiQLSource, Context, EmptyMutation, EmptySubscription, Error, Object, Schema,
+    SimpleObject,
+};
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use axum::{
+    extract::State,
+    response::{Html, IntoResponse},
+};
+use once_cell::sync::OnceCell;
+
+use crate::{
+    api::{datasets::Dataset, names::Vocabulary, types::SignalDto},
+    config::Settings,
+    nlp::summaries::{self, Evidence},
+    signals::{self, QuarterMetric},
+};
+
+use super::AppState;
+
+pub type ApiSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Deepest selection accepted; `signal { drug { signals { event { signals ... } } } }`
+/// otherwise lets one request fan out without bound.
+const MAX_DEPTH: usize = 8;
+
+pub fn schema() -> ApiSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .limit_depth(MAX_DEPTH)
+        .finish()
+}
+
+/// Queries arrive as `POST` JSON bodies or, for clients limited to anonymous reads,
+/// as `GET /graphql?query=...`.
+pub async fn execute(
+    states: State<AppState>,
+    dataset: Dataset,
+    request: GraphQLRequest,
+) -> GraphQLResponse {
+    let request = request.into_inner().data(Snapshot::new(dataset));
+    states.graphql.execute(request).await.into()
+}
+
+/// In-browser query editor.
+pub async fn graphiql() -> impl IntoResponse {
+    Html(GraphiQLSource::build().endpoint("/v1/graphql").finish())
+}
+
+/// Artefacts pinned for one request, so every resolver sees the same run even if the
+/// dataset cache is dropped mid-query.
+struct Snapshot {
+    dataset: Dataset,
+    vocab: OnceCell<Arc<Vocabulary>>,
+    signals: OnceCell<RankedSignals>,
+    relations: OnceCell<HashMap<(String, String), Vec<Evidence>>>,
+}
+
+/// Signals shared with the dataset cache, with their indices by descending score.
+struct RankedSignals {
+    rows: Arc<Vec<SignalDto>>,
+    order: Vec<usize>,
+}
+
+impl Snapshot {
+    fn new(dataset: Dataset) -> Self {
+        Self {
+            dataset,
+            vocab: OnceCell::new(),
+            signals: OnceCell::new(),
+            relations: OnceCell::new(),
+        }
+    }
+
+    fn settings(&self) -> &Settings {
+        &self.dataset.settings
+    }
+
+    fn vocab(&self) -> async_graphql::Result<&Vocabulary> {
+        self.vocab
+            .get_or_try_init(|| self.dataset.cache.vocabulary(self.settings()))
+            .map(|vocab| vocab.as_ref())
+            .map_err(Error::from)
+    }
+
+    /// Ranked signals, highest score first.
+    fn signals(&self) -> async_graphql::Result<impl Iterator<Item = &SignalDto>> {
+        let ranked = self.signals.get_or_try_init(|| {
+            let rows = self.dataset.cache.signals(self.settings())?;
+            let mut order: Vec<usize> = (0..rows.len()).collect();
+            order.sort_by(|a, b| rows[*b].score.total_cmp(&rows[*a].score));
+            Ok::<_, Error>(RankedSignals { rows, order })
+        })?;
+        Ok(ranked.order.iter().map(|idx| &ranked.rows[*idx]))
+    }
+
+    fn relations(&self, drug_id: &str, event_id: &str) -> async_graphql::Result<&[Evidence]> {
+        let relations = self
+            .relations
+            .get_or_try_init(|| summaries::evidence_by_pair(self.settings()))?;
+        Ok(relations
+            .get(&(drug_id.to_string(), event_id.to_string()))
+            .map_or(&[], Vec::as_slice))
+    }
+}
+
+fn snapshot<'a>(ctx: &Context<'a>) -> &'a Snapshot {
+    ctx.data_unchecked::<Snapshot>()
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Ranked signals, highest score first.
+    async fn signals(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Drug id or canonical name.")] drug: Option<String>,
+        #[graphql(desc = "Case-insensitive substring of the SMQ label.")] smq: Option<String>,
+        #[graphql(default = 100)] limit: usize,
+    ) -> async_graphql::Result<Vec<Signal>> {
+        let snapshot = snapshot(ctx);
+        let drug_id = match &drug {
+            Some(drug) => Some(snapshot.vocab()?.resolve_drug(drug)),
+            None => None,
+        };
+        let smq = smq.map(|smq| smq.to_lowercase());
+        Ok(snapshot
+            .signals()?
+            .filter(|s| {
+                drug_id
+                    .as_ref()
+                    .is_none_or(|id| s.drug_id.eq_ignore_ascii_case(id))
+            })
+            .filter(|s| {
+                smq.as_ref()
+                    .is_none_or(|smq| !s.smq.is_empty() && s.smq.to_lowercase().contains(smq))
+            })
+            .take(limit)
+            .cloned()
+            .map(Signal)
+            .collect())
+    }
+
+    /// One ranked pair; drug and event accept ids or canonical names.
+    async fn signal(
+        &self,
+        ctx: &Context<'_>,
+        drug: String,
+        event: String,
+    ) -> async_graphql::Result<Option<Signal>> {
+        let snapshot = snapshot(ctx);
+        let vocab = snapshot.vocab()?;
+        let (drug_id, event_id) = (vocab.resolve_drug(&drug), vocab.resolve_event(&event));
+        Ok(snapshot
+            .signals()?
+            .find(|s| s.drug_id == drug_id && s.event_id == event_id)
+            .cloned()
+            .map(Signal))
+    }
+
+    /// A drug by id or canonical name; null when neither `drugs.parquet` nor the signals
+    /// know it.
+    async fn drug(&self, ctx: &Context<'_>, id: String) -> async_graphql::Result<Option<Drug>> {
+        let snapshot = snapshot(ctx);
+        let drug_id = snapshot.vocab()?.resolve_drug(&id);
+        let known = snapshot.vocab()?.drug_name(&drug_id).is_some()
+            || snapshot.signals()?.any(|s| s.drug_id == drug_id);
+        Ok(known.then_some(Drug { id: drug_id }))
+    }
+
+    /// An event by id or canonical term; null when neither `events.parquet` nor the signals
+    /// know it.
+    async fn event(&self, ctx: &Context<'_>, id: String) -> async_graphql::Result<Option<Event>> {
+        let snapshot = snapshot(ctx);
+        let event_id = snapshot.vocab()?.resolve_event(&id);
+        let known = snapshot.vocab()?.event_term(&event_id).is_some()
+            || snapshot.signals()?.any(|s| s.event_id == event_id);
+        Ok(known.then_some(Event { id: event_id }))
+    }
+}
+
+/// A ranked drug-event pair from `signals.csv`.
+pub struct Signal(SignalDto);
+
+#[Object]
+impl Signal {
+    async fn drug_id(&self) -> &str {
+        &self.0.drug_id
+    }
+
+    async fn event_id(&self) -> &str {
+        &self.0.event_id
+    }
+
+    async fn drug_name(&self) -> Option<&str> {
+        self.0.drug_name.as_deref()
+    }
+
+    async fn event_term(&self) -> Option<&str> {
+        self.0.event_term.as_deref()
+    }
+
+    async fn year_quarter(&self) -> &str {
+        &self.0.year_quarter
+    }
+
+    async fn recent_ror(&self) -> f64 {
+        self.0.recent_ror
+    }
+
+    async fn ci_low(&self) -> f64 {
+        self.0.ci_low
+    }
+
+    async fn ci_high(&self) -> f64 {
+        self.0.ci_high
+    }
+
+    async fn lit_support(&self) -> i64 {
+        self.0.lit_support
+    }
+
+    async fn trend_z(&self) -> f64 {
+        self.0.trend_z
+    }
+
+    async fn score(&self) -> f64 {
+        self.0.score
+    }
+
+    async fn labeled(&self) -> bool {
+        self.0.labeled
+    }
+
+    async fn sider_known(&self) -> bool {
+        self.0.sider_known
+    }
+
+    /// SMQ label; empty for preferred-term rows.
+    async fn smq(&self) -> &str {
+        &self.0.smq
+    }
+
+    async fn drug(&self) -> Drug {
+        Drug {
+            id: self.0.drug_id.clone(),
+        }
+    }
+
+    async fn event(&self) -> Event {
+        Event {
+            id: self.0.event_id.clone(),
+        }
+    }
+
+    /// Supporting sentences from `relations.parquet`, most confident first.
+    async fn relations(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 10)] limit: usize,
+    ) -> async_graphql::Result<Vec<Relation>> {
+        Ok(snapshot(ctx)
+            .relations(&self.0.drug_id, &self.0.event_id)?
+            .iter()
+            .take(limit)
+            .map(Relation::from)
+            .collect())
+    }
+
+    /// Distinct PMIDs of the supporting sentences, ordered by their most confident sentence.
+    async fn top_pmids(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 5)] limit: usize,
+    ) -> async_graphql::Result<Vec<String>> {
+        let mut pmids: Vec<String> = Vec::new();
+        for evidence in snapshot(ctx).relations(&self.0.drug_id, &self.0.event_id)? {
+            if pmids.len() == limit {
+                break;
+            }
+            if !pmids.contains(&evidence.pmid) {
+                pmids.push(evidence.pmid.clone());
+            }
+        }
+        Ok(pmids)
+    }
+
+    /// Raw and shrunk ROR per quarter, oldest first.
+    async fn history(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Quarter>> {
+        let settings = snapshot(ctx).settings();
+        Ok(
+            signals::pair_history(settings, &self.0.drug_id, &self.0.event_id)?
+                .into_iter()
+                .map(Quarter::from)
+                .collect(),
+        )
+    }
+}
+
+pub struct Drug {
+    id: String,
+}
+
+#[Object]
+impl Drug {
+    async fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Canonical name from `drugs.parquet`.
+    async fn name(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<String>> {
+        Ok(snapshot(ctx).vocab()?.drug_name(&self.id))
+    }
+
+    /// Ranked signals of the drug, highest score first.
+    async fn signals(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 100)] limit: usize,
+    ) -> async_graphql::Result<Vec<Signal>> {
+        Ok(snapshot(ctx)
+            .signals()?
+            .filter(|s| s.drug_id == self.id)
+            .take(limit)
+            .cloned()
+            .map(Signal)
+            .collect())
+    }
+}
+
+pub struct Event {
+    id: String,
+}
+
+#[Object]
+impl Event {
+    async fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Canonical term from `events.parquet`.
+    async fn term(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<String>> {
+        Ok(snapshot(ctx).vocab()?.event_term(&self.id))
+    }
+
+    /// Ranked signals of the event, highest score first.
+    async fn signals(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 100)] limit: usize,
+    ) -> async_graphql::Result<Vec<Signal>> {
+        Ok(snapshot(ctx)
+            .signals()?
+            .filter(|s| s.event_id == self.id)
+            .take(limit)
+            .cloned()
+            .map(Signal)
+            .collect())
+    }
+}
+
+/// One literature sentence pairing the drug and event.
+#[derive(SimpleObject)]
+pub struct Relation {
+    pmid: String,
+    sent_idx: i64,
+    confidence: f64,
+    section: Option<String>,
+    sentence: String,
+}
+
+impl From<&Evidence> for Relation {
+    fn from(evidence: &Evidence) -> Self {
+        Self {
+            pmid: evidence.pmid.clone(),
+            sent_idx: evidence.sent_idx,
+            confidence: evidence.confidence,
+            section: evidence.section.clone(),
+            sentence: evidence.sentence.clone(),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Quarter {
+    year_quarter: String,
+    ror: f64,
+    ci_low: f64,
+    ci_high: f64,
+    ror_shrunk: f64,
+    shrunk_ci_low: f64,
+    shrunk_ci_high: f64,
+    trend_z: f64,
+}
+
+impl From<QuarterMetric> for Quarter {
+    fn from(metric: QuarterMetric) -> Self {
+        Self {
+            year_quarter: metric.year_quarter,
+            ror: metric.ror,
+            ci_low: metric.ci_low,
+            ci_high: metric.ci_high,
+            ror_shrunk: metric.ror_shrunk,
+            shrunk_ci_low: metric.shrunk_ci_low,
+            shrunk_ci_high: metric.shrunk_ci_high,
+            trend_z: metric.trend_z,
+        }
+    }
+}
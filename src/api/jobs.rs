@@ -0,0 +1,271 @@
+//! Pipeline stages run in the background on request of `POST /jobs`.
+//!
+//! Jobs run one at a time on a dedicated worker thread with its own runtime, so long
+//! CPU-bound stages never stall request handling. Each job is parsed through the regular
+//! CLI, so its `params` accept exactly the flags of the corresponding sub-commands.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use anyhow::Result;
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use clap::{CommandFactory, Parser};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::{error, info, info_span, Instrument};
+
+use crate::{
+    api::{
+        datasets::Dataset,
+        error::{self, ApiError},
+    },
+    cli::Cli,
+    config::Settings,
+    logging,
+};
+
+/// Pipeline stage requested by a job; `all` runs fetch, normalize, signal and rank in turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStage {
+    Fetch,
+    Normalize,
+    Signal,
+    Rank,
+    All,
+}
+
+impl JobStage {
+    /// CLI sub-commands run for the stage, in order.
+    fn commands(self) -> &'static [&'static str] {
+        match self {
+            Self::Fetch => &["fetch"],
+            Self::Normalize => &["normalize"],
+            Self::Signal => &["signal"],
+            Self::Rank => &["rank"],
+            Self::All => &["fetch", "normalize", "signal", "rank"],
+        }
+    }
+}
+
+/// Body of `POST /jobs`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobRequest {
+    pub stage: JobStage,
+    /// CLI flags without the leading dashes, e.g. `{"quarters": ["2024Q1"], "cumulative": true}`.
+    /// With `all`, each flag goes to every stage that accepts it.
+    #[serde(default)]
+    pub params: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: u64,
+    pub stage: JobStage,
+    pub params: Map<String, Value>,
+    /// Dataset whose directories the job reads and writes.
+    pub dataset: String,
+    pub status: JobStatus,
+    pub submitted_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Why a job failed: a reference to the server log line holding its error chain.
+    pub error: Option<String>,
+    /// Log lines emitted while the job ran (only in `GET /jobs/:id`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<String>,
+}
+
+type JobTable = Arc<Mutex<BTreeMap<u64, Job>>>;
+
+/// Job registry shared by the handlers, plus the channel to the worker thread.
+#[derive(Clone)]
+pub struct Jobs {
+    table: JobTable,
+    worker: mpsc::Sender<(u64, Arc<Settings>, Vec<Cli>)>,
+}
+
+impl Jobs {
+    /// Start the worker thread that runs submitted jobs with their dataset's settings.
+    pub fn start() -> Result<Self> {
+        let table = JobTable::default();
+        let (worker, queue) = mpsc::channel::<(u64, Arc<Settings>, Vec<Cli>)>();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let jobs = table.clone();
+        thread::Builder::new()
+            .name("pipeline-jobs".to_string())
+            .spawn(move || {
+                while let Ok((id, settings, commands)) = queue.recv() {
+                    update(&jobs, id, |job| {
+                        job.status = JobStatus::Running;
+                        job.started_at = Some(Utc::now());
+                    });
+                    let span = info_span!("job", job_id = id);
+                    let result = runtime.block_on(
+                        async {
+                            info!(stages = commands.len(), "job started");
+                            for cli in commands {
+                                cli.dispatch(Settings::clone(&settings)).await?;
+                            }
+                            info!("job finished");
+                            anyhow::Ok(())
+                        }
+                        .instrument(span),
+                    );
+                    // The chain names server paths and upstream URLs, so it stays in the log.
+                    let result = result.map_err(|err| {
+                        let reference = error::reference();
+                        error!(job = id, %reference, error = %format!("{err:#}"), "job failed");
+                        format!("job failed; reference {reference}")
+                    });
+                    update(&jobs, id, |job| {
+                        job.finished_at = Some(Utc::now());
+                        match result {
+                            Ok(()) => job.status = JobStatus::Succeeded,
+                            Err(message) => {
+                                job.status = JobStatus::Failed;
+                                job.error = Some(message);
+                            }
+                        }
+                    });
+                }
+            })?;
+        Ok(Self { table, worker })
+    }
+
+    /// Validate the request against the CLI and queue it. Fails with 400 for flags the
+    /// stage does not accept and 409 while another job is queued or running.
+    pub fn submit(&self, request: JobRequest, dataset: Dataset) -> Result<Job, ApiError> {
+        let commands = parse_commands(&request)?;
+        let mut table = self.table.lock().map_err(internal)?;
+        if let Some(active) = table
+            .values()
+            .find(|job| matches!(job.status, JobStatus::Queued | JobStatus::Running))
+        {
+            return Err(ApiError::conflict(
+                format!("job {} is still {:?}", active.id, active.status).to_lowercase(),
+            ));
+        }
+        let id = table.keys().next_back().map_or(1, |last| last + 1);
+        let job = Job {
+            id,
+            stage: request.stage,
+            params: request.params,
+            dataset: dataset.name,
+            status: JobStatus::Queued,
+            submitted_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+            error: None,
+            logs: Vec::new(),
+        };
+        table.insert(id, job.clone());
+        self.worker
+            .send((id, dataset.settings, commands))
+            .map_err(|_| internal("job worker stopped"))?;
+        info!(job = id, stage = ?request.stage, "queued job");
+        Ok(job)
+    }
+
+    /// Every job, newest first, without logs.
+    pub fn list(&self) -> Vec<Job> {
+        self.table
+            .lock()
+            .map(|table| table.values().rev().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// One job with the log lines captured so far.
+    pub fn get(&self, id: u64) -> Option<Job> {
+        let mut job = self.table.lock().ok()?.get(&id).cloned()?;
+        job.logs = logging::job_log(id);
+        Some(job)
+    }
+}
+
+fn update(table: &JobTable, id: u64, change: impl FnOnce(&mut Job)) {
+    if let Ok(mut table) = table.lock() {
+        if let Some(job) = table.get_mut(&id) {
+            change(job);
+        }
+    }
+}
+
+fn internal(err: impl ToString) -> ApiError {
+    ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+}
+
+/// Parse one CLI invocation per stage command from the request params.
+///
+/// Only the sub-commands' own flags are passed on; global flags such as `config`, `offline`
+/// or `verbose` configure the server process, not a job, and are rejected like unknown ones.
+fn parse_commands(request: &JobRequest) -> Result<Vec<Cli>, ApiError> {
+    let cli = Cli::command();
+    let stages = request.stage.commands();
+    let mut unused: BTreeSet<&str> = request.params.keys().map(String::as_str).collect();
+    let mut commands = Vec::with_capacity(stages.len());
+    for &stage in stages {
+        let accepted: BTreeSet<String> = cli
+            .find_subcommand(stage)
+            .map(|command| {
+                command
+                    .get_arguments()
+                    .filter_map(|arg| arg.get_long().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut argv = vec!["rwe-assistant".to_string(), stage.to_string()];
+        for (name, value) in &request.params {
+            let flag = name.replace('_', "-");
+            if !accepted.contains(&flag) {
+                continue;
+            }
+            unused.remove(name.as_str());
+            push_flag(&mut argv, &flag, value).map_err(ApiError::bad_request)?;
+        }
+        let parsed = Cli::try_parse_from(&argv)
+            .map_err(|err| ApiError::bad_request(format!("{stage}: {}", err.render())))?;
+        commands.push(parsed);
+    }
+    if let Some(name) = unused.into_iter().next() {
+        return Err(ApiError::bad_request(format!(
+            "no stage accepts the parameter {name:?}"
+        )));
+    }
+    Ok(commands)
+}
+
+fn push_flag(argv: &mut Vec<String>, flag: &str, value: &Value) -> Result<(), String> {
+    match value {
+        Value::Null | Value::Bool(false) => {}
+        Value::Bool(true) => argv.push(format!("--{flag}")),
+        Value::String(text) => argv.extend([format!("--{flag}"), text.clone()]),
+        Value::Number(number) => argv.extend([format!("--{flag}"), number.to_string()]),
+        Value::Array(items) => {
+            for item in items {
+                match item {
+                    Value::String(text) => argv.extend([format!("--{flag}"), text.clone()]),
+                    Value::Number(number) => argv.extend([format!("--{flag}"), number.to_string()]),
+                    _ => return Err(format!("{flag}: list items must be strings or numbers")),
+                }
+            }
+        }
+        Value::Object(_) => return Err(format!("{flag}: objects are not valid parameters")),
+    }
+    Ok(())
+}
@@ -0,0 +1,62 @@
+//! Prometheus request metrics for the API.
+
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use metrics::Label;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+use super::AppState;
+
+const REQUESTS_TOTAL: &str = "http_requests_total";
+const REQUEST_DURATION: &str = "http_request_duration_seconds";
+
+/// Latency buckets in seconds, from cached lookups up to full CSV scans.
+const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Install the process-wide Prometheus recorder.
+pub fn install() -> Result<PrometheusHandle> {
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full(REQUEST_DURATION.to_string()),
+            DURATION_BUCKETS,
+        )?
+        .install_recorder()
+        .context("installing Prometheus recorder")
+}
+
+/// Count requests and record their latency by method, route and status.
+pub async fn track(request: Request, next: Next) -> Response {
+    let start = Instant::now();
+    // Label by route template so ids in paths do not explode the label set.
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|matched| matched.as_str().to_string())
+        .unwrap_or_else(|| "static".to_string());
+    let method = request.method().to_string();
+    let response = next.run(request).await;
+    let labels = vec![
+        Label::new("method", method),
+        Label::new("path", path),
+        Label::new("status", response.status().as_u16().to_string()),
+    ];
+    metrics::counter!(REQUESTS_TOTAL, labels.clone()).increment(1);
+    metrics::histogram!(REQUEST_DURATION, labels).record(start.elapsed().as_secs_f64());
+    response
+}
+
+/// `GET /metrics` in the Prometheus text format.
+pub async fn render(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [("content-type", "text/plain; version=0.0.4")],
+        state.metrics.render(),
+    )
+}
@@ -1,38 +1,234 @@
 //! HTTP layer exposing computed signals and static UI.
 
+pub mod annotations;
+pub mod auth;
+pub mod datasets;
+pub mod error;
+pub mod etag;
+pub mod export;
+pub mod graphql;
+pub mod jobs;
+pub mod metrics;
+pub mod names;
+pub mod reload;
+#[cfg(feature = "askama")]
+pub mod report;
 pub mod routes;
+pub mod store;
 pub mod types;
+pub mod watchlists;
 
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
-use anyhow::Result;
-use axum::{routing::get, Router};
+use anyhow::{bail, Context, Result};
+use axum::{
+    extract::DefaultBodyLimit,
+    http::HeaderValue,
+    middleware,
+    response::Response,
+    routing::{get, post},
+    Router,
+};
+use axum_server::{tls_rustls::RustlsConfig, Handle};
+use metrics_exporter_prometheus::PrometheusHandle;
 use tokio::net::TcpListener;
-use tower_http::{services::ServeDir, trace::TraceLayer};
-use tracing::info;
+use tower::Layer;
+use tower_governor::{governor::GovernorConfigBuilder, GovernorLayer};
+use tower_http::{
+    limit::RequestBodyLimitLayer,
+    services::ServeDir,
+    timeout::{RequestBodyTimeoutLayer, TimeoutLayer},
+    trace::TraceLayer,
+};
+use tracing::{info, warn};
 
-use crate::config::Settings;
+use crate::{config::Settings, ui};
 
 #[derive(Clone)]
 pub struct AppState {
+    /// Server-wide settings (keys, rate limits, TLS) and the default dataset.
     pub settings: Settings,
+    pub datasets: Arc<datasets::DatasetMap>,
+    pub metrics: PrometheusHandle,
+    pub jobs: jobs::Jobs,
+    pub graphql: graphql::ApiSchema,
 }
 
 pub async fn serve(settings: Settings, host: String, port: u16) -> Result<()> {
     let state = AppState {
         settings: settings.clone(),
+        datasets: Arc::new(datasets::from_settings(&settings)),
+        metrics: metrics::install()?,
+        jobs: jobs::Jobs::start()?,
+        graphql: graphql::schema(),
     };
-    let static_dir = ServeDir::new("src/ui/static");
-    let router = Router::new()
+    // Kept alive for as long as the server runs.
+    let _watcher = reload::watch(&state.datasets)?;
+    if settings.api_keys.is_empty() {
+        warn!("no API_KEYS configured; every route is open to anyone who can reach the port");
+    }
+    let app = router(state)?;
+
+    let addr: SocketAddr = format!("{host}:{port}").parse()?;
+    // Peer addresses key the rate limiter.
+    let service = app.into_make_service_with_connect_info::<SocketAddr>();
+    match (&settings.tls_cert_path, &settings.tls_key_path) {
+        (Some(cert), Some(key)) => {
+            let tls = RustlsConfig::from_pem_file(cert, key)
+                .await
+                .with_context(|| format!("loading TLS certificate {cert:?} and key {key:?}"))?;
+            let handle = Handle::new();
+            let grace = (settings.request_timeout_secs > 0)
+                .then(|| Duration::from_secs(settings.request_timeout_secs));
+            tokio::spawn({
+                let handle = handle.clone();
+                async move {
+                    shutdown_signal().await;
+                    handle.graceful_shutdown(grace);
+                }
+            });
+            info!(%addr, "serving rwe-assistant API over HTTPS");
+            axum_server::bind_rustls(addr, tls)
+                .handle(handle)
+                .serve(service)
+                .await?;
+        }
+        (None, None) => {
+            info!(%addr, "serving rwe-assistant API");
+            let listener = TcpListener::bind(addr).await?;
+            // In-flight requests end within the request timeout, which bounds the drain.
+            axum::serve(listener, service)
+                .with_graceful_shutdown(shutdown_signal())
+                .await?;
+        }
+        _ => bail!("TLS_CERT_PATH and TLS_KEY_PATH must be set together"),
+    }
+    info!("server stopped");
+    Ok(())
+}
+
+/// Routes, static UI and middleware, from the rate limiter and key check down to the
+/// handlers.
+pub fn router(state: AppState) -> Result<Router> {
+    let settings = state.settings.clone();
+    let static_dir = ServeDir::new(ui::STATIC_DIR);
+    let v1 = Router::new()
         .route("/signals", get(routes::list_signals))
+        .route("/signals/:drug_id/:event_id", get(routes::signal_detail))
+        .route(
+            "/signals/:drug_id/:event_id/annotations",
+            post(routes::add_annotation).get(routes::list_annotations),
+        )
         .route("/events/:drug_id", get(routes::list_events))
-        .fallback_service(static_dir)
+        .route("/evidence/:drug_id/:event_id", get(routes::pair_literature))
+        .route("/export/signals", get(routes::export_signals))
+        .route("/graphql", get(graphql::execute).post(graphql::execute))
+        .route("/normalize/metrics", get(routes::normalize_metrics))
+        .route(
+            "/watchlists",
+            post(routes::save_watchlist).get(routes::list_watchlists),
+        )
+        .route(
+            "/watchlists/:name",
+            get(routes::get_watchlist).delete(routes::delete_watchlist),
+        )
+        .route("/jobs", post(routes::submit_job).get(routes::list_jobs))
+        .route("/jobs/:id", get(routes::job_status))
+        .route("/datasets", get(datasets::list));
+    #[cfg(feature = "askama")]
+    let v1 = v1.route("/reports/:drug_id", get(report::drug_report));
+    // Unversioned paths predate `/v1` and stay as aliases for existing clients.
+    let legacy = v1.clone().layer(middleware::map_response(deprecated));
+    let mut router = Router::new()
+        .nest("/v1", v1)
+        .merge(legacy)
+        .route("/graphiql", get(graphql::graphiql))
+        .route("/healthz", get(routes::healthz))
+        .route("/metrics", get(metrics::render))
+        .fallback_service(static_dir);
+    if settings.read_timeout_secs > 0 {
+        let timeout = Duration::from_secs(settings.read_timeout_secs);
+        router = router.layer(RequestBodyTimeoutLayer::new(timeout));
+    }
+    if settings.request_timeout_secs > 0 {
+        let timeout = Duration::from_secs(settings.request_timeout_secs);
+        router = router.layer(TimeoutLayer::new(timeout));
+    }
+    let router = router
+        // Extractors check the first, bodies read as streams (GraphQL) the second.
+        .layer(DefaultBodyLimit::max(settings.max_body_bytes))
+        .layer(RequestBodyLimitLayer::new(settings.max_body_bytes))
+        .layer(middleware::from_fn(error::envelope))
+        .layer(middleware::from_fn(metrics::track))
         .layer(TraceLayer::new_for_http())
-        .with_state(state);
+        .with_state(state.clone());
+    // Dataset selection rewrites the path, so it has to run before routing. Wrapping it in a
+    // Router lets both the plain and the TLS server accept it. Keys are checked first, so
+    // clients without one cannot tell configured dataset names from unknown ones.
+    let mut app = Router::new()
+        .fallback_service(middleware::from_fn_with_state(state, datasets::select).layer(router))
+        .layer(middleware::from_fn_with_state(
+            Arc::new(settings.clone()),
+            auth::require_key,
+        ));
+    if settings.rate_limit_per_second > 0 && settings.rate_limit_burst > 0 {
+        // Token bucket per client IP: one request back every `1000 / rate` ms.
+        let config = Arc::new(
+            GovernorConfigBuilder::default()
+                .per_millisecond((1000 / settings.rate_limit_per_second).max(1))
+                .burst_size(settings.rate_limit_burst)
+                .use_headers()
+                .finish()
+                .context("invalid RATE_LIMIT_PER_SECOND or RATE_LIMIT_BURST")?,
+        );
+        let limiter = config.limiter().clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                limiter.retain_recent();
+            }
+        });
+        app = app.layer(GovernorLayer { config });
+    }
+    // Rate-limited and rejected requests get the same error body as the routes.
+    Ok(app.layer(middleware::from_fn(error::envelope)))
+}
 
-    let addr: SocketAddr = format!("{host}:{port}").parse()?;
-    info!(%addr, "serving rwe-assistant API");
-    let listener = TcpListener::bind(addr).await?;
-    axum::serve(listener, router.into_make_service()).await?;
-    Ok(())
+/// Resolves on Ctrl-C or, on Unix, SIGTERM, after which the server stops accepting
+/// connections and drains the open ones, and `watch` stops between runs.
+pub(crate) async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            warn!(%err, "cannot listen for Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(err) => {
+                warn!(%err, "cannot listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+    info!("shutdown requested");
+}
+
+/// Marks responses to unversioned paths, which answer like their `/v1` counterparts.
+async fn deprecated(mut response: Response) -> Response {
+    response
+        .headers_mut()
+        .insert("deprecation", HeaderValue::from_static("true"));
+    response
 }
@@ -0,0 +1,81 @@
+//! Canonical drug names and event terms for API responses and path parameters.
+
+use std::{collections::HashMap, fs::File};
+
+use anyhow::Result;
+use polars::prelude::{ParquetReader, SerReader};
+
+use crate::config::Settings;
+
+/// Id ↔ name maps from `drugs.parquet` and `events.parquet`.
+#[derive(Debug, Clone, Default)]
+pub struct Vocabulary {
+    drugs: Names,
+    events: Names,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Names {
+    by_id: HashMap<String, String>,
+    /// Lowercase id or name → id.
+    lookup: HashMap<String, String>,
+}
+
+impl Names {
+    fn load(settings: &Settings, file: &str, id_column: &str, name_column: &str) -> Result<Self> {
+        let path = settings.join_data("clean").join(file);
+        let mut names = Self::default();
+        if !path.exists() {
+            return Ok(names);
+        }
+        let df = ParquetReader::new(File::open(&path)?).finish()?;
+        let ids = df.column(id_column)?.str()?;
+        let labels = df.column(name_column)?.str()?;
+        for (id, label) in ids.into_iter().zip(labels) {
+            let (Some(id), Some(label)) = (id, label) else {
+                continue;
+            };
+            names.lookup.insert(id.to_lowercase(), id.to_string());
+            names
+                .lookup
+                .entry(label.to_lowercase())
+                .or_insert_with(|| id.to_string());
+            names.by_id.insert(id.to_string(), label.to_string());
+        }
+        Ok(names)
+    }
+
+    fn resolve(&self, id_or_name: &str) -> String {
+        self.lookup
+            .get(&id_or_name.trim().to_lowercase())
+            .cloned()
+            .unwrap_or_else(|| id_or_name.trim().to_string())
+    }
+}
+
+impl Vocabulary {
+    pub fn load(settings: &Settings) -> Result<Self> {
+        Ok(Self {
+            drugs: Names::load(settings, "drugs.parquet", "drug_id", "name_canonical")?,
+            events: Names::load(settings, "events.parquet", "event_id", "term_canonical")?,
+        })
+    }
+
+    pub fn drug_name(&self, drug_id: &str) -> Option<String> {
+        self.drugs.by_id.get(drug_id).cloned()
+    }
+
+    pub fn event_term(&self, event_id: &str) -> Option<String> {
+        self.events.by_id.get(event_id).cloned()
+    }
+
+    /// Drug id for an id or canonical name (case-insensitive); unknown input is returned as is.
+    pub fn resolve_drug(&self, id_or_name: &str) -> String {
+        self.drugs.resolve(id_or_name)
+    }
+
+    /// Event id for an id or canonical term (case-insensitive); unknown input is returned as is.
+    pub fn resolve_event(&self, id_or_name: &str) -> String {
+        self.events.resolve(id_or_name)
+    }
+}
@@ -0,0 +1,68 @@
+//! Drops cached artefacts when the pipeline rewrites them, so a long-running server serves
+//! the latest `rank` or `normalize` run without a restart.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tracing::{debug, info, warn};
+
+use super::datasets::{Dataset, DatasetMap};
+
+/// Files the caches are built from.
+const WATCHED_FILES: &[&str] = &["signals.csv", "drugs.parquet", "events.parquet"];
+
+/// Watch every dataset's output and clean-data directories. Events arrive on the watcher's
+/// own thread; the returned watcher stops when dropped.
+pub fn watch(datasets: &DatasetMap) -> Result<RecommendedWatcher> {
+    let mut dirs: Vec<(PathBuf, Dataset)> = Vec::new();
+    for dataset in datasets.values() {
+        let settings = &dataset.settings;
+        for dir in [settings.outputs_dir.clone(), settings.join_data("clean")] {
+            std::fs::create_dir_all(&dir).with_context(|| format!("create {dir:?}"))?;
+            // Events name paths as the OS sees them, so compare canonical forms.
+            dirs.push((dir.canonicalize()?, dataset.clone()));
+        }
+    }
+    let targets = dirs.clone();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        let event = match event {
+            Ok(event) => event,
+            Err(err) => {
+                warn!(%err, "file watcher error");
+                return;
+            }
+        };
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        for path in &event.paths {
+            let watched = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| WATCHED_FILES.contains(&name));
+            if !watched {
+                continue;
+            }
+            let Some(dir) = path.parent().and_then(|dir| dir.canonicalize().ok()) else {
+                continue;
+            };
+            // A rewrite fires one event per write, so keep this at debug level.
+            for (_, dataset) in targets.iter().filter(|(target, _)| *target == dir) {
+                dataset.cache.invalidate();
+                debug!(
+                    dataset = %dataset.name,
+                    path = %path.display(),
+                    "artefact changed; cache dropped"
+                );
+            }
+        }
+    })?;
+    for (dir, _) in &dirs {
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("watch {dir:?}"))?;
+    }
+    info!(dirs = dirs.len(), "watching artefacts for changes");
+    Ok(watcher)
+}
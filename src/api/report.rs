@@ -0,0 +1,287 @@
+//! Self-contained safety dossier of one drug, rendered from the same artefacts as the JSON
+//! routes: served as HTML by `GET /reports/:drug_id` and written as HTML or Markdown by the
+//! `report` command.
+
+use std::{collections::HashSet, fmt::Write};
+
+use anyhow::Result;
+use askama::Template;
+use axum::{extract::Path, response::Html};
+use chrono::Utc;
+
+use crate::{
+    api::{
+        datasets::Dataset,
+        error::ApiError,
+        store::{self, SignalCache, SignalFilter, SignalOrder},
+        watchlists::Watchlists,
+    },
+    config::Settings,
+    data::pubmed,
+    nlp::summaries::{self, Summary},
+    signals::{self, Contingency, QuarterMetric},
+};
+
+/// Events listed in the table.
+const TOP_EVENTS: usize = 20;
+
+/// Events of the table that also get a trend chart, citations and summary.
+const DETAILED_EVENTS: usize = 10;
+
+/// Articles cited per event.
+const CITATIONS_PER_EVENT: usize = 5;
+
+#[derive(Template)]
+#[template(path = "report.html")]
+pub struct DrugReport {
+    pub drug_id: String,
+    pub drug_name: String,
+    pub dataset: String,
+    pub generated_at: String,
+    pub total_signals: usize,
+    pub events: Vec<ReportEvent>,
+}
+
+/// The same dossier as Markdown, for wikis and tickets.
+#[derive(Template)]
+#[template(path = "report.md")]
+struct MarkdownReport<'a> {
+    report: &'a DrugReport,
+}
+
+pub struct ReportEvent {
+    pub event_id: String,
+    pub event_term: String,
+    pub year_quarter: String,
+    pub ror: f64,
+    pub ci_low: f64,
+    pub ci_high: f64,
+    pub lit_support: i64,
+    pub trend_z: f64,
+    pub score: f64,
+    pub labeled: bool,
+    pub sider_known: bool,
+    pub watched: bool,
+    /// Rendered with a chart, counts, citations and summary below the table.
+    pub detailed: bool,
+    /// 2x2 counts of the event's latest quarter; absent when `faers_norm` lacks the pair.
+    pub counts: Option<Contingency>,
+    /// Inline SVG of the shrunk ROR trend; absent with fewer than two quarters.
+    pub chart: Option<String>,
+    /// The same trend as block characters, for Markdown.
+    pub sparkline: Option<String>,
+    pub citations: Vec<Citation>,
+    pub summary: Option<Summary>,
+}
+
+/// Best supporting sentence of one article.
+pub struct Citation {
+    pub pmid: String,
+    pub title: Option<String>,
+    pub year: Option<i32>,
+    pub confidence: f64,
+    pub sentence: String,
+}
+
+/// Accepts a drug id or canonical name.
+pub async fn drug_report(
+    Path(drug): Path<String>,
+    dataset: Dataset,
+) -> Result<Html<String>, ApiError> {
+    let report = build(&dataset.settings, &dataset.cache, &drug, &dataset.name)?
+        .ok_or_else(|| ApiError::not_found(format!("no signals computed for {drug}")))?;
+    Ok(Html(report.render().map_err(anyhow::Error::from)?))
+}
+
+impl DrugReport {
+    pub fn markdown(&self) -> Result<String> {
+        Ok(MarkdownReport { report: self }.render()?)
+    }
+}
+
+/// The dossier of `drug` (an id or canonical name); `None` when it has no ranked signals.
+pub fn build(
+    settings: &Settings,
+    cache: &SignalCache,
+    drug: &str,
+    dataset: &str,
+) -> Result<Option<DrugReport>> {
+    let vocab = cache.vocabulary(settings)?;
+    let drug_id = vocab.resolve_drug(drug);
+    let filter = SignalFilter {
+        drug_id: Some(drug_id.clone()),
+        order: SignalOrder::Score,
+        ..SignalFilter::default()
+    };
+    let ranked = store::query_signals(settings, cache, &filter)?;
+    if ranked.is_empty() {
+        return Ok(None);
+    }
+    let watchlists = Watchlists::load(settings)?;
+    let watched = watchlists.watched();
+    let mut evidence = summaries::evidence_by_pair(settings)?;
+    let counts = signals::drug_contingency(settings, &drug_id)?;
+
+    let mut events = Vec::new();
+    for (rank, signal) in ranked.iter().take(TOP_EVENTS).enumerate() {
+        let detailed = rank < DETAILED_EVENTS;
+        let pair_evidence = evidence
+            .remove(&(signal.drug_id.clone(), signal.event_id.clone()))
+            .unwrap_or_default();
+        let (chart, sparkline, citations, summary) = if detailed {
+            let history = signals::pair_history(settings, &signal.drug_id, &signal.event_id)?;
+            let known: HashSet<&str> = pair_evidence.iter().map(|e| e.pmid.as_str()).collect();
+            let summary =
+                summaries::load_summary(settings, &signal.drug_id, &signal.event_id, &known)?;
+            // Evidence is sorted by confidence, so the first sentence per article is its best.
+            let mut seen = HashSet::new();
+            let mut citations: Vec<Citation> = pair_evidence
+                .iter()
+                .filter(|item| seen.insert(item.pmid.as_str()))
+                .take(CITATIONS_PER_EVENT)
+                .map(|item| Citation {
+                    pmid: item.pmid.clone(),
+                    title: None,
+                    year: None,
+                    confidence: item.confidence,
+                    sentence: item.sentence.clone(),
+                })
+                .collect();
+            let pmids: HashSet<&str> = citations.iter().map(|c| c.pmid.as_str()).collect();
+            let records = pubmed::load_records(&pmids, settings)?;
+            for citation in &mut citations {
+                if let Some(record) = records.get(&citation.pmid) {
+                    citation.title = Some(record.title.clone());
+                    citation.year = record.year;
+                }
+            }
+            (
+                trend_chart(&history),
+                sparkline(&history),
+                citations,
+                summary,
+            )
+        } else {
+            (None, None, Vec::new(), None)
+        };
+        events.push(ReportEvent {
+            event_id: signal.event_id.clone(),
+            event_term: signal
+                .event_term
+                .clone()
+                .unwrap_or_else(|| signal.event_id.clone()),
+            year_quarter: signal.year_quarter.clone(),
+            ror: signal.recent_ror,
+            ci_low: signal.ci_low,
+            ci_high: signal.ci_high,
+            lit_support: signal.lit_support,
+            trend_z: signal.trend_z,
+            score: signal.score,
+            labeled: signal.labeled,
+            sider_known: signal.sider_known,
+            watched: watched.contains(&(signal.drug_id.as_str(), signal.event_id.as_str())),
+            detailed,
+            counts: counts
+                .get(&(signal.event_id.clone(), signal.year_quarter.clone()))
+                .copied(),
+            chart,
+            sparkline,
+            citations,
+            summary,
+        });
+    }
+
+    Ok(Some(DrugReport {
+        drug_name: vocab.drug_name(&drug_id).unwrap_or_else(|| drug_id.clone()),
+        drug_id,
+        dataset: dataset.to_string(),
+        generated_at: Utc::now().format("%Y-%m-%d %H:%M UTC").to_string(),
+        total_signals: ranked.len(),
+        events,
+    }))
+}
+
+/// Shrunk ROR per quarter as block characters scaled between the lowest and highest value.
+fn sparkline(history: &[QuarterMetric]) -> Option<String> {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    if history.len() < 2 {
+        return None;
+    }
+    let values: Vec<f64> = history.iter().map(|q| q.ror_shrunk).collect();
+    let (lo, hi) = values
+        .iter()
+        .filter(|v| v.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| {
+            (lo.min(v), hi.max(v))
+        });
+    if !lo.is_finite() {
+        return None;
+    }
+    let span = (hi - lo).max(f64::EPSILON);
+    Some(
+        values
+            .iter()
+            .map(|&v| {
+                if v.is_finite() {
+                    BLOCKS[(((v - lo) / span) * 7.0).round() as usize]
+                } else {
+                    ' '
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Sparkline of the shrunk ROR with its interval as a band and a dashed line at ROR = 1.
+fn trend_chart(history: &[QuarterMetric]) -> Option<String> {
+    const WIDTH: f64 = 480.0;
+    const HEIGHT: f64 = 120.0;
+    const PAD: f64 = 8.0;
+    if history.len() < 2 {
+        return None;
+    }
+    let finite = |v: f64| v.is_finite().then_some(v);
+    let (lo, hi) = history
+        .iter()
+        .flat_map(|q| [finite(q.shrunk_ci_low), finite(q.shrunk_ci_high)])
+        .flatten()
+        .fold((1.0_f64, 1.0_f64), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    let span = (hi - lo).max(f64::EPSILON);
+    let step = (WIDTH - 2.0 * PAD) / (history.len() - 1) as f64;
+    let x = |i: usize| PAD + step * i as f64;
+    let y = |v: f64| PAD + (hi - v.clamp(lo, hi)) / span * (HEIGHT - 2.0 * PAD);
+
+    let mut band = String::new();
+    for (i, q) in history.iter().enumerate() {
+        let _ = write!(band, "{:.1},{:.1} ", x(i), y(q.shrunk_ci_high));
+    }
+    for (i, q) in history.iter().enumerate().rev() {
+        let _ = write!(band, "{:.1},{:.1} ", x(i), y(q.shrunk_ci_low));
+    }
+    let line: String = history
+        .iter()
+        .enumerate()
+        .map(|(i, q)| format!("{:.1},{:.1} ", x(i), y(q.ror_shrunk)))
+        .collect();
+    let first = &history[0].year_quarter;
+    let last = &history[history.len() - 1].year_quarter;
+    Some(format!(
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" "#,
+            r#"viewBox="0 0 {w} {h}" role="img" aria-label="Shrunk ROR {first} to {last}">"#,
+            r##"<polygon points="{band}" fill="#bae6fd" />"##,
+            r##"<line x1="{pad}" x2="{right}" y1="{one:.1}" y2="{one:.1}" stroke="#64748b" stroke-dasharray="4 3" />"##,
+            r##"<polyline points="{line}" fill="none" stroke="#0369a1" stroke-width="2" />"##,
+            "</svg>"
+        ),
+        w = WIDTH,
+        h = HEIGHT,
+        pad = PAD,
+        right = WIDTH - PAD,
+        one = y(1.0),
+        band = band.trim_end(),
+        line = line.trim_end(),
+        first = first,
+        last = last,
+    ))
+}
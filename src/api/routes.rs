@@ -1,56 +1,209 @@
 //! HTTP route handlers for Axum.
 
-use std::cmp::Ordering;
+use std::{collections::HashSet, fs::File, io::Read, sync::Arc};
 
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    Json,
+    http::{
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Extension, Json,
 };
-use csv::ReaderBuilder;
 use serde::Deserialize;
-use tracing::warn;
+use tracing::{debug, warn};
 
 use crate::{
-    api::types::{EventDto, SignalDto},
-    config::Settings,
+    api::types::{
+        ArtefactCheck, ArticleEvidence, EventDto, EvidenceDto, HealthDto, NormalizeMetricsDto,
+        SignalDetailDto, SignalDto,
+    },
+    api::{
+        annotations::{self, Annotation},
+        auth::KeyId,
+        datasets::Dataset,
+        error::ApiError,
+        etag,
+        export::ExportFormat,
+        jobs::{Job, JobRequest},
+        names::Vocabulary,
+        store::{self, SignalFilter, SignalOrder},
+        watchlists::{self, WatchedPair, Watchlist, Watchlists},
+    },
+    data::{dailymed, pubmed, quality, sider},
+    nlp::summaries,
+    signals,
 };
 
 use super::AppState;
 
-type ApiResult<T> = Result<Json<T>, (StatusCode, String)>;
+type ApiResult<T> = Result<Json<T>, ApiError>;
 
 #[derive(Debug, Deserialize)]
 pub struct SignalQuery {
+    /// Drug id or canonical name.
     pub drug: Option<String>,
+    /// Case-insensitive substring match on the SMQ label; restricts results to SMQ-level rows.
+    pub smq: Option<String>,
 }
 
 pub async fn list_signals(
-    states: State<AppState>,
+    dataset: Dataset,
     Query(query): Query<SignalQuery>,
-) -> ApiResult<Vec<SignalDto>> {
-    let mut signals = load_signals(&states.settings)?;
-    if let Some(drug) = query.drug {
-        let drug_norm = drug.to_ascii_uppercase();
-        signals.retain(|s| s.drug_id.to_ascii_uppercase() == drug_norm);
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let tag = etag::signals_etag(&dataset.settings);
+    if let Some(not_modified) = etag::not_modified(&headers, tag.as_deref()) {
+        return Ok(not_modified);
+    }
+    let vocab = load_vocabulary(&dataset)?;
+    let filter = SignalFilter {
+        limit: Some(100),
+        ..query.filter(&vocab)
+    };
+    let mut signals = query_signals(&dataset, &filter)?;
+    mark_watched(&dataset, &mut signals)?;
+    Ok(etag::tagged(tag, Json(signals)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+    #[serde(flatten)]
+    pub filter: SignalQuery,
+}
+
+/// Every ranked signal matching the `/signals` filters, as a downloadable file.
+pub async fn export_signals(
+    dataset: Dataset,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, ApiError> {
+    let vocab = load_vocabulary(&dataset)?;
+    let mut signals = query_signals(&dataset, &query.filter.filter(&vocab))?;
+    mark_watched(&dataset, &mut signals)?;
+    let format = query.format;
+    let body = format.encode(&signals)?;
+    let disposition = format!("attachment; filename=\"signals.{}\"", format.extension());
+    Ok((
+        [
+            (CONTENT_TYPE, format.content_type().to_string()),
+            (CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Path parameters accept ids or canonical names.
+pub async fn signal_detail(
+    Path((drug, event)): Path<(String, String)>,
+    dataset: Dataset,
+) -> ApiResult<SignalDetailDto> {
+    let settings = &dataset.settings;
+    let vocab = load_vocabulary(&dataset)?;
+    let (drug_id, event_id) = (vocab.resolve_drug(&drug), vocab.resolve_event(&event));
+    let filter = SignalFilter {
+        drug_id: Some(drug_id.clone()),
+        event_id: Some(event_id.clone()),
+        limit: Some(1),
+        ..SignalFilter::default()
+    };
+    let mut signal = query_signals(&dataset, &filter)?;
+    mark_watched(&dataset, &mut signal)?;
+    let signal = signal.pop();
+    let history = signals::pair_history(settings, &drug_id, &event_id)?;
+    if signal.is_none() && history.is_empty() {
+        return Err(ApiError::not_found(format!(
+            "no signal computed for {drug_id}/{event_id}"
+        )));
+    }
+    let key = (drug_id.clone(), event_id.clone());
+    let labeled = dailymed::load_labeled_pairs(settings)?.contains(&key);
+    let sider_known = sider::load_known_pairs(settings)?.contains(&key);
+    let literature = summaries::pair_evidence(settings, &drug_id, &event_id)?;
+    let annotations = annotations::for_pair(settings, &drug_id, &event_id)?;
+    Ok(Json(SignalDetailDto {
+        drug_name: vocab.drug_name(&drug_id),
+        event_term: vocab.event_term(&event_id),
+        drug_id,
+        event_id,
+        signal,
+        labeled,
+        sider_known,
+        history,
+        literature,
+        annotations,
+    }))
+}
+
+/// Supporting articles for a pair, with titles and years from the PubMed cache. Path
+/// parameters accept ids or canonical names; a pair without literature has no articles.
+pub async fn pair_literature(
+    Path((drug, event)): Path<(String, String)>,
+    dataset: Dataset,
+) -> ApiResult<EvidenceDto> {
+    let settings = &dataset.settings;
+    let vocab = load_vocabulary(&dataset)?;
+    let (drug_id, event_id) = (vocab.resolve_drug(&drug), vocab.resolve_event(&event));
+    let evidence = summaries::pair_evidence(settings, &drug_id, &event_id)?;
+    let pmids: HashSet<&str> = evidence.iter().map(|item| item.pmid.as_str()).collect();
+    let records = pubmed::load_records(&pmids, settings)?;
+    // Evidence is sorted by confidence, so each article's first sentence is its best.
+    let mut articles: Vec<ArticleEvidence> = Vec::new();
+    for item in evidence {
+        match articles
+            .iter_mut()
+            .find(|article| article.pmid == item.pmid)
+        {
+            Some(article) => article.sentences.push(item),
+            None => {
+                let record = records.get(&item.pmid);
+                articles.push(ArticleEvidence {
+                    pmid: item.pmid.clone(),
+                    title: record.map(|r| r.title.clone()),
+                    year: record.and_then(|r| r.year),
+                    journal: record.and_then(|r| r.journal.clone()),
+                    confidence: item.confidence,
+                    sentences: vec![item],
+                });
+            }
+        }
     }
-    signals.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
-    signals.truncate(100);
-    Ok(Json(signals))
+    Ok(Json(EvidenceDto {
+        drug_name: vocab.drug_name(&drug_id),
+        event_term: vocab.event_term(&event_id),
+        drug_id,
+        event_id,
+        articles,
+    }))
 }
 
+/// The path parameter accepts a drug id or canonical name.
 pub async fn list_events(
-    Path(drug_id): Path<String>,
-    states: State<AppState>,
-) -> ApiResult<Vec<EventDto>> {
-    let signals = load_signals(&states.settings)?;
-    let drug_norm = drug_id.to_ascii_uppercase();
-    let mut events: Vec<EventDto> = signals
+    Path(drug): Path<String>,
+    dataset: Dataset,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let tag = etag::signals_etag(&dataset.settings);
+    if let Some(not_modified) = etag::not_modified(&headers, tag.as_deref()) {
+        return Ok(not_modified);
+    }
+    let vocab = load_vocabulary(&dataset)?;
+    let filter = SignalFilter {
+        drug_id: Some(vocab.resolve_drug(&drug)),
+        order: SignalOrder::Ror,
+        limit: Some(200),
+        ..SignalFilter::default()
+    };
+    let events: Vec<EventDto> = query_signals(&dataset, &filter)?
         .into_iter()
-        .filter(|s| s.drug_id.to_ascii_uppercase() == drug_norm)
         .map(|s| EventDto {
             drug_id: s.drug_id,
             event_id: s.event_id,
+            drug_name: s.drug_name,
+            event_term: s.event_term,
             year_quarter: s.year_quarter,
             recent_ror: s.recent_ror,
             ci_low: s.ci_low,
@@ -58,60 +211,226 @@ pub async fn list_events(
             trend_z: s.trend_z,
         })
         .collect();
-    events.sort_by(|a, b| {
-        b.recent_ror
-            .partial_cmp(&a.recent_ror)
-            .unwrap_or(Ordering::Equal)
-    });
-    events.truncate(200);
-    Ok(Json(events))
-}
-
-fn load_signals(settings: &Settings) -> Result<Vec<SignalDto>, (StatusCode, String)> {
-    let path = settings.join_output("signals.csv");
-    if !path.exists() {
-        warn!("signals.csv missing; run rank first");
-        return Ok(Vec::new());
-    }
-    let mut reader = ReaderBuilder::new()
-        .has_headers(true)
-        .from_path(&path)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-    let mut out = Vec::new();
-    for result in reader.deserialize::<RawSignal>() {
-        match result {
-            Ok(raw) => out.push(raw.into()),
-            Err(err) => return Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
-        }
+    Ok(etag::tagged(tag, Json(events)))
+}
+
+/// Body of `POST /signals/:drug/:event/annotations`.
+#[derive(Debug, Deserialize)]
+pub struct AnnotationRequest {
+    pub author: String,
+    pub text: String,
+}
+
+/// Attach an assessment note to a pair; path parameters accept ids or canonical names.
+/// The note records the fingerprint of the key that sent it next to the self-reported author.
+pub async fn add_annotation(
+    Path((drug, event)): Path<(String, String)>,
+    dataset: Dataset,
+    key_id: Option<Extension<KeyId>>,
+    Json(request): Json<AnnotationRequest>,
+) -> Result<(StatusCode, Json<Annotation>), ApiError> {
+    let (author, text) = (request.author.trim(), request.text.trim());
+    if author.is_empty() || text.is_empty() {
+        return Err(ApiError::bad_request("annotation needs an author and text"));
     }
-    Ok(out)
+    let vocab = load_vocabulary(&dataset)?;
+    let (drug_id, event_id) = (vocab.resolve_drug(&drug), vocab.resolve_event(&event));
+    let key_id = key_id.as_ref().map(|Extension(KeyId(id))| id.as_str());
+    let annotation =
+        annotations::add(&dataset.settings, &drug_id, &event_id, author, key_id, text)?;
+    Ok((StatusCode::CREATED, Json(annotation)))
+}
+
+/// Notes on a pair, oldest first.
+pub async fn list_annotations(
+    Path((drug, event)): Path<(String, String)>,
+    dataset: Dataset,
+) -> ApiResult<Vec<Annotation>> {
+    let vocab = load_vocabulary(&dataset)?;
+    let (drug_id, event_id) = (vocab.resolve_drug(&drug), vocab.resolve_event(&event));
+    annotations::for_pair(&dataset.settings, &drug_id, &event_id)
+        .map(Json)
+        .map_err(ApiError::from)
 }
 
+/// Body of `POST /watchlists`.
 #[derive(Debug, Deserialize)]
-struct RawSignal {
-    drug_id: String,
-    event_id: String,
-    year_quarter: String,
-    recent_ror: f64,
-    ci_low: f64,
-    ci_high: f64,
-    lit_support: i64,
-    trend_z: f64,
-    score: f64,
-}
-
-impl From<RawSignal> for SignalDto {
-    fn from(value: RawSignal) -> Self {
-        SignalDto {
-            drug_id: value.drug_id,
-            event_id: value.event_id,
-            year_quarter: value.year_quarter,
-            recent_ror: value.recent_ror,
-            ci_low: value.ci_low,
-            ci_high: value.ci_high,
-            lit_support: value.lit_support,
-            trend_z: value.trend_z,
-            score: value.score,
+pub struct WatchlistRequest {
+    pub name: String,
+    pub pairs: Vec<PairRef>,
+}
+
+/// A drug-event pair by id or canonical name.
+#[derive(Debug, Deserialize)]
+pub struct PairRef {
+    pub drug: String,
+    pub event: String,
+}
+
+/// Create a watchlist (201) or replace the pairs of an existing one (200).
+pub async fn save_watchlist(
+    dataset: Dataset,
+    Json(request): Json<WatchlistRequest>,
+) -> Result<(StatusCode, Json<Watchlist>), ApiError> {
+    let name = request.name.trim();
+    if name.is_empty() {
+        return Err(ApiError::bad_request("watchlist name is empty"));
+    }
+    let vocab = load_vocabulary(&dataset)?;
+    let pairs = request
+        .pairs
+        .iter()
+        .map(|pair| WatchedPair {
+            drug_id: vocab.resolve_drug(&pair.drug),
+            event_id: vocab.resolve_event(&pair.event),
+        })
+        .collect();
+    let (list, created) = watchlists::upsert(&dataset.settings, name, pairs)?;
+    let status = if created {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    Ok((status, Json(list)))
+}
+
+pub async fn list_watchlists(dataset: Dataset) -> ApiResult<Vec<Watchlist>> {
+    Ok(Json(
+        load_watchlists(&dataset)?.lists.into_values().collect(),
+    ))
+}
+
+pub async fn get_watchlist(Path(name): Path<String>, dataset: Dataset) -> ApiResult<Watchlist> {
+    load_watchlists(&dataset)?
+        .lists
+        .remove(&name)
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("no watchlist {name}")))
+}
+
+pub async fn delete_watchlist(
+    Path(name): Path<String>,
+    dataset: Dataset,
+) -> Result<StatusCode, ApiError> {
+    match watchlists::remove(&dataset.settings, &name) {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(ApiError::not_found(format!("no watchlist {name}"))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn submit_job(
+    states: State<AppState>,
+    dataset: Dataset,
+    Json(request): Json<JobRequest>,
+) -> Result<(StatusCode, Json<Job>), ApiError> {
+    let job = states.jobs.submit(request, dataset)?;
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+pub async fn list_jobs(states: State<AppState>) -> Json<Vec<Job>> {
+    Json(states.jobs.list())
+}
+
+pub async fn job_status(Path(id): Path<u64>, states: State<AppState>) -> ApiResult<Job> {
+    states
+        .jobs
+        .get(id)
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("no job {id}")))
+}
+
+pub async fn normalize_metrics(dataset: Dataset) -> ApiResult<NormalizeMetricsDto> {
+    let latest = quality::load_latest(&dataset.settings)?;
+    let history = quality::load_history(&dataset.settings)?;
+    Ok(Json(NormalizeMetricsDto { latest, history }))
+}
+
+/// Artefacts checked by `/healthz`, relative to the output or data directory, and whether
+/// the API is useless without them.
+const HEALTH_ARTEFACTS: &[(&str, bool)] = &[
+    ("outputs/signals.csv", true),
+    ("clean/drugs.parquet", true),
+    ("clean/events.parquet", true),
+    ("clean/signal_metrics.parquet", false),
+    ("clean/relations.parquet", false),
+];
+
+/// Liveness and readiness probe: 200 when every required artefact can be opened, 503
+/// otherwise.
+pub async fn healthz(dataset: Dataset) -> (StatusCode, Json<HealthDto>) {
+    let artefacts: Vec<ArtefactCheck> = HEALTH_ARTEFACTS
+        .iter()
+        .map(|&(name, required)| {
+            let path = match name.strip_prefix("outputs/") {
+                Some(file) => dataset.settings.join_output(file),
+                None => dataset.settings.join_data(name),
+            };
+            let readable = match File::open(&path).and_then(|mut file| file.read(&mut [0u8; 1])) {
+                Ok(_) => true,
+                Err(err) if required => {
+                    warn!(path = %path.display(), %err, "required artefact unreadable");
+                    false
+                }
+                Err(err) => {
+                    debug!(path = %path.display(), %err, "optional artefact unreadable");
+                    false
+                }
+            };
+            ArtefactCheck {
+                name: name.rsplit('/').next().unwrap_or(name),
+                required,
+                status: if readable { "ok" } else { "degraded" },
+            }
+        })
+        .collect();
+    let healthy = artefacts
+        .iter()
+        .all(|check| check.status == "ok" || !check.required);
+    let status = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let body = HealthDto {
+        status: if healthy { "ok" } else { "unavailable" },
+        artefacts,
+    };
+    (status, Json(body))
+}
+
+impl SignalQuery {
+    /// The `drug` and `smq` filters, by descending score.
+    fn filter(&self, vocab: &Vocabulary) -> SignalFilter {
+        SignalFilter {
+            drug_id: self.drug.as_deref().map(|drug| vocab.resolve_drug(drug)),
+            smq: self.smq.clone(),
+            ..SignalFilter::default()
         }
     }
 }
+
+fn load_vocabulary(dataset: &Dataset) -> Result<Arc<Vocabulary>, ApiError> {
+    dataset
+        .cache
+        .vocabulary(&dataset.settings)
+        .map_err(ApiError::from)
+}
+
+fn load_watchlists(dataset: &Dataset) -> Result<Watchlists, ApiError> {
+    Watchlists::load(&dataset.settings).map_err(ApiError::from)
+}
+
+/// Flag the signals that are on any watchlist.
+fn mark_watched(dataset: &Dataset, signals: &mut [SignalDto]) -> Result<(), ApiError> {
+    let watchlists = load_watchlists(dataset)?;
+    let watched = watchlists.watched();
+    for signal in signals {
+        signal.watched = watched.contains(&(signal.drug_id.as_str(), signal.event_id.as_str()));
+    }
+    Ok(())
+}
+
+fn query_signals(dataset: &Dataset, filter: &SignalFilter) -> Result<Vec<SignalDto>, ApiError> {
+    store::query_signals(&dataset.settings, &dataset.cache, filter).map_err(ApiError::from)
+}
@@ -0,0 +1,389 @@
+//! Ranked signals from `signals.csv`, filtered, sorted and limited for the API.
+//!
+//! Each dataset keeps its vocabulary and signals in a [`SignalCache`], so requests filter rows
+//! already in memory; the cache is dropped when the pipeline rewrites the files, and entries
+//! older than `SIGNAL_CACHE_TTL_SECS` are checked against the files' fingerprint in case the
+//! watcher missed the rewrite (network mounts do not report changes). Built with the
+//! `duckdb` feature, the cache holds `signals.csv` as an in-memory DuckDB table instead, and
+//! filters, ordering and limits are pushed down into a query over it, so a request only
+//! materialises the rows it returns. Both paths order ties by drug id, then event id.
+
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+use crate::{
+    api::{etag, names::Vocabulary, types::SignalDto},
+    config::Settings,
+};
+
+/// Sort key, descending.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SignalOrder {
+    #[default]
+    Score,
+    Ror,
+}
+
+impl SignalOrder {
+    /// Descending by the key, ties by drug id and then event id, as the DuckDB query sorts.
+    #[cfg_attr(feature = "duckdb", allow(dead_code))]
+    fn compare(self, a: &SignalDto, b: &SignalDto) -> std::cmp::Ordering {
+        let (a_key, b_key) = match self {
+            Self::Score => (a.score, b.score),
+            Self::Ror => (a.recent_ror, b.recent_ror),
+        };
+        b_key
+            .partial_cmp(&a_key)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.drug_id.cmp(&b.drug_id))
+            .then_with(|| a.event_id.cmp(&b.event_id))
+    }
+}
+
+/// Row selection for [`query_signals`]. Ids are compared case-insensitively.
+#[derive(Debug, Clone, Default)]
+pub struct SignalFilter {
+    pub drug_id: Option<String>,
+    pub event_id: Option<String>,
+    /// Case-insensitive substring of the SMQ label; restricts results to SMQ-level rows.
+    pub smq: Option<String>,
+    pub order: SignalOrder,
+    pub limit: Option<usize>,
+}
+
+impl SignalFilter {
+    #[cfg_attr(feature = "duckdb", allow(dead_code))]
+    fn matches(&self, signal: &SignalDto) -> bool {
+        let same = |id: &str, wanted: &Option<String>| {
+            wanted
+                .as_ref()
+                .is_none_or(|wanted| id.eq_ignore_ascii_case(wanted))
+        };
+        same(&signal.drug_id, &self.drug_id)
+            && same(&signal.event_id, &self.event_id)
+            && self.smq.as_ref().is_none_or(|smq| {
+                !signal.smq.is_empty() && signal.smq.to_lowercase().contains(&smq.to_lowercase())
+            })
+    }
+}
+
+/// A dataset's vocabulary and ranked signals, loaded on first use and kept until
+/// [`SignalCache::invalidate`] or until their input files change.
+#[derive(Debug)]
+pub struct SignalCache {
+    /// How long an entry is served before its inputs are fingerprinted again.
+    ttl: Duration,
+    /// Bumped on every invalidation, so a load that overlapped a rewrite is not kept.
+    generation: AtomicU64,
+    vocab: RwLock<Option<Entry<Vocabulary>>>,
+    signals: RwLock<Option<Entry<Vec<SignalDto>>>>,
+    #[cfg(feature = "duckdb")]
+    signal_table: RwLock<Option<Entry<duck::SignalTable>>>,
+}
+
+#[derive(Debug)]
+struct Entry<T> {
+    /// [`etag::fingerprint`] of the inputs when the value was loaded.
+    fingerprint: String,
+    checked: Instant,
+    value: Arc<T>,
+}
+
+impl SignalCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            generation: AtomicU64::new(0),
+            vocab: RwLock::new(None),
+            signals: RwLock::new(None),
+            #[cfg(feature = "duckdb")]
+            signal_table: RwLock::new(None),
+        }
+    }
+
+    pub fn vocabulary(&self, settings: &Settings) -> Result<Arc<Vocabulary>> {
+        self.cached(&self.vocab, &vocabulary_inputs(settings), || {
+            Vocabulary::load(settings)
+        })
+    }
+
+    /// Every ranked signal in file order; empty before `rank` has run.
+    pub fn signals(&self, settings: &Settings) -> Result<Arc<Vec<SignalDto>>> {
+        let vocab = self.vocabulary(settings)?;
+        let mut inputs = vocabulary_inputs(settings);
+        inputs.push(settings.join_output("signals.csv"));
+        self.cached(&self.signals, &inputs, || load_signals(settings, &vocab))
+    }
+
+    /// `signals.csv` loaded into an in-memory DuckDB table.
+    #[cfg(feature = "duckdb")]
+    fn signal_table(&self, settings: &Settings) -> Result<Arc<duck::SignalTable>> {
+        let path = settings.join_output("signals.csv");
+        self.cached(&self.signal_table, std::slice::from_ref(&path), || {
+            duck::SignalTable::load(&path)
+        })
+    }
+
+    /// Forget everything loaded so far; the next request reads the files again.
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        if let Ok(mut vocab) = self.vocab.write() {
+            *vocab = None;
+        }
+        if let Ok(mut signals) = self.signals.write() {
+            *signals = None;
+        }
+        #[cfg(feature = "duckdb")]
+        if let Ok(mut table) = self.signal_table.write() {
+            *table = None;
+        }
+    }
+
+    fn cached<T>(
+        &self,
+        slot: &RwLock<Option<Entry<T>>>,
+        inputs: &[PathBuf],
+        load: impl FnOnce() -> Result<T>,
+    ) -> Result<Arc<T>> {
+        if let Ok(cached) = slot.read() {
+            if let Some(entry) = cached.as_ref().filter(|e| e.checked.elapsed() < self.ttl) {
+                return Ok(entry.value.clone());
+            }
+        }
+        let started = self.generation.load(Ordering::SeqCst);
+        let fingerprint = etag::fingerprint(inputs);
+        if let Ok(mut cached) = slot.write() {
+            match cached.as_mut() {
+                Some(entry) if entry.fingerprint == fingerprint => {
+                    entry.checked = Instant::now();
+                    return Ok(entry.value.clone());
+                }
+                Some(_) => debug!(?inputs, "inputs changed on disk; reloading"),
+                None => {}
+            }
+        }
+        let value = Arc::new(load()?);
+        // A rewrite during the load may have produced a torn read: serve it, but do not keep it.
+        if let Ok(mut cached) = slot.write() {
+            if self.generation.load(Ordering::SeqCst) == started {
+                *cached = Some(Entry {
+                    fingerprint,
+                    checked: Instant::now(),
+                    value: value.clone(),
+                });
+            }
+        }
+        Ok(value)
+    }
+}
+
+/// Files [`Vocabulary::load`] reads.
+fn vocabulary_inputs(settings: &Settings) -> Vec<PathBuf> {
+    vec![
+        settings.join_data("clean/drugs.parquet"),
+        settings.join_data("clean/events.parquet"),
+    ]
+}
+
+/// Signals matching `filter` in descending `filter.order`; empty before `rank` has run.
+pub fn query_signals(
+    settings: &Settings,
+    cache: &SignalCache,
+    filter: &SignalFilter,
+) -> Result<Vec<SignalDto>> {
+    #[cfg(feature = "duckdb")]
+    {
+        let path = settings.join_output("signals.csv");
+        if !path.exists() {
+            warn!("signals.csv missing; run rank first");
+            return Ok(Vec::new());
+        }
+        let vocab = cache.vocabulary(settings)?;
+        Ok(cache
+            .signal_table(settings)?
+            .query(filter)?
+            .into_iter()
+            .map(|row| row.into_dto(&vocab))
+            .collect())
+    }
+    #[cfg(not(feature = "duckdb"))]
+    {
+        let mut rows: Vec<SignalDto> = cache
+            .signals(settings)?
+            .iter()
+            .filter(|signal| filter.matches(signal))
+            .cloned()
+            .collect();
+        rows.sort_by(|a, b| filter.order.compare(a, b));
+        if let Some(limit) = filter.limit {
+            rows.truncate(limit);
+        }
+        Ok(rows)
+    }
+}
+
+fn load_signals(settings: &Settings, vocab: &Vocabulary) -> Result<Vec<SignalDto>> {
+    let path = settings.join_output("signals.csv");
+    if !path.exists() {
+        warn!("signals.csv missing; run rank first");
+        return Ok(Vec::new());
+    }
+    Ok(read_all(&path)?
+        .into_iter()
+        .map(|row| row.into_dto(vocab))
+        .collect())
+}
+
+fn read_all(path: &std::path::Path) -> Result<Vec<RawSignal>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .from_path(path)?;
+    Ok(reader
+        .deserialize::<RawSignal>()
+        .collect::<Result<_, _>>()?)
+}
+
+#[derive(Debug, Deserialize)]
+struct RawSignal {
+    drug_id: String,
+    event_id: String,
+    year_quarter: String,
+    recent_ror: f64,
+    ci_low: f64,
+    ci_high: f64,
+    lit_support: i64,
+    trend_z: f64,
+    score: f64,
+    #[serde(default)]
+    labeled: bool,
+    #[serde(default)]
+    sider_known: bool,
+    #[serde(default)]
+    smq: String,
+}
+
+impl RawSignal {
+    fn into_dto(self, vocab: &Vocabulary) -> SignalDto {
+        SignalDto {
+            drug_name: vocab.drug_name(&self.drug_id),
+            event_term: vocab.event_term(&self.event_id),
+            drug_id: self.drug_id,
+            event_id: self.event_id,
+            year_quarter: self.year_quarter,
+            recent_ror: self.recent_ror,
+            ci_low: self.ci_low,
+            ci_high: self.ci_high,
+            lit_support: self.lit_support,
+            trend_z: self.trend_z,
+            score: self.score,
+            labeled: self.labeled,
+            sider_known: self.sider_known,
+            smq: self.smq,
+            watched: false,
+        }
+    }
+}
+
+#[cfg(feature = "duckdb")]
+mod duck {
+    use std::{fmt, path::Path, sync::Mutex};
+
+    use anyhow::{anyhow, Result};
+    use duckdb::{params_from_iter, Connection};
+
+    use super::{RawSignal, SignalFilter, SignalOrder};
+
+    /// `signals.csv` copied into an in-memory database, so the pipeline can rewrite the CSV
+    /// (or hold `rwe.duckdb`) while the server queries its copy.
+    pub(super) struct SignalTable(Mutex<Connection>);
+
+    impl fmt::Debug for SignalTable {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("SignalTable")
+        }
+    }
+
+    impl SignalTable {
+        pub(super) fn load(path: &Path) -> Result<Self> {
+            let source = path.to_string_lossy().replace('\'', "''");
+            let conn = Connection::open_in_memory()?;
+            conn.execute_batch(&format!(
+                "CREATE TABLE signals AS SELECT CAST(drug_id AS VARCHAR) AS drug_id, \
+                 CAST(event_id AS VARCHAR) AS event_id, \
+                 CAST(year_quarter AS VARCHAR) AS year_quarter, \
+                 CAST(recent_ror AS DOUBLE) AS recent_ror, CAST(ci_low AS DOUBLE) AS ci_low, \
+                 CAST(ci_high AS DOUBLE) AS ci_high, \
+                 CAST(lit_support AS BIGINT) AS lit_support, \
+                 CAST(trend_z AS DOUBLE) AS trend_z, CAST(score AS DOUBLE) AS score, \
+                 coalesce(CAST(labeled AS BOOLEAN), false) AS labeled, \
+                 coalesce(CAST(sider_known AS BOOLEAN), false) AS sider_known, \
+                 coalesce(CAST(smq AS VARCHAR), '') AS smq \
+                 FROM read_csv_auto('{source}', header = true)"
+            ))?;
+            Ok(Self(Mutex::new(conn)))
+        }
+
+        /// Run the filter as SQL, ordered like [`SignalOrder::compare`].
+        pub(super) fn query(&self, filter: &SignalFilter) -> Result<Vec<RawSignal>> {
+            let mut sql = "SELECT drug_id, event_id, year_quarter, recent_ror, ci_low, ci_high, \
+                           lit_support, trend_z, score, labeled, sider_known, smq \
+                           FROM signals WHERE true"
+                .to_string();
+            let mut params: Vec<String> = Vec::new();
+            if let Some(drug_id) = &filter.drug_id {
+                sql.push_str(" AND lower(drug_id) = lower(?)");
+                params.push(drug_id.clone());
+            }
+            if let Some(event_id) = &filter.event_id {
+                sql.push_str(" AND lower(event_id) = lower(?)");
+                params.push(event_id.clone());
+            }
+            if let Some(smq) = &filter.smq {
+                sql.push_str(" AND smq <> '' AND contains(lower(smq), lower(?))");
+                params.push(smq.clone());
+            }
+            sql.push_str(match filter.order {
+                SignalOrder::Score => " ORDER BY score DESC",
+                SignalOrder::Ror => " ORDER BY recent_ror DESC",
+            });
+            // A fresh database compares strings byte-wise, like Rust's string ordering.
+            sql.push_str(", drug_id, event_id");
+            if let Some(limit) = filter.limit {
+                sql.push_str(&format!(" LIMIT {limit}"));
+            }
+
+            let conn = self
+                .0
+                .lock()
+                .map_err(|_| anyhow!("signal table lock poisoned"))?;
+            let mut statement = conn.prepare(&sql)?;
+            let rows = statement.query_map(params_from_iter(params.iter()), |row| {
+                Ok(RawSignal {
+                    drug_id: row.get(0)?,
+                    event_id: row.get(1)?,
+                    year_quarter: row.get(2)?,
+                    recent_ror: row.get(3)?,
+                    ci_low: row.get(4)?,
+                    ci_high: row.get(5)?,
+                    lit_support: row.get(6)?,
+                    trend_z: row.get(7)?,
+                    score: row.get(8)?,
+                    labeled: row.get(9)?,
+                    sider_known: row.get(10)?,
+                    smq: row.get(11)?,
+                })
+            })?;
+            Ok(rows.collect::<duckdb::Result<_>>()?)
+        }
+    }
+}
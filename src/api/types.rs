@@ -2,10 +2,19 @@
 
 use serde::Serialize;
 
+use crate::{
+    api::annotations::Annotation, data::quality::NormalizeMetrics, nlp::summaries::Evidence,
+    signals::QuarterMetric,
+};
+
 #[derive(Debug, Clone, Serialize)]
 pub struct SignalDto {
     pub drug_id: String,
     pub event_id: String,
+    /// Canonical drug name; absent when `drugs.parquet` does not list the id.
+    pub drug_name: Option<String>,
+    /// Canonical event term; absent for ids missing from `events.parquet` (e.g. SMQs).
+    pub event_term: Option<String>,
     pub year_quarter: String,
     pub recent_ror: f64,
     pub ci_low: f64,
@@ -13,15 +22,94 @@ pub struct SignalDto {
     pub lit_support: i64,
     pub trend_z: f64,
     pub score: f64,
+    pub labeled: bool,
+    pub sider_known: bool,
+    pub smq: String,
+    /// On at least one watchlist.
+    pub watched: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct EventDto {
     pub drug_id: String,
     pub event_id: String,
+    pub drug_name: Option<String>,
+    pub event_term: Option<String>,
     pub year_quarter: String,
     pub recent_ror: f64,
     pub ci_low: f64,
     pub ci_high: f64,
     pub trend_z: f64,
 }
+
+/// Everything known about one drug-event pair.
+#[derive(Debug, Clone, Serialize)]
+pub struct SignalDetailDto {
+    pub drug_id: String,
+    pub event_id: String,
+    pub drug_name: Option<String>,
+    pub event_term: Option<String>,
+    /// Latest ranked row; absent when the pair is not in `signals.csv`.
+    pub signal: Option<SignalDto>,
+    /// Listed in the DailyMed label.
+    pub labeled: bool,
+    /// Known to SIDER.
+    pub sider_known: bool,
+    /// Raw and shrunk ROR with confidence intervals per quarter, oldest first.
+    pub history: Vec<QuarterMetric>,
+    /// Supporting literature sentences, most confident first.
+    pub literature: Vec<Evidence>,
+    /// Reviewer notes, oldest first.
+    pub annotations: Vec<Annotation>,
+}
+
+/// Literature supporting one drug-event pair, one entry per article.
+#[derive(Debug, Clone, Serialize)]
+pub struct EvidenceDto {
+    pub drug_id: String,
+    pub event_id: String,
+    pub drug_name: Option<String>,
+    pub event_term: Option<String>,
+    /// Ordered by each article's most confident sentence.
+    pub articles: Vec<ArticleEvidence>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArticleEvidence {
+    pub pmid: String,
+    /// From the PubMed cache; absent when the article is no longer cached.
+    pub title: Option<String>,
+    pub year: Option<i32>,
+    pub journal: Option<String>,
+    /// Highest relation confidence among the article's sentences.
+    pub confidence: f64,
+    /// Sentences pairing the drug and event, most confident first.
+    pub sentences: Vec<Evidence>,
+}
+
+/// Readiness of the artefacts the API serves.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthDto {
+    /// `ok`, or `unavailable` when a required artefact cannot be read.
+    pub status: &'static str,
+    pub artefacts: Vec<ArtefactCheck>,
+}
+
+/// One artefact's readiness. Paths and IO errors go to the server log only, as `/healthz`
+/// needs no key.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArtefactCheck {
+    /// File name, e.g. `signals.csv`.
+    pub name: &'static str,
+    /// Whether `/healthz` fails without it.
+    pub required: bool,
+    /// `ok`, or `degraded` when the file cannot be read.
+    pub status: &'static str,
+}
+
+/// Latest normalization quality metrics plus every recorded run, oldest first.
+#[derive(Debug, Clone, Serialize)]
+pub struct NormalizeMetricsDto {
+    pub latest: Option<NormalizeMetrics>,
+    pub history: Vec<NormalizeMetrics>,
+}
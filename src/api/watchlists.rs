@@ -0,0 +1,108 @@
+//! Named sets of drug-event pairs that reviewers pin, stored in `data/watchlists.json`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    sync::Mutex,
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Settings;
+
+const WATCHLIST_FILE: &str = "watchlists.json";
+
+/// Serialises read-modify-write cycles from concurrent requests.
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct WatchedPair {
+    pub drug_id: String,
+    pub event_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Watchlist {
+    pub name: String,
+    pub pairs: BTreeSet<WatchedPair>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Every watchlist of a dataset, keyed by name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Watchlists {
+    pub lists: BTreeMap<String, Watchlist>,
+}
+
+impl Watchlists {
+    /// Load `data/watchlists.json`, empty when nothing was saved yet.
+    pub fn load(settings: &Settings) -> Result<Self> {
+        let path = settings.join_data(WATCHLIST_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(&path).with_context(|| format!("read {path:?}"))?;
+        serde_json::from_str(&text).with_context(|| format!("parse {path:?}"))
+    }
+
+    fn save(&self, settings: &Settings) -> Result<()> {
+        let path = settings.join_data(WATCHLIST_FILE);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("write {tmp:?}"))?;
+        std::fs::rename(&tmp, &path).with_context(|| format!("replace {path:?}"))?;
+        Ok(())
+    }
+
+    /// Pairs on any watchlist.
+    pub fn watched(&self) -> HashSet<(&str, &str)> {
+        self.lists
+            .values()
+            .flat_map(|list| &list.pairs)
+            .map(|pair| (pair.drug_id.as_str(), pair.event_id.as_str()))
+            .collect()
+    }
+}
+
+/// Create the watchlist `name`, or replace its pairs. Returns the list and whether it is new.
+pub fn upsert(
+    settings: &Settings,
+    name: &str,
+    pairs: BTreeSet<WatchedPair>,
+) -> Result<(Watchlist, bool)> {
+    let _guard = WRITE_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut watchlists = Watchlists::load(settings)?;
+    let now = Utc::now();
+    let created = !watchlists.lists.contains_key(name);
+    let list = watchlists
+        .lists
+        .entry(name.to_string())
+        .or_insert_with(|| Watchlist {
+            name: name.to_string(),
+            pairs: BTreeSet::new(),
+            created_at: now,
+            updated_at: now,
+        });
+    list.pairs = pairs;
+    list.updated_at = now;
+    let list = list.clone();
+    watchlists.save(settings)?;
+    Ok((list, created))
+}
+
+/// Delete the watchlist `name`; `false` when it did not exist.
+pub fn remove(settings: &Settings, name: &str) -> Result<bool> {
+    let _guard = WRITE_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut watchlists = Watchlists::load(settings)?;
+    if watchlists.lists.remove(name).is_none() {
+        return Ok(false);
+    }
+    watchlists.save(settings)?;
+    Ok(true)
+}
@@ -0,0 +1,67 @@
+//! CLI entry-point for purging cached artefacts selectively.
+
+use anyhow::{bail, Result};
+use clap::Args as ClapArgs;
+use tracing::{info, instrument};
+
+use crate::{
+    config::Settings,
+    data::purge::{self, Target},
+};
+
+/// Args for the `clean` command.
+#[derive(Debug, Clone, ClapArgs)]
+pub struct Args {
+    /// Remove downloaded source files under data/raw (FAERS, PubMed, SIDER, DailyMed, openFDA
+    /// device reports); licensed vocabularies and imported cases are kept.
+    #[arg(long)]
+    pub raw: bool,
+    /// Remove normalised tables under data/clean.
+    #[arg(long)]
+    pub clean: bool,
+    /// Remove ranked signals, summaries and reports in the outputs directory, keeping the
+    /// normalize metrics and schedule histories.
+    #[arg(long)]
+    pub outputs: bool,
+    /// Remove model weights under data/models and the embedding cache.
+    #[arg(long)]
+    pub models: bool,
+    /// Only remove files last modified longer ago than this, e.g. 90d, 12h or 2w.
+    #[arg(long, value_parser = purge::parse_age)]
+    pub older_than: Option<std::time::Duration>,
+    /// List what would be removed without removing anything.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[instrument(skip(settings))]
+pub async fn run(args: Args, settings: Settings) -> Result<()> {
+    let targets: Vec<Target> = [
+        (args.raw, Target::Raw),
+        (args.clean, Target::Clean),
+        (args.outputs, Target::Outputs),
+        (args.models, Target::Models),
+    ]
+    .into_iter()
+    .filter_map(|(selected, target)| selected.then_some(target))
+    .collect();
+    if targets.is_empty() {
+        bail!("nothing selected; pass at least one of --raw, --clean, --outputs or --models");
+    }
+    let report = purge::purge(&settings, &targets, args.older_than, args.dry_run)?;
+    let megabytes = report.bytes as f64 / (1024.0 * 1024.0);
+    if args.dry_run {
+        for path in &report.paths {
+            println!("would remove {}", path.display());
+        }
+        info!(files = report.files, megabytes, "dry run; nothing removed");
+    } else {
+        info!(
+            files = report.files,
+            megabytes,
+            manifest_entries = report.manifest_entries,
+            "removed cached artefacts"
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,29 @@
+//! CLI entry-point for generating shell completion scripts.
+
+use std::io;
+
+use anyhow::Result;
+use clap::{Args as ClapArgs, CommandFactory};
+use clap_complete::Shell;
+
+use super::Cli;
+
+/// Args for the `completions` command.
+#[derive(Debug, Clone, ClapArgs)]
+pub struct Args {
+    /// Shell to generate the script for.
+    #[arg(value_enum)]
+    pub shell: Shell,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    print(args.shell)
+}
+
+/// Write the completion script for `shell` to stdout.
+pub fn print(shell: Shell) -> Result<()> {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, &mut io::stdout());
+    Ok(())
+}
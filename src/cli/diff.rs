@@ -0,0 +1,138 @@
+//! CLI entry-point for comparing two ranking runs, the delta a periodic safety review reads.
+
+use anyhow::Result;
+use clap::{Args as ClapArgs, ValueEnum};
+use tracing::instrument;
+
+use crate::{
+    api::names::Vocabulary,
+    config::Settings,
+    signals::diff::{self, Direction},
+};
+
+/// How the delta is printed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    #[default]
+    Table,
+    Json,
+}
+
+/// Args for the `diff` command.
+#[derive(Debug, Clone, ClapArgs)]
+pub struct Args {
+    /// Earlier run: `previous`, `latest`, an archived run id, a dataset name, a signals.csv
+    /// path or a directory holding one.
+    #[arg(default_value = "previous")]
+    pub before: String,
+    /// Later run, in the same forms.
+    #[arg(default_value = "latest")]
+    pub after: String,
+    /// Score whose crossing is reported, in either direction.
+    #[arg(long, default_value_t = 2.0)]
+    pub threshold: f64,
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    pub format: Format,
+    /// Rows per table section; the JSON always holds every pair.
+    #[arg(long, default_value_t = 20)]
+    pub limit: usize,
+}
+
+#[instrument(skip(settings))]
+pub async fn run(args: Args, settings: Settings) -> Result<()> {
+    let before = diff::resolve_run(&settings, &args.before)?;
+    let after = diff::resolve_run(&settings, &args.after)?;
+    let delta = diff::diff_runs(&before, &after, args.threshold)?;
+    if args.format == Format::Json {
+        println!("{}", serde_json::to_string_pretty(&delta)?);
+        return Ok(());
+    }
+
+    let vocab = Vocabulary::load(&settings)?;
+    let pair = |drug_id: &str, event_id: &str| {
+        format!(
+            "{} / {}",
+            vocab
+                .drug_name(drug_id)
+                .unwrap_or_else(|| drug_id.to_string()),
+            vocab
+                .event_term(event_id)
+                .unwrap_or_else(|| event_id.to_string())
+        )
+    };
+    println!("before: {} ({} pairs)", delta.before, delta.pairs_before);
+    println!("after:  {} ({} pairs)", delta.after, delta.pairs_after);
+
+    section("NEW PAIRS", delta.new_pairs.len(), args.limit);
+    for change in delta.new_pairs.iter().take(args.limit) {
+        println!(
+            "{:<60} {:>8} {:>6}",
+            pair(&change.drug_id, &change.event_id),
+            number(change.score_after),
+            rank(change.rank_after)
+        );
+    }
+    section("DROPPED PAIRS", delta.dropped_pairs.len(), args.limit);
+    for change in delta.dropped_pairs.iter().take(args.limit) {
+        println!(
+            "{:<60} {:>8} {:>6}",
+            pair(&change.drug_id, &change.event_id),
+            number(change.score_before),
+            rank(change.rank_before)
+        );
+    }
+    section("THRESHOLD CROSSINGS", delta.crossings.len(), args.limit);
+    for crossing in delta.crossings.iter().take(args.limit) {
+        let arrow = match crossing.direction {
+            Direction::Above => "above",
+            Direction::Below => "below",
+        };
+        println!(
+            "{:<60} {:<6} {:>8.2} -> {:>8.2} now {arrow} {}",
+            pair(&crossing.drug_id, &crossing.event_id),
+            crossing.metric,
+            crossing.before,
+            crossing.after,
+            crossing.threshold
+        );
+    }
+    section("MOVED", delta.changed.len(), args.limit);
+    for change in delta.changed.iter().take(args.limit) {
+        println!(
+            "{:<60} {:>8} -> {:>8} ({}) rank {} -> {} ({})",
+            pair(&change.drug_id, &change.event_id),
+            number(change.score_before),
+            number(change.score_after),
+            signed(change.score_delta.map(|d| format!("{d:.2}"))),
+            rank(change.rank_before),
+            rank(change.rank_after),
+            signed(change.rank_delta.map(|d| d.to_string()))
+        );
+    }
+    Ok(())
+}
+
+fn section(title: &str, count: usize, limit: usize) {
+    println!();
+    if count > limit {
+        println!("{title} ({count}, first {limit})");
+    } else {
+        println!("{title} ({count})");
+    }
+}
+
+fn number(value: Option<f64>) -> String {
+    value.map_or("-".to_string(), |v| format!("{v:.2}"))
+}
+
+fn rank(value: Option<usize>) -> String {
+    value.map_or("-".to_string(), |r| format!("#{r}"))
+}
+
+fn signed(value: Option<String>) -> String {
+    match value {
+        Some(v) if v.starts_with('-') => v,
+        Some(v) => format!("+{v}"),
+        None => "-".to_string(),
+    }
+}
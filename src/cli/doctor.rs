@@ -0,0 +1,514 @@
+//! CLI entry-point for diagnosing the environment: network reachability, optional models,
+//! directory permissions, disk space and compiled features, each with a remediation hint.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{bail, Result};
+use clap::{Args as ClapArgs, ValueEnum};
+use serde::Serialize;
+use tracing::instrument;
+
+use crate::{
+    config::Settings,
+    data::{dailymed, device, faers, pubmed, rxnorm},
+    nlp::{embeddings::EMBEDDING_MODEL, summaries::SummaryBackend},
+};
+
+/// How long a reachability probe waits for any HTTP response.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Free space below which a warning is raised; one FAERS quarter takes about 1 GB raw and
+/// normalised.
+const LOW_DISK_BYTES: u64 = 10 << 30;
+/// Free space below which the pipeline is likely to fail mid-run.
+const CRITICAL_DISK_BYTES: u64 = 1 << 30;
+/// Directory fastembed downloads models to unless `FASTEMBED_CACHE_PATH` is set.
+const FASTEMBED_DEFAULT_CACHE: &str = ".fastembed_cache";
+
+/// How the report is printed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    #[default]
+    Table,
+    Json,
+}
+
+/// Args for the `doctor` command.
+#[derive(Debug, Clone, ClapArgs)]
+pub struct Args {
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    pub format: Format,
+    /// Skip the network probes, e.g. on a machine that is meant to run offline.
+    #[arg(long)]
+    pub no_network: bool,
+}
+
+/// Outcome of one check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    /// Works, but something is degraded or will be slow.
+    Warn,
+    /// Part of the pipeline cannot run until this is fixed.
+    Fail,
+    /// Not applicable to this build or configuration.
+    Skip,
+}
+
+impl Status {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Warn => "warn",
+            Self::Fail => "FAIL",
+            Self::Skip => "skip",
+        }
+    }
+}
+
+/// One line of the report.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub area: &'static str,
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+    /// What to do about a warning or failure.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn new(area: &'static str, name: impl Into<String>, status: Status, detail: String) -> Self {
+        Self {
+            area,
+            name: name.into(),
+            status,
+            detail,
+            hint: None,
+        }
+    }
+
+    fn hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+#[instrument(skip(settings))]
+pub async fn run(args: Args, settings: Settings) -> Result<()> {
+    let mut checks = Vec::new();
+    checks.extend(network(&settings, args.no_network).await);
+    checks.extend(models(&settings));
+    checks.extend(directories(&settings));
+    checks.extend(disk_space(&settings));
+    checks.extend(features());
+    checks.extend(configuration(&settings));
+
+    match args.format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&checks)?),
+        Format::Table => print_table(&checks)?,
+    }
+    let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+    if failed > 0 {
+        bail!("{failed} checks failed");
+    }
+    Ok(())
+}
+
+fn print_table(checks: &[Check]) -> Result<()> {
+    let mut out = std::io::stdout().lock();
+    writeln!(out, "{:<10} {:<22} {:<6} DETAIL", "AREA", "CHECK", "STATUS")?;
+    for check in checks {
+        writeln!(
+            out,
+            "{:<10} {:<22} {:<6} {}",
+            check.area,
+            check.name,
+            check.status.label(),
+            check.detail
+        )?;
+        if let Some(hint) = &check.hint {
+            writeln!(out, "{:<41}-> {hint}", "")?;
+        }
+    }
+    Ok(())
+}
+
+/// Probe each service the pipeline downloads from; any HTTP response counts as reachable.
+async fn network(settings: &Settings, skip: bool) -> Vec<Check> {
+    let mut services: Vec<(&str, Vec<String>, &str)> = vec![
+        (
+            "FDA FAERS downloads",
+            faers::BASE_URLS
+                .iter()
+                .map(|base| format!("{base}/"))
+                .collect(),
+            "fetch",
+        ),
+        (
+            "NCBI E-utilities",
+            vec![format!("{}/einfo.fcgi", pubmed::EUTILS_BASE)],
+            "fetch --drugs",
+        ),
+        (
+            "RxNav",
+            vec![format!("{}/version.json", rxnorm::RXNAV_BASE)],
+            "normalize",
+        ),
+        (
+            "DailyMed",
+            vec![format!("{}/spls.json?pagesize=1", dailymed::DAILYMED_BASE)],
+            "fetch --labels",
+        ),
+        (
+            "openFDA",
+            vec![format!("{}?limit=1", device::OPENFDA_DEVICE_EVENT)],
+            "fetch --devices",
+        ),
+    ];
+    if settings.summary_backend == SummaryBackend::Openai {
+        services.push((
+            "summary API",
+            vec![format!(
+                "{}/models",
+                settings.summary_api_url.trim_end_matches('/')
+            )],
+            "summarize",
+        ));
+    }
+    if skip || settings.offline {
+        let why = if skip { "--no-network" } else { "offline mode" };
+        return services
+            .into_iter()
+            .map(|(name, _, _)| Check::new("network", name, Status::Skip, why.to_string()))
+            .collect();
+    }
+
+    let client = match reqwest::Client::builder()
+        .timeout(PROBE_TIMEOUT)
+        .user_agent(format!("rwe-assistant/0.1 (+{})", settings.pubmed_email))
+        .build()
+    {
+        Ok(client) => client,
+        Err(err) => {
+            return vec![Check::new(
+                "network",
+                "HTTP client",
+                Status::Fail,
+                err.to_string(),
+            )]
+        }
+    };
+    let probes = services.into_iter().map(|(name, urls, needed_by)| {
+        let client = client.clone();
+        async move {
+            let mut last_error = String::new();
+            for url in &urls {
+                match client.head(url).send().await {
+                    Ok(resp) => {
+                        return Check::new(
+                            "network",
+                            name,
+                            Status::Ok,
+                            format!("{url} answered {}", resp.status()),
+                        )
+                    }
+                    Err(err) => last_error = format!("{url}: {}", probe_error(&err)),
+                }
+            }
+            Check::new("network", name, Status::Fail, last_error).hint(format!(
+                "needed by {needed_by}; check the proxy (HTTPS_PROXY) and firewall, or run with \
+                 --offline on cached data"
+            ))
+        }
+    });
+    futures::future::join_all(probes).await
+}
+
+fn probe_error(err: &reqwest::Error) -> String {
+    if err.is_timeout() {
+        format!("no response within {}s", PROBE_TIMEOUT.as_secs())
+    } else if err.is_connect() {
+        "connection failed (DNS, proxy or firewall)".to_string()
+    } else {
+        err.to_string()
+    }
+}
+
+fn models(settings: &Settings) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let gguf = &settings.llama_model_path;
+    checks.push(match settings.summary_backend {
+        SummaryBackend::Openai => Check::new(
+            "models",
+            "GGUF model",
+            Status::Skip,
+            "SUMMARY_BACKEND=openai".to_string(),
+        ),
+        SummaryBackend::Llama if !cfg!(feature = "summaries") => Check::new(
+            "models",
+            "GGUF model",
+            Status::Warn,
+            "built without the summaries feature; summarize prints a placeholder".to_string(),
+        )
+        .hint("rebuild with --features summaries, or set SUMMARY_BACKEND=openai"),
+        SummaryBackend::Llama if gguf.is_file() => Check::new(
+            "models",
+            "GGUF model",
+            Status::Ok,
+            format!("{} ({})", gguf.display(), human_bytes(file_size(gguf))),
+        ),
+        SummaryBackend::Llama => Check::new(
+            "models",
+            "GGUF model",
+            Status::Warn,
+            format!(
+                "{} not found; summarize prints a placeholder",
+                gguf.display()
+            ),
+        )
+        .hint("download a GGUF model there or point LLAMA_MODEL_PATH at one"),
+    });
+
+    let cache = std::env::var_os("FASTEMBED_CACHE_PATH")
+        .filter(|v| !v.is_empty())
+        .map_or_else(|| PathBuf::from(FASTEMBED_DEFAULT_CACHE), PathBuf::from);
+    let cached = std::fs::read_dir(&cache)
+        .map(|entries| {
+            entries.flatten().any(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .to_lowercase()
+                    .contains(EMBEDDING_MODEL)
+            })
+        })
+        .unwrap_or(false);
+    checks.push(if !cfg!(feature = "embeddings") {
+        Check::new(
+            "models",
+            "embedding model",
+            Status::Skip,
+            "built without the embeddings feature".to_string(),
+        )
+    } else if cached {
+        Check::new(
+            "models",
+            "embedding model",
+            Status::Ok,
+            format!("{} cached in {}", EMBEDDING_MODEL, cache.display()),
+        )
+    } else {
+        Check::new(
+            "models",
+            "embedding model",
+            Status::Warn,
+            format!(
+                "not in {}; embed downloads it on first use",
+                cache.display()
+            ),
+        )
+        .hint("run embed once online, or copy the model cache and set FASTEMBED_CACHE_PATH")
+    });
+    checks
+}
+
+/// Each directory the pipeline writes to must exist and accept a new file.
+fn directories(settings: &Settings) -> Vec<Check> {
+    [
+        ("data", settings.data_dir.clone()),
+        ("raw", settings.join_data("raw")),
+        ("clean", settings.join_data("clean")),
+        ("cache", settings.join_data("cache")),
+        ("outputs", settings.outputs_dir.clone()),
+    ]
+    .into_iter()
+    .map(|(name, dir)| match writable(&dir) {
+        Ok(()) => Check::new(
+            "dirs",
+            name,
+            Status::Ok,
+            format!("{} writable", dir.display()),
+        ),
+        Err(err) => Check::new(
+            "dirs",
+            name,
+            Status::Fail,
+            format!("{}: {err}", dir.display()),
+        )
+        .hint(if name == "outputs" {
+            "fix the owner or permissions (chown/chmod), or set OUTPUTS_DIR"
+        } else {
+            "fix the owner or permissions (chown/chmod), or set DATA_DIR"
+        }),
+    })
+    .collect()
+}
+
+fn writable(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let probe = dir.join(format!(".doctor-{}", std::process::id()));
+    std::fs::write(&probe, b"")?;
+    std::fs::remove_file(&probe)
+}
+
+fn disk_space(settings: &Settings) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let mut seen = Vec::new();
+    for (name, dir) in [
+        ("data volume", &settings.data_dir),
+        ("outputs volume", &settings.outputs_dir),
+    ] {
+        let Ok(free) = fs2::available_space(dir) else {
+            checks.push(Check::new(
+                "disk",
+                name,
+                Status::Skip,
+                format!("cannot read free space of {}", dir.display()),
+            ));
+            continue;
+        };
+        // Data and outputs usually share a volume; report it once.
+        let total = fs2::total_space(dir).unwrap_or(0);
+        if seen.contains(&(free, total)) {
+            continue;
+        }
+        seen.push((free, total));
+        let detail = format!("{} free at {}", human_bytes(free), dir.display());
+        checks.push(if free < CRITICAL_DISK_BYTES {
+            Check::new("disk", name, Status::Fail, detail)
+        } else if free < LOW_DISK_BYTES {
+            Check::new("disk", name, Status::Warn, detail)
+        } else {
+            Check::new("disk", name, Status::Ok, detail)
+        });
+        if let Some(check) = checks.last_mut().filter(|c| c.status != Status::Ok) {
+            check.hint = Some(
+                "free space, e.g. `clean --raw --older-than 180d` once quarters are normalised, \
+                 or move DATA_DIR to a larger volume"
+                    .to_string(),
+            );
+        }
+    }
+    checks
+}
+
+/// Optional features compiled into this binary, and what each enables.
+fn features() -> Vec<Check> {
+    [
+        (
+            "askama",
+            cfg!(feature = "askama"),
+            "report command and /reports pages",
+        ),
+        (
+            "duckdb",
+            cfg!(feature = "duckdb"),
+            "query command and SQL-backed API filters",
+        ),
+        (
+            "embeddings",
+            cfg!(feature = "embeddings"),
+            "embed command (fastembed)",
+        ),
+        (
+            "summaries",
+            cfg!(feature = "summaries"),
+            "local llama.cpp summaries",
+        ),
+    ]
+    .into_iter()
+    .map(|(feature, enabled, enables)| {
+        if enabled {
+            Check::new(
+                "features",
+                feature,
+                Status::Ok,
+                format!("enabled: {enables}"),
+            )
+        } else {
+            Check::new(
+                "features",
+                feature,
+                Status::Skip,
+                format!("disabled: {enables}"),
+            )
+            .hint(format!("rebuild with --features {feature} if you need it"))
+        }
+    })
+    .collect()
+}
+
+fn configuration(settings: &Settings) -> Vec<Check> {
+    let mut checks = vec![match &settings.config_file {
+        Some(path) => Check::new(
+            "config",
+            "config file",
+            Status::Ok,
+            path.display().to_string(),
+        ),
+        None => Check::new(
+            "config",
+            "config file",
+            Status::Skip,
+            "none; environment and .env only".to_string(),
+        ),
+    }];
+    checks.push(if settings.pubmed_email == "research@example.com" {
+        Check::new(
+            "config",
+            "PUBMED_EMAIL",
+            Status::Warn,
+            "still the placeholder address".to_string(),
+        )
+        .hint("set PUBMED_EMAIL to a real contact; NCBI may block anonymous heavy users")
+    } else {
+        Check::new(
+            "config",
+            "PUBMED_EMAIL",
+            Status::Ok,
+            settings.pubmed_email.clone(),
+        )
+    });
+    checks.push(if settings.pubmed_api_key.is_some() {
+        Check::new(
+            "config",
+            "PUBMED_API_KEY",
+            Status::Ok,
+            "set; 10 requests/s".to_string(),
+        )
+    } else {
+        Check::new(
+            "config",
+            "PUBMED_API_KEY",
+            Status::Warn,
+            "unset; 3 requests/s".to_string(),
+        )
+        .hint("request a free NCBI API key to speed up fetch --pubmed about threefold")
+    });
+    checks
+}
+
+fn file_size(path: &Path) -> u64 {
+    path.metadata().map(|meta| meta.len()).unwrap_or(0)
+}
+
+fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
@@ -1,11 +1,41 @@
-//! CLI entry-point for embedding and clustering event terminology.
+//! CLI entry-point for embedding and clustering event and drug terminology.
 
 use anyhow::Result;
+use clap::Args as ClapArgs;
 use tracing::instrument;
 
-use crate::{config::Settings, nlp};
+use crate::{
+    config::Settings,
+    nlp::{
+        self,
+        clustering::{ClusterAlgorithm, ClusterParams},
+    },
+};
+
+/// Args for the `embed` command.
+#[derive(Debug, Clone, ClapArgs)]
+pub struct Args {
+    /// Clustering algorithm for near-duplicate terms.
+    #[arg(long, default_value = "greedy", value_enum)]
+    pub algorithm: ClusterAlgorithm,
+    /// Cosine similarity at which terms merge (greedy and average linkage).
+    #[arg(long, default_value_t = 0.85)]
+    pub threshold: f32,
+    /// Smallest cluster HDBSCAN keeps; smaller groups stay as separate terms.
+    #[arg(long, default_value_t = 2)]
+    pub min_cluster_size: usize,
+    /// Cosine similarity at which drug names merge; drugs of one class embed close together.
+    #[arg(long, default_value_t = 0.97)]
+    pub drug_threshold: f32,
+}
 
 #[instrument(skip(settings))]
-pub async fn run(settings: Settings) -> Result<()> {
-    nlp::build_embeddings(&settings).await
+pub async fn run(args: Args, settings: Settings) -> Result<()> {
+    let params = ClusterParams {
+        algorithm: args.algorithm,
+        threshold: args.threshold,
+        min_cluster_size: args.min_cluster_size,
+        drug_threshold: args.drug_threshold,
+    };
+    nlp::build_embeddings(&settings, &params).await
 }
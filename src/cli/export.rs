@@ -0,0 +1,184 @@
+//! CLI entry-point for exporting ranked signals and metrics for stakeholders.
+
+use std::{fs::File, io::BufWriter, io::Write, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::{Args as ClapArgs, ValueEnum};
+use tracing::{info, instrument};
+
+use crate::{
+    api::names::Vocabulary,
+    config::Settings,
+    data::export::{self, Cell, Filter, Table},
+};
+
+/// Artefact to export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Source {
+    /// Ranked signals from `signals.csv`.
+    Signals,
+    /// Per-quarter disproportionality from `signal_metrics.parquet`.
+    Metrics,
+}
+
+/// File format written by `export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Excel workbook with a filterable header.
+    Xlsx,
+    /// One JSON object per line.
+    Jsonl,
+    /// Zip of every clean artefact and `signals.csv`.
+    Bundle,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Xlsx => "xlsx",
+            Self::Jsonl => "jsonl",
+            Self::Bundle => "zip",
+        }
+    }
+}
+
+/// Args for the `export` command.
+#[derive(Debug, Clone, ClapArgs)]
+pub struct Args {
+    #[arg(long, value_enum)]
+    pub format: Format,
+    #[arg(long, value_enum, default_value_t = Source::Signals)]
+    pub source: Source,
+    /// Output file; defaults to `outputs/exports/<source>.<ext>`.
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+    /// Comma-separated columns to keep, in this order.
+    #[arg(long, value_delimiter = ',')]
+    pub columns: Vec<String>,
+    /// Row condition `column<op>value` with `=`, `!=`, `<`, `<=`, `>` or `>=`; repeatable,
+    /// all must hold.
+    #[arg(long = "filter", value_parser = Filter::parse)]
+    pub filters: Vec<Filter>,
+    /// Only this drug, by id or canonical name.
+    #[arg(long)]
+    pub drug: Option<String>,
+    /// Only this event, by id or canonical term.
+    #[arg(long)]
+    pub event: Option<String>,
+}
+
+#[instrument(skip(settings))]
+pub async fn run(args: Args, settings: Settings) -> Result<()> {
+    let output = match &args.output {
+        Some(path) => path.clone(),
+        None => {
+            let name = match args.format {
+                Format::Bundle => "bundle",
+                _ => match args.source {
+                    Source::Signals => "signals",
+                    Source::Metrics => "signal_metrics",
+                },
+            };
+            settings
+                .join_output("exports")
+                .join(format!("{name}.{}", args.format.extension()))
+        }
+    };
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    if args.format == Format::Bundle {
+        if !args.columns.is_empty()
+            || !args.filters.is_empty()
+            || args.drug.is_some()
+            || args.event.is_some()
+        {
+            bail!("a bundle holds whole artefacts; --columns, --filter, --drug and --event do not apply");
+        }
+        let files = export::write_bundle(&settings, &output)?;
+        info!(path = %output.display(), files, "wrote artefact bundle");
+        return Ok(());
+    }
+
+    let mut table = match args.source {
+        Source::Signals => {
+            let path = settings.join_output("signals.csv");
+            if !path.exists() {
+                bail!("{path:?} does not exist; run rank first");
+            }
+            Table::read_csv(&path)?
+        }
+        Source::Metrics => {
+            let path = settings.join_data("clean/signal_metrics.parquet");
+            if !path.exists() {
+                bail!("{path:?} does not exist; run signal first");
+            }
+            Table::read_parquet(&path)?
+        }
+    };
+    let vocab = Vocabulary::load(&settings)?;
+    add_names(&mut table, &vocab);
+
+    let mut filters = args.filters.clone();
+    if let Some(drug) = &args.drug {
+        filters.push(Filter::parse(&format!(
+            "drug_id={}",
+            vocab.resolve_drug(drug)
+        ))?);
+    }
+    if let Some(event) = &args.event {
+        filters.push(Filter::parse(&format!(
+            "event_id={}",
+            vocab.resolve_event(event)
+        ))?);
+    }
+    table.retain(&filters)?;
+    if !args.columns.is_empty() {
+        table.select(&args.columns)?;
+    }
+
+    match args.format {
+        Format::Xlsx => {
+            let sheet = match args.source {
+                Source::Signals => "signals",
+                Source::Metrics => "signal_metrics",
+            };
+            table.write_xlsx(&output, sheet)?;
+        }
+        Format::Jsonl => {
+            let file = File::create(&output).with_context(|| format!("create {output:?}"))?;
+            let mut writer = BufWriter::new(file);
+            table.write_jsonl(&mut writer)?;
+            writer.flush()?;
+        }
+        Format::Bundle => unreachable!("bundles are written above"),
+    }
+    info!(path = %output.display(), rows = table.rows.len(), columns = table.columns.len(), "exported");
+    Ok(())
+}
+
+/// Canonical drug and event names after their ids, so readers need not look ids up.
+fn add_names(table: &mut Table, vocab: &Vocabulary) {
+    for (id_column, name_column) in [("drug_id", "drug_name"), ("event_id", "event_term")] {
+        if table.columns.iter().any(|column| column == name_column) {
+            continue;
+        }
+        let Ok(index) = table.position(id_column) else {
+            continue;
+        };
+        let names = table
+            .rows
+            .iter()
+            .map(|row| {
+                let name = match &row[index] {
+                    Cell::Text(id) if id_column == "drug_id" => vocab.drug_name(id),
+                    Cell::Text(id) => vocab.event_term(id),
+                    _ => None,
+                };
+                name.map_or(Cell::Null, Cell::Text)
+            })
+            .collect();
+        table.insert_column(index + 1, name_column, names);
+    }
+}
@@ -1,10 +1,16 @@
 //! CLI entry-point for relation extraction.
 
+use std::path::PathBuf;
+
 use anyhow::Result;
 use clap::Args as ClapArgs;
 use tracing::instrument;
 
-use crate::{cli::ExtractMode, config::Settings, nlp};
+use crate::{
+    cli::ExtractMode,
+    config::Settings,
+    nlp::{self, Classifier, ExtractOptions},
+};
 
 /// Args for the `extract` command.
 #[derive(Debug, Clone, ClapArgs)]
@@ -12,9 +18,35 @@ pub struct Args {
     /// Extraction strategy.
     #[arg(long, default_value = "weakly-supervised", value_enum)]
     pub mode: ExtractMode,
+    /// Cross-validation folds for `outputs/extract_metrics.json` (0 or 1 disables evaluation).
+    #[arg(long, default_value_t = 5)]
+    pub folds: usize,
+    /// Gold annotations CSV (`pmid,sent_idx,drug,event,label`) for `--mode supervised`;
+    /// annotated candidates are also left out of `--select-uncertain`.
+    #[arg(long, required_if_eq("mode", "supervised"))]
+    pub labels: Option<PathBuf>,
+    /// Relation classifier trained in the weakly supervised and supervised modes.
+    #[arg(long, default_value = "logistic", value_enum)]
+    pub classifier: Classifier,
+    /// Export the N candidates with probability nearest 0.5 to
+    /// `outputs/annotation_candidates.csv` for labelling.
+    #[arg(long, value_name = "N")]
+    pub select_uncertain: Option<usize>,
+    /// Keep abstracts detected as non-English (flagged in the `language` column of
+    /// `relations.parquet`) instead of dropping them.
+    #[arg(long)]
+    pub keep_non_english: bool,
 }
 
 #[instrument(skip(settings))]
 pub async fn run(args: Args, settings: Settings) -> Result<()> {
-    nlp::extract_relations(&settings, args.mode).await
+    let options = ExtractOptions {
+        mode: args.mode,
+        folds: args.folds,
+        labels: args.labels,
+        classifier: args.classifier,
+        select_uncertain: args.select_uncertain,
+        keep_non_english: args.keep_non_english,
+    };
+    nlp::extract_relations(&settings, &options).await
 }
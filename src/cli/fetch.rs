@@ -1,16 +1,19 @@
 //! CLI entry-point for fetching FAERS and PubMed artefacts.
 
-use std::time::Duration;
-
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
 use clap::Args as ClapArgs;
 use futures::stream::{self, StreamExt};
-use tokio::time::sleep;
 use tracing::{info, instrument};
 
 use crate::{
     config::Settings,
-    data::{self, pubmed::PubRecord},
+    data::{
+        self,
+        pubmed::{PmidIndex, PubRecord, PublicationType, PubmedQuery},
+    },
+    net,
+    signals::trend,
 };
 
 /// Args for the `fetch` sub-command.
@@ -19,12 +22,46 @@ pub struct Args {
     /// Comma separated list of canonical drug names.
     #[arg(long, value_delimiter = ',')]
     pub drugs: Vec<String>,
-    /// FAERS quarters to download (e.g., 2024Q1).
+    /// FAERS quarters to download (e.g., 2024Q1 or a range 2020Q1..2024Q4), or `latest` for
+    /// everything newer than the cache.
     #[arg(long, value_delimiter = ',', default_value = "2024Q1,2024Q2")]
     pub quarters: Vec<String>,
+    /// Download every published quarter from this one onwards (e.g., 2023Q1).
+    #[arg(long, conflicts_with = "quarters")]
+    pub since: Option<String>,
+    /// Download all four quarters of a year or inclusive year range (e.g., 2022-2024).
+    #[arg(long, conflicts_with_all = ["quarters", "since"])]
+    pub years: Option<String>,
     /// Override maximum PubMed abstracts per drug.
     #[arg(long)]
     pub max_pubmed_per_drug: Option<usize>,
+    /// PubMed query template; `{drug}` is replaced by each drug name.
+    #[arg(long, default_value = data::pubmed::DEFAULT_QUERY_TEMPLATE)]
+    pub pubmed_query: String,
+    /// Only search articles published on or after this date (YYYY, YYYY/MM or YYYY/MM/DD).
+    #[arg(long)]
+    pub pubmed_since: Option<String>,
+    /// Only search articles published on or before this date.
+    #[arg(long)]
+    pub pubmed_until: Option<String>,
+    /// Ignore the per-drug PMID index and rebuild the PubMed cache from scratch.
+    #[arg(long)]
+    pub refresh_pubmed: bool,
+    /// Also retrieve open-access PubMed Central full text (results/discussion sections).
+    #[arg(long)]
+    pub pmc: bool,
+    /// Restrict PubMed to these publication types (comma separated).
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub publication_types: Vec<PublicationType>,
+    /// Also download DailyMed product labels for labeledness checks.
+    #[arg(long)]
+    pub labels: bool,
+    /// Also download the SIDER side-effect tables used as the event dictionary.
+    #[arg(long)]
+    pub sider: bool,
+    /// Also fetch openFDA device adverse events (MAUDE) for these device names (comma separated).
+    #[arg(long, value_delimiter = ',')]
+    pub devices: Vec<String>,
 }
 
 #[instrument(skip(settings))]
@@ -33,25 +70,87 @@ pub async fn run(args: Args, settings: Settings) -> Result<()> {
         .max_pubmed_per_drug
         .unwrap_or(settings.max_pubmed_per_drug);
 
-    info!(quarters = ?args.quarters, "fetching FAERS quarters");
-    let _faers_paths = data::faers::fetch_faers_quarters(&args.quarters, &settings).await?;
+    let quarters = resolve_quarters(&args, &settings).await?;
+    info!(?quarters, "fetching FAERS quarters");
+    let _faers_paths = data::faers::fetch_faers_quarters(&quarters, &settings).await?;
+
+    if args.sider {
+        info!("fetching SIDER side-effect tables");
+        data::sider::fetch_sider(&settings).await?;
+    }
+
+    if !args.devices.is_empty() {
+        info!(devices = ?args.devices, "fetching openFDA device events");
+        data::device::fetch_device_events(&args.devices, &quarters, &settings).await?;
+    }
 
+    if args.labels {
+        info!(drugs = ?args.drugs, "fetching DailyMed labels");
+        data::dailymed::fetch_labels(&args.drugs, &settings).await?;
+    }
+
+    let query = PubmedQuery {
+        template: args.pubmed_query.clone(),
+        since: args.pubmed_since.clone(),
+        until: args.pubmed_until.clone(),
+        publication_types: args.publication_types.clone(),
+        added_since: None,
+    };
+    let pmc = args.pmc;
+    let refresh = args.refresh_pubmed;
     let concurrency = 2usize;
     stream::iter(args.drugs.clone())
         .map(|drug| {
             let settings = settings.clone();
+            let mut query = query.clone();
             async move {
-                info!(%drug, "searching pubmed");
-                let pmids = data::pubmed::search_pubmed(&drug, max_pubmed, &settings)
+                if settings.offline {
+                    let path = settings.join_data(format!("raw/pubmed/{drug}.jsonl"));
+                    if !path.exists() {
+                        net::ensure_online(&settings, &format!("PubMed cache {}", path.display()))?;
+                    }
+                    info!(%drug, "offline: using cached pubmed records");
+                    return Ok(());
+                }
+                let mut index = if refresh {
+                    PmidIndex::default()
+                } else {
+                    PmidIndex::load(&drug, &settings)?
+                };
+                query.added_since = index.last_fetched.clone();
+                let started = Utc::now().format("%Y/%m/%d").to_string();
+                info!(%drug, since = ?query.added_since, "searching pubmed");
+                let pmids: Vec<String> =
+                    data::pubmed::search_pubmed(&drug, max_pubmed, &query, &settings)
+                        .await
+                        .with_context(|| format!("search pubmed for {drug}"))?
+                        .into_iter()
+                        .filter(|pmid| !index.pmids.contains(pmid))
+                        .collect();
+                let mut records: Vec<PubRecord> = data::pubmed::fetch_pubmed(&pmids, &settings)
                     .await
-                    .with_context(|| format!("search pubmed for {drug}"))?;
-                sleep(Duration::from_millis(350)).await; // be nice to E-utilities
-                let records: Vec<PubRecord> =
-                    data::pubmed::fetch_pubmed(&pmids, &settings)
+                    .with_context(|| format!("fetch pubmed abstracts for {drug}"))?;
+                if pmc {
+                    let attached = data::pmc::attach_full_text(&mut records, &settings)
                         .await
-                        .with_context(|| format!("fetch pubmed abstracts for {drug}"))?;
-                data::pubmed::persist_records(&drug, &records, &settings)
+                        .with_context(|| format!("fetch PMC full text for {drug}"))?;
+                    info!(%drug, attached, "attached PMC full text");
+                }
+                let append = index.last_fetched.is_some();
+                let path = data::pubmed::persist_records(&drug, &records, append, &settings)
                     .with_context(|| format!("save pubmed records for {drug}"))?;
+                data::manifest::record(
+                    &settings,
+                    &path,
+                    &query.source_url(&drug),
+                    &[],
+                    std::slice::from_ref(&drug),
+                )?;
+                index
+                    .pmids
+                    .extend(records.into_iter().map(|record| record.pmid));
+                index.last_fetched = Some(started);
+                index.save(&drug, &settings)?;
                 Ok::<_, anyhow::Error>(())
             }
         })
@@ -63,3 +162,73 @@ pub async fn run(args: Args, settings: Settings) -> Result<()> {
 
     Ok(())
 }
+
+/// Expand `--since`, `--years`, quarter ranges and `--quarters latest` into concrete quarters.
+async fn resolve_quarters(args: &Args, settings: &Settings) -> Result<Vec<String>> {
+    if let Some(years) = &args.years {
+        return expand_years(years);
+    }
+    if let Some(since) = &args.since {
+        return data::faers::discover_quarters(parse_bound(since)?, settings).await;
+    }
+    if !args
+        .quarters
+        .iter()
+        .any(|q| q.eq_ignore_ascii_case("latest"))
+    {
+        return expand_ranges(&args.quarters);
+    }
+    match data::faers::cached_quarters(settings)?.last() {
+        Some(newest) => {
+            info!(cached = %trend::format_quarter(*newest), "discovering quarters newer than cache");
+            data::faers::discover_quarters(trend::next_quarter(*newest), settings).await
+        }
+        None => Ok(data::faers::latest_published_quarter(settings)
+            .await?
+            .into_iter()
+            .collect()),
+    }
+}
+
+/// Expand `2020Q1..2024Q4` entries into every quarter in the inclusive range.
+fn expand_ranges(specs: &[String]) -> Result<Vec<String>> {
+    let mut quarters = Vec::new();
+    for spec in specs {
+        let Some((from, to)) = spec.split_once("..") else {
+            quarters.push(spec.trim().to_string());
+            continue;
+        };
+        let start = parse_bound(from)?;
+        let end = parse_bound(to)?;
+        if start > end {
+            return Err(anyhow!("quarter range {spec} runs backwards"));
+        }
+        let mut current = start;
+        while current <= end {
+            quarters.push(trend::format_quarter(current));
+            current = trend::next_quarter(current);
+        }
+    }
+    Ok(quarters)
+}
+
+/// Expand `2022-2024` (or a single `2023`) into all quarters of those years.
+fn expand_years(spec: &str) -> Result<Vec<String>> {
+    let (from, to) = spec.split_once('-').unwrap_or((spec, spec));
+    let parse_year = |year: &str| {
+        year.trim()
+            .parse::<i32>()
+            .map_err(|_| anyhow!("invalid --years {spec}; expected e.g. 2022-2024"))
+    };
+    let (start, end) = (parse_year(from)?, parse_year(to)?);
+    if start > end {
+        return Err(anyhow!("year range {spec} runs backwards"));
+    }
+    expand_ranges(&[format!("{start}Q1..{end}Q4")])
+}
+
+fn parse_bound(quarter: &str) -> Result<(i32, u8)> {
+    trend::parse_quarter(&quarter.trim().to_ascii_uppercase())
+        .filter(|(_, q)| (1..=4).contains(q))
+        .ok_or_else(|| anyhow!("invalid quarter {quarter}; expected e.g. 2020Q1"))
+}
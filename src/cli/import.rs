@@ -0,0 +1,77 @@
+//! CLI entry-point for importing third-party safety databases.
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::{Args as ClapArgs, ValueEnum};
+use tracing::{info, instrument};
+
+use crate::{config::Settings, data, signals::trend};
+
+/// Args for the `import` command.
+#[derive(Debug, Clone, ClapArgs)]
+pub struct Args {
+    /// Format of the source export.
+    #[arg(long, value_enum)]
+    pub source: ImportSource,
+    /// File or directory containing the export.
+    #[arg(long)]
+    pub path: PathBuf,
+    /// Quarter assigned to rows without a usable report date (e.g., 2024Q1).
+    #[arg(long, value_parser = parse_quarter)]
+    pub quarter: Option<String>,
+    /// TOML column mapping for `--source custom` (case_id, drug, event, optional date).
+    #[arg(long, required_if_eq("source", "custom"))]
+    pub mapping: Option<PathBuf>,
+}
+
+/// Supported import formats.
+#[derive(Clone, Debug, ValueEnum)]
+pub enum ImportSource {
+    /// WHO VigiBase custom extract (case id, drug, MedDRA PT).
+    Vigibase,
+    /// ICH E2B(R2/R3) ICSR XML files or batches.
+    E2b,
+    /// Any CSV/TSV/XLSX export (e.g., Argus, ArisG) described by a `--mapping` file.
+    Custom,
+}
+
+#[instrument(skip(settings))]
+pub async fn run(args: Args, settings: Settings) -> Result<()> {
+    let written = match args.source {
+        ImportSource::Vigibase => {
+            data::vigibase::import_extract(&args.path, args.quarter.as_deref(), &settings)?
+        }
+        ImportSource::E2b => {
+            data::e2b::import_reports(&args.path, args.quarter.as_deref(), &settings)?
+        }
+        ImportSource::Custom => {
+            let mapping_path = args
+                .mapping
+                .as_deref()
+                .ok_or_else(|| anyhow!("--mapping is required for --source custom"))?;
+            let mapping = data::custom::ColumnMapping::load(mapping_path)?;
+            data::custom::import_export(&args.path, &mapping, args.quarter.as_deref(), &settings)?
+        }
+    };
+    let source = data::manifest::file_source(&args.path);
+    for path in &written {
+        // Raw files are named `<source>_<quarter>.csv`.
+        let quarter = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|stem| stem.rsplit('_').next())
+            .map(str::to_string);
+        data::manifest::record(&settings, path, &source, quarter.as_slice(), &[])?;
+    }
+    info!(files = written.len(), "import complete; run normalize next");
+    Ok(())
+}
+
+/// Accept `2024Q1` (or `2024q1`) and normalise it to upper case.
+fn parse_quarter(quarter: &str) -> Result<String> {
+    trend::parse_quarter(quarter.trim())
+        .filter(|(_, q)| (1..=4).contains(q))
+        .map(trend::format_quarter)
+        .ok_or_else(|| anyhow!("invalid quarter {quarter}; expected YYYYQN, e.g. 2024Q1"))
+}
@@ -1,23 +1,66 @@
 //! Command-line interface wiring for rwe-assistant.
 
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
 use anyhow::Result;
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
 
-use crate::config::Settings;
+use crate::{
+    config::Settings,
+    logging::{LogFormat, LogOptions},
+};
 
+pub mod clean;
+pub mod completions;
+pub mod diff;
+pub mod doctor;
 pub mod embed;
+pub mod export;
 pub mod extract;
 pub mod fetch;
+pub mod import;
 pub mod normalize;
+#[cfg(feature = "duckdb")]
+pub mod query;
 pub mod rank;
+#[cfg(feature = "askama")]
+pub mod report;
+pub mod schedule;
 pub mod serve;
 pub mod signal;
+pub mod status;
 pub mod summarize;
+pub mod validate;
+pub mod watch;
 
 /// Top-level CLI definition.
 #[derive(Debug, Parser)]
 #[command(author, version, about = "Real-world evidence assistant", long_about = None)]
 pub struct Cli {
+    /// Never touch the network; use cached artefacts only (same as `OFFLINE=1`).
+    #[arg(long, global = true)]
+    offline: bool,
+    /// TOML config file layered under environment variables; defaults to a discovered
+    /// `rwe-assistant.toml`.
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Log line layout; overrides `LOG_FORMAT`.
+    #[arg(long, global = true, value_enum)]
+    log_format: Option<LogFormat>,
+    /// Also append logs to this file, rolled over per `LOG_ROTATION`; overrides `LOG_FILE`.
+    #[arg(long, global = true, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+    /// Log more: `-v` for debug events, `-vv` for trace; replaces `RUST_LOG`.
+    #[arg(short, long, global = true, action = ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+    /// Log less: `-q` for warnings and errors only, `-qq` for errors; also hides progress
+    /// bars.
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    quiet: u8,
     #[command(subcommand)]
     command: Commands,
 }
@@ -28,17 +71,80 @@ impl Cli {
         <Self as Parser>::parse()
     }
 
+    /// Shell to print completions for, when that is the selected command; it needs neither
+    /// settings nor logging.
+    pub fn completion_shell(&self) -> Option<Shell> {
+        match &self.command {
+            Commands::Completions(args) => Some(args.shell),
+            _ => None,
+        }
+    }
+
+    /// Config file passed with `--config`.
+    pub fn config(&self) -> Option<&Path> {
+        self.config.as_deref()
+    }
+
+    /// Logging configured by `settings`, with the CLI flags taking precedence.
+    pub fn log_options(&self, settings: &Settings) -> LogOptions {
+        LogOptions {
+            format: self.log_format.unwrap_or(settings.log_format),
+            file: self.log_file.clone().or_else(|| settings.log_file.clone()),
+            rotation: settings.log_rotation,
+            verbosity: (i16::from(self.verbose) - i16::from(self.quiet))
+                .clamp(i8::MIN.into(), i8::MAX.into()) as i8,
+        }
+    }
+
+    /// The global logging flags given, to pass on to child processes.
+    fn logging_args(&self) -> Vec<OsString> {
+        let mut args = Vec::new();
+        if let Some(format) = self
+            .log_format
+            .and_then(|format| format.to_possible_value())
+        {
+            args.extend(["--log-format".into(), format.get_name().into()]);
+        }
+        if let Some(file) = &self.log_file {
+            args.extend(["--log-file".into(), file.into()]);
+        }
+        args.extend(std::iter::repeat_n("--verbose".into(), self.verbose.into()));
+        args.extend(std::iter::repeat_n("--quiet".into(), self.quiet.into()));
+        args
+    }
+
+    /// Whether `-q` was given.
+    pub fn quiet(&self) -> bool {
+        self.quiet > 0
+    }
+
     /// Dispatch the selected sub-command.
-    pub async fn dispatch(self, settings: Settings) -> Result<()> {
+    pub async fn dispatch(self, mut settings: Settings) -> Result<()> {
+        settings.offline |= self.offline;
+        let logging_args = self.logging_args();
         match self.command {
             Commands::Fetch(args) => fetch::run(args, settings).await,
-            Commands::Normalize => normalize::run(settings).await,
+            Commands::Import(args) => import::run(args, settings).await,
+            Commands::Normalize(args) => normalize::run(args, settings).await,
             Commands::Extract(args) => extract::run(args, settings).await,
-            Commands::Embed => embed::run(settings).await,
+            Commands::Embed(args) => embed::run(args, settings).await,
             Commands::Signal => signal::run(settings).await,
             Commands::Rank => rank::run(settings).await,
             Commands::Serve(args) => serve::run(args, settings).await,
             Commands::Summarize(args) => summarize::run(args, settings).await,
+            Commands::Status => status::run(settings).await,
+            Commands::Doctor(args) => doctor::run(args, settings).await,
+            Commands::Validate(args) => validate::run(args, settings).await,
+            Commands::Clean(args) => clean::run(args, settings).await,
+            Commands::Watch(args) => watch::run(args, settings).await,
+            Commands::Schedule(args) => schedule::run(args, settings, logging_args).await,
+            Commands::Export(args) => export::run(args, settings).await,
+            Commands::Diff(args) => diff::run(args, settings).await,
+            #[cfg(feature = "duckdb")]
+            Commands::Query(args) => query::run(args, settings).await,
+            #[cfg(feature = "askama")]
+            Commands::Report(args) => report::run(args, settings).await,
+            Commands::Completions(args) => completions::run(args),
         }
     }
 }
@@ -48,12 +154,14 @@ impl Cli {
 pub enum Commands {
     /// Download FAERS and PubMed artefacts.
     Fetch(fetch::Args),
+    /// Import cases from an external safety database export.
+    Import(import::Args),
     /// Canonicalise terminology and aggregate counts.
-    Normalize,
+    Normalize(normalize::Args),
     /// Run relation extraction over PubMed abstracts.
     Extract(extract::Args),
-    /// Build embeddings for deduplication.
-    Embed,
+    /// Cluster near-duplicate events and drugs by embedding.
+    Embed(embed::Args),
     /// Compute disproportionality and trend metrics.
     Signal,
     /// Rank safety signals.
@@ -62,6 +170,32 @@ pub enum Commands {
     Serve(serve::Args),
     /// Produce optional local summaries.
     Summarize(summarize::Args),
+    /// Show artefact freshness and the provenance of cached artefacts.
+    Status,
+    /// Check network access, models, directories, disk space and features, with fixes.
+    Doctor(doctor::Args),
+    /// Check artefact invariants and print a JSON report; fails on violations.
+    Validate(validate::Args),
+    /// Purge cached raw files, clean tables, outputs or models, optionally by age.
+    Clean(clean::Args),
+    /// Export signals or metrics as a formatted workbook or JSON lines, or bundle all clean
+    /// artefacts into a zip.
+    Export(export::Args),
+    /// Compare two ranking runs: new and dropped pairs, score and rank moves, threshold
+    /// crossings.
+    Diff(diff::Args),
+    /// Run SQL over the clean parquet files and signals.csv in the DuckDB store.
+    #[cfg(feature = "duckdb")]
+    Query(query::Args),
+    /// Re-run normalize, signal and rank when new FAERS quarters or raw files appear.
+    Watch(watch::Args),
+    /// Run commands on a cron expression, recording each run and never overlapping runs.
+    Schedule(schedule::Args),
+    /// Write a standalone safety dossier of one drug as HTML, Markdown or PDF.
+    #[cfg(feature = "askama")]
+    Report(report::Args),
+    /// Print a shell completion script for bash, zsh, fish, powershell or elvish.
+    Completions(completions::Args),
 }
 
 /// Operation mode for extraction.
@@ -71,10 +205,12 @@ pub enum ExtractMode {
     WeaklySupervised,
     /// Skip training and use pattern-only predictions.
     PatternsOnly,
+    /// Train the `--classifier` model on gold annotations from `--labels`.
+    Supervised,
 }
 
 impl ExtractMode {
     pub fn is_training(&self) -> bool {
-        matches!(self, Self::WeaklySupervised)
+        matches!(self, Self::WeaklySupervised | Self::Supervised)
     }
 }
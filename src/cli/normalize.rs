@@ -1,12 +1,57 @@
 //! CLI entry-point for terminology normalization.
 
 use anyhow::Result;
+use clap::Args as ClapArgs;
 use tracing::instrument;
 
-use crate::{config::Settings, data};
+use crate::{
+    config::Settings,
+    data,
+    data::normalize::{DrugRole, EventBackend, NormalizeOptions},
+};
+
+/// Args for the `normalize` command.
+#[derive(Debug, Clone, ClapArgs)]
+pub struct Args {
+    /// Also compute SMQ-level counts from MedDRA files in `data/raw/meddra`.
+    #[arg(long)]
+    pub smq: bool,
+    /// Event normalization backend.
+    #[arg(long, default_value = "heuristic", value_enum)]
+    pub event_backend: EventBackend,
+    /// FAERS drug roles counted as exposures (ps, ss, c, i); concomitants are excluded by default.
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "ps,ss")]
+    pub drug_roles: Vec<DrugRole>,
+    /// Only normalize quarters that are new or whose input files changed since the last run,
+    /// reusing earlier name mappings.
+    #[arg(long)]
+    pub incremental: bool,
+    /// Collapse events clustered by `embed` onto their representatives before counting.
+    #[arg(long, conflicts_with = "incremental")]
+    pub merge_clusters: bool,
+    /// Collapse drugs clustered by `embed` (at its stricter `--drug-threshold`) as well.
+    #[arg(long, conflicts_with = "incremental")]
+    pub merge_drug_clusters: bool,
+    /// Build one table across all quarters, counting each follow-up case once (latest version).
+    #[arg(long, conflicts_with = "incremental")]
+    pub cumulative: bool,
+    /// Count the device reports fetched with `fetch --devices` instead of drug reports.
+    #[arg(long)]
+    pub devices: bool,
+}
 
 #[instrument(skip(settings))]
-pub async fn run(settings: Settings) -> Result<()> {
-    data::normalize::canonicalise(&settings).await?;
+pub async fn run(args: Args, settings: Settings) -> Result<()> {
+    let options = NormalizeOptions {
+        smq: args.smq,
+        event_backend: args.event_backend,
+        drug_roles: args.drug_roles,
+        incremental: args.incremental,
+        merge_clusters: args.merge_clusters,
+        merge_drug_clusters: args.merge_drug_clusters,
+        cumulative: args.cumulative,
+        devices: args.devices,
+    };
+    data::normalize::canonicalise(&settings, &options).await?;
     Ok(())
 }
@@ -0,0 +1,147 @@
+//! CLI entry-point for ad-hoc SQL over the pipeline artefacts.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    path::PathBuf,
+};
+
+use anyhow::{bail, Context, Result};
+use clap::{Args as ClapArgs, ValueEnum};
+use tracing::{info, instrument};
+
+use crate::{
+    config::Settings,
+    data::{
+        export::{Cell, Table},
+        store::DuckStore,
+    },
+};
+
+/// Widest a terminal table column grows before values are cut.
+const MAX_COLUMN_WIDTH: usize = 40;
+
+/// How the result is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Aligned columns for the terminal.
+    #[default]
+    Table,
+    Csv,
+    Jsonl,
+    /// Excel workbook; needs `--output`.
+    Xlsx,
+}
+
+/// Args for the `query` command.
+#[derive(Debug, Clone, ClapArgs)]
+pub struct Args {
+    /// SQL to run, or `-` to read it from stdin.
+    pub sql: String,
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    pub format: Format,
+    /// Write the result to this file instead of stdout.
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[instrument(skip(settings))]
+pub async fn run(args: Args, settings: Settings) -> Result<()> {
+    let sql = if args.sql == "-" {
+        let mut sql = String::new();
+        io::stdin().read_to_string(&mut sql)?;
+        sql
+    } else {
+        args.sql.clone()
+    };
+    if sql.trim().is_empty() {
+        bail!("no SQL given");
+    }
+
+    let store = DuckStore::open(&settings)?;
+    let views = store.register_views(&settings)?;
+    info!(?views, "registered views");
+    let table = store.query(&sql)?;
+
+    if args.format == Format::Xlsx {
+        let Some(output) = &args.output else {
+            bail!("--format xlsx needs --output");
+        };
+        table.write_xlsx(output, "query")?;
+    } else {
+        let mut writer: Box<dyn Write> = match &args.output {
+            Some(path) => Box::new(BufWriter::new(
+                File::create(path).with_context(|| format!("create {path:?}"))?,
+            )),
+            None => Box::new(io::stdout().lock()),
+        };
+        match args.format {
+            Format::Table => write_table(&table, &mut writer)?,
+            Format::Csv => table.write_csv(&mut writer)?,
+            Format::Jsonl => table.write_jsonl(&mut writer)?,
+            Format::Xlsx => unreachable!("workbooks are written above"),
+        }
+        writer.flush()?;
+    }
+    if let Some(output) = &args.output {
+        info!(path = %output.display(), rows = table.rows.len(), "wrote query result");
+    }
+    Ok(())
+}
+
+/// Columns padded to their widest value, numbers right-aligned, then a row count.
+fn write_table<W: Write>(table: &Table, writer: &mut W) -> Result<()> {
+    let cells: Vec<Vec<String>> = table
+        .rows
+        .iter()
+        .map(|row| row.iter().map(|cell| clip(&cell.to_string())).collect())
+        .collect();
+    let widths: Vec<usize> = table
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(index, column)| {
+            cells
+                .iter()
+                .map(|row| row[index].chars().count())
+                .chain([clip(column).chars().count()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let header: Vec<String> = table
+        .columns
+        .iter()
+        .zip(&widths)
+        .map(|(column, width)| format!("{:<width$}", clip(column)))
+        .collect();
+    writeln!(writer, "{}", header.join("  ").trim_end())?;
+    for (row, values) in table.rows.iter().zip(&cells) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(values)
+            .zip(&widths)
+            .map(|((cell, value), width)| match cell {
+                Cell::Int(_) | Cell::Float(_) => format!("{value:>width$}"),
+                _ => format!("{value:<width$}"),
+            })
+            .collect();
+        writeln!(writer, "{}", line.join("  ").trim_end())?;
+    }
+    writeln!(
+        writer,
+        "({} row{})",
+        table.rows.len(),
+        if table.rows.len() == 1 { "" } else { "s" }
+    )?;
+    Ok(())
+}
+
+fn clip(value: &str) -> String {
+    if value.chars().count() <= MAX_COLUMN_WIDTH {
+        return value.to_string();
+    }
+    let mut clipped: String = value.chars().take(MAX_COLUMN_WIDTH - 1).collect();
+    clipped.push('…');
+    clipped
+}
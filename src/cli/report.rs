@@ -0,0 +1,200 @@
+//! CLI entry-point for writing a standalone safety dossier of one drug.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use askama::Template;
+use clap::{Args as ClapArgs, ValueEnum};
+use tracing::{debug, info, instrument, warn};
+
+use crate::{
+    api::{report, store::SignalCache},
+    config::Settings,
+    nlp::{self, summaries},
+};
+
+/// File format of the dossier.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    #[default]
+    Html,
+    Md,
+    /// HTML printed to PDF by `REPORT_PDF_COMMAND`, `wkhtmltopdf` or headless Chromium.
+    Pdf,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Md => "md",
+            Self::Pdf => "pdf",
+        }
+    }
+}
+
+/// Args for the `report` command.
+#[derive(Debug, Clone, ClapArgs)]
+pub struct Args {
+    /// Drug id or canonical name.
+    #[arg(long)]
+    pub drug: String,
+    #[arg(long, value_enum, default_value_t = Format::Html)]
+    pub format: Format,
+    /// Output file; defaults to `outputs/reports/<drug>.<ext>`.
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+    /// Summarise the literature of detailed events that have no saved summary yet, with the
+    /// configured summary backend.
+    #[arg(long)]
+    pub summarize: bool,
+    /// Relations per summary prompt with `--summarize`.
+    #[arg(long, default_value_t = 5)]
+    pub topk: usize,
+}
+
+#[instrument(skip(settings))]
+pub async fn run(args: Args, settings: Settings) -> Result<()> {
+    // A one-off run reads each artefact once; there is nothing to revalidate.
+    let cache = SignalCache::new(Duration::ZERO);
+    let Some(mut dossier) = report::build(&settings, &cache, &args.drug, "default")? else {
+        bail!("no ranked signals for {:?}; run rank first", args.drug);
+    };
+    if args.summarize {
+        let mut generated = 0;
+        for event in dossier.events.iter().filter(|event| {
+            event.detailed && event.summary.is_none() && !event.citations.is_empty()
+        }) {
+            let summary =
+                nlp::summarize(&settings, &dossier.drug_name, &event.event_term, args.topk).await?;
+            // Placeholders must not replace a real summary kept for reports.
+            if summary.claims.is_empty() {
+                continue;
+            }
+            summaries::save_summary(&settings, &summary)?;
+            generated += 1;
+        }
+        if generated > 0 {
+            info!(generated, "saved new summaries");
+            dossier = report::build(&settings, &cache, &args.drug, "default")?
+                .context("signals disappeared while summarising")?;
+        }
+    }
+
+    let output = args.output.clone().unwrap_or_else(|| {
+        settings.join_output("reports").join(format!(
+            "{}.{}",
+            file_stem(&dossier.drug_name),
+            args.format.extension()
+        ))
+    });
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    match args.format {
+        Format::Html => std::fs::write(&output, dossier.render()?)
+            .with_context(|| format!("write {output:?}"))?,
+        Format::Md => std::fs::write(&output, dossier.markdown()?)
+            .with_context(|| format!("write {output:?}"))?,
+        Format::Pdf => {
+            let html = output.with_extension("html");
+            std::fs::write(&html, dossier.render()?).with_context(|| format!("write {html:?}"))?;
+            let printed = print_pdf(&settings, &html, &output);
+            if let Err(err) = std::fs::remove_file(&html) {
+                warn!(path = %html.display(), %err, "could not remove intermediate HTML");
+            }
+            printed?;
+        }
+    }
+    info!(
+        path = %output.display(),
+        events = dossier.events.len(),
+        "wrote report"
+    );
+    Ok(())
+}
+
+/// Lower-case name with anything but letters, digits and dashes replaced by `_`.
+fn file_stem(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Print `html` to `pdf` with the configured command, else the first converter on `PATH`.
+fn print_pdf(settings: &Settings, html: &Path, pdf: &Path) -> Result<()> {
+    if let Some(template) = &settings.report_pdf_command {
+        // Split before substituting, so paths with spaces stay one argument.
+        let (input, output) = (html.display().to_string(), pdf.display().to_string());
+        let mut words = template
+            .split_whitespace()
+            .map(|word| word.replace("{input}", &input).replace("{output}", &output));
+        let program = words.next().context("REPORT_PDF_COMMAND is empty")?;
+        return run_converter(Command::new(program).args(words));
+    }
+    // Chromium resolves relative paths against its own profile, not the working directory.
+    let html = &html
+        .canonicalize()
+        .with_context(|| format!("resolve {html:?}"))?;
+    let pdf = &std::env::current_dir()?.join(pdf);
+    let print_to = format!("--print-to-pdf={}", pdf.display());
+    let candidates: [(&str, Vec<&std::ffi::OsStr>); 4] = [
+        ("wkhtmltopdf", vec![html.as_os_str(), pdf.as_os_str()]),
+        ("chromium", chrome_args(html, &print_to)),
+        ("chromium-browser", chrome_args(html, &print_to)),
+        ("google-chrome", chrome_args(html, &print_to)),
+    ];
+    for (program, args) in candidates {
+        match Command::new(program).args(args).output() {
+            Ok(output) => {
+                debug!(program, "printing report to PDF");
+                return check(program, output);
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err).with_context(|| format!("run {program}")),
+        }
+    }
+    bail!(
+        "no PDF converter found; install wkhtmltopdf or Chromium, set REPORT_PDF_COMMAND, \
+         or use --format html and print from a browser"
+    )
+}
+
+fn chrome_args<'a>(html: &'a Path, print_to: &'a str) -> Vec<&'a std::ffi::OsStr> {
+    vec![
+        "--headless".as_ref(),
+        "--disable-gpu".as_ref(),
+        "--no-pdf-header-footer".as_ref(),
+        print_to.as_ref(),
+        html.as_os_str(),
+    ]
+}
+
+fn run_converter(command: &mut Command) -> Result<()> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command.output().with_context(|| format!("run {program}"))?;
+    check(&program, output)
+}
+
+fn check(program: &str, output: std::process::Output) -> Result<()> {
+    if !output.status.success() {
+        bail!(
+            "{program} failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,339 @@
+//! CLI entry-point for running rwe-assistant commands on a cron schedule, so deployments need
+//! no external scheduler for periodic refreshes.
+
+use std::{
+    ffi::OsString,
+    fs::{File, OpenOptions},
+    future::Future,
+    io::Write,
+    path::PathBuf,
+    pin::Pin,
+    str::FromStr,
+    time::Instant,
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use clap::{Args as ClapArgs, Parser};
+use cron::Schedule;
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use tracing::{error, info, instrument, warn};
+
+use crate::{
+    api,
+    cli::{Cli, Commands},
+    config::Settings,
+};
+
+/// Run history, one JSON object per scheduled run, under the outputs directory.
+pub const HISTORY_FILE: &str = "schedule_history.jsonl";
+/// Held while a scheduled command runs, so two schedulers never run at once.
+const LOCK_FILE: &str = ".schedule.lock";
+/// Separates commands run one after another, as in `-- fetch ';' normalize`.
+const STAGE_SEPARATOR: &str = ";";
+
+/// Args for the `schedule` command.
+#[derive(Debug, Clone, ClapArgs)]
+pub struct Args {
+    /// Cron expression: `minute hour day-of-month month day-of-week`, or six fields with
+    /// seconds first. Day-of-week counts from Sunday = 0, as in crontab.
+    #[arg(long, value_parser = parse_cron)]
+    pub cron: Schedule,
+    /// Read the expression in UTC instead of local time.
+    #[arg(long)]
+    pub utc: bool,
+    /// Also run once at start, before the first scheduled time.
+    #[arg(long)]
+    pub run_now: bool,
+    /// Command to run after `--`, e.g. `-- fetch --quarters latest`; separate several
+    /// commands, run in order until one fails, with `';'`.
+    #[arg(last = true, required = true, num_args = 1..)]
+    pub command: Vec<String>,
+}
+
+/// How a scheduled run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Succeeded,
+    Failed,
+    /// Not started because the previous run was still going.
+    Skipped,
+}
+
+/// One line of the run history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// Time the run was due.
+    pub scheduled: DateTime<Utc>,
+    pub started: Option<DateTime<Utc>>,
+    pub finished: Option<DateTime<Utc>>,
+    pub command: String,
+    pub outcome: Outcome,
+    /// Exit code of the command that ended the run.
+    pub exit_code: Option<i32>,
+    pub detail: Option<String>,
+}
+
+/// Parse a five-field crontab expression, or the six- and seven-field form with seconds
+/// (and years) of the `cron` crate.
+pub fn parse_cron(expression: &str) -> Result<Schedule> {
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    let full = match fields.as_slice() {
+        [minute, hour, day, month, weekday] => {
+            format!(
+                "0 {minute} {hour} {day} {month} {}",
+                crontab_weekdays(weekday)?
+            )
+        }
+        [_, _, _, _, _, _] | [_, _, _, _, _, _, _] => fields.join(" "),
+        _ => bail!("cron expression {expression:?} needs 5 fields (minute hour day month weekday)"),
+    };
+    Schedule::from_str(&full).with_context(|| format!("invalid cron expression {expression:?}"))
+}
+
+/// Rewrite crontab day numbers (0 or 7 = Sunday) as names, since the `cron` crate numbers
+/// days from Sunday = 1.
+fn crontab_weekdays(field: &str) -> Result<String> {
+    let parts = field
+        .split(',')
+        .map(|part| {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (range, Some(step)),
+                None => (part, None),
+            };
+            let range = match range.split_once('-') {
+                // Sunday to Sunday is the whole week.
+                Some(("0", "7")) => "SUN-SAT".to_string(),
+                // A range ending on Sunday = 7 wraps past Saturday.
+                Some((from, "7")) if step.is_none() => format!("{}-SAT,SUN", day_name(from)?),
+                Some((from, to)) => format!("{}-{}", day_name(from)?, day_name(to)?),
+                None => day_name(range)?.to_string(),
+            };
+            Ok(match step {
+                Some(step) => format!("{range}/{step}"),
+                None => range,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(parts.join(","))
+}
+
+/// Name of a crontab day number, or `day` itself when it is already a name.
+fn day_name(day: &str) -> Result<&str> {
+    const NAMES: [&str; 8] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT", "SUN"];
+    match day.parse::<usize>() {
+        Ok(n) if n < NAMES.len() => Ok(NAMES[n]),
+        Ok(_) => bail!("day of week {day:?} is not 0-7"),
+        Err(_) => Ok(day),
+    }
+}
+
+/// First time after `after` the schedule fires, reading it in local time unless `utc`.
+pub fn next_after(schedule: &Schedule, after: DateTime<Utc>, utc: bool) -> Option<DateTime<Utc>> {
+    if utc {
+        schedule.after(&after).next()
+    } else {
+        schedule
+            .after(&after.with_timezone(&Local))
+            .next()
+            .map(|time| time.with_timezone(&Utc))
+    }
+}
+
+/// Run the schedule until Ctrl-C or SIGTERM. `logging_args` are the global logging flags
+/// this process was started with, passed on to every command it runs.
+#[instrument(skip(settings))]
+pub async fn run(args: Args, settings: Settings, logging_args: Vec<OsString>) -> Result<()> {
+    let stages = stages(&args.command)?;
+    let command = args.command.join(" ");
+    let lock_path = settings.join_data(LOCK_FILE);
+    // A fresh deployment has neither directory until the first command creates it.
+    for dir in [settings.join_data(""), settings.join_output("")] {
+        std::fs::create_dir_all(&dir).with_context(|| format!("create {dir:?}"))?;
+    }
+    let shutdown = api::shutdown_signal();
+    tokio::pin!(shutdown);
+
+    let mut due = if args.run_now {
+        Some(Utc::now())
+    } else {
+        next_after(&args.cron, Utc::now(), args.utc)
+    };
+    while let Some(scheduled) = due {
+        info!(%command, next = %scheduled.with_timezone(&Local), "waiting for next run");
+        let wait = (scheduled - Utc::now()).to_std().unwrap_or_default();
+        tokio::select! {
+            () = shutdown.as_mut() => break,
+            () = tokio::time::sleep(wait) => {}
+        }
+
+        let mut record = RunRecord {
+            scheduled,
+            started: None,
+            finished: None,
+            command: command.clone(),
+            outcome: Outcome::Skipped,
+            exit_code: None,
+            detail: None,
+        };
+        let lock = File::create(&lock_path).with_context(|| format!("create {lock_path:?}"))?;
+        let mut stopping = false;
+        if lock.try_lock_exclusive().is_err() {
+            warn!(%command, "another scheduled run is still going; skipping this one");
+            record.detail = Some(format!("{} is held by another run", lock_path.display()));
+        } else {
+            info!(%command, "starting scheduled run");
+            let clock = Instant::now();
+            record.started = Some(Utc::now());
+            record.outcome = Outcome::Succeeded;
+            stopping = run_stages(
+                &settings,
+                &stages,
+                &logging_args,
+                &mut record,
+                shutdown.as_mut(),
+            )
+            .await?;
+            record.finished = Some(Utc::now());
+            if record.outcome == Outcome::Succeeded {
+                info!(%command, elapsed = ?clock.elapsed(), "scheduled run succeeded");
+            } else {
+                error!(
+                    %command,
+                    exit_code = ?record.exit_code,
+                    detail = ?record.detail,
+                    "scheduled run failed"
+                );
+            }
+        }
+        // Dropping the file releases the lock.
+        drop(lock);
+        append_history(&settings, &record);
+        if stopping {
+            break;
+        }
+
+        // Times that passed while this run was going are not caught up, so runs never overlap.
+        let now = Utc::now();
+        let mut next = next_after(&args.cron, scheduled, args.utc);
+        while let Some(missed) = next.filter(|time| *time <= now) {
+            warn!(%command, scheduled = %missed, "previous run overran this time; skipping it");
+            append_history(
+                &settings,
+                &RunRecord {
+                    scheduled: missed,
+                    started: None,
+                    finished: None,
+                    command: command.clone(),
+                    outcome: Outcome::Skipped,
+                    exit_code: None,
+                    detail: Some("previous run still going".to_string()),
+                },
+            );
+            next = next_after(&args.cron, missed, args.utc);
+        }
+        due = next;
+    }
+    info!("scheduler stopped");
+    Ok(())
+}
+
+/// Split the trailing arguments into commands and check each parses and terminates.
+fn stages(command: &[String]) -> Result<Vec<Vec<String>>> {
+    let stages: Vec<Vec<String>> = command
+        .split(|arg| arg == STAGE_SEPARATOR)
+        .filter(|stage| !stage.is_empty())
+        .map(<[String]>::to_vec)
+        .collect();
+    if stages.is_empty() {
+        bail!("no command to schedule; pass one after `--`");
+    }
+    for stage in &stages {
+        let cli = Cli::try_parse_from(
+            std::iter::once("rwe-assistant").chain(stage.iter().map(String::as_str)),
+        )
+        .map_err(|err| anyhow::anyhow!("invalid scheduled command {:?}: {err}", stage.join(" ")))?;
+        if matches!(
+            cli.command,
+            Commands::Serve(_) | Commands::Watch(_) | Commands::Schedule(_)
+        ) {
+            bail!(
+                "`{}` never exits, so it cannot be scheduled",
+                stage.join(" ")
+            );
+        }
+    }
+    Ok(stages)
+}
+
+/// Run each stage as a child process until one fails; returns whether shutdown was requested,
+/// which lets the current stage finish but starts no further ones.
+///
+/// On Unix each child runs in its own process group, so a Ctrl-C in the terminal reaches
+/// only the scheduler and the running command is not interrupted.
+async fn run_stages(
+    settings: &Settings,
+    stages: &[Vec<String>],
+    logging_args: &[OsString],
+    record: &mut RunRecord,
+    mut shutdown: Pin<&mut impl Future<Output = ()>>,
+) -> Result<bool> {
+    let exe = std::env::current_exe().context("locate the rwe-assistant executable")?;
+    let mut stopping = false;
+    for stage in stages {
+        if stopping {
+            record.outcome = Outcome::Failed;
+            record.detail = Some(format!("shutdown before `{}`", stage.join(" ")));
+            break;
+        }
+        let mut child = Command::new(&exe);
+        child.args(logging_args).args(stage).kill_on_drop(false);
+        #[cfg(unix)]
+        child.process_group(0);
+        // Children read the same configuration as this process.
+        if let Some(config) = &settings.config_file {
+            child.env("RWE_CONFIG", config);
+        }
+        if settings.offline {
+            child.env("OFFLINE", "1");
+        }
+        let mut child = child
+            .spawn()
+            .with_context(|| format!("start {}", exe.display()))?;
+        let status = tokio::select! {
+            status = child.wait() => status?,
+            () = shutdown.as_mut() => {
+                stopping = true;
+                info!(command = %stage.join(" "), "waiting for the running command to finish");
+                child.wait().await?
+            }
+        };
+        record.exit_code = status.code();
+        if !status.success() {
+            record.outcome = Outcome::Failed;
+            record.detail = Some(format!("`{}` exited with {status}", stage.join(" ")));
+            break;
+        }
+    }
+    Ok(stopping)
+}
+
+/// Append `record` to the history; a failure is logged, as it is no reason to stop scheduling.
+fn append_history(settings: &Settings, record: &RunRecord) {
+    let path: PathBuf = settings.join_output(HISTORY_FILE);
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("open {path:?}"))
+        .and_then(|mut file| {
+            writeln!(file, "{}", serde_json::to_string(record)?)
+                .with_context(|| format!("write {path:?}"))
+        });
+    if let Err(err) = written {
+        error!(error = %format!("{err:#}"), "could not record the scheduled run");
+    }
+}
@@ -1,5 +1,7 @@
 //! CLI entry-point for serving the HTTP API and static UI.
 
+use std::path::PathBuf;
+
 use anyhow::Result;
 use clap::Args as ClapArgs;
 use tracing::instrument;
@@ -15,9 +17,19 @@ pub struct Args {
     /// Host address, defaults to localhost.
     #[arg(long, default_value = "127.0.0.1")]
     pub host: String,
+    /// PEM certificate chain for HTTPS (overrides `TLS_CERT_PATH`).
+    #[arg(long, requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+    /// PEM private key for HTTPS (overrides `TLS_KEY_PATH`).
+    #[arg(long, requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
 }
 
 #[instrument(skip(settings))]
-pub async fn run(args: Args, settings: Settings) -> Result<()> {
+pub async fn run(args: Args, mut settings: Settings) -> Result<()> {
+    if let (Some(cert), Some(key)) = (args.tls_cert, args.tls_key) {
+        settings.tls_cert_path = Some(cert);
+        settings.tls_key_path = Some(key);
+    }
     api::serve(settings, args.host, args.port).await
 }
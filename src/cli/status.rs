@@ -0,0 +1,254 @@
+//! CLI entry-point for reporting how fresh the pipeline artefacts are and where cached raw
+//! artefacts came from.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use polars::prelude::{ParquetReader, SerReader};
+use tracing::{instrument, warn};
+
+use crate::{
+    config::Settings,
+    data::{
+        faers,
+        manifest::Manifest,
+        normalize::{DEVICE_SOURCE_DIR, RAW_SOURCE_DIRS},
+    },
+};
+
+/// One pipeline artefact, the stage that writes it and the artefacts it is built from.
+struct Artefact {
+    name: &'static str,
+    /// Relative to the data directory, or to the outputs directory with an `outputs/` prefix.
+    paths: &'static [&'static str],
+    stage: &'static str,
+    inputs: &'static [&'static str],
+}
+
+/// Upstream artefacts come first, so inputs are always resolved before their consumers.
+const PIPELINE: &[Artefact] = &[
+    Artefact {
+        name: "raw quarters",
+        paths: RAW_SOURCE_DIRS,
+        stage: "fetch/import",
+        inputs: &[],
+    },
+    Artefact {
+        name: "raw devices",
+        paths: &[DEVICE_SOURCE_DIR],
+        stage: "fetch",
+        inputs: &[],
+    },
+    Artefact {
+        name: "raw pubmed",
+        paths: &["raw/pubmed"],
+        stage: "fetch",
+        inputs: &[],
+    },
+    Artefact {
+        name: "faers_norm",
+        paths: &["clean/faers_norm.parquet"],
+        stage: "normalize",
+        inputs: &["raw quarters"],
+    },
+    // Mentions are matched against the drugs and events `normalize` writes with faers_norm.
+    Artefact {
+        name: "relations",
+        paths: &["clean/relations.parquet"],
+        stage: "extract",
+        inputs: &["raw pubmed", "faers_norm"],
+    },
+    Artefact {
+        name: "signal_metrics",
+        paths: &["clean/signal_metrics.parquet"],
+        stage: "signal",
+        inputs: &["faers_norm"],
+    },
+    Artefact {
+        name: "signals.csv",
+        paths: &["outputs/signals.csv"],
+        stage: "rank",
+        inputs: &["signal_metrics", "relations"],
+    },
+];
+
+/// What `status` found for one artefact.
+struct Freshness {
+    /// Newest modification time of its files; `None` when none exist.
+    modified: Option<SystemTime>,
+    /// Why the artefact needs its stage re-run, if it does.
+    stale: Option<String>,
+}
+
+#[instrument(skip(settings))]
+pub async fn run(settings: Settings) -> Result<()> {
+    print_freshness(&settings)?;
+    println!();
+    print_provenance(&settings)
+}
+
+fn print_freshness(settings: &Settings) -> Result<()> {
+    println!(
+        "{:<16} {:>14} {:<20} {:<14} STATUS",
+        "ARTEFACT", "COUNT", "MODIFIED", "STAGE"
+    );
+    let mut seen: HashMap<&str, Freshness> = HashMap::new();
+    for artefact in PIPELINE {
+        let files = files(settings, artefact)?;
+        let modified = files
+            .iter()
+            .filter_map(|path| path.metadata().and_then(|meta| meta.modified()).ok())
+            .max();
+        let count = if modified.is_none() {
+            "-".to_string()
+        } else {
+            count(settings, artefact, &files)?
+        };
+        let stale = modified.and_then(|modified| {
+            artefact.inputs.iter().find_map(|input| {
+                let upstream = &seen[input];
+                if upstream.stale.is_some() {
+                    Some(format!("{input} is stale"))
+                } else if upstream.modified.is_some_and(|time| time > modified) {
+                    Some(format!("{input} is newer"))
+                } else {
+                    None
+                }
+            })
+        });
+        let status = match (&modified, &stale) {
+            (None, _) => "missing".to_string(),
+            (Some(_), Some(reason)) => format!("stale: {reason}; re-run {}", artefact.stage),
+            (Some(_), None) => "ok".to_string(),
+        };
+        println!(
+            "{:<16} {:>14} {:<20} {:<14} {}",
+            artefact.name,
+            count,
+            modified.map_or("-".to_string(), format_time),
+            artefact.stage,
+            status
+        );
+        seen.insert(artefact.name, Freshness { modified, stale });
+    }
+    Ok(())
+}
+
+/// Existing files of the artefact; directories contribute their files, not subdirectories.
+fn files(settings: &Settings, artefact: &Artefact) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for relative in artefact.paths {
+        let path = match relative.strip_prefix("outputs/") {
+            Some(file) => settings.join_output(file),
+            None => settings.join_data(relative),
+        };
+        if path.is_dir() {
+            for entry in std::fs::read_dir(&path).with_context(|| format!("read {path:?}"))? {
+                let entry = entry?.path();
+                if entry.is_file() && !is_bookkeeping(&entry) {
+                    files.push(entry);
+                }
+            }
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Index files written next to the data, which do not change its content.
+fn is_bookkeeping(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(".index.json"))
+}
+
+/// Quarters for raw cases, records for PubMed, rows for tables.
+fn count(settings: &Settings, artefact: &Artefact, files: &[PathBuf]) -> Result<String> {
+    Ok(match artefact.name {
+        "raw quarters" => format!("{} quarters", faers::cached_quarters(settings)?.len()),
+        "raw pubmed" => {
+            let mut records = 0;
+            for path in files.iter().filter(|p| has_extension(p, "jsonl")) {
+                records += line_count(path)?;
+            }
+            format!("{records} records")
+        }
+        _ => {
+            let mut rows = 0;
+            for path in files {
+                rows += if has_extension(path, "parquet") {
+                    ParquetReader::new(File::open(path)?)
+                        .num_rows()
+                        .with_context(|| format!("read {path:?}"))?
+                } else {
+                    // Minus the CSV header.
+                    line_count(path)?.saturating_sub(1)
+                };
+            }
+            format!("{rows} rows")
+        }
+    })
+}
+
+fn format_time(time: SystemTime) -> String {
+    DateTime::<Local>::from(time)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some(extension)
+}
+
+fn line_count(path: &Path) -> Result<usize> {
+    let file = File::open(path).with_context(|| format!("open {path:?}"))?;
+    let mut lines = 0;
+    for line in BufReader::new(file).lines() {
+        if !line?.trim().is_empty() {
+            lines += 1;
+        }
+    }
+    Ok(lines)
+}
+
+fn print_provenance(settings: &Settings) -> Result<()> {
+    let manifest = Manifest::load(settings)?;
+    if manifest.artefacts.is_empty() {
+        warn!("data/manifest.json has no artefacts yet; run fetch or import first");
+        return Ok(());
+    }
+
+    println!(
+        "{:<44} {:>12} {:<20} {:<16} {:<24} SOURCE",
+        "ARTEFACT", "BYTES", "RETRIEVED", "SHA256", "COVERS"
+    );
+    for (path, record) in &manifest.artefacts {
+        let covers = record
+            .quarters
+            .iter()
+            .chain(&record.drugs)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(",");
+        let exists = settings.join_data(path).exists();
+        println!(
+            "{:<44} {:>12} {:<20} {:<16} {:<24} {}{}",
+            path,
+            record.size_bytes,
+            record.retrieved_at.format("%Y-%m-%d %H:%M:%S"),
+            &record.sha256[..record.sha256.len().min(16)],
+            covers,
+            record.source,
+            if exists { "" } else { " (missing)" }
+        );
+    }
+    Ok(())
+}
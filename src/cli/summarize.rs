@@ -1,10 +1,13 @@
-//! CLI entry-point for generating optional local summaries.
+//! CLI entry-point for generating structured evidence summaries.
 
 use anyhow::Result;
 use clap::Args as ClapArgs;
 use tracing::{info, instrument};
 
-use crate::{config::Settings, nlp};
+use crate::{
+    config::Settings,
+    nlp::{self, summaries},
+};
 
 /// Args for the `summarize` command.
 #[derive(Debug, Clone, ClapArgs)]
@@ -23,7 +26,16 @@ pub struct Args {
 #[instrument(skip(settings))]
 pub async fn run(args: Args, settings: Settings) -> Result<()> {
     let summary = nlp::summarize(&settings, &args.drug, &args.event, args.topk).await?;
-    info!(%summary, "generated summary");
-    println!("{summary}");
+    info!(
+        claims = summary.claims.len(),
+        verified = ?summary.verified,
+        "generated summary"
+    );
+    println!("{}", serde_json::to_string_pretty(&summary)?);
+    // Placeholders must not replace a real summary kept for reports.
+    if !summary.claims.is_empty() {
+        let path = summaries::save_summary(&settings, &summary)?;
+        info!(path = %path.display(), "saved summary");
+    }
     Ok(())
 }
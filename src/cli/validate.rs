@@ -0,0 +1,46 @@
+//! CLI entry-point for checking pipeline artefacts against their invariants.
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::Args as ClapArgs;
+use tracing::{info, instrument, warn};
+
+use crate::{config::Settings, data::validation};
+
+/// Args for the `validate` command.
+#[derive(Debug, Clone, ClapArgs)]
+pub struct Args {
+    /// Also write the JSON report to this file.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+}
+
+#[instrument(skip(settings))]
+pub async fn run(args: Args, settings: Settings) -> Result<()> {
+    let report = validation::validate(&settings)?;
+    let json = serde_json::to_string_pretty(&report)?;
+    println!("{json}");
+    if let Some(path) = &args.output {
+        std::fs::write(path, &json).with_context(|| format!("write {path:?}"))?;
+        info!(path = %path.display(), "wrote validation report");
+    }
+    for check in &report.checks {
+        if let Some(reason) = &check.skipped {
+            warn!(check = check.check, artefact = check.artefact, %reason, "check skipped");
+        } else if check.violations > 0 {
+            warn!(
+                check = check.check,
+                artefact = check.artefact,
+                violations = check.violations,
+                rows = check.rows_checked,
+                "invariant violated"
+            );
+        }
+    }
+    if !report.passed {
+        bail!("{} invariant violations", report.violations);
+    }
+    info!(checks = report.checks.len(), "all invariants hold");
+    Ok(())
+}
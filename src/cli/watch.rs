@@ -0,0 +1,257 @@
+//! CLI entry-point for re-running the pipeline when new FAERS quarters are published or raw
+//! case files change.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{Context, Result};
+use clap::Args as ClapArgs;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, instrument, warn};
+
+use crate::{
+    api,
+    config::Settings,
+    data::{
+        self,
+        normalize::{DrugRole, EventBackend, NormalizeOptions, RAW_SOURCE_DIRS},
+        purge,
+    },
+    signals::{self, trend},
+};
+
+/// Args for the `watch` command.
+#[derive(Debug, Clone, ClapArgs)]
+pub struct Args {
+    /// How often to look for newly published FAERS quarters, e.g. 30m, 6h or 1d.
+    #[arg(long, default_value = "1h", value_parser = purge::parse_age)]
+    pub interval: Duration,
+    /// Seconds without further file changes before re-running, so copies can finish.
+    #[arg(long, default_value_t = 30)]
+    pub debounce_secs: u64,
+    /// Never download quarters; only react to files placed under data/raw.
+    #[arg(long)]
+    pub no_fetch: bool,
+    /// Also compute SMQ-level counts, as `normalize --smq`.
+    #[arg(long)]
+    pub smq: bool,
+    /// Event normalization backend, as for `normalize`.
+    #[arg(long, default_value = "heuristic", value_enum)]
+    pub event_backend: EventBackend,
+    /// FAERS drug roles counted as exposures, as for `normalize`.
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "ps,ss")]
+    pub drug_roles: Vec<DrugRole>,
+}
+
+/// Size and modification time of every raw case file, by path.
+type Snapshot = BTreeMap<PathBuf, (u64, SystemTime)>;
+
+/// Raw files that appeared, changed or disappeared between two snapshots.
+#[derive(Debug, Default)]
+struct Changes {
+    added: Vec<PathBuf>,
+    modified: Vec<PathBuf>,
+    removed: Vec<PathBuf>,
+}
+
+impl Changes {
+    fn between(before: &Snapshot, after: &Snapshot) -> Self {
+        let mut changes = Self::default();
+        for (path, stat) in after {
+            match before.get(path) {
+                None => changes.added.push(path.clone()),
+                Some(old) if old != stat => changes.modified.push(path.clone()),
+                Some(_) => {}
+            }
+        }
+        changes.removed = before
+            .keys()
+            .filter(|path| !after.contains_key(*path))
+            .cloned()
+            .collect();
+        changes
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.modified.is_empty() && self.removed.is_empty()
+    }
+}
+
+#[instrument(skip(settings))]
+pub async fn run(args: Args, settings: Settings) -> Result<()> {
+    let (events, mut changed) = mpsc::unbounded_channel();
+    let _watcher = watch_raw(&settings, events)?;
+    let mut snapshot = snapshot(&settings)?;
+    info!(
+        files = snapshot.len(),
+        interval = ?args.interval,
+        "watching for new quarters and raw files"
+    );
+
+    // Catch up with files that arrived while nothing was watching.
+    let norm = settings.join_data("clean/faers_norm.parquet");
+    let built = norm.metadata().and_then(|meta| meta.modified()).ok();
+    let behind = snapshot
+        .values()
+        .any(|(_, modified)| built.is_none_or(|built| *modified > built));
+    if behind && !snapshot.is_empty() {
+        info!("raw files are newer than faers_norm.parquet; re-running the pipeline");
+        if let Err(err) = rerun(&settings, &args, false).await {
+            error!(error = %format!("{err:#}"), "pipeline run failed");
+        }
+    }
+
+    let mut ticker = tokio::time::interval(args.interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let shutdown = api::shutdown_signal();
+    tokio::pin!(shutdown);
+    loop {
+        tokio::select! {
+            () = &mut shutdown => break,
+            _ = ticker.tick() => {
+                if let Err(err) = fetch_new_quarters(&settings, &args).await {
+                    warn!(error = %format!("{err:#}"), "could not check for new quarters");
+                }
+            }
+            Some(()) = changed.recv() => {
+                let quiet = Duration::from_secs(args.debounce_secs);
+                while let Ok(Some(())) = tokio::time::timeout(quiet, changed.recv()).await {}
+            }
+        }
+        let Some(current) = snapshot_or_warn(&settings) else {
+            continue;
+        };
+        let changes = Changes::between(&snapshot, &current);
+        if changes.is_empty() {
+            debug!("no raw changes");
+            continue;
+        }
+        for path in &changes.added {
+            info!(path = %path.display(), "new raw file");
+        }
+        for path in &changes.modified {
+            info!(path = %path.display(), "raw file changed");
+        }
+        for path in &changes.removed {
+            info!(path = %path.display(), "raw file removed");
+        }
+        // Incremental runs recount new and rewritten quarters; removals need a full run.
+        let incremental = changes.removed.is_empty() && norm.exists();
+        match rerun(&settings, &args, incremental).await {
+            Ok(()) => snapshot = current,
+            // The old snapshot stays, so the next check retries.
+            Err(err) => error!(error = %format!("{err:#}"), "pipeline run failed"),
+        }
+    }
+    info!("stopped watching");
+    Ok(())
+}
+
+/// Download quarters published since the newest cached one.
+async fn fetch_new_quarters(settings: &Settings, args: &Args) -> Result<()> {
+    if args.no_fetch || settings.offline {
+        return Ok(());
+    }
+    let quarters = match data::faers::cached_quarters(settings)?.last() {
+        Some(newest) => {
+            data::faers::discover_quarters(trend::next_quarter(*newest), settings).await?
+        }
+        None => data::faers::latest_published_quarter(settings)
+            .await?
+            .into_iter()
+            .collect(),
+    };
+    if quarters.is_empty() {
+        debug!("no new FAERS quarters published");
+        return Ok(());
+    }
+    info!(?quarters, "new FAERS quarters published; fetching");
+    data::faers::fetch_faers_quarters(&quarters, settings).await?;
+    Ok(())
+}
+
+/// `normalize`, `signal` and `rank`, in order.
+async fn rerun(settings: &Settings, args: &Args, incremental: bool) -> Result<()> {
+    let started = Instant::now();
+    let options = NormalizeOptions {
+        smq: args.smq,
+        event_backend: args.event_backend,
+        drug_roles: args.drug_roles.clone(),
+        incremental,
+        ..NormalizeOptions::default()
+    };
+    info!(incremental, "normalizing");
+    data::normalize::canonicalise(settings, &options).await?;
+    info!("computing signals");
+    signals::compute(settings).await?;
+    info!("ranking signals");
+    signals::rank(settings).await?;
+    info!(elapsed = ?started.elapsed(), "pipeline up to date");
+    Ok(())
+}
+
+fn snapshot_or_warn(settings: &Settings) -> Option<Snapshot> {
+    match snapshot(settings) {
+        Ok(snapshot) => Some(snapshot),
+        Err(err) => {
+            warn!(error = %format!("{err:#}"), "could not list raw files");
+            None
+        }
+    }
+}
+
+fn snapshot(settings: &Settings) -> Result<Snapshot> {
+    let mut snapshot = Snapshot::new();
+    for dir in RAW_SOURCE_DIRS {
+        collect(&settings.join_data(dir), &mut snapshot)?;
+    }
+    Ok(snapshot)
+}
+
+fn collect(path: &Path, snapshot: &mut Snapshot) -> Result<()> {
+    if path.is_dir() {
+        for entry in std::fs::read_dir(path).with_context(|| format!("read {path:?}"))? {
+            collect(&entry?.path(), snapshot)?;
+        }
+    } else if path.is_file() && !is_partial(path) {
+        let meta = path.metadata().with_context(|| format!("stat {path:?}"))?;
+        snapshot.insert(
+            path.to_path_buf(),
+            (
+                meta.len(),
+                meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            ),
+        );
+    }
+    Ok(())
+}
+
+/// Downloads in progress and hidden files, which are not case data yet.
+fn is_partial(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.') || name.ends_with(".part"))
+}
+
+/// Signal `events` whenever a file under `data/raw` changes; stops when dropped.
+fn watch_raw(settings: &Settings, events: mpsc::UnboundedSender<()>) -> Result<RecommendedWatcher> {
+    let root = settings.join_data("raw");
+    std::fs::create_dir_all(&root).with_context(|| format!("create {root:?}"))?;
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+            Ok(event) if matches!(event.kind, EventKind::Access(_)) => {}
+            Ok(event) if event.paths.iter().all(|path| is_partial(path)) => {}
+            Ok(_) => {
+                let _ = events.send(());
+            }
+            Err(err) => warn!(%err, "file watcher error"),
+        })?;
+    watcher
+        .watch(&root, RecursiveMode::Recursive)
+        .with_context(|| format!("watch {root:?}"))?;
+    Ok(watcher)
+}
@@ -1,35 +1,269 @@
 //! Runtime configuration utilities for rwe-assistant.
 
 use std::{
+    collections::{BTreeMap, HashMap},
     env,
     path::{Path, PathBuf},
 };
 
-use anyhow::Context;
+use anyhow::{bail, Context};
+use clap::ValueEnum;
 use serde::Deserialize;
 
-/// Application configuration resolved from `.env` and defaults.
+use crate::{
+    data::pubmed::PublicationType,
+    logging::{LogFormat, LogRotation},
+    nlp::{
+        embeddings::EmbeddingProvider,
+        summaries::{SamplingParams, SummaryBackend},
+    },
+};
+
+/// Config file looked up in the working directory, then in the user config directory.
+pub const CONFIG_FILE_NAME: &str = "rwe-assistant.toml";
+
+/// Every environment variable `Settings::load` reads; a config file may set any of them.
+pub const CONFIG_KEYS: &[&str] = &[
+    "PUBMED_EMAIL",
+    "PUBMED_TOOL",
+    "PUBMED_API_KEY",
+    "MAX_PUBMED_PER_DRUG",
+    "DATA_DIR",
+    "OUTPUTS_DIR",
+    "DRUG_DICTIONARY",
+    "DRUG_NAME_RULES",
+    "EVENT_DICTIONARY",
+    "EVENT_MATCH_THRESHOLD",
+    "RXNORM_APPROX_MIN_SCORE",
+    "UMLS_API_KEY",
+    "OPENFDA_API_KEY",
+    "OFFLINE",
+    "STUDY_TYPE_WEIGHTS",
+    "SCORE_LITERATURE_WEIGHT",
+    "SCORE_TREND_WEIGHT",
+    "SPECULATIVE_WEIGHT",
+    "CONCLUSIONS_WEIGHT",
+    "EMBEDDING_PROVIDERS",
+    "EMBEDDING_BATCH_SIZE",
+    "SUMMARY_BACKEND",
+    "SUMMARY_API_URL",
+    "SUMMARY_MODEL",
+    "SUMMARY_API_KEY",
+    "SUMMARY_TEMPERATURE",
+    "SUMMARY_TOP_P",
+    "SUMMARY_TOP_K",
+    "SUMMARY_MAX_TOKENS",
+    "SUMMARY_SEED",
+    "LLAMA_MODEL_PATH",
+    "LLAMA_CONTEXT_LENGTH",
+    "LLAMA_CHAT_TEMPLATE",
+    "API_KEYS",
+    "API_KEYS_FILE",
+    "API_ANONYMOUS_READ",
+    "RATE_LIMIT_PER_SECOND",
+    "RATE_LIMIT_BURST",
+    "TLS_CERT_PATH",
+    "TLS_KEY_PATH",
+    "REQUEST_TIMEOUT_SECS",
+    "READ_TIMEOUT_SECS",
+    "MAX_BODY_BYTES",
+    "SIGNAL_CACHE_TTL_SECS",
+    "DATASETS",
+    "REPORT_PDF_COMMAND",
+    "LOG_FORMAT",
+    "LOG_FILE",
+    "LOG_ROTATION",
+];
+
+/// Variables holding comma-separated `name=value` pairs, written as a table in the file.
+const PAIR_KEYS: &[&str] = &["STUDY_TYPE_WEIGHTS", "DATASETS"];
+
+/// Application configuration resolved from the environment, `.env`, an optional
+/// `rwe-assistant.toml` and defaults.
 #[derive(Debug, Clone, Deserialize)]
 pub struct Settings {
     /// Contact email for PubMed E-utilities courtesy policy.
     pub pubmed_email: String,
     /// Tool name sent with PubMed requests.
     pub pubmed_tool: String,
+    /// NCBI API key; raises the E-utilities rate limit from 3 to 10 requests per second.
+    pub pubmed_api_key: Option<String>,
     /// Maximum abstracts fetched per drug.
     pub max_pubmed_per_drug: usize,
     /// Root folder for cached data artefacts.
     pub data_dir: PathBuf,
     /// Root folder for analytic outputs.
     pub outputs_dir: PathBuf,
+    /// Optional `raw name, canonical ingredient` CSV/TSV consulted before built-in drug maps.
+    pub drug_dictionary: Option<PathBuf>,
+    /// Optional TOML rules extending the built-in salt/strength/formulation stripping.
+    pub drug_name_rules: Option<PathBuf>,
+    /// Optional event dictionary (canonical term plus synonyms per line, or a MedDRA PT list).
+    pub event_dictionary: Option<PathBuf>,
+    /// Jaro-Winkler similarity above which a raw event term is fuzzily matched to the
+    /// dictionary; unset, event terms match only exactly or after normalization.
+    pub event_match_threshold: Option<f64>,
+    /// Minimum RxNav `approximateTerm` score accepted when exact drug lookup misses.
+    pub rxnorm_approx_min_score: f64,
+    /// UTS API key used by the UMLS event backend when no local MRCONSO is installed.
+    pub umls_api_key: Option<String>,
+    /// openFDA API key; optional, raises the daily request quota for device event queries.
+    pub openfda_api_key: Option<String>,
+    /// Disable all network access and rely on cached artefacts only.
+    pub offline: bool,
+    /// Multiplier applied to literature evidence by the study design of its source.
+    pub study_type_weights: StudyTypeWeights,
+    /// Weights combining disproportionality, literature and trend into the ranking score.
+    pub score_weights: ScoreWeights,
+    /// ONNX Runtime execution providers for embeddings, in order of preference.
+    pub embedding_providers: Vec<EmbeddingProvider>,
+    /// Texts per embedding batch; larger batches keep GPUs busy.
+    pub embedding_batch_size: usize,
+    /// Language model used by `summarize`.
+    pub summary_backend: SummaryBackend,
+    /// Base URL of the OpenAI-compatible API (up to `/v1`) for the `openai` backend.
+    pub summary_api_url: String,
+    /// Model name sent to the OpenAI-compatible API.
+    pub summary_model: Option<String>,
+    /// Bearer token for the OpenAI-compatible API; local servers usually need none.
+    pub summary_api_key: Option<String>,
+    /// Sampling parameters for either summary backend.
+    pub summary_sampling: SamplingParams,
+    /// GGUF model loaded by the `llama` backend.
+    pub llama_model_path: PathBuf,
+    /// llama.cpp context window in tokens; must hold the prompt and the generated summary.
+    pub llama_context_length: u32,
+    /// Chat template name known to llama.cpp (`chatml`, `llama3`, ...) or a template string;
+    /// `None` uses the template embedded in the GGUF file.
+    pub llama_chat_template: Option<String>,
+    /// Keys accepted by the API, from `API_KEYS` and `API_KEYS_FILE`; empty disables auth.
+    pub api_keys: Vec<String>,
+    /// Let requests without a key read (`GET`/`HEAD`) when keys are configured.
+    pub api_anonymous_read: bool,
+    /// Requests per second each client IP regains; 0 disables rate limiting.
+    pub rate_limit_per_second: u64,
+    /// Requests a client IP may make in a burst before being throttled.
+    pub rate_limit_burst: u32,
+    /// PEM certificate chain; with `tls_key_path`, `serve` speaks HTTPS.
+    pub tls_cert_path: Option<PathBuf>,
+    /// PEM private key for `tls_cert_path`.
+    pub tls_key_path: Option<PathBuf>,
+    /// Seconds a request may take from arrival to response; 0 disables the limit. It also
+    /// bounds how long shutdown waits for in-flight requests.
+    pub request_timeout_secs: u64,
+    /// Seconds allowed between chunks of a request body; 0 disables the limit.
+    pub read_timeout_secs: u64,
+    /// Largest request body accepted, in bytes.
+    pub max_body_bytes: usize,
+    /// Seconds the API serves cached signals before checking whether their files changed.
+    pub signal_cache_ttl_secs: u64,
+    /// Extra datasets served by the API, by name, from `DATASETS`; each root holds its own
+    /// `data/` and `outputs/` directories.
+    pub datasets: BTreeMap<String, PathBuf>,
+    /// Command printing an HTML report to PDF, with `{input}` and `{output}` placeholders;
+    /// `None` tries `wkhtmltopdf`, then headless Chromium.
+    pub report_pdf_command: Option<String>,
+    /// Layout of log lines; `--log-format` overrides it.
+    pub log_format: LogFormat,
+    /// File logs are also appended to; `--log-file` overrides it.
+    pub log_file: Option<PathBuf>,
+    /// How often `log_file` rolls over to a new file.
+    pub log_rotation: LogRotation,
+    /// Config file the settings were layered from, if any.
+    pub config_file: Option<PathBuf>,
+}
+
+/// How `rank` weighs each component of a signal's score.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScoreWeights {
+    /// Multiplier of `ln(1 + weighted literature support)`.
+    pub literature: f64,
+    /// Multiplier of the trend z-score.
+    pub trend: f64,
+    /// Weight of a hedged ("may cause", "could not be excluded") literature sentence.
+    pub speculative: f64,
+    /// Weight of a sentence from the CONCLUSIONS section of a structured abstract.
+    pub conclusions: f64,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        Self {
+            literature: 0.3,
+            trend: 0.2,
+            speculative: 0.5,
+            conclusions: 1.5,
+        }
+    }
+}
+
+/// Per-study-design weights for `lit_support`, parsed from `STUDY_TYPE_WEIGHTS`
+/// (`meta-analysis=2,rct=1.5,...`). Citations with no graded publication type weigh 1.0.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StudyTypeWeights(HashMap<PublicationType, f64>);
+
+impl Default for StudyTypeWeights {
+    fn default() -> Self {
+        Self(HashMap::from([
+            (PublicationType::MetaAnalysis, 2.0),
+            (PublicationType::Rct, 1.5),
+            (PublicationType::ClinicalTrial, 1.25),
+            (PublicationType::Observational, 1.0),
+            (PublicationType::Review, 0.75),
+            (PublicationType::CaseReport, 0.5),
+        ]))
+    }
+}
+
+impl StudyTypeWeights {
+    /// Override the defaults with comma-separated `type=weight` pairs.
+    pub fn parse(spec: &str) -> anyhow::Result<Self> {
+        let mut weights = Self::default();
+        for pair in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let Some((name, weight)) = pair.split_once('=') else {
+                bail!("study type weight {pair:?} is not `type=weight`");
+            };
+            let kind = PublicationType::from_str(name.trim(), true)
+                .map_err(|_| anyhow::anyhow!("unknown study type {name:?} in {pair:?}"))?;
+            let weight: f64 = weight
+                .trim()
+                .parse()
+                .with_context(|| format!("study type weight {pair:?}"))?;
+            weights.0.insert(kind, weight);
+        }
+        Ok(weights)
+    }
+
+    /// Weight of evidence from a citation of this study design.
+    pub fn weight(&self, study_type: Option<PublicationType>) -> f64 {
+        study_type
+            .and_then(|kind| self.0.get(&kind).copied())
+            .unwrap_or(1.0)
+    }
 }
 
 impl Settings {
-    /// Load configuration from environment with reasonable defaults.
-    pub fn load() -> anyhow::Result<Self> {
+    /// Load configuration with reasonable defaults. Environment variables win over `.env`,
+    /// which wins over the config file: `config` when given, else a discovered
+    /// `rwe-assistant.toml`.
+    pub fn load(config: Option<&Path>) -> anyhow::Result<Self> {
         dotenvy::dotenv().ok();
+        let config_file = config.map(Path::to_path_buf).or_else(discover_config);
+        if let Some(path) = &config_file {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("reading config {path:?}"))?;
+            let vars = config_vars(&text).with_context(|| format!("parsing config {path:?}"))?;
+            for (key, value) in vars {
+                // Empty variables, as left by copying `.env.example`, do not shadow the file.
+                if env::var(&key).map_or(true, |v| v.is_empty()) {
+                    env::set_var(&key, value);
+                }
+            }
+        }
         let pubmed_email =
             env::var("PUBMED_EMAIL").unwrap_or_else(|_| "research@example.com".to_string());
         let pubmed_tool = env::var("PUBMED_TOOL").unwrap_or_else(|_| "rwe_assistant".to_string());
+        let pubmed_api_key = env::var("PUBMED_API_KEY").ok().filter(|v| !v.is_empty());
         let max_pubmed_per_drug = env::var("MAX_PUBMED_PER_DRUG")
             .ok()
             .and_then(|v| v.parse().ok())
@@ -37,6 +271,208 @@ impl Settings {
         let data_dir = env::var("DATA_DIR")
             .map(PathBuf::from)
             .unwrap_or_else(|_| PathBuf::from("./data"));
+        let drug_dictionary = env::var("DRUG_DICTIONARY")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from);
+        let drug_name_rules = env::var("DRUG_NAME_RULES")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from);
+        let event_dictionary = env::var("EVENT_DICTIONARY")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from);
+        let event_match_threshold = env::var("EVENT_MATCH_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse().ok());
+        let rxnorm_approx_min_score = env::var("RXNORM_APPROX_MIN_SCORE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(8.0);
+        let umls_api_key = env::var("UMLS_API_KEY").ok().filter(|v| !v.is_empty());
+        let openfda_api_key = env::var("OPENFDA_API_KEY").ok().filter(|v| !v.is_empty());
+        let offline = env::var("OFFLINE")
+            .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        let study_type_weights = match env::var("STUDY_TYPE_WEIGHTS") {
+            Ok(spec) => StudyTypeWeights::parse(&spec).context("parsing STUDY_TYPE_WEIGHTS")?,
+            Err(_) => StudyTypeWeights::default(),
+        };
+        let weight_defaults = ScoreWeights::default();
+        let score_weights = ScoreWeights {
+            literature: env::var("SCORE_LITERATURE_WEIGHT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(weight_defaults.literature),
+            trend: env::var("SCORE_TREND_WEIGHT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(weight_defaults.trend),
+            speculative: env::var("SPECULATIVE_WEIGHT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(weight_defaults.speculative),
+            conclusions: env::var("CONCLUSIONS_WEIGHT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(weight_defaults.conclusions),
+        };
+        let embedding_providers = match env::var("EMBEDDING_PROVIDERS") {
+            Ok(spec) if !spec.trim().is_empty() => spec
+                .split(',')
+                .map(|name| {
+                    EmbeddingProvider::from_str(name.trim(), true).map_err(|_| {
+                        anyhow::anyhow!(
+                            "unknown embedding provider {name:?} in EMBEDDING_PROVIDERS"
+                        )
+                    })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?,
+            _ => vec![EmbeddingProvider::Cpu],
+        };
+        let embedding_batch_size = env::var("EMBEDDING_BATCH_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&size| size > 0)
+            .unwrap_or(256);
+        let summary_backend = match env::var("SUMMARY_BACKEND") {
+            Ok(name) if !name.trim().is_empty() => SummaryBackend::from_str(name.trim(), true)
+                .map_err(|_| anyhow::anyhow!("unknown SUMMARY_BACKEND {name:?}"))?,
+            _ => SummaryBackend::default(),
+        };
+        let summary_api_url = env::var("SUMMARY_API_URL")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "http://localhost:11434/v1".to_string());
+        let summary_model = env::var("SUMMARY_MODEL").ok().filter(|v| !v.is_empty());
+        let summary_api_key = env::var("SUMMARY_API_KEY").ok().filter(|v| !v.is_empty());
+        let defaults = SamplingParams::default();
+        let summary_sampling = SamplingParams {
+            temperature: env::var("SUMMARY_TEMPERATURE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.temperature),
+            top_p: env::var("SUMMARY_TOP_P")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.top_p),
+            top_k: env::var("SUMMARY_TOP_K")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.top_k),
+            max_tokens: env::var("SUMMARY_MAX_TOKENS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_tokens),
+            seed: env::var("SUMMARY_SEED")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.seed),
+        };
+        let llama_model_path = env::var("LLAMA_MODEL_PATH")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| data_dir.join("models/llama-tiny.gguf"));
+        let llama_context_length = env::var("LLAMA_CONTEXT_LENGTH")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(4096);
+        let llama_chat_template = env::var("LLAMA_CHAT_TEMPLATE")
+            .ok()
+            .filter(|v| !v.is_empty());
+        let mut api_keys: Vec<String> = env::var("API_KEYS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(str::to_string)
+            .collect();
+        if let Ok(path) = env::var("API_KEYS_FILE") {
+            if !path.is_empty() {
+                let text = std::fs::read_to_string(&path)
+                    .with_context(|| format!("reading API_KEYS_FILE {path}"))?;
+                api_keys.extend(
+                    text.lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                        .map(str::to_string),
+                );
+            }
+        }
+        let api_anonymous_read = env::var("API_ANONYMOUS_READ")
+            .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        let rate_limit_per_second = env::var("RATE_LIMIT_PER_SECOND")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10);
+        let rate_limit_burst = env::var("RATE_LIMIT_BURST")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(50);
+        let tls_cert_path = env::var("TLS_CERT_PATH")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from);
+        let tls_key_path = env::var("TLS_KEY_PATH")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from);
+        let request_timeout_secs = env::var("REQUEST_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60);
+        let read_timeout_secs = env::var("READ_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10);
+        let max_body_bytes = env::var("MAX_BODY_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1024 * 1024);
+        let signal_cache_ttl_secs = env::var("SIGNAL_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30);
+        let mut datasets = BTreeMap::new();
+        for entry in env::var("DATASETS").unwrap_or_default().split(',') {
+            let entry = entry.trim();
+            if entry.is_empty() {
+                continue;
+            }
+            let Some((name, root)) = entry.split_once('=') else {
+                bail!("DATASETS entry {entry:?} is not name=path");
+            };
+            let name = name.trim();
+            if name.is_empty()
+                || name == "default"
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                bail!("invalid dataset name {name:?} in DATASETS");
+            }
+            datasets.insert(name.to_string(), PathBuf::from(root.trim()));
+        }
+        let report_pdf_command = env::var("REPORT_PDF_COMMAND")
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+        let log_format = match env::var("LOG_FORMAT") {
+            Ok(name) if !name.trim().is_empty() => LogFormat::from_str(name.trim(), true)
+                .map_err(|_| anyhow::anyhow!("unknown LOG_FORMAT {name:?}"))?,
+            _ => LogFormat::default(),
+        };
+        let log_file = env::var("LOG_FILE")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from);
+        let log_rotation = match env::var("LOG_ROTATION") {
+            Ok(name) if !name.trim().is_empty() => LogRotation::from_str(name.trim(), true)
+                .map_err(|_| anyhow::anyhow!("unknown LOG_ROTATION {name:?}"))?,
+            _ => LogRotation::default(),
+        };
         let outputs_dir = env::var("OUTPUTS_DIR")
             .map(PathBuf::from)
             .unwrap_or_else(|_| PathBuf::from("./outputs"));
@@ -47,12 +483,58 @@ impl Settings {
         Ok(Self {
             pubmed_email,
             pubmed_tool,
+            pubmed_api_key,
             max_pubmed_per_drug,
             data_dir,
             outputs_dir,
+            drug_dictionary,
+            drug_name_rules,
+            event_dictionary,
+            event_match_threshold,
+            rxnorm_approx_min_score,
+            umls_api_key,
+            openfda_api_key,
+            offline,
+            study_type_weights,
+            score_weights,
+            embedding_providers,
+            embedding_batch_size,
+            summary_backend,
+            summary_api_url,
+            summary_model,
+            summary_api_key,
+            summary_sampling,
+            llama_model_path,
+            llama_context_length,
+            llama_chat_template,
+            api_keys,
+            api_anonymous_read,
+            rate_limit_per_second,
+            rate_limit_burst,
+            tls_cert_path,
+            tls_key_path,
+            request_timeout_secs,
+            read_timeout_secs,
+            max_body_bytes,
+            signal_cache_ttl_secs,
+            datasets,
+            report_pdf_command,
+            log_format,
+            log_file,
+            log_rotation,
+            config_file,
         })
     }
 
+    /// These settings with data and outputs under `root/data` and `root/outputs`.
+    pub fn for_dataset(&self, root: &Path) -> Self {
+        Self {
+            data_dir: root.join("data"),
+            outputs_dir: root.join("outputs"),
+            ..self.clone()
+        }
+    }
+
     /// Convenience helper for derived path segments.
     pub fn join_data<P: AsRef<Path>>(&self, path: P) -> PathBuf {
         self.data_dir.join(path)
@@ -63,3 +545,76 @@ impl Settings {
         self.outputs_dir.join(path)
     }
 }
+
+/// `$RWE_CONFIG`, else `rwe-assistant.toml` in the working directory, else in
+/// `$XDG_CONFIG_HOME/rwe-assistant` (default `~/.config/rwe-assistant`).
+fn discover_config() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("RWE_CONFIG").filter(|v| !v.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    let local = PathBuf::from(CONFIG_FILE_NAME);
+    if local.is_file() {
+        return Some(local);
+    }
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    let global = config_home.join("rwe-assistant").join(CONFIG_FILE_NAME);
+    global.is_file().then_some(global)
+}
+
+/// Environment variables set by a TOML config file. Keys are variable names in any case;
+/// a table prefixes its keys, so `[summary] temperature = 0.2` sets `SUMMARY_TEMPERATURE`.
+/// Arrays become comma-separated lists and tables under `study_type_weights` or `datasets`
+/// become `name=value` pairs.
+pub fn config_vars(text: &str) -> anyhow::Result<Vec<(String, String)>> {
+    let table: toml::Table = toml::from_str(text)?;
+    let mut vars = Vec::new();
+    for (key, value) in &table {
+        flatten(&key.to_ascii_uppercase(), value, &mut vars)?;
+    }
+    for (key, _) in &vars {
+        if !CONFIG_KEYS.contains(&key.as_str()) {
+            bail!("unknown setting {:?}", key.to_ascii_lowercase());
+        }
+    }
+    Ok(vars)
+}
+
+fn flatten(key: &str, value: &toml::Value, vars: &mut Vec<(String, String)>) -> anyhow::Result<()> {
+    match value {
+        toml::Value::Table(table) if PAIR_KEYS.contains(&key) => {
+            let pairs = table
+                .iter()
+                .map(|(name, value)| Ok(format!("{name}={}", scalar(key, value)?)))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            vars.push((key.to_string(), pairs.join(",")));
+        }
+        toml::Value::Table(table) => {
+            for (name, value) in table {
+                flatten(&format!("{key}_{}", name.to_ascii_uppercase()), value, vars)?;
+            }
+        }
+        toml::Value::Array(items) => {
+            let items = items
+                .iter()
+                .map(|item| scalar(key, item))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            vars.push((key.to_string(), items.join(",")));
+        }
+        value => vars.push((key.to_string(), scalar(key, value)?)),
+    }
+    Ok(())
+}
+
+fn scalar(key: &str, value: &toml::Value) -> anyhow::Result<String> {
+    Ok(match value {
+        toml::Value::String(text) => text.clone(),
+        toml::Value::Integer(number) => number.to_string(),
+        toml::Value::Float(number) => number.to_string(),
+        toml::Value::Boolean(flag) => flag.to_string(),
+        toml::Value::Datetime(time) => time.to_string(),
+        _ => bail!("{key:?} must be a string, number or boolean"),
+    })
+}
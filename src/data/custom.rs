@@ -0,0 +1,222 @@
+//! Generic CSV/XLSX import for proprietary safety database exports (Argus, ArisG, ...).
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use calamine::{open_workbook_auto, Data, DataType, Reader};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::{
+    config::Settings,
+    data::{faers::detect_delimiter, normalize},
+    signals::trend,
+};
+
+/// Column mapping supplied with `import --source custom --mapping <file.toml>`.
+///
+/// ```toml
+/// case_id = "Case Number"
+/// drug = "Suspect Product"
+/// event = "Event PT"
+/// date = "Initial Receipt Date"   # optional
+/// sheet = "Cases"                 # optional, XLSX only
+/// case_prefix = "ARGUS"           # optional
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct ColumnMapping {
+    pub case_id: String,
+    pub drug: String,
+    pub event: String,
+    #[serde(default)]
+    pub date: Option<String>,
+    #[serde(default)]
+    pub sheet: Option<String>,
+    #[serde(default = "default_case_prefix")]
+    pub case_prefix: String,
+}
+
+fn default_case_prefix() -> String {
+    "CU".to_string()
+}
+
+impl ColumnMapping {
+    /// Read a TOML mapping file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("read mapping {path:?}"))?;
+        toml::from_str(&text).with_context(|| format!("parse mapping {path:?}"))
+    }
+
+    /// Indices of the case, drug, event and optional date columns.
+    fn locate(&self, headers: &[String]) -> Result<(usize, usize, usize, Option<usize>)> {
+        let find = |name: &str| {
+            headers
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(name.trim()))
+        };
+        let required = |name: &str| {
+            find(name).ok_or_else(|| anyhow!("mapped column {name:?} not found in export header"))
+        };
+        let date_idx = match &self.date {
+            Some(name) => Some(required(name)?),
+            None => None,
+        };
+        Ok((
+            required(&self.case_id)?,
+            required(&self.drug)?,
+            required(&self.event)?,
+            date_idx,
+        ))
+    }
+}
+
+/// Import a mapped CSV/TSV/XLSX export (file or directory) into `data/raw/custom`.
+pub fn import_export(
+    source: &Path,
+    mapping: &ColumnMapping,
+    default_quarter: Option<&str>,
+    settings: &Settings,
+) -> Result<Vec<PathBuf>> {
+    let files = export_files(source)?;
+    if files.is_empty() {
+        warn!(path = %source.display(), "no export files found");
+        return Ok(Vec::new());
+    }
+
+    let mut by_quarter: BTreeMap<String, Vec<[String; 3]>> = BTreeMap::new();
+    for file in &files {
+        info!(file = %file.display(), "reading custom export");
+        let rows = if is_spreadsheet(file) {
+            read_spreadsheet(file, mapping.sheet.as_deref())
+        } else {
+            read_delimited(file)
+        }
+        .with_context(|| format!("read export {file:?}"))?;
+        collect_rows(rows, mapping, default_quarter, &mut by_quarter)
+            .with_context(|| format!("map export {file:?}"))?;
+    }
+
+    normalize::write_raw_quarters(&settings.join_data("raw/custom"), "custom", &by_quarter)
+}
+
+fn is_spreadsheet(path: &Path) -> bool {
+    matches!(
+        extension(path).as_deref(),
+        Some("xlsx" | "xlsm" | "xls" | "ods")
+    )
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_ascii_lowercase())
+}
+
+fn export_files(source: &Path) -> Result<Vec<PathBuf>> {
+    if source.is_file() {
+        return Ok(vec![source.to_path_buf()]);
+    }
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(source).with_context(|| format!("read dir {source:?}"))? {
+        let path = entry?.path();
+        if is_spreadsheet(&path)
+            || matches!(extension(&path).as_deref(), Some("csv" | "tsv" | "txt"))
+        {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Header row followed by data rows, all as strings.
+type Table = (Vec<String>, Vec<Vec<String>>);
+
+fn read_delimited(path: &Path) -> Result<Table> {
+    let mut header_line = String::new();
+    BufReader::new(File::open(path)?).read_line(&mut header_line)?;
+    let delimiter = detect_delimiter(&header_line) as u8;
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_path(path)?;
+    let headers = reader.headers()?.iter().map(str::to_string).collect();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        rows.push(record?.iter().map(str::to_string).collect());
+    }
+    Ok((headers, rows))
+}
+
+fn read_spreadsheet(path: &Path, sheet: Option<&str>) -> Result<Table> {
+    let mut workbook = open_workbook_auto(path)?;
+    let range = match sheet {
+        Some(name) => workbook.worksheet_range(name)?,
+        None => workbook
+            .worksheet_range_at(0)
+            .ok_or_else(|| anyhow!("workbook has no sheets"))??,
+    };
+    let mut rows = range.rows().map(|row| row.iter().map(cell_text).collect());
+    let headers = rows.next().ok_or_else(|| anyhow!("sheet is empty"))?;
+    Ok((headers, rows.collect()))
+}
+
+/// Render a cell as text; date cells become `YYYYMMDD` so quarters can be derived.
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::DateTime(_) | Data::DateTimeIso(_) => cell
+            .as_date()
+            .map(|date| date.format("%Y%m%d").to_string())
+            .unwrap_or_default(),
+        Data::Empty => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn collect_rows(
+    (headers, rows): Table,
+    mapping: &ColumnMapping,
+    default_quarter: Option<&str>,
+    sink: &mut BTreeMap<String, Vec<[String; 3]>>,
+) -> Result<()> {
+    let (case_idx, drug_idx, event_idx, date_idx) = mapping.locate(&headers)?;
+
+    let mut skipped = 0u64;
+    for (line, row) in (2..).zip(&rows) {
+        let field = |idx: usize| row.get(idx).map(|s| s.trim()).unwrap_or_default();
+        let (case, drug, event) = (field(case_idx), field(drug_idx), field(event_idx));
+        if case.is_empty() || drug.is_empty() || event.is_empty() {
+            skipped += 1;
+            continue;
+        }
+        let quarter = match date_idx.map(field).filter(|date| !date.is_empty()) {
+            Some(date) => Some(trend::quarter_from_date(date).ok_or_else(|| {
+                anyhow!("row {line}: report date {date:?} is not YYYY-MM-DD or YYYYMMDD")
+            })?),
+            None => default_quarter.map(str::to_string),
+        };
+        let Some(quarter) = quarter else {
+            skipped += 1;
+            continue;
+        };
+        sink.entry(quarter).or_default().push([
+            format!("{}{case}", mapping.case_prefix),
+            drug.to_string(),
+            event.to_string(),
+        ]);
+    }
+    if skipped > 0 {
+        warn!(
+            skipped,
+            "skipped export rows missing case, drug, event or date"
+        );
+    }
+    Ok(())
+}
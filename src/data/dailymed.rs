@@ -0,0 +1,200 @@
+//! DailyMed structured product label (SPL) ingestion for labeledness.
+
+use std::{collections::HashSet, fs::File, path::PathBuf};
+
+use anyhow::{Context, Result};
+use polars::prelude::{DataFrame, NamedFrom, ParquetWriter, Series};
+use quick_xml::{events::Event, Reader};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::{
+    config::Settings,
+    data::{dictionary::mentions_term, manifest, normalize, read_string_pairs},
+    net::{self, HttpClient},
+};
+
+pub(crate) const DAILYMED_BASE: &str = "https://dailymed.nlm.nih.gov/dailymed/services/v2";
+/// LOINC section code for "Adverse Reactions" in SPL documents.
+const ADVERSE_REACTIONS_LOINC: &str = "34084-4";
+
+/// Download the current SPL for each drug and cache its Adverse Reactions section text.
+///
+/// Sections are saved under the drug's canonical ingredient name (`imatinib.txt` for
+/// "Gleevec"), the name `build_labeled_events` looks up in `drugs.parquet`.
+pub async fn fetch_labels(drugs: &[String], settings: &Settings) -> Result<Vec<PathBuf>> {
+    let client = net::client(settings)?;
+    let dest_root = settings.join_data("raw/dailymed");
+    std::fs::create_dir_all(&dest_root)?;
+    let drugs: Vec<String> = drugs
+        .iter()
+        .map(|drug| drug.trim().to_string())
+        .filter(|drug| !drug.is_empty())
+        .collect();
+    let ingredients = normalize::resolve_drug_names(settings, &drugs).await?;
+
+    let mut outputs = Vec::new();
+    for drug in &drugs {
+        let stem = ingredients
+            .get(drug)
+            .map_or_else(|| drug.to_lowercase(), |names| names.join(" + "));
+        let path = dest_root.join(format!("{stem}.txt"));
+        if settings.offline && path.exists() {
+            info!(%drug, "offline: using cached label section");
+            outputs.push(path);
+            continue;
+        }
+        net::ensure_online(settings, &format!("DailyMed label for {drug}"))?;
+        let Some(set_id) = latest_set_id(client, drug).await? else {
+            warn!(%drug, "no DailyMed label found");
+            continue;
+        };
+        let url = format!("{DAILYMED_BASE}/spls/{set_id}.xml");
+        let xml = client.get(&url).await?.error_for_status()?.text().await?;
+        let section = adverse_reactions_text(&xml)?;
+        if section.is_empty() {
+            warn!(%drug, %set_id, "label has no adverse reactions section");
+            continue;
+        }
+        std::fs::write(&path, &section).with_context(|| format!("write {path:?}"))?;
+        manifest::record(settings, &path, &url, &[], std::slice::from_ref(drug))?;
+        info!(%drug, %stem, %set_id, chars = section.len(), "saved adverse reactions section");
+        outputs.push(path);
+    }
+    Ok(outputs)
+}
+
+async fn latest_set_id(client: &HttpClient, drug: &str) -> Result<Option<String>> {
+    let url = format!(
+        "{DAILYMED_BASE}/spls.json?drug_name={}&pagesize=1",
+        urlencoding::encode(drug)
+    );
+    let payload: SplListResponse = client.get(&url).await?.error_for_status()?.json().await?;
+    Ok(payload.data.into_iter().next().map(|spl| spl.setid))
+}
+
+/// Extract the plain text of the Adverse Reactions section(s) from an SPL document.
+pub fn adverse_reactions_text(xml: &str) -> Result<String> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    // One flag per open <section>, true when it (or an ancestor) is the AR section.
+    let mut sections: Vec<bool> = Vec::new();
+    // One flag per open element, true for <section> elements.
+    let mut elements: Vec<bool> = Vec::new();
+    let mut text = String::new();
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                let is_section = e.local_name().as_ref() == b"section";
+                if is_section {
+                    let inherited = sections.last().copied().unwrap_or(false);
+                    sections.push(inherited);
+                } else if e.local_name().as_ref() == b"code" && elements.last() == Some(&true) {
+                    mark_ar_section(&e, &mut sections)?;
+                }
+                elements.push(is_section);
+            }
+            Event::Empty(e)
+                if e.local_name().as_ref() == b"code" && elements.last() == Some(&true) =>
+            {
+                mark_ar_section(&e, &mut sections)?;
+            }
+            Event::End(_) => {
+                let closes_section = elements.pop() == Some(true);
+                if closes_section {
+                    sections.pop();
+                }
+            }
+            Event::Text(t) if sections.last() == Some(&true) => {
+                text.push_str(&t.unescape()?);
+                text.push(' ');
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(text.trim().to_string())
+}
+
+fn mark_ar_section(e: &quick_xml::events::BytesStart<'_>, sections: &mut [bool]) -> Result<()> {
+    for attribute in e.attributes() {
+        let attribute = attribute?;
+        if attribute.key.local_name().as_ref() == b"code"
+            && attribute.unescape_value()? == ADVERSE_REACTIONS_LOINC
+        {
+            if let Some(flag) = sections.last_mut() {
+                *flag = true;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Match cached label sections against the event vocabulary, writing `labeled_events.parquet`.
+pub fn build_labeled_events(settings: &Settings) -> Result<()> {
+    let label_root = settings.join_data("raw/dailymed");
+    let drugs_path = settings.join_data("clean/drugs.parquet");
+    let events_path = settings.join_data("clean/events.parquet");
+    if !label_root.exists() || !drugs_path.exists() || !events_path.exists() {
+        return Ok(());
+    }
+    let drugs = read_string_pairs(&drugs_path, "name_canonical", "drug_id")?;
+    let events = read_string_pairs(&events_path, "term_canonical", "event_id")?;
+
+    let mut drug_ids = Vec::new();
+    let mut event_ids = Vec::new();
+    for entry in std::fs::read_dir(&label_root)? {
+        let path = entry?.path();
+        if path.extension().and_then(|s| s.to_str()) != Some("txt") {
+            continue;
+        }
+        let Some(drug) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let Some((_, drug_id)) = drugs
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(drug))
+        else {
+            continue;
+        };
+        let section = std::fs::read_to_string(&path)?.to_lowercase();
+        for (term, event_id) in &events {
+            if mentions_term(&section, &term.to_lowercase()) {
+                drug_ids.push(drug_id.clone());
+                event_ids.push(event_id.clone());
+            }
+        }
+    }
+
+    let out_path = settings.join_data("clean/labeled_events.parquet");
+    let rows = drug_ids.len();
+    let mut df = DataFrame::new(vec![
+        Series::new("drug_id".into(), drug_ids),
+        Series::new("event_id".into(), event_ids),
+    ])?;
+    ParquetWriter::new(File::create(&out_path)?).finish(&mut df)?;
+    info!(path = %out_path.display(), rows, "wrote labeled events parquet");
+    Ok(())
+}
+
+/// Load labeled `(drug_id, event_id)` pairs, empty when labels were never fetched.
+pub fn load_labeled_pairs(settings: &Settings) -> Result<HashSet<(String, String)>> {
+    let path = settings.join_data("clean/labeled_events.parquet");
+    if !path.exists() {
+        return Ok(HashSet::new());
+    }
+    Ok(read_string_pairs(&path, "drug_id", "event_id")?
+        .into_iter()
+        .collect())
+}
+
+#[derive(Debug, Deserialize)]
+struct SplListResponse {
+    #[serde(default)]
+    data: Vec<SplSummary>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SplSummary {
+    setid: String,
+}
@@ -0,0 +1,210 @@
+//! openFDA device adverse event (MAUDE) ingestion.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use reqwest::{Method, StatusCode};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::{
+    config::Settings,
+    data::{manifest, normalize},
+    net,
+    signals::trend,
+};
+
+pub(crate) const OPENFDA_DEVICE_EVENT: &str = "https://api.fda.gov/device/event.json";
+/// openFDA page size and the largest `skip` it accepts.
+const PAGE_SIZE: usize = 1000;
+const MAX_SKIP: usize = 25_000;
+
+/// Prefix applied to MAUDE report numbers so they never collide with FAERS ids.
+pub const CASE_PREFIX: &str = "DEV";
+
+/// Download device reports for each device name and quarter into `data/raw/device`.
+///
+/// Devices take the place of drugs (brand name, falling back to generic name) and patient
+/// problems take the place of reactions, so `normalize --devices` and `signal` run unchanged.
+pub async fn fetch_device_events(
+    devices: &[String],
+    quarters: &[String],
+    settings: &Settings,
+) -> Result<Vec<PathBuf>> {
+    let dir = settings.join_data("raw/device");
+    if settings.offline {
+        let cached: Vec<PathBuf> = quarters
+            .iter()
+            .map(|q| dir.join(format!("device_{q}.csv")))
+            .collect();
+        if cached.iter().all(|path| path.exists()) {
+            info!("offline: using cached device event files");
+            return Ok(cached);
+        }
+    }
+    net::ensure_online(settings, "openFDA device events")?;
+    let client = net::client(settings)?;
+    let mut by_quarter: BTreeMap<String, Vec<[String; 3]>> = BTreeMap::new();
+    for device in devices {
+        let device = device.trim();
+        if device.is_empty() {
+            continue;
+        }
+        for quarter in quarters {
+            let (start, end) = quarter_bounds(quarter)?;
+            // Spaces become `+` when the query is encoded, which openFDA reads as OR.
+            let phrase = device.replace('"', "");
+            let search = format!(
+                "(device.brand_name:\"{phrase}\" device.generic_name:\"{phrase}\") AND date_received:[{start} TO {end}]"
+            );
+            let mut skip = 0;
+            loop {
+                let mut query = vec![
+                    ("search", search.clone()),
+                    ("limit", PAGE_SIZE.to_string()),
+                    ("skip", skip.to_string()),
+                ];
+                if let Some(key) = &settings.openfda_api_key {
+                    query.push(("api_key", key.clone()));
+                }
+                let request = client
+                    .request(Method::GET, OPENFDA_DEVICE_EVENT)
+                    .query(&query);
+                let resp = client.send(request).await?;
+                if resp.status() == StatusCode::NOT_FOUND {
+                    // openFDA answers 404 when a search has no matches.
+                    break;
+                }
+                // Errors carry the request URL, which holds the api key.
+                let page: DeviceEventPage = resp
+                    .error_for_status()
+                    .map_err(net::redact)?
+                    .json()
+                    .await
+                    .map_err(net::redact)?;
+                let fetched = page.results.len();
+                for report in page.results {
+                    if let Some((quarter, rows)) = report.into_rows(quarter) {
+                        by_quarter.entry(quarter).or_default().extend(rows);
+                    }
+                }
+                skip += fetched;
+                let total = page.meta.results.total;
+                if fetched < PAGE_SIZE || skip >= total {
+                    break;
+                }
+                if skip > MAX_SKIP {
+                    warn!(%device, %quarter, total, "openFDA paging limit reached; results truncated");
+                    break;
+                }
+            }
+            info!(%device, %quarter, "fetched device events");
+        }
+    }
+
+    let written = normalize::write_raw_quarters(&dir, "device", &by_quarter)?;
+    for (path, quarter) in written.iter().zip(by_quarter.keys()) {
+        manifest::record(
+            settings,
+            path,
+            OPENFDA_DEVICE_EVENT,
+            std::slice::from_ref(quarter),
+            devices,
+        )?;
+    }
+    Ok(written)
+}
+
+/// `YYYYMMDD` bounds of a quarter such as 2024Q1.
+pub fn quarter_bounds(quarter: &str) -> Result<(String, String)> {
+    let (year, q) = trend::parse_quarter(quarter)
+        .filter(|(_, q)| (1..=4).contains(q))
+        .ok_or_else(|| anyhow!("invalid quarter {quarter}"))?;
+    let (start, end) = match q {
+        1 => ("0101", "0331"),
+        2 => ("0401", "0630"),
+        3 => ("0701", "0930"),
+        _ => ("1001", "1231"),
+    };
+    Ok((format!("{year}{start}"), format!("{year}{end}")))
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceEventPage {
+    meta: PageMeta,
+    #[serde(default)]
+    results: Vec<DeviceReport>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PageMeta {
+    results: PageCounts,
+}
+
+#[derive(Debug, Deserialize)]
+struct PageCounts {
+    total: usize,
+}
+
+/// One openFDA device event report.
+#[derive(Debug, Deserialize)]
+pub struct DeviceReport {
+    report_number: Option<String>,
+    mdr_report_key: Option<String>,
+    date_received: Option<String>,
+    #[serde(default)]
+    device: Vec<DeviceEntry>,
+    #[serde(default)]
+    patient: Vec<PatientEntry>,
+    #[serde(default)]
+    product_problems: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceEntry {
+    brand_name: Option<String>,
+    generic_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PatientEntry {
+    #[serde(default)]
+    patient_problems: Vec<String>,
+}
+
+impl DeviceReport {
+    /// Expand a report into `(case, device, problem)` rows for its receipt quarter.
+    pub fn into_rows(self, fallback_quarter: &str) -> Option<(String, Vec<[String; 3]>)> {
+        let id = self.report_number.or(self.mdr_report_key)?;
+        let quarter = self
+            .date_received
+            .as_deref()
+            .and_then(trend::quarter_from_date)
+            .unwrap_or_else(|| fallback_quarter.to_string());
+        let devices: Vec<String> = self
+            .device
+            .into_iter()
+            .filter_map(|d| d.brand_name.or(d.generic_name))
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
+        let mut problems: Vec<String> = self
+            .patient
+            .into_iter()
+            .flat_map(|p| p.patient_problems)
+            .collect();
+        if problems.is_empty() {
+            problems = self.product_problems;
+        }
+        let case_id = format!("{CASE_PREFIX}{id}");
+        let rows = devices
+            .iter()
+            .flat_map(|device| {
+                problems
+                    .iter()
+                    .map(|problem| [case_id.clone(), device.clone(), problem.trim().to_string()])
+            })
+            .collect();
+        Some((quarter, rows))
+    }
+}
@@ -0,0 +1,209 @@
+//! User-supplied terminology dictionaries consulted before the built-in vocabularies.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use tracing::info;
+
+use crate::config::Settings;
+
+/// Load the drug dictionary named by `DRUG_DICTIONARY`, empty when unset.
+pub fn load_drug_dictionary(settings: &Settings) -> Result<HashMap<String, String>> {
+    match &settings.drug_dictionary {
+        Some(path) => read_drug_dictionary(path),
+        None => Ok(HashMap::new()),
+    }
+}
+
+/// Read a `raw name, canonical ingredient` CSV (or TSV) as an upper-cased raw → ingredient map.
+///
+/// The file has no header; blank lines and lines starting with `#` are skipped. When a raw
+/// name is listed twice, the first entry wins.
+pub fn read_drug_dictionary(path: &Path) -> Result<HashMap<String, String>> {
+    let delimiter = if path.extension().is_some_and(|ext| ext == "tsv") {
+        b'\t'
+    } else {
+        b','
+    };
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .delimiter(delimiter)
+        .comment(Some(b'#'))
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .with_context(|| format!("open drug dictionary {}", path.display()))?;
+    let mut mapping = HashMap::new();
+    for record in reader.records() {
+        let record = record.with_context(|| format!("parse {}", path.display()))?;
+        let (Some(raw), Some(canonical)) = (record.get(0), record.get(1)) else {
+            bail!(
+                "{} line {}: expected `raw name, canonical ingredient`",
+                path.display(),
+                record.position().map_or(0, |pos| pos.line())
+            );
+        };
+        if raw.is_empty() || canonical.is_empty() {
+            continue;
+        }
+        mapping
+            .entry(raw.to_ascii_uppercase())
+            .or_insert_with(|| canonical.to_lowercase());
+    }
+    info!(entries = mapping.len(), path = %path.display(), "loaded user drug dictionary");
+    Ok(mapping)
+}
+
+/// Canonical event terms with synonyms, shared by `normalize` and sentence extraction.
+#[derive(Debug, Clone, Default)]
+pub struct EventDictionary {
+    /// Lower-cased canonical terms in dictionary order.
+    pub terms: Vec<String>,
+    /// Lower-cased synonym → canonical term.
+    pub synonyms: HashMap<String, String>,
+    known: HashSet<String>,
+    /// [`term_key`] of every term and synonym → canonical term.
+    normalized: HashMap<String, String>,
+}
+
+/// Lower-cased words of `text`, ignoring punctuation and spacing ("Rash-pruritic" and
+/// "rash pruritic" share a key).
+pub fn term_key(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl EventDictionary {
+    /// Dictionary of canonical terms without synonyms (e.g. the SIDER vocabulary).
+    pub fn from_terms(terms: Vec<String>) -> Self {
+        let mut dictionary = Self {
+            known: terms.iter().cloned().collect(),
+            terms,
+            ..Self::default()
+        };
+        dictionary.index();
+        dictionary
+    }
+
+    /// Canonical term for an exact (case-insensitive) term or synonym match, or failing that
+    /// one equal after [`term_key`] normalization.
+    pub fn canonical(&self, text: &str) -> Option<&str> {
+        let key = text.trim().to_lowercase();
+        if let Some(canonical) = self.synonyms.get(&key) {
+            return Some(canonical);
+        }
+        if let Some(term) = self.known.get(&key) {
+            return Some(term);
+        }
+        self.normalized.get(&term_key(text)).map(String::as_str)
+    }
+
+    fn index(&mut self) {
+        let mut normalized = HashMap::new();
+        for (surface, canonical) in self.entries() {
+            normalized
+                .entry(term_key(surface))
+                .or_insert_with(|| canonical.to_string());
+        }
+        self.normalized = normalized;
+    }
+
+    /// Every `(surface form, canonical term)` pair, canonical terms first.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.terms
+            .iter()
+            .map(|t| (t.as_str(), t.as_str()))
+            .chain(self.synonyms.iter().map(|(s, c)| (s.as_str(), c.as_str())))
+    }
+
+    /// Canonical term of the first entry mentioned as whole words in already lower-cased
+    /// `text` ("rash" is not found in "crash").
+    pub fn find_in(&self, text: &str) -> Option<&str> {
+        self.entries()
+            .find(|(surface, _)| mentions_term(text, surface))
+            .map(|(_, canonical)| canonical)
+    }
+}
+
+/// Whether `term` occurs in `text` with no letter or digit directly before or after it.
+pub fn mentions_term(text: &str, term: &str) -> bool {
+    if term.is_empty() {
+        return false;
+    }
+    text.match_indices(term).any(|(start, _)| {
+        let end = start + term.len();
+        let before = text[..start].chars().next_back();
+        let after = text[end..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// Load the event dictionary named by `EVENT_DICTIONARY`, or fall back to `default_terms`.
+pub fn load_event_dictionary(
+    settings: &Settings,
+    default_terms: &[String],
+) -> Result<EventDictionary> {
+    match &settings.event_dictionary {
+        Some(path) => read_event_dictionary(path),
+        None => Ok(EventDictionary::from_terms(default_terms.to_vec())),
+    }
+}
+
+/// Read an event dictionary file.
+///
+/// Each line is a canonical term optionally followed by synonyms, separated by `|` or tabs.
+/// A MedDRA `pt.asc` (`$`-delimited, `pt_code$pt_name$...`) is read as a plain PT list.
+/// Blank lines and lines starting with `#` are skipped.
+pub fn read_event_dictionary(path: &Path) -> Result<EventDictionary> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("read event dictionary {}", path.display()))?;
+    let mut dictionary = EventDictionary::default();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields: Vec<String> = if line.contains('$') {
+            line.split('$')
+                .nth(1)
+                .into_iter()
+                .map(str::to_string)
+                .collect()
+        } else {
+            line.split(['|', '\t']).map(str::to_string).collect()
+        };
+        fields.retain_mut(|field| {
+            *field = field.trim().to_lowercase();
+            !field.is_empty()
+        });
+        let Some((canonical, synonyms)) = fields.split_first() else {
+            continue;
+        };
+        if dictionary.known.insert(canonical.clone()) {
+            dictionary.terms.push(canonical.clone());
+        }
+        for synonym in synonyms {
+            dictionary
+                .synonyms
+                .entry(synonym.clone())
+                .or_insert_with(|| canonical.clone());
+        }
+    }
+    if dictionary.terms.is_empty() {
+        bail!("event dictionary {} has no terms", path.display());
+    }
+    dictionary.index();
+    info!(
+        terms = dictionary.terms.len(),
+        synonyms = dictionary.synonyms.len(),
+        path = %path.display(),
+        "loaded user event dictionary"
+    );
+    Ok(dictionary)
+}
@@ -0,0 +1,227 @@
+//! Clean-up rules that strip strengths, salts and dosage forms from raw drug names.
+
+use std::{collections::HashSet, path::Path};
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use tracing::info;
+
+use crate::config::Settings;
+
+/// Strengths (`40 MG`, `0.5 MG/ML`, `2%`) and parenthesised qualifiers.
+const DEFAULT_PATTERNS: &[&str] = &[
+    r"\([^)]*\)",
+    r"\[[^\]]*\]",
+    r"\b\d+(?:[.,]\d+)?\s*(?:MG|MCG|UG|G|KG|ML|L|IU|UNITS?|MEQ|MMOL|%)(?:\s*/\s*(?:\d+(?:[.,]\d+)?\s*)?(?:ML|L|G|KG|H|HR|HOUR|DOSE|ACTUATION|M2))?",
+];
+
+/// Salt and hydrate suffixes; only stripped after the first token so `CALCIUM CARBONATE` survives.
+const DEFAULT_SALTS: &[&str] = &[
+    "HCL",
+    "HYDROCHLORIDE",
+    "DIHYDROCHLORIDE",
+    "HYDROBROMIDE",
+    "SODIUM",
+    "POTASSIUM",
+    "CALCIUM",
+    "MAGNESIUM",
+    "MESYLATE",
+    "MESILATE",
+    "MALEATE",
+    "SULFATE",
+    "SULPHATE",
+    "ACETATE",
+    "CITRATE",
+    "TARTRATE",
+    "BITARTRATE",
+    "PHOSPHATE",
+    "BESYLATE",
+    "BESILATE",
+    "SUCCINATE",
+    "FUMARATE",
+    "TOSYLATE",
+    "BROMIDE",
+    "CHLORIDE",
+    "HYDRATE",
+    "MONOHYDRATE",
+    "DIHYDRATE",
+    "TRIHYDRATE",
+    "ANHYDROUS",
+];
+
+/// Metal and ammonium cations. A name starting with one (`FERROUS SULFATE`, `ZINC ACETATE`)
+/// is an inorganic salt, and its anion is the drug rather than a counter-ion.
+const CATIONS: &[&str] = &[
+    "SODIUM",
+    "POTASSIUM",
+    "CALCIUM",
+    "MAGNESIUM",
+    "LITHIUM",
+    "ZINC",
+    "FERROUS",
+    "FERRIC",
+    "ALUMINIUM",
+    "ALUMINUM",
+    "AMMONIUM",
+    "BARIUM",
+    "CUPRIC",
+    "SILVER",
+];
+
+/// Dosage form and release words, stripped wherever they appear.
+const DEFAULT_FORMULATIONS: &[&str] = &[
+    "TABLET",
+    "TABLETS",
+    "TAB",
+    "TABS",
+    "CAPSULE",
+    "CAPSULES",
+    "CAP",
+    "CAPS",
+    "INJECTION",
+    "INJECTABLE",
+    "INFUSION",
+    "SOLUTION",
+    "SUSPENSION",
+    "SYRUP",
+    "ELIXIR",
+    "CREAM",
+    "OINTMENT",
+    "GEL",
+    "PATCH",
+    "POWDER",
+    "VIAL",
+    "ORAL",
+    "TOPICAL",
+    "INTRAVENOUS",
+    "SUBCUTANEOUS",
+    "IV",
+    "SC",
+    "ER",
+    "XR",
+    "SR",
+    "CR",
+    "DR",
+    "XL",
+    "LA",
+    "EXTENDED",
+    "DELAYED",
+    "SUSTAINED",
+    "RELEASE",
+    "FILM",
+    "COATED",
+    "CHEWABLE",
+];
+
+/// Optional TOML file (`DRUG_NAME_RULES`) extending or replacing the built-in rules.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RulesFile {
+    /// Drop the built-in rules instead of extending them.
+    replace_defaults: bool,
+    /// Regexes matched against the upper-cased name and replaced by a space.
+    patterns: Vec<String>,
+    salts: Vec<String>,
+    formulations: Vec<String>,
+}
+
+/// Compiled name clean-up rules applied before dictionary and RxNorm lookups.
+#[derive(Debug, Clone)]
+pub struct DrugNameRules {
+    patterns: Vec<Regex>,
+    salts: HashSet<String>,
+    formulations: HashSet<String>,
+}
+
+impl Default for DrugNameRules {
+    fn default() -> Self {
+        Self {
+            patterns: DEFAULT_PATTERNS
+                .iter()
+                .map(|p| Regex::new(p).expect("valid built-in pattern"))
+                .collect(),
+            salts: DEFAULT_SALTS.iter().map(|s| s.to_string()).collect(),
+            formulations: DEFAULT_FORMULATIONS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+impl DrugNameRules {
+    /// Built-in rules, extended (or replaced) by the file named in `DRUG_NAME_RULES`.
+    pub fn load(settings: &Settings) -> Result<Self> {
+        match &settings.drug_name_rules {
+            Some(path) => Self::from_file(path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("read drug name rules {}", path.display()))?;
+        let file: RulesFile =
+            toml::from_str(&text).with_context(|| format!("parse {}", path.display()))?;
+        let mut rules = if file.replace_defaults {
+            Self {
+                patterns: Vec::new(),
+                salts: HashSet::new(),
+                formulations: HashSet::new(),
+            }
+        } else {
+            Self::default()
+        };
+        for pattern in &file.patterns {
+            rules.patterns.push(
+                Regex::new(&format!("(?i){pattern}")).with_context(|| {
+                    format!("invalid pattern {pattern:?} in {}", path.display())
+                })?,
+            );
+        }
+        rules
+            .salts
+            .extend(file.salts.iter().map(|s| s.trim().to_ascii_uppercase()));
+        rules.formulations.extend(
+            file.formulations
+                .iter()
+                .map(|s| s.trim().to_ascii_uppercase()),
+        );
+        info!(
+            patterns = rules.patterns.len(),
+            salts = rules.salts.len(),
+            formulations = rules.formulations.len(),
+            "loaded drug name rules"
+        );
+        Ok(rules)
+    }
+
+    /// Upper-cased `name` with strengths, salts and dosage forms removed.
+    ///
+    /// Inorganic salts (`POTASSIUM CHLORIDE`, `FERROUS SULFATE`) keep both of their words.
+    /// Returns the trimmed, upper-cased input unchanged when the rules would strip everything.
+    pub fn apply(&self, name: &str) -> String {
+        let original = name.trim().to_ascii_uppercase();
+        let mut text = original.clone();
+        for pattern in &self.patterns {
+            text = pattern.replace_all(&text, " ").into_owned();
+        }
+        let tokens: Vec<&str> = text
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|t| !t.is_empty() && !self.formulations.contains(*t))
+            .collect();
+        // `SODIUM CHLORIDE` is a drug in its own right, not sodium with a counter-ion.
+        let inorganic = tokens
+            .first()
+            .is_some_and(|first| CATIONS.contains(first) || self.salts.contains(*first));
+        let kept: Vec<&str> = tokens
+            .iter()
+            .enumerate()
+            .filter(|(idx, token)| *idx == 0 || inorganic || !self.salts.contains(**token))
+            .map(|(_, token)| *token)
+            .collect();
+        if kept.is_empty() {
+            original
+        } else {
+            kept.join(" ")
+        }
+    }
+}
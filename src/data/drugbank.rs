@@ -0,0 +1,49 @@
+//! DrugBank open vocabulary import for offline drug synonym resolution.
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::config::Settings;
+
+#[derive(Debug, Deserialize)]
+struct VocabularyRow {
+    #[serde(rename = "Common name")]
+    common_name: String,
+    #[serde(rename = "Synonyms", default)]
+    synonyms: String,
+}
+
+/// Load `data/raw/drugbank/drugbank_vocabulary.csv` as an upper-cased synonym → ingredient map.
+///
+/// Returns an empty map when the vocabulary has not been downloaded.
+pub fn load_synonyms(settings: &Settings) -> Result<HashMap<String, String>> {
+    let path = settings.join_data("raw/drugbank/drugbank_vocabulary.csv");
+    let mut mapping = HashMap::new();
+    if !path.exists() {
+        return Ok(mapping);
+    }
+    let mut reader =
+        csv::Reader::from_path(&path).with_context(|| format!("open {}", path.display()))?;
+    for result in reader.deserialize() {
+        let row: VocabularyRow = result?;
+        let canonical = row.common_name.trim().to_lowercase();
+        if canonical.is_empty() {
+            continue;
+        }
+        mapping.insert(
+            row.common_name.trim().to_ascii_uppercase(),
+            canonical.clone(),
+        );
+        for synonym in row.synonyms.split('|') {
+            let key = synonym.trim().to_ascii_uppercase();
+            if !key.is_empty() {
+                mapping.entry(key).or_insert_with(|| canonical.clone());
+            }
+        }
+    }
+    info!(entries = mapping.len(), "loaded drugbank synonyms");
+    Ok(mapping)
+}
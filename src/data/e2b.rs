@@ -0,0 +1,271 @@
+//! ICH E2B (R2 and R3) individual case safety report import.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use tracing::{info, warn};
+
+use crate::{config::Settings, data::normalize, signals::trend};
+
+/// Prefix applied to E2B safety report ids so they never collide with FAERS ids.
+pub const CASE_PREFIX: &str = "E2B";
+
+/// HL7 OID identifying the worldwide unique safety report id in E2B(R3).
+const R3_REPORT_ID_ROOT: &str = "2.16.840.1.113883.3.989.2.1.3.1";
+/// HL7 OID for MedDRA coded values.
+const MEDDRA_CODE_SYSTEM: &str = "2.16.840.1.113883.6.163";
+/// E2B(R3) observation code for a reaction/event.
+const R3_REACTION_CODE: &str = "29";
+
+/// Case-level view of a single ICSR.
+#[derive(Debug, Clone, Default)]
+pub struct IcsrCase {
+    pub case_id: String,
+    pub receipt_date: Option<String>,
+    pub drugs: Vec<String>,
+    pub reactions: Vec<String>,
+}
+
+/// Import E2B XML files (a single file or a directory of `.xml` batches) into the raw case schema.
+///
+/// Files are read in name order and the last report seen for a case id is kept.
+pub fn import_reports(
+    source: &Path,
+    default_quarter: Option<&str>,
+    settings: &Settings,
+) -> Result<Vec<PathBuf>> {
+    let files = xml_files(source)?;
+    if files.is_empty() {
+        warn!(path = %source.display(), "no E2B xml files found");
+        return Ok(Vec::new());
+    }
+
+    // Rows per case id; a later report for the same case is a follow-up and replaces it.
+    let mut latest: IndexMap<String, (String, Vec<[String; 3]>)> = IndexMap::new();
+    let mut skipped = 0u64;
+    for file in &files {
+        let bytes = std::fs::read(file).with_context(|| format!("read {file:?}"))?;
+        let xml = String::from_utf8_lossy(&bytes);
+        let cases = parse_icsr_xml(&xml).with_context(|| format!("parse E2B file {file:?}"))?;
+        info!(file = %file.display(), cases = cases.len(), "parsed E2B reports");
+        for case in cases {
+            let quarter = case
+                .receipt_date
+                .as_deref()
+                .and_then(trend::quarter_from_date)
+                .or_else(|| default_quarter.map(str::to_string));
+            let Some(quarter) = quarter else {
+                skipped += 1;
+                continue;
+            };
+            let case_id = format!("{CASE_PREFIX}{}", case.case_id);
+            let mut rows = Vec::new();
+            for drug in &case.drugs {
+                for reaction in &case.reactions {
+                    rows.push([case_id.clone(), drug.clone(), reaction.clone()]);
+                }
+            }
+            latest.insert(case_id, (quarter, rows));
+        }
+    }
+    if skipped > 0 {
+        warn!(skipped, "skipped E2B reports without a receipt date");
+    }
+
+    let mut by_quarter: BTreeMap<String, Vec<[String; 3]>> = BTreeMap::new();
+    for (quarter, rows) in latest.into_values() {
+        by_quarter.entry(quarter).or_default().extend(rows);
+    }
+    normalize::write_raw_quarters(&settings.join_data("raw/e2b"), "e2b", &by_quarter)
+}
+
+/// Parse an E2B(R2) `ichicsr` or E2B(R3) HL7 message into case summaries.
+pub fn parse_icsr_xml(xml: &str) -> Result<Vec<IcsrCase>> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut parser = IcsrParser::default();
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                parser.open(&e)?;
+            }
+            Event::Empty(e) => {
+                parser.open(&e)?;
+                parser.close();
+            }
+            Event::Text(t) => parser.text(&t.unescape()?),
+            Event::CData(t) => parser.text(&String::from_utf8_lossy(&t)),
+            Event::End(_) => parser.close(),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(parser.cases)
+}
+
+#[derive(Default)]
+struct IcsrParser {
+    cases: Vec<IcsrCase>,
+    stack: Vec<String>,
+    current: Option<IcsrCase>,
+    r2_product: Option<String>,
+    r2_substance: Option<String>,
+    r2_pt: Option<String>,
+    r2_verbatim: Option<String>,
+    r3_observations: Vec<bool>,
+    r3_reaction: Option<String>,
+}
+
+impl IcsrParser {
+    fn open(&mut self, e: &BytesStart<'_>) -> Result<()> {
+        let name = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+        let parent = self.stack.last().map(String::as_str).unwrap_or_default();
+        match name.as_str() {
+            "safetyreport" | "investigationEvent" => self.current = Some(IcsrCase::default()),
+            "observation" => self.r3_observations.push(false),
+            "id" if parent == "investigationEvent"
+                && attr(e, "root")?.as_deref() == Some(R3_REPORT_ID_ROOT) =>
+            {
+                if let (Some(case), Some(ext)) = (self.current.as_mut(), attr(e, "extension")?) {
+                    case.case_id = ext;
+                }
+            }
+            "availabilityTime" if parent == "investigationEvent" => {
+                if let (Some(case), Some(value)) = (self.current.as_mut(), attr(e, "value")?) {
+                    case.receipt_date.get_or_insert(value);
+                }
+            }
+            "code" if parent == "observation" => {
+                let is_reaction = attr(e, "code")?.as_deref() == Some(R3_REACTION_CODE);
+                if let Some(flag) = self.r3_observations.last_mut() {
+                    *flag = is_reaction;
+                }
+            }
+            "value"
+                if parent == "observation"
+                    && self.r3_observations.last() == Some(&true)
+                    && attr(e, "codeSystem")?.as_deref() == Some(MEDDRA_CODE_SYSTEM) =>
+            {
+                self.r3_reaction = attr(e, "displayName")?.or(attr(e, "code")?);
+            }
+            _ => {}
+        }
+        self.stack.push(name);
+        Ok(())
+    }
+
+    fn text(&mut self, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+        let depth = self.stack.len();
+        let parent = self.stack.last().map(String::as_str).unwrap_or_default();
+        let grandparent = depth
+            .checked_sub(2)
+            .map(|idx| self.stack[idx].as_str())
+            .unwrap_or_default();
+        let Some(case) = self.current.as_mut() else {
+            return;
+        };
+        match (grandparent, parent) {
+            (_, "safetyreportid") => case.case_id = text.to_string(),
+            (_, "receiptdate") => case.receipt_date = Some(text.to_string()),
+            (_, "medicinalproduct") => self.r2_product = Some(text.to_string()),
+            (_, "activesubstancename") => self.r2_substance = Some(text.to_string()),
+            (_, "reactionmeddrapt") => self.r2_pt = Some(text.to_string()),
+            (_, "primarysourcereaction") => self.r2_verbatim = Some(text.to_string()),
+            ("kindOfProduct", "name") => push_unique(&mut case.drugs, text),
+            ("value", "originalText") if self.r3_reaction.is_some() => {
+                self.r3_reaction = Some(text.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    fn close(&mut self) {
+        let Some(name) = self.stack.pop() else {
+            return;
+        };
+        match name.as_str() {
+            "drug" => {
+                let drug = self.r2_product.take().or(self.r2_substance.take());
+                self.r2_substance = None;
+                if let (Some(case), Some(drug)) = (self.current.as_mut(), drug) {
+                    push_unique(&mut case.drugs, &drug);
+                }
+            }
+            "reaction" => {
+                let reaction = self.r2_pt.take().or(self.r2_verbatim.take());
+                self.r2_verbatim = None;
+                if let (Some(case), Some(reaction)) = (self.current.as_mut(), reaction) {
+                    push_unique(&mut case.reactions, &reaction);
+                }
+            }
+            "value" => {
+                if let (Some(case), Some(reaction)) =
+                    (self.current.as_mut(), self.r3_reaction.take())
+                {
+                    push_unique(&mut case.reactions, &reaction);
+                }
+            }
+            "observation" => {
+                self.r3_observations.pop();
+            }
+            "safetyreport" | "investigationEvent" => {
+                if let Some(case) = self.current.take() {
+                    if case.case_id.is_empty() || case.drugs.is_empty() || case.reactions.is_empty()
+                    {
+                        warn!(case_id = %case.case_id, "skipping incomplete E2B report");
+                    } else {
+                        self.cases.push(case);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn attr(e: &BytesStart<'_>, key: &str) -> Result<Option<String>> {
+    for attribute in e.attributes() {
+        let attribute = attribute?;
+        if attribute.key.local_name().as_ref() == key.as_bytes() {
+            return Ok(Some(attribute.unescape_value()?.into_owned()));
+        }
+    }
+    Ok(None)
+}
+
+fn push_unique(values: &mut Vec<String>, value: &str) {
+    if !values.iter().any(|v| v.eq_ignore_ascii_case(value)) {
+        values.push(value.to_string());
+    }
+}
+
+fn xml_files(source: &Path) -> Result<Vec<PathBuf>> {
+    if source.is_file() {
+        return Ok(vec![source.to_path_buf()]);
+    }
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(source).with_context(|| format!("read dir {source:?}"))? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .and_then(|s| s.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("xml"))
+        {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,393 @@
+//! Pipeline tables converted for people outside the pipeline: formatted workbooks, JSON
+//! lines, or a zip bundle of every clean artefact.
+
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use polars::prelude::{AnyValue, ParquetReader, SerReader};
+use rust_xlsxwriter::{Format, FormatBorder, Workbook};
+use tracing::debug;
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::config::Settings;
+
+/// Rows Excel can hold below the header.
+const MAX_XLSX_ROWS: usize = 1_048_575;
+
+/// One value of a [`Table`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl Cell {
+    /// Typed value of a CSV field: integer, float, boolean, or text; empty is null.
+    pub fn infer(field: &str) -> Self {
+        if field.is_empty() {
+            Self::Null
+        } else if let Ok(number) = field.parse() {
+            Self::Int(number)
+        } else if let Ok(number) = field.parse() {
+            Self::Float(number)
+        } else if let Ok(flag) = field.parse() {
+            Self::Bool(flag)
+        } else {
+            Self::Text(field.to_string())
+        }
+    }
+
+    fn from_any(value: AnyValue) -> Self {
+        match value {
+            AnyValue::Null => Self::Null,
+            AnyValue::Boolean(flag) => Self::Bool(flag),
+            AnyValue::String(text) => Self::Text(text.to_string()),
+            AnyValue::StringOwned(text) => Self::Text(text.to_string()),
+            AnyValue::Int8(n) => Self::Int(n.into()),
+            AnyValue::Int16(n) => Self::Int(n.into()),
+            AnyValue::Int32(n) => Self::Int(n.into()),
+            AnyValue::Int64(n) => Self::Int(n),
+            AnyValue::UInt8(n) => Self::Int(n.into()),
+            AnyValue::UInt16(n) => Self::Int(n.into()),
+            AnyValue::UInt32(n) => Self::Int(n.into()),
+            AnyValue::UInt64(n) => i64::try_from(n).map_or(Self::Float(n as f64), Self::Int),
+            AnyValue::Float32(n) => Self::Float(n.into()),
+            AnyValue::Float64(n) => Self::Float(n),
+            other => Self::Text(other.to_string()),
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Int(n) => Some(*n as f64),
+            Self::Float(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Self::Null => serde_json::Value::Null,
+            Self::Bool(flag) => (*flag).into(),
+            Self::Int(n) => (*n).into(),
+            // NaN and infinities have no JSON form and become null.
+            Self::Float(n) => {
+                serde_json::Number::from_f64(*n).map_or(serde_json::Value::Null, Into::into)
+            }
+            Self::Text(text) => text.as_str().into(),
+        }
+    }
+}
+
+/// Plain text of the value, empty for null, as written to CSV and terminal tables.
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Null => Ok(()),
+            Self::Bool(flag) => write!(f, "{flag}"),
+            Self::Int(n) => write!(f, "{n}"),
+            Self::Float(n) => write!(f, "{n}"),
+            Self::Text(text) => f.write_str(text),
+        }
+    }
+}
+
+/// Comparison of a [`Filter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Row condition written `column<op>value`, e.g. `score>=2` or `smq!=`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    pub column: String,
+    pub op: Op,
+    pub value: String,
+}
+
+impl Filter {
+    pub fn parse(text: &str) -> Result<Self> {
+        // Two-character operators first, so `>=` is not read as `>` followed by `=value`.
+        const OPS: &[(&str, Op)] = &[
+            (">=", Op::Ge),
+            ("<=", Op::Le),
+            ("!=", Op::Ne),
+            ("==", Op::Eq),
+            ("=", Op::Eq),
+            (">", Op::Gt),
+            ("<", Op::Lt),
+        ];
+        let Some((at, symbol, op)) = OPS
+            .iter()
+            .filter_map(|(symbol, op)| text.find(symbol).map(|at| (at, *symbol, *op)))
+            .min_by_key(|(at, symbol, _)| (*at, usize::MAX - symbol.len()))
+        else {
+            bail!("filter {text:?} is not `column<op>value` with one of = != < <= > >=");
+        };
+        let column = text[..at].trim();
+        if column.is_empty() {
+            bail!("filter {text:?} names no column");
+        }
+        Ok(Self {
+            column: column.to_string(),
+            op,
+            value: text[at + symbol.len()..].trim().to_string(),
+        })
+    }
+
+    /// Numbers compare numerically and text case-insensitively; a null cell equals only the
+    /// empty value.
+    fn matches(&self, cell: &Cell) -> bool {
+        let ordering = match (cell, cell.as_f64(), self.value.parse::<f64>()) {
+            (Cell::Null, ..) => {
+                return match self.op {
+                    Op::Eq => self.value.is_empty(),
+                    Op::Ne => !self.value.is_empty(),
+                    _ => false,
+                }
+            }
+            (_, Some(number), Ok(value)) => number.partial_cmp(&value),
+            (Cell::Bool(flag), ..) => Some(flag.to_string().cmp(&self.value.to_ascii_lowercase())),
+            (Cell::Text(text), ..) => Some(text.to_lowercase().cmp(&self.value.to_lowercase())),
+            _ => None,
+        };
+        let Some(ordering) = ordering else {
+            return false;
+        };
+        match self.op {
+            Op::Eq => ordering.is_eq(),
+            Op::Ne => ordering.is_ne(),
+            Op::Lt => ordering.is_lt(),
+            Op::Le => ordering.is_le(),
+            Op::Gt => ordering.is_gt(),
+            Op::Ge => ordering.is_ge(),
+        }
+    }
+}
+
+/// A pipeline artefact read into memory, columns in file order.
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    pub fn read_csv(path: &Path) -> Result<Self> {
+        let mut reader = csv::Reader::from_path(path).with_context(|| format!("open {path:?}"))?;
+        let columns = reader.headers()?.iter().map(str::to_string).collect();
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            rows.push(record?.iter().map(Cell::infer).collect());
+        }
+        Ok(Self { columns, rows })
+    }
+
+    pub fn read_parquet(path: &Path) -> Result<Self> {
+        let df = ParquetReader::new(File::open(path).with_context(|| format!("open {path:?}"))?)
+            .finish()?;
+        let columns = df
+            .get_column_names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        let mut rows = Vec::with_capacity(df.height());
+        for i in 0..df.height() {
+            let row = df
+                .get_columns()
+                .iter()
+                .map(|series| series.get(i).map(Cell::from_any))
+                .collect::<Result<_, _>>()?;
+            rows.push(row);
+        }
+        Ok(Self { columns, rows })
+    }
+
+    pub fn position(&self, column: &str) -> Result<usize> {
+        match self.columns.iter().position(|name| name == column) {
+            Some(index) => Ok(index),
+            None => bail!(
+                "no column {column:?}; available: {}",
+                self.columns.join(", ")
+            ),
+        }
+    }
+
+    /// Insert `column` at `index`, with one value per row.
+    pub fn insert_column(&mut self, index: usize, column: &str, values: Vec<Cell>) {
+        self.columns.insert(index, column.to_string());
+        for (row, value) in self.rows.iter_mut().zip(values) {
+            row.insert(index, value);
+        }
+    }
+
+    /// Keep the rows matching every filter.
+    pub fn retain(&mut self, filters: &[Filter]) -> Result<()> {
+        let filters = filters
+            .iter()
+            .map(|filter| Ok((self.position(&filter.column)?, filter)))
+            .collect::<Result<Vec<_>>>()?;
+        self.rows.retain(|row| {
+            filters
+                .iter()
+                .all(|(index, filter)| filter.matches(&row[*index]))
+        });
+        Ok(())
+    }
+
+    /// Keep only `columns`, in that order.
+    pub fn select(&mut self, columns: &[String]) -> Result<()> {
+        let indexes = columns
+            .iter()
+            .map(|column| self.position(column))
+            .collect::<Result<Vec<_>>>()?;
+        self.rows = self
+            .rows
+            .iter()
+            .map(|row| indexes.iter().map(|&index| row[index].clone()).collect())
+            .collect();
+        self.columns = columns.to_vec();
+        Ok(())
+    }
+
+    /// CSV with a header row.
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(&self.columns)?;
+        for row in &self.rows {
+            writer.write_record(row.iter().map(Cell::to_string))?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// One JSON object per row, keys in column order.
+    pub fn write_jsonl<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let keys = self
+            .columns
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()?;
+        for row in &self.rows {
+            let fields = keys
+                .iter()
+                .zip(row)
+                .map(|(key, cell)| Ok(format!("{key}:{}", serde_json::to_string(&cell.to_json())?)))
+                .collect::<Result<Vec<_>>>()?;
+            writeln!(writer, "{{{}}}", fields.join(","))?;
+        }
+        Ok(())
+    }
+
+    /// A workbook with a bold, frozen and filterable header row, two-decimal numbers and
+    /// columns sized to their contents.
+    pub fn write_xlsx(&self, path: &Path, sheet_name: &str) -> Result<()> {
+        if self.rows.len() > MAX_XLSX_ROWS {
+            bail!(
+                "{} rows exceed the Excel limit of {MAX_XLSX_ROWS}; add filters or export JSON lines",
+                self.rows.len()
+            );
+        }
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet().set_name(sheet_name)?;
+        let header = Format::new()
+            .set_bold()
+            .set_border_bottom(FormatBorder::Thin);
+        let decimal = Format::new().set_num_format("0.00");
+        for (col, name) in self.columns.iter().enumerate() {
+            sheet.write_string_with_format(0, col as u16, name, &header)?;
+        }
+        for (index, row) in self.rows.iter().enumerate() {
+            let excel_row = index as u32 + 1;
+            for (col, cell) in row.iter().enumerate() {
+                let col = col as u16;
+                match cell {
+                    Cell::Null => {}
+                    Cell::Bool(flag) => {
+                        sheet.write_boolean(excel_row, col, *flag)?;
+                    }
+                    Cell::Int(n) => {
+                        sheet.write_number(excel_row, col, *n as f64)?;
+                    }
+                    Cell::Float(n) if n.is_finite() => {
+                        sheet.write_number_with_format(excel_row, col, *n, &decimal)?;
+                    }
+                    Cell::Float(n) => {
+                        sheet.write_string(excel_row, col, n.to_string())?;
+                    }
+                    Cell::Text(text) => {
+                        sheet.write_string(excel_row, col, text)?;
+                    }
+                }
+            }
+        }
+        if !self.columns.is_empty() {
+            sheet.set_freeze_panes(1, 0)?;
+            sheet.autofilter(0, 0, self.rows.len() as u32, self.columns.len() as u16 - 1)?;
+        }
+        sheet.autofit();
+        workbook
+            .save(path)
+            .with_context(|| format!("write {path:?}"))?;
+        Ok(())
+    }
+}
+
+/// Zip every file under `data/clean` and `signals.csv` into `path`, stored as
+/// `clean/...` and `outputs/signals.csv`; returns the number of files written.
+pub fn write_bundle(settings: &Settings, path: &Path) -> Result<usize> {
+    let mut files = Vec::new();
+    collect_files(&settings.join_data("clean"), Path::new("clean"), &mut files)?;
+    let signals = settings.join_output("signals.csv");
+    if signals.is_file() {
+        files.push((signals, "outputs/signals.csv".to_string()));
+    }
+    if files.is_empty() {
+        bail!("no clean artefacts to bundle; run normalize first");
+    }
+    let out = File::create(path).with_context(|| format!("create {path:?}"))?;
+    let mut zip = ZipWriter::new(BufWriter::new(out));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (source, name) in &files {
+        debug!(%name, "adding to bundle");
+        zip.start_file(name.as_str(), options)?;
+        let mut file = File::open(source).with_context(|| format!("open {source:?}"))?;
+        io::copy(&mut file, &mut zip)?;
+    }
+    zip.finish()?.flush()?;
+    Ok(files.len())
+}
+
+fn collect_files(dir: &Path, prefix: &Path, files: &mut Vec<(PathBuf, String)>) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    let mut entries = std::fs::read_dir(dir)
+        .with_context(|| format!("read {dir:?}"))?
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let name = prefix.join(entry.file_name());
+        if path.is_dir() {
+            collect_files(&path, &name, files)?;
+        } else {
+            files.push((path, name.to_string_lossy().replace('\\', "/")));
+        }
+    }
+    Ok(())
+}
@@ -1,35 +1,52 @@
 //! FAERS ingestion utilities.
 
 use std::{
-    collections::HashMap,
-    fs::File,
-    io::{Read, Write},
+    borrow::Cow,
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs::{File, OpenOptions},
+    hash::{Hash, Hasher},
+    io::{BufRead, BufReader, Read, Seek, Write},
     path::{Path, PathBuf},
 };
 
 use anyhow::{anyhow, Context, Result};
-use chrono::Utc;
-use reqwest::Client;
+use chrono::{Datelike, Utc};
+use encoding_rs::WINDOWS_1252;
+use reqwest::{
+    header::{CONTENT_RANGE, RANGE},
+    Method, StatusCode,
+};
+use sha2::{Digest, Sha256};
 use tracing::{info, warn};
 use zip::ZipArchive;
 
-use crate::config::Settings;
+use crate::{
+    config::Settings,
+    data::manifest,
+    net::{self, HttpClient},
+    progress,
+    signals::trend,
+};
 
-const BASE_URLS: &[&str] = &[
+pub(crate) const BASE_URLS: &[&str] = &[
     "https://download-001.fda.gov/faers",
     "https://download-002.fda.gov/faers",
     "https://download-003.fda.gov/faers",
 ];
 
+/// Case-id hash partitions for the DRUG/REAC join; one partition's reactions are held in
+/// memory at a time.
+const JOIN_PARTITIONS: usize = 64;
+
+/// First quarter published as a FAERS ASCII archive; earlier ones were legacy AERS.
+pub const FIRST_QUARTER: (i32, u8) = (2012, 4);
+
 /// Download and cache FAERS quarterly archives, returning filtered CSV paths.
 pub async fn fetch_faers_quarters(
     quarters: &[String],
     settings: &Settings,
 ) -> Result<Vec<PathBuf>> {
-    let client = Client::builder()
-        .user_agent(format!("rwe-assistant/0.1 (+{})", settings.pubmed_email))
-        .gzip(true)
-        .build()?;
+    let client = net::client(settings)?;
 
     let dest_root = settings.join_data("raw/faers");
     std::fs::create_dir_all(&dest_root)?;
@@ -38,16 +55,53 @@ pub async fn fetch_faers_quarters(
     for quarter in quarters {
         let archive_name = format!("FAERS_ASCII_{quarter}.zip");
         let archive_path = dest_root.join(&archive_name);
+        let filtered_path = dest_root.join(format!("faers_{quarter}.csv"));
+        if settings.offline && filtered_path.exists() {
+            info!(%quarter, "offline: using cached filtered faers file");
+            outputs.push(filtered_path);
+            continue;
+        }
+        if archive_path.exists() && !filtered_path.exists() {
+            if let Err(err) = validate_archive(&archive_path) {
+                warn!(%quarter, %err, "cached faers archive is corrupt");
+                quarantine(&archive_path)?;
+            }
+        }
         if !archive_path.exists() {
-            download_archive(&client, quarter, &archive_path).await?;
+            net::ensure_online(
+                settings,
+                &format!("FAERS archive {}", archive_path.display()),
+            )?;
+            let mut source = download_archive(client, quarter, &archive_path).await?;
+            if let Err(err) = validate_archive(&archive_path) {
+                warn!(%quarter, %err, "downloaded faers archive is corrupt; retrying once");
+                quarantine(&archive_path)?;
+                source = download_archive(client, quarter, &archive_path).await?;
+                validate_archive(&archive_path).with_context(|| {
+                    format!("FAERS archive for {quarter} is still invalid after redownload")
+                })?;
+            }
+            manifest::record(
+                settings,
+                &archive_path,
+                &source,
+                std::slice::from_ref(quarter),
+                &[],
+            )?;
         } else {
             info!(%quarter, "using cached faers archive");
         }
 
-        let filtered_path = dest_root.join(format!("faers_{quarter}.csv"));
         if !filtered_path.exists() {
             info!(%quarter, "filtering faers archive");
             filter_archive(&archive_path, quarter, &filtered_path)?;
+            manifest::record(
+                settings,
+                &filtered_path,
+                &manifest::file_source(&archive_path),
+                std::slice::from_ref(quarter),
+                &[],
+            )?;
         }
         outputs.push(filtered_path);
     }
@@ -55,86 +109,398 @@ pub async fn fetch_faers_quarters(
     Ok(outputs)
 }
 
-async fn download_archive(client: &Client, quarter: &str, dest: &Path) -> Result<()> {
+/// Quarters already cached locally, as filtered CSVs or downloaded archives.
+pub fn cached_quarters(settings: &Settings) -> Result<Vec<(i32, u8)>> {
+    let root = settings.join_data("raw/faers");
+    let mut quarters = Vec::new();
+    if !root.exists() {
+        return Ok(quarters);
+    }
+    for entry in std::fs::read_dir(root)? {
+        let name = entry?.file_name().to_string_lossy().to_ascii_uppercase();
+        let stem = name
+            .strip_prefix("FAERS_ASCII_")
+            .and_then(|rest| rest.strip_suffix(".ZIP"))
+            .or_else(|| {
+                name.strip_prefix("FAERS_")
+                    .and_then(|rest| rest.strip_suffix(".CSV"))
+            });
+        if let Some(quarter) = stem.and_then(trend::parse_quarter) {
+            quarters.push(quarter);
+        }
+    }
+    quarters.sort_unstable();
+    quarters.dedup();
+    Ok(quarters)
+}
+
+/// Probe the FDA mirrors for every quarter from `since` through the current calendar quarter.
+///
+/// Quarters before [`FIRST_QUARTER`] are not probed.
+pub async fn discover_quarters(since: (i32, u8), settings: &Settings) -> Result<Vec<String>> {
+    net::ensure_online(settings, "the list of published FAERS quarters")?;
+    let client = net::client(settings)?;
+    let today = Utc::now();
+    let current = (today.year(), (today.month0() / 3 + 1) as u8);
+
+    let mut available = Vec::new();
+    let mut quarter = since;
+    if quarter < FIRST_QUARTER {
+        warn!(
+            since = %trend::format_quarter(since),
+            first = %trend::format_quarter(FIRST_QUARTER),
+            "no FAERS archives before the first published quarter; starting there"
+        );
+        quarter = FIRST_QUARTER;
+    }
+    while quarter <= current {
+        let label = trend::format_quarter(quarter);
+        if archive_exists(client, &label).await {
+            available.push(label);
+        } else {
+            info!(quarter = %label, "faers quarter not published yet");
+        }
+        quarter = trend::next_quarter(quarter);
+    }
+    Ok(available)
+}
+
+/// Find the most recent published quarter, looking back at most two years.
+pub async fn latest_published_quarter(settings: &Settings) -> Result<Option<String>> {
+    net::ensure_online(settings, "the latest published FAERS quarter")?;
+    let client = net::client(settings)?;
+    let today = Utc::now();
+    let (mut year, mut quarter) = (today.year(), (today.month0() / 3 + 1) as u8);
+    for _ in 0..8 {
+        let label = trend::format_quarter((year, quarter));
+        if archive_exists(client, &label).await {
+            return Ok(Some(label));
+        }
+        (year, quarter) = if quarter == 1 {
+            (year - 1, 4)
+        } else {
+            (year, quarter - 1)
+        };
+    }
+    Ok(None)
+}
+
+async fn archive_exists(client: &HttpClient, quarter: &str) -> bool {
     for base in BASE_URLS {
         let url = format!("{base}/FAERS_ASCII_{quarter}.zip");
-        info!(%url, "attempting FAERS download");
-        match client.get(&url).send().await {
-            Ok(resp) if resp.status().is_success() => {
-                let bytes = resp.bytes().await?;
-                let mut file = File::create(dest).with_context(|| format!("create {dest:?}"))?;
-                file.write_all(&bytes)?;
-                info!(?dest, size = bytes.len(), "downloaded faers archive");
-                return Ok(());
+        if let Ok(resp) = client.head(&url).await {
+            if resp.status().is_success() {
+                return true;
             }
-            Ok(resp) => {
-                warn!(status = %resp.status(), "failed url, trying next mirror");
+        }
+    }
+    false
+}
+
+/// Download from the first mirror that serves the archive, returning its URL.
+async fn download_archive(client: &HttpClient, quarter: &str, dest: &Path) -> Result<String> {
+    let part = dest.with_extension("zip.part");
+    for base in BASE_URLS {
+        let url = format!("{base}/FAERS_ASCII_{quarter}.zip");
+        info!(%url, "attempting FAERS download");
+        match download_resumable(client, &url, &part).await {
+            Ok(size) => {
+                std::fs::rename(&part, dest)
+                    .with_context(|| format!("rename {part:?} to {dest:?}"))?;
+                info!(?dest, size, "downloaded faers archive");
+                return Ok(url);
             }
-            Err(err) => warn!(%err, "download error, next mirror"),
+            Err(err) => warn!(%err, "download failed, trying next mirror"),
         }
     }
     Err(anyhow!("unable to download FAERS archive for {quarter}"))
 }
 
-fn filter_archive(archive_path: &Path, quarter: &str, dest_csv: &Path) -> Result<()> {
-    let file =
-        File::open(archive_path).with_context(|| format!("open archive {archive_path:?}"))?;
-    let mut archive = ZipArchive::new(file)?;
+/// Stream `url` into `part`, resuming from any bytes already on disk via an HTTP Range request.
+///
+/// Returns the final file size once it matches the size advertised by the server.
+async fn download_resumable(client: &HttpClient, url: &str, part: &Path) -> Result<u64> {
+    let existing = std::fs::metadata(part).map(|m| m.len()).unwrap_or(0);
+    let mut request = client.request(Method::GET, url);
+    if existing > 0 {
+        info!(bytes = existing, "resuming partial download");
+        request = request.header(RANGE, format!("bytes={existing}-"));
+    }
+    let mut resp = client.send(request).await?;
+    let status = resp.status();
+    if status == StatusCode::RANGE_NOT_SATISFIABLE {
+        std::fs::remove_file(part)?;
+        return Err(anyhow!(
+            "server rejected resume offset; partial file discarded"
+        ));
+    }
+    if !status.is_success() {
+        return Err(anyhow!("unexpected status {status}"));
+    }
 
-    let mut drug_map: HashMap<String, Vec<String>> = HashMap::new();
-    let mut reaction_map: HashMap<String, Vec<String>> = HashMap::new();
+    let resumed = status == StatusCode::PARTIAL_CONTENT;
+    let expected_total = if resumed {
+        resp.headers()
+            .get(CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit('/').next())
+            .and_then(|total| total.parse::<u64>().ok())
+    } else {
+        resp.content_length()
+    };
+    let mut file = if resumed {
+        OpenOptions::new().append(true).open(part)?
+    } else {
+        File::create(part)?
+    };
+    let mut written = if resumed { existing } else { 0 };
+    let name = url.rsplit('/').next().unwrap_or(url).to_string();
+    let bar = progress::bytes(name, expected_total);
+    bar.set_position(written);
+    // Rate and ETA cover this session only, not bytes resumed from disk.
+    bar.reset_eta();
+    while let Some(chunk) = resp.chunk().await? {
+        file.write_all(&chunk)?;
+        written += chunk.len() as u64;
+        bar.set_position(written);
+    }
+    file.flush()?;
+    bar.finish_and_clear();
 
-    for i in 0..archive.len() {
-        let mut entry = archive.by_index(i)?;
-        let name = entry.name().to_ascii_uppercase();
-        if !name.ends_with(".TXT") {
-            continue;
+    if let Some(total) = expected_total {
+        if written != total {
+            return Err(anyhow!("incomplete download: {written} of {total} bytes"));
         }
-        if name.contains("DRUG") {
-            info!(file = %entry.name(), "processing drug file");
-            let text = read_to_string(&mut entry)?;
-            parse_table(&text, "CASEID", "DRUGNAME", &mut drug_map)?;
-        } else if name.contains("REAC") {
-            info!(file = %entry.name(), "processing reaction file");
-            let text = read_to_string(&mut entry)?;
-            parse_table(&text, "CASEID", "PT", &mut reaction_map)?;
+    }
+    Ok(written)
+}
+
+/// Check that an archive has a readable ZIP central directory, DRUG and REAC members, and
+/// matches the SHA-256 in an optional `<archive>.sha256` sidecar file.
+pub fn validate_archive(path: &Path) -> Result<()> {
+    let file = File::open(path).with_context(|| format!("open archive {path:?}"))?;
+    let archive = ZipArchive::new(file).context("unreadable ZIP central directory")?;
+    let names: Vec<String> = archive
+        .file_names()
+        .map(|name| name.to_ascii_uppercase())
+        .collect();
+    for required in ["DRUG", "REAC"] {
+        if !names
+            .iter()
+            .any(|name| name.ends_with(".TXT") && name.contains(required))
+        {
+            return Err(anyhow!("archive has no {required} member file"));
         }
     }
 
+    let sidecar = PathBuf::from(format!("{}.sha256", path.display()));
+    if sidecar.exists() {
+        let expected = std::fs::read_to_string(&sidecar)?
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut File::open(path)?, &mut hasher)?;
+        let actual = format!("{:x}", hasher.finalize());
+        if actual != expected {
+            return Err(anyhow!(
+                "sha256 mismatch: expected {expected}, got {actual}"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Move a corrupt archive into `quarantine/` next to it so it can be inspected and re-fetched.
+fn quarantine(path: &Path) -> Result<PathBuf> {
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    let dir = parent.join("quarantine");
+    std::fs::create_dir_all(&dir)?;
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "archive.zip".to_string());
+    let target = dir.join(format!(
+        "{}.{file_name}",
+        Utc::now().format("%Y%m%dT%H%M%S")
+    ));
+    std::fs::rename(path, &target).with_context(|| format!("quarantine {path:?}"))?;
+    warn!(from = %path.display(), to = %target.display(), "quarantined archive");
+    Ok(target)
+}
+
+/// Join the quarter's DRUG and REAC tables on case id into `dest_csv`.
+///
+/// Both tables are spilled to case-id hash partitions next to `dest_csv`, so only one
+/// partition's reactions are held in memory while its drug rows are joined.
+pub fn filter_archive(archive_path: &Path, quarter: &str, dest_csv: &Path) -> Result<()> {
+    let file =
+        File::open(archive_path).with_context(|| format!("open archive {archive_path:?}"))?;
+    let mut archive = ZipArchive::new(file)?;
+    let reac_idx = member_index(&mut archive, "REAC")?;
+    let drug_idx = member_index(&mut archive, "DRUG")?;
+
+    let spill_dir = dest_csv.with_extension("parts");
+    std::fs::create_dir_all(&spill_dir).with_context(|| format!("create {spill_dir:?}"))?;
+    let entry = archive.by_index(reac_idx)?;
+    info!(file = %entry.name(), "partitioning reaction file");
+    let reactions = partition_table(entry, &["CASEID", "PT"], &spill_dir, "reac")?;
+    let entry = archive.by_index(drug_idx)?;
+    info!(file = %entry.name(), "partitioning drug file");
+    let drugs = partition_table(
+        entry,
+        &["CASEID", "DRUGNAME", "ROLE_COD"],
+        &spill_dir,
+        "drug",
+    )?;
+
     let mut writer = csv::Writer::from_path(dest_csv)?;
-    writer.write_record(["CASEID", "DRUGNAME", "PT", "YEAR_QUARTER"])?;
+    writer.write_record(["CASEID", "DRUGNAME", "PT", "YEAR_QUARTER", "ROLE_COD"])?;
 
     let mut count = 0u64;
-    for (case, drugs) in &drug_map {
-        if let Some(events) = reaction_map.get(case) {
-            for drug in drugs {
+    let mut record = csv::StringRecord::new();
+    for (reac_path, drug_path) in reactions.iter().zip(&drugs) {
+        let mut reaction_map: HashMap<String, Vec<String>> = HashMap::new();
+        let mut rows = spill_reader(reac_path)?;
+        while rows.read_record(&mut record)? {
+            reaction_map
+                .entry(record[0].to_string())
+                .or_default()
+                .push(record[1].to_string());
+        }
+        let mut rows = spill_reader(drug_path)?;
+        while rows.read_record(&mut record)? {
+            let (case, drug, role) = (&record[0], &record[1], &record[2]);
+            if let Some(events) = reaction_map.get(case) {
                 for event in events {
-                    writer.write_record([case, drug, event, quarter])?;
+                    writer.write_record([case, drug, event, quarter, role])?;
                     count += 1;
                 }
             }
         }
     }
     writer.flush()?;
+    std::fs::remove_dir_all(&spill_dir).with_context(|| format!("remove {spill_dir:?}"))?;
     info!(rows = count, path = %dest_csv.display(), "wrote filtered FAERS file");
     Ok(())
 }
 
-fn read_to_string(entry: &mut zip::read::ZipFile<'_>) -> Result<String> {
-    let mut buf = String::new();
-    entry.read_to_string(&mut buf)?;
-    Ok(buf)
+/// Stream `columns` of a FAERS table into [`JOIN_PARTITIONS`] CSV files under `dir`, keyed by
+/// a hash of the first column (the case id), and return their paths in partition order.
+fn partition_table<R: Read>(
+    reader: R,
+    columns: &[&str],
+    dir: &Path,
+    kind: &str,
+) -> Result<Vec<PathBuf>> {
+    let paths: Vec<PathBuf> = (0..JOIN_PARTITIONS)
+        .map(|idx| dir.join(format!("{kind}-{idx:02}.csv")))
+        .collect();
+    let mut writers = paths
+        .iter()
+        .map(csv::Writer::from_path)
+        .collect::<csv::Result<Vec<_>>>()?;
+    stream_table(reader, columns, |fields| {
+        let mut hasher = DefaultHasher::new();
+        fields[0].hash(&mut hasher);
+        writers[hasher.finish() as usize % JOIN_PARTITIONS].write_record(fields)?;
+        Ok(())
+    })?;
+    for writer in &mut writers {
+        writer.flush()?;
+    }
+    Ok(paths)
+}
+
+fn spill_reader(path: &Path) -> Result<csv::Reader<File>> {
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_path(path)
+        .with_context(|| format!("open {path:?}"))
+}
+
+/// Locate the `.TXT` member whose name contains `kind` (e.g., `DRUG`, `REAC`).
+fn member_index<R: Read + Seek>(archive: &mut ZipArchive<R>, kind: &str) -> Result<usize> {
+    for i in 0..archive.len() {
+        let name = archive.by_index(i)?.name().to_ascii_uppercase();
+        if name.ends_with(".TXT") && name.contains(kind) {
+            return Ok(i);
+        }
+    }
+    Err(anyhow!("archive has no {kind} member file"))
 }
 
-fn parse_table(
-    text: &str,
-    case_header: &str,
-    value_header: &str,
-    sink: &mut HashMap<String, Vec<String>>,
+/// Stream a delimited FAERS table, calling `sink` with the requested `columns` of each row.
+///
+/// Rows whose first two columns (case id and value) are empty are skipped. Fields that are
+/// not valid UTF-8 (older quarters carry Latin-1 bytes) are decoded as Windows-1252 rather
+/// than aborting the quarter.
+fn stream_table<R: Read>(
+    reader: R,
+    columns: &[&str],
+    mut sink: impl FnMut(&[&str]) -> Result<()>,
 ) -> Result<()> {
-    let mut lines = text.lines();
-    let header_line = lines.next().ok_or_else(|| anyhow!("missing header"))?;
-    let delimiter = if header_line.contains('|') {
+    let mut buffered = BufReader::new(reader);
+    let mut header_bytes = Vec::new();
+    if buffered.read_until(b'\n', &mut header_bytes)? == 0 {
+        return Err(anyhow!("missing header"));
+    }
+    let header_line = decode_field(&header_bytes);
+    let delimiter = detect_delimiter(&header_line);
+    let headers: Vec<&str> = header_line.trim_end().split(delimiter).collect();
+    let indices = columns
+        .iter()
+        .map(|column| {
+            headers
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(column))
+                .ok_or_else(|| anyhow!("missing {column}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut rows = csv::ReaderBuilder::new()
+        .delimiter(delimiter as u8)
+        .has_headers(false)
+        .flexible(true)
+        .quoting(false)
+        .from_reader(buffered);
+    let mut record = csv::ByteRecord::new();
+    let mut recoded = 0u64;
+    while rows.read_byte_record(&mut record)? {
+        let fields: Vec<Cow<'_, str>> = indices
+            .iter()
+            .map(|&idx| decode_field(record.get(idx).unwrap_or_default()))
+            .collect();
+        if fields.iter().any(|f| matches!(f, Cow::Owned(_))) {
+            recoded += 1;
+        }
+        let fields: Vec<&str> = fields.iter().map(|f| f.trim()).collect();
+        if fields.iter().take(2).any(|f| f.is_empty()) {
+            continue;
+        }
+        sink(&fields)?;
+    }
+    if recoded > 0 {
+        warn!(
+            rows = recoded,
+            "decoded non-UTF-8 FAERS rows as Windows-1252"
+        );
+    }
+    Ok(())
+}
+
+/// Decode a raw field as UTF-8, falling back to Windows-1252 (a superset of Latin-1).
+fn decode_field(bytes: &[u8]) -> Cow<'_, str> {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Cow::Borrowed(text),
+        Err(_) => WINDOWS_1252.decode_without_bom_handling(bytes).0,
+    }
+}
+
+/// Guess the column delimiter used by a FAERS-style text header line.
+pub fn detect_delimiter(header_line: &str) -> char {
+    if header_line.contains('|') {
         '|'
     } else if header_line.contains('$') {
         '$'
@@ -142,33 +508,7 @@ fn parse_table(
         '\t'
     } else {
         ','
-    };
-    let headers: Vec<&str> = header_line.split(delimiter).collect();
-    let case_idx = headers
-        .iter()
-        .position(|h| h.eq_ignore_ascii_case(case_header))
-        .ok_or_else(|| anyhow!("missing {case_header}"))?;
-    let value_idx = headers
-        .iter()
-        .position(|h| h.eq_ignore_ascii_case(value_header))
-        .ok_or_else(|| anyhow!("missing {value_header}"))?;
-
-    for line in lines {
-        let cols: Vec<&str> = line.split(delimiter).collect();
-        if cols.len() <= case_idx || cols.len() <= value_idx {
-            continue;
-        }
-        let case = cols[case_idx].trim();
-        let value = cols[value_idx].trim();
-        if case.is_empty() || value.is_empty() {
-            continue;
-        }
-        sink.entry(case.to_string())
-            .or_default()
-            .push(value.to_string());
     }
-
-    Ok(())
 }
 
 /// Helper to stamp the data refresh time.
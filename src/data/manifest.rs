@@ -0,0 +1,119 @@
+//! Provenance manifest (`data/manifest.json`) for every raw artefact on disk.
+
+use std::{collections::BTreeMap, fs::File, io, path::Path, sync::Mutex};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+use crate::config::Settings;
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Serialises read-modify-write cycles from concurrent fetch tasks.
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Where an artefact came from and what it covers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtefactRecord {
+    /// Download URL, or `file://` path for imported exports and derived files.
+    pub source: String,
+    pub retrieved_at: DateTime<Utc>,
+    pub size_bytes: u64,
+    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quarters: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drugs: Vec<String>,
+}
+
+/// All recorded artefacts keyed by path relative to the data directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub artefacts: BTreeMap<String, ArtefactRecord>,
+}
+
+impl Manifest {
+    /// Load `data/manifest.json`, empty when no fetch has recorded anything yet.
+    pub fn load(settings: &Settings) -> Result<Self> {
+        let path = settings.join_data(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(&path).with_context(|| format!("read {path:?}"))?;
+        serde_json::from_str(&text).with_context(|| format!("parse {path:?}"))
+    }
+
+    fn save(&self, settings: &Settings) -> Result<()> {
+        let path = settings.join_data(MANIFEST_FILE);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("write {tmp:?}"))?;
+        std::fs::rename(&tmp, &path).with_context(|| format!("replace {path:?}"))?;
+        Ok(())
+    }
+}
+
+/// Hash `path` and record (or replace) its manifest entry.
+pub fn record(
+    settings: &Settings,
+    path: &Path,
+    source: &str,
+    quarters: &[String],
+    drugs: &[String],
+) -> Result<()> {
+    let (size_bytes, sha256) = hash_file(path)?;
+    let key = path
+        .strip_prefix(&settings.data_dir)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/");
+    let entry = ArtefactRecord {
+        source: source.to_string(),
+        retrieved_at: Utc::now(),
+        size_bytes,
+        sha256,
+        quarters: quarters.to_vec(),
+        drugs: drugs.to_vec(),
+    };
+
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut manifest = Manifest::load(settings).unwrap_or_else(|err| {
+        warn!(%err, "unreadable manifest; starting a new one");
+        Manifest::default()
+    });
+    debug!(%key, %source, "recording artefact provenance");
+    manifest.artefacts.insert(key, entry);
+    manifest.save(settings)
+}
+
+/// Drop entries whose file no longer exists; returns how many were dropped.
+pub fn prune(settings: &Settings) -> Result<usize> {
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut manifest = Manifest::load(settings)?;
+    let before = manifest.artefacts.len();
+    manifest
+        .artefacts
+        .retain(|key, _| settings.join_data(key).exists());
+    let dropped = before - manifest.artefacts.len();
+    if dropped > 0 {
+        debug!(dropped, "pruned manifest entries for removed artefacts");
+        manifest.save(settings)?;
+    }
+    Ok(dropped)
+}
+
+/// `file://` source string for local inputs.
+pub fn file_source(path: &Path) -> String {
+    let absolute = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    format!("file://{}", absolute.display())
+}
+
+fn hash_file(path: &Path) -> Result<(u64, String)> {
+    let mut file = File::open(path).with_context(|| format!("open {path:?}"))?;
+    let mut hasher = Sha256::new();
+    let size = io::copy(&mut file, &mut hasher)?;
+    Ok((size, format!("{:x}", hasher.finalize())))
+}
@@ -1,7 +1,47 @@
 //! Data ingestion and normalisation layer.
 
+pub mod custom;
+pub mod dailymed;
+pub mod device;
+pub mod dictionary;
+pub mod drug_rules;
+pub mod drugbank;
+pub mod e2b;
+pub mod export;
 pub mod faers;
+pub mod manifest;
 pub mod normalize;
+pub mod pmc;
 pub mod pubmed;
+pub mod purge;
+pub mod quality;
+pub mod rxnorm;
+pub mod sider;
+pub mod smq;
+pub mod spelling;
 #[cfg(feature = "duckdb")]
 pub mod store;
+pub mod umls;
+pub mod validation;
+pub mod vigibase;
+
+use std::{fs::File, path::Path};
+
+use anyhow::Result;
+use polars::prelude::{ParquetReader, SerReader};
+
+/// Read two string columns of a parquet artefact as `(left, right)` pairs.
+pub(crate) fn read_string_pairs(
+    path: &Path,
+    left: &str,
+    right: &str,
+) -> Result<Vec<(String, String)>> {
+    let df = ParquetReader::new(File::open(path)?).finish()?;
+    let left_col = df.column(left)?.str()?;
+    let right_col = df.column(right)?.str()?;
+    Ok(left_col
+        .into_no_null_iter()
+        .zip(right_col.into_no_null_iter())
+        .map(|(l, r)| (l.to_string(), r.to_string()))
+        .collect())
+}
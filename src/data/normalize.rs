@@ -1,20 +1,40 @@
 //! Terminology normalisation and contingency table construction.
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs::File,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
-use anyhow::Result;
-use indexmap::IndexMap;
-use polars::prelude::{DataFrame, NamedFrom, ParquetWriter, Series};
-use reqwest::Client;
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use indexmap::{IndexMap, IndexSet};
+use once_cell::sync::Lazy;
+use polars::prelude::{DataFrame, NamedFrom, ParquetReader, ParquetWriter, SerReader, Series};
+use rayon::prelude::*;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use strsim::jaro_winkler;
-use tracing::info;
+use tracing::{debug, info, warn};
 
-use crate::config::Settings;
+use crate::{
+    config::Settings,
+    data::{
+        dailymed,
+        dictionary::{self, EventDictionary},
+        drug_rules::DrugNameRules,
+        drugbank,
+        quality::{MappingCoverage, NormalizeMetrics},
+        rxnorm::RxNormResolver,
+        sider::{self, SiderVocabulary},
+        smq,
+        spelling::DrugSpeller,
+        umls::{UmlsConcept, UmlsNormalizer},
+    },
+    progress,
+    signals::trend,
+};
 
 const DRUG_SEED_MAP: &[(&str, &str)] = &[
     ("GLEEVEC", "imatinib"),
@@ -31,19 +51,59 @@ const DRUG_SEED_MAP: &[(&str, &str)] = &[
     ("IPILIMUMAB", "ipilimumab"),
 ];
 
-const SIDER_TERMS: &[&str] = &[
-    "hepatotoxicity",
-    "rash",
-    "diarrhoea",
-    "neutropenia",
-    "fatigue",
-    "nausea",
-    "fever",
-    "cardiotoxicity",
-    "anemia",
-    "thrombocytopenia",
-    "headache",
-];
+/// Raw drug case directories sharing the `CASEID, DRUGNAME, PT, YEAR_QUARTER` schema.
+pub const RAW_SOURCE_DIRS: &[&str] = &["raw/faers", "raw/vigibase", "raw/e2b", "raw/custom"];
+
+/// Device reports in the same schema, counted only by `normalize --devices`, so devices
+/// never enter the background of drug signals.
+pub const DEVICE_SOURCE_DIR: &str = "raw/device";
+
+/// Separators between ingredients of a combination product (`A\B`, `A / B`, `A + B`, `A AND B`).
+static COMBINATION_SEPARATOR: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\s*(?:\\|/|\+|;|\bAND\b)\s*").expect("valid regex"));
+/// A split fragment that is the denominator of a strength (`5 MG/ML`), not an ingredient.
+static STRENGTH_UNIT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:ML|L|G|MG|MCG|KG|M2|H|HR|HOUR|DAY|DOSE|ACTUATION|SPRAY)\b")
+        .expect("valid regex")
+});
+
+/// Split a raw drug name into its ingredient components.
+///
+/// Single-ingredient names come back as a one-element list.
+pub fn split_combination(name: &str) -> Vec<String> {
+    let name = name.trim().to_ascii_uppercase();
+    let mut components: Vec<String> = Vec::new();
+    for part in COMBINATION_SEPARATOR.split(&name) {
+        let part = part.trim();
+        if part.is_empty() || components.iter().any(|c| c == part) {
+            continue;
+        }
+        match components.last_mut() {
+            Some(previous) if STRENGTH_UNIT.is_match(part) => {
+                previous.push('/');
+                previous.push_str(part);
+            }
+            _ => components.push(part.to_string()),
+        }
+    }
+    if components.is_empty() {
+        components.push(name);
+    }
+    components
+}
+
+/// Hex digits of the canonical-name digest kept in drug and event ids (48 bits).
+const STABLE_ID_HEX_LEN: usize = 12;
+
+/// Deterministic id for a canonical drug (`D…`) or event (`E…`) name.
+///
+/// Derived from the SHA-256 of the canonical string so ids stay joinable across runs and
+/// datasets instead of depending on the order names were first seen.
+pub fn stable_id(prefix: char, canonical: &str) -> String {
+    let digest = Sha256::digest(canonical.as_bytes());
+    let hex = format!("{digest:x}");
+    format!("{prefix}{}", &hex[..STABLE_ID_HEX_LEN])
+}
 
 /// Public helper for integration tests to assert seed mappings.
 pub fn seed_lookup(name: &str) -> Option<&'static str> {
@@ -64,6 +124,9 @@ struct FaersRawRow {
     event: String,
     #[serde(rename = "YEAR_QUARTER")]
     quarter: String,
+    /// FAERS drug role (`PS`, `SS`, `C`, `I`); absent for imports and pre-role caches.
+    #[serde(rename = "ROLE_COD", default)]
+    role: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -76,6 +139,9 @@ struct DrugRow {
 struct EventRow {
     event_id: String,
     term_canonical: String,
+    umls_cui: Option<String>,
+    /// UMLS preferred name; `term_canonical` stays the dictionary (MedDRA PT) term.
+    umls_name: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -83,57 +149,871 @@ struct FaersNormRow {
     drug_id: String,
     event_id: String,
     year_quarter: String,
+    /// Quarters covered by a cumulative table (e.g. `2020Q1-2024Q4`); `None` per quarter.
+    period: Option<String>,
     a: i64,
     b: i64,
     c: i64,
     d: i64,
 }
 
-pub async fn canonicalise(settings: &Settings) -> Result<()> {
-    let raw_rows = load_faers_rows(settings)?;
+/// Optional behaviours for `canonicalise`.
+#[derive(Debug, Clone, Default)]
+pub struct NormalizeOptions {
+    /// Also aggregate events into MedDRA SMQ pseudo-events (narrow and broad scope).
+    pub smq: bool,
+    /// Strategy used to map reaction strings onto canonical event terms.
+    pub event_backend: EventBackend,
+    /// Drug roles counted in contingency tables; empty counts every role.
+    pub drug_roles: Vec<DrugRole>,
+    /// Only count quarters that are new or whose input files changed since the last run,
+    /// reusing earlier name mappings.
+    pub incremental: bool,
+    /// Count every event in an `event_clusters.parquet` cluster as its representative.
+    pub merge_clusters: bool,
+    /// Count every drug in a `drug_clusters.parquet` cluster as its representative.
+    pub merge_drug_clusters: bool,
+    /// Build one table across all quarters, counting each case once from its latest quarter.
+    pub cumulative: bool,
+    /// Count the device reports in [`DEVICE_SOURCE_DIR`] instead of the drug sources.
+    pub devices: bool,
+}
+
+/// FAERS `ROLE_COD` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DrugRole {
+    /// Primary suspect.
+    Ps,
+    /// Secondary suspect.
+    Ss,
+    /// Concomitant.
+    C,
+    /// Interacting.
+    I,
+}
+
+impl DrugRole {
+    fn code(self) -> &'static str {
+        match self {
+            Self::Ps => "PS",
+            Self::Ss => "SS",
+            Self::C => "C",
+            Self::I => "I",
+        }
+    }
+}
+
+/// Whether a raw row's drug counts under the configured roles. Rows without a role always count.
+fn role_counted(row: &FaersRawRow, roles: &[DrugRole]) -> bool {
+    match row.role.as_deref().map(str::trim) {
+        Some(code) if !code.is_empty() && !roles.is_empty() => {
+            roles.iter().any(|r| r.code().eq_ignore_ascii_case(code))
+        }
+        _ => true,
+    }
+}
+
+/// How a raw drug or event string was resolved, reported in the normalization metrics.
+///
+/// Ordered from most to least trusted; a combination takes its weakest component's source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum MatchSource {
+    User,
+    Seed,
+    DrugBank,
+    Exact,
+    RxNorm,
+    Spelling,
+    Fuzzy,
+    Cached,
+    Passthrough,
+}
+
+impl MatchSource {
+    fn label(self) -> &'static str {
+        match self {
+            MatchSource::User => "user_dictionary",
+            MatchSource::Seed => "seed",
+            MatchSource::DrugBank => "drugbank",
+            MatchSource::Exact => "exact",
+            MatchSource::RxNorm => "rxnorm",
+            MatchSource::Spelling => "spelling",
+            MatchSource::Fuzzy => "fuzzy",
+            MatchSource::Cached => "previous_run",
+            MatchSource::Passthrough => "passthrough",
+        }
+    }
+}
+
+/// Event normalization strategies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum EventBackend {
+    /// Jaro-Winkler matching against the SIDER dictionary.
+    #[default]
+    Heuristic,
+    /// Heuristic matching, plus the UMLS concept (local MRCONSO or UTS API) of each term.
+    Umls,
+}
+
+pub async fn canonicalise(settings: &Settings, options: &NormalizeOptions) -> Result<()> {
+    let sources = if options.devices {
+        &[DEVICE_SOURCE_DIR]
+    } else {
+        RAW_SOURCE_DIRS
+    };
+    let (mut raw_rows, inputs) = load_faers_rows(settings, sources)?;
+    let state = NormalizeState::new(options, inputs);
+    let previous = if options.incremental {
+        PreviousRun::load(settings, &state)?
+    } else {
+        None
+    };
+    if let Some(previous) = &previous {
+        let before = raw_rows.len();
+        raw_rows.retain(|row| !previous.quarters.contains(&row.quarter));
+        info!(
+            quarters = previous.quarters.len(),
+            skipped_rows = before - raw_rows.len(),
+            "incremental: skipping quarters whose inputs are unchanged"
+        );
+    }
     if raw_rows.is_empty() {
-        info!("no FAERS rows found; normalization is a no-op");
+        info!("no new FAERS rows found; normalization is a no-op");
         return Ok(());
     }
+    let previous = previous.unwrap_or_default();
+    let raw_row_count = raw_rows.len();
+    let case_versions_read = count_case_versions(&raw_rows);
+    let cases_read = raw_rows
+        .iter()
+        .map(|r| r.caseid.as_str())
+        .collect::<HashSet<_>>()
+        .len();
+    let period = if options.cumulative {
+        collapse_to_latest_version(&mut raw_rows)
+    } else {
+        None
+    };
 
-    let client = Client::builder().user_agent("rwe-assistant/0.1").build()?;
+    let unique_drugs = collect_unique(raw_rows.iter().map(|r| r.drugname.clone()))
+        .into_iter()
+        .filter(|name| !previous.drug_names.contains_key(name))
+        .collect::<Vec<_>>();
+    let unique_events = collect_unique(raw_rows.iter().map(|r| r.event.clone()))
+        .into_iter()
+        .filter(|name| !previous.event_names.contains_key(name))
+        .collect::<Vec<_>>();
 
-    let unique_drugs = collect_unique(raw_rows.iter().map(|r| r.drugname.clone()));
-    let unique_events = collect_unique(raw_rows.iter().map(|r| r.event.clone()));
+    let sider_vocab = sider::load_vocabulary(settings)?;
+
+    let user_drugs = dictionary::load_drug_dictionary(settings)?;
+    let name_rules = DrugNameRules::load(settings)?;
+    let drugbank_synonyms = drugbank::load_synonyms(settings)?;
+    let mut rxnorm = RxNormResolver::new(settings)?;
+    let (mut drug_map, drug_sources) = build_drug_map(
+        &unique_drugs,
+        &user_drugs,
+        &drugbank_synonyms,
+        &name_rules,
+        &mut rxnorm,
+    )
+    .await;
+    let event_dictionary = dictionary::load_event_dictionary(settings, &sider_vocab.terms)?;
+    let (mut event_map, event_sources) = build_event_map(
+        &unique_events,
+        &event_dictionary,
+        settings.event_match_threshold,
+    );
+    let mut event_concepts = if options.event_backend == EventBackend::Umls {
+        let mut umls = UmlsNormalizer::load(settings)?;
+        link_umls(&unique_events, &event_map, &mut umls).await
+    } else {
+        HashMap::new()
+    };
+
+    let drug_candidates = FuzzyIndex::new(
+        user_drugs
+            .iter()
+            .chain(&drugbank_synonyms)
+            .map(|(raw, canon)| (raw.as_str(), canon.as_str()))
+            .chain(DRUG_SEED_MAP.iter().copied()),
+    );
+    let unmapped_drugs = passthrough_names(&drug_sources);
+    let unmapped_events = passthrough_names(&event_sources);
+    write_normalize_report(
+        &raw_rows,
+        &unmapped_drugs,
+        &drug_candidates,
+        &unmapped_events,
+        &event_dictionary,
+        settings.join_output("normalize_report.csv"),
+    )?;
+
+    drug_map.extend(previous.drug_names);
+    event_map.extend(previous.event_names);
+    for (term, concept) in previous.event_concepts {
+        event_concepts.entry(term).or_insert(concept);
+    }
+    write_name_map(
+        &drug_map,
+        &event_map,
+        settings.join_data("clean/name_map.parquet"),
+    )?;
 
-    let drug_map = build_drug_map(&unique_drugs, &client).await;
-    let event_map = build_event_map(&unique_events);
+    let (drug_rows, mut drug_lookup) = materialise_drugs(&drug_map);
+    let (mut event_rows, mut event_lookup) = materialise_events(&event_map, &event_concepts);
 
-    let (drug_rows, drug_lookup) = materialise_drugs(&drug_map);
-    let (event_rows, event_lookup) = materialise_events(&event_map);
+    let mut smq_lookup = if options.smq {
+        smq_memberships(settings, &mut event_rows)?
+    } else {
+        HashMap::new()
+    };
+
+    if options.merge_clusters {
+        let events = load_cluster_representatives(settings, "event_clusters.parquet", "event_id")?;
+        merge_event_clusters(&events, &mut event_lookup, &mut smq_lookup);
+        remap_relations(settings, "event_id", &events)?;
+    }
+    if options.merge_drug_clusters {
+        let drugs = load_cluster_representatives(settings, "drug_clusters.parquet", "drug_id")?;
+        merge_drug_clusters(&drugs, &mut drug_lookup);
+        remap_relations(settings, "drug_id", &drugs)?;
+    }
 
     write_drugs(&drug_rows, settings.join_data("clean/drugs.parquet"))?;
     write_events(&event_rows, settings.join_data("clean/events.parquet"))?;
 
-    let norm_rows = build_contingency(&raw_rows, &drug_lookup, &event_lookup);
+    let mut norm_rows = previous.norm_rows;
+    let counted = build_contingency(
+        &raw_rows,
+        &drug_lookup,
+        &event_lookup,
+        &smq_lookup,
+        &options.drug_roles,
+    );
+    norm_rows.extend(counted.into_iter().map(|row| FaersNormRow {
+        period: period.clone(),
+        ..row
+    }));
     write_norm(&norm_rows, settings.join_data("clean/faers_norm.parquet"))?;
+    state.persist(settings)?;
+    write_known_associations(
+        &norm_rows,
+        &drug_rows,
+        &event_rows,
+        &sider_vocab,
+        settings.join_data("clean/known_associations.parquet"),
+    )?;
+    dailymed::build_labeled_events(settings)?;
+
+    NormalizeMetrics {
+        generated_at: Utc::now(),
+        raw_rows: raw_row_count,
+        cases_read,
+        cases_dropped_followups: case_versions_read - count_case_versions(&raw_rows),
+        cases_without_exposure: count_unexposed_cases(&raw_rows, &options.drug_roles),
+        drugs: coverage(raw_rows.iter().map(|r| &r.drugname), &drug_sources),
+        events: coverage(raw_rows.iter().map(|r| &r.event), &event_sources),
+    }
+    .persist(settings)?;
     Ok(())
 }
 
-fn load_faers_rows(settings: &Settings) -> Result<Vec<FaersRawRow>> {
-    let mut rows = Vec::new();
-    let root = settings.join_data("raw/faers");
-    if !root.exists() {
-        return Ok(rows);
+/// Mentions per resolution source; names resolved by an earlier run count as `previous_run`.
+fn coverage<'a>(
+    names: impl Iterator<Item = &'a String>,
+    sources: &HashMap<String, MatchSource>,
+) -> MappingCoverage {
+    MappingCoverage::from_counts(names.map(|name| {
+        let source = sources
+            .get(&name.trim().to_ascii_uppercase())
+            .copied()
+            .unwrap_or(MatchSource::Cached);
+        (source.label(), 1)
+    }))
+}
+
+fn passthrough_names(sources: &HashMap<String, MatchSource>) -> HashSet<String> {
+    sources
+        .iter()
+        .filter(|(_, source)| **source == MatchSource::Passthrough)
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Distinct `(quarter, case)` pairs, i.e. case versions across quarterly files.
+fn count_case_versions(rows: &[FaersRawRow]) -> usize {
+    rows.iter()
+        .map(|r| (r.quarter.as_str(), r.caseid.as_str()))
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// Cases whose latest version has no drug in a counted role. Each case counts once, however
+/// many quarters report it.
+fn count_unexposed_cases(rows: &[FaersRawRow], roles: &[DrugRole]) -> usize {
+    let order = |quarter: &str| trend::parse_quarter(quarter).unwrap_or((0, 0));
+    let mut latest: HashMap<&str, (&str, bool)> = HashMap::new();
+    for row in rows {
+        let counted = role_counted(row, roles);
+        let entry = latest
+            .entry(row.caseid.as_str())
+            .or_insert((row.quarter.as_str(), false));
+        if order(&row.quarter) > order(entry.0) {
+            *entry = (row.quarter.as_str(), counted);
+        } else if row.quarter == entry.0 {
+            entry.1 |= counted;
+        }
     }
-    for entry in std::fs::read_dir(root)? {
-        let entry = entry?;
-        if entry.path().extension().and_then(|s| s.to_str()) != Some("csv") {
+    latest.values().filter(|(_, exposed)| !exposed).count()
+}
+
+/// Keep each case's rows from the latest quarter it appears in, so follow-up reports are
+/// counted once, and return the covered period (e.g. `2020Q1-2024Q4`).
+///
+/// Every row is relabelled with the latest quarter, which keeps `year_quarter` parseable for
+/// trends, pair history and sorting.
+fn collapse_to_latest_version(rows: &mut Vec<FaersRawRow>) -> Option<String> {
+    let order = |quarter: &str| trend::parse_quarter(quarter).unwrap_or((0, 0));
+    let mut latest: HashMap<&str, &str> = HashMap::new();
+    for row in rows.iter() {
+        let entry = latest
+            .entry(row.caseid.as_str())
+            .or_insert(row.quarter.as_str());
+        if order(&row.quarter) > order(entry) {
+            *entry = row.quarter.as_str();
+        }
+    }
+    let keep: Vec<bool> = rows
+        .iter()
+        .map(|row| latest.get(row.caseid.as_str()) == Some(&row.quarter.as_str()))
+        .collect();
+    let cases = latest.len();
+
+    let before = rows.len();
+    let mut flags = keep.into_iter();
+    rows.retain(|_| flags.next().unwrap_or(false));
+    let first = rows
+        .iter()
+        .map(|r| r.quarter.as_str())
+        .min_by_key(|q| order(q))?
+        .to_string();
+    let last = rows
+        .iter()
+        .map(|r| r.quarter.as_str())
+        .max_by_key(|q| order(q))?
+        .to_string();
+    let period = if first == last {
+        last.clone()
+    } else {
+        format!("{first}-{last}")
+    };
+    for row in rows.iter_mut() {
+        row.quarter = last.clone();
+    }
+    info!(
+        cases,
+        dropped_rows = before - rows.len(),
+        %period,
+        "cumulative: kept the latest version of each case"
+    );
+    Some(period)
+}
+
+/// What a `normalize` run counted, written to `clean/normalize_state.json` so `--incremental`
+/// knows which quarters it can keep.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct NormalizeState {
+    /// Options that change the counts; a run with other options recounts every quarter.
+    options: String,
+    /// A cumulative table has no per-quarter rows to keep.
+    cumulative: bool,
+    /// Fingerprint of the input files each quarter was counted from.
+    quarters: BTreeMap<String, String>,
+}
+
+impl NormalizeState {
+    const FILE: &'static str = "clean/normalize_state.json";
+
+    fn new(options: &NormalizeOptions, quarters: BTreeMap<String, String>) -> Self {
+        Self {
+            options: format!(
+                "drug_roles={:?} event_backend={:?} smq={} merge_clusters={} \
+                 merge_drug_clusters={} devices={}",
+                options.drug_roles,
+                options.event_backend,
+                options.smq,
+                options.merge_clusters,
+                options.merge_drug_clusters,
+                options.devices
+            ),
+            cumulative: options.cumulative,
+            quarters,
+        }
+    }
+
+    fn load(settings: &Settings) -> Result<Option<Self>> {
+        let path = settings.join_data(Self::FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_slice(&std::fs::read(path)?)?))
+    }
+
+    fn persist(&self, settings: &Settings) -> Result<()> {
+        std::fs::write(
+            settings.join_data(Self::FILE),
+            serde_json::to_vec_pretty(self)?,
+        )?;
+        Ok(())
+    }
+}
+
+/// Outputs of an earlier `normalize` run reused by `--incremental`.
+#[derive(Debug, Default)]
+struct PreviousRun {
+    quarters: HashSet<String>,
+    drug_names: HashMap<String, Vec<String>>,
+    event_names: HashMap<String, String>,
+    event_concepts: HashMap<String, UmlsConcept>,
+    norm_rows: Vec<FaersNormRow>,
+}
+
+impl PreviousRun {
+    /// Load the previous run's contingency rows and name mappings for the quarters whose
+    /// inputs and options match `current`. `None` when there is nothing to reuse.
+    fn load(settings: &Settings, current: &NormalizeState) -> Result<Option<Self>> {
+        let norm_path = settings.join_data("clean/faers_norm.parquet");
+        let map_path = settings.join_data("clean/name_map.parquet");
+        if !norm_path.exists() || !map_path.exists() {
+            warn!("no previous normalize outputs; running a full normalization");
+            return Ok(None);
+        }
+        let Some(state) = NormalizeState::load(settings)? else {
+            warn!("no record of the previous run's inputs; running a full normalization");
+            return Ok(None);
+        };
+        if state.cumulative {
+            bail!(
+                "faers_norm.parquet holds a cumulative table, which --incremental cannot extend; \
+                 rerun with --cumulative instead"
+            );
+        }
+        if state.options != current.options {
+            warn!(
+                previous = %state.options,
+                current = %current.options,
+                "normalize options changed; running a full normalization"
+            );
+            return Ok(None);
+        }
+        // New imports or rewritten files change a quarter's fingerprint, so it is recounted.
+        let quarters: HashSet<String> = state
+            .quarters
+            .iter()
+            .filter(|(quarter, fingerprint)| current.quarters.get(*quarter) == Some(fingerprint))
+            .map(|(quarter, _)| quarter.clone())
+            .collect();
+
+        let norm = ParquetReader::new(File::open(&norm_path)?).finish()?;
+        let mut norm_rows = Vec::with_capacity(norm.height());
+        let counts = [
+            norm.column("a")?.i64()?,
+            norm.column("b")?.i64()?,
+            norm.column("c")?.i64()?,
+            norm.column("d")?.i64()?,
+        ];
+        for (idx, ((drug_id, event_id), quarter)) in norm
+            .column("drug_id")?
+            .str()?
+            .into_no_null_iter()
+            .zip(norm.column("event_id")?.str()?.into_no_null_iter())
+            .zip(norm.column("year_quarter")?.str()?.into_no_null_iter())
+            .enumerate()
+        {
+            if !quarters.contains(quarter) {
+                continue;
+            }
+            let [a, b, c, d] = counts.map(|col| col.get(idx).unwrap_or(0));
+            norm_rows.push(FaersNormRow {
+                drug_id: drug_id.to_string(),
+                event_id: event_id.to_string(),
+                year_quarter: quarter.to_string(),
+                period: None,
+                a,
+                b,
+                c,
+                d,
+            });
+        }
+
+        let names = ParquetReader::new(File::open(&map_path)?).finish()?;
+        let mut drug_names: HashMap<String, Vec<String>> = HashMap::new();
+        let mut event_names = HashMap::new();
+        for ((kind, raw), canonical) in names
+            .column("kind")?
+            .str()?
+            .into_no_null_iter()
+            .zip(names.column("raw_name")?.str()?.into_no_null_iter())
+            .zip(names.column("canonical")?.str()?.into_no_null_iter())
+        {
+            if kind == "drug" {
+                drug_names
+                    .entry(raw.to_string())
+                    .or_default()
+                    .push(canonical.to_string());
+            } else {
+                event_names.insert(raw.to_string(), canonical.to_string());
+            }
+        }
+
+        let mut event_concepts = HashMap::new();
+        let events_path = settings.join_data("clean/events.parquet");
+        if events_path.exists() {
+            let events = ParquetReader::new(File::open(&events_path)?).finish()?;
+            let terms = events.column("term_canonical")?.str()?;
+            let cuis = events.column("umls_cui")?.str()?;
+            // Older outputs stored the UMLS name as the canonical term itself.
+            let names = match events.column("umls_name") {
+                Ok(column) => column.str()?.clone(),
+                Err(_) => terms.clone(),
+            };
+            for ((term, cui), name) in terms.into_iter().zip(cuis).zip(&names) {
+                if let (Some(term), Some(cui), Some(name)) = (term, cui, name) {
+                    let concept = UmlsConcept {
+                        cui: cui.to_string(),
+                        name: name.to_string(),
+                    };
+                    event_concepts.insert(term.to_string(), concept);
+                }
+            }
+        }
+
+        info!(
+            rows = norm_rows.len(),
+            drugs = drug_names.len(),
+            events = event_names.len(),
+            "loaded previous normalize outputs"
+        );
+        Ok(Some(Self {
+            quarters,
+            drug_names,
+            event_names,
+            event_concepts,
+            norm_rows,
+        }))
+    }
+}
+
+/// Map each clustered id in `clean/<file>` to its cluster's representative id, chosen by
+/// `embed` as the member with the most exposed cases.
+///
+/// Only ids that differ from their representative are included.
+fn load_cluster_representatives(
+    settings: &Settings,
+    file: &str,
+    id_column: &str,
+) -> Result<HashMap<String, String>> {
+    let path = settings.join_data("clean").join(file);
+    if !path.exists() {
+        warn!(
+            file,
+            "cluster file missing; run embed before merging clusters"
+        );
+        return Ok(HashMap::new());
+    }
+    let df = ParquetReader::new(File::open(&path)?).finish()?;
+    let rep_ids = df
+        .column("rep_id")
+        .with_context(|| format!("{file} has no rep_id column; re-run embed"))?;
+    let mut clusters = HashSet::new();
+    let mut representatives = HashMap::new();
+    for ((id, cluster_id), rep) in df
+        .column(id_column)?
+        .str()?
+        .into_no_null_iter()
+        .zip(df.column("cluster_id")?.i64()?.into_no_null_iter())
+        .zip(rep_ids.str()?.into_no_null_iter())
+    {
+        clusters.insert(cluster_id);
+        if rep != id {
+            representatives.insert(id.to_string(), rep.to_string());
+        }
+    }
+    info!(
+        clusters = clusters.len(),
+        merged = representatives.len(),
+        file,
+        "loaded cluster representatives"
+    );
+    Ok(representatives)
+}
+
+/// Point raw event terms at their cluster representative so cases are counted once per cluster.
+fn merge_event_clusters(
+    representatives: &HashMap<String, String>,
+    event_lookup: &mut HashMap<String, String>,
+    smq_lookup: &mut HashMap<String, Vec<String>>,
+) {
+    for event_id in event_lookup.values_mut() {
+        if let Some(rep) = representatives.get(event_id) {
+            *event_id = rep.clone();
+        }
+    }
+    for (member, rep) in representatives {
+        let Some(groups) = smq_lookup.get(member).cloned() else {
             continue;
+        };
+        let rep_groups = smq_lookup.entry(rep.clone()).or_default();
+        for group in groups {
+            if !rep_groups.contains(&group) {
+                rep_groups.push(group);
+            }
         }
-        let mut reader = csv::Reader::from_path(entry.path())?;
-        for result in reader.deserialize() {
-            let row: FaersRawRow = result?;
-            rows.push(row);
+    }
+}
+
+/// Point raw drug names at their cluster representatives; a combination whose ingredients
+/// collapse onto one representative keeps it once.
+fn merge_drug_clusters(
+    representatives: &HashMap<String, String>,
+    drug_lookup: &mut HashMap<String, Vec<String>>,
+) {
+    for drug_ids in drug_lookup.values_mut() {
+        let mut merged: Vec<String> = Vec::with_capacity(drug_ids.len());
+        for drug_id in drug_ids.drain(..) {
+            let drug_id = representatives.get(&drug_id).cloned().unwrap_or(drug_id);
+            if !merged.contains(&drug_id) {
+                merged.push(drug_id);
+            }
         }
+        *drug_ids = merged;
+    }
+}
+
+/// Rewrite the `column` ids of `relations.parquet` onto cluster representatives.
+fn remap_relations(
+    settings: &Settings,
+    column: &str,
+    representatives: &HashMap<String, String>,
+) -> Result<()> {
+    let path = settings.join_data("clean/relations.parquet");
+    if representatives.is_empty() || !path.exists() {
+        return Ok(());
     }
-    info!(rows = rows.len(), "loaded faers raw rows");
-    Ok(rows)
+    let mut df = ParquetReader::new(File::open(&path)?).finish()?;
+    let mut remapped = 0usize;
+    let ids: Vec<String> = df
+        .column(column)?
+        .str()?
+        .into_no_null_iter()
+        .map(|id| match representatives.get(id) {
+            Some(rep) => {
+                remapped += 1;
+                rep.clone()
+            }
+            None => id.to_string(),
+        })
+        .collect();
+    df.with_column(Series::new(column.into(), ids))?;
+    let file = File::create(&path)?;
+    ParquetWriter::new(file).finish(&mut df)?;
+    info!(path = %path.display(), column, remapped, "merged relation ids onto cluster representatives");
+    Ok(())
+}
+
+/// Persist raw → canonical name mappings so `--incremental` runs can skip resolved names.
+fn write_name_map(
+    drugs: &HashMap<String, Vec<String>>,
+    events: &HashMap<String, String>,
+    path: PathBuf,
+) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut kinds = Vec::new();
+    let mut raw_names = Vec::new();
+    let mut canonicals = Vec::new();
+    for (raw, ingredients) in drugs {
+        for canonical in ingredients {
+            kinds.push("drug");
+            raw_names.push(raw.clone());
+            canonicals.push(canonical.clone());
+        }
+    }
+    for (raw, canonical) in events {
+        kinds.push("event");
+        raw_names.push(raw.clone());
+        canonicals.push(canonical.clone());
+    }
+    let rows = kinds.len();
+    let mut df = DataFrame::new(vec![
+        Series::new("kind".into(), kinds),
+        Series::new("raw_name".into(), raw_names),
+        Series::new("canonical".into(), canonicals),
+    ])?;
+    let file = File::create(&path)?;
+    ParquetWriter::new(file).finish(&mut df)?;
+    info!(path = %path.display(), rows, "wrote name map parquet");
+    Ok(())
+}
+
+/// Map event ids to the SMQ pseudo-event ids they roll up into, registering SMQ event rows.
+fn smq_memberships(
+    settings: &Settings,
+    event_rows: &mut Vec<EventRow>,
+) -> Result<HashMap<String, Vec<String>>> {
+    let Some(index) = smq::load_index(settings)? else {
+        return Ok(HashMap::new());
+    };
+    let mut lookup = HashMap::new();
+    let mut groups = IndexMap::new();
+    for row in event_rows.iter() {
+        let members = index.groups_for(&row.term_canonical);
+        if members.is_empty() {
+            continue;
+        }
+        let ids = members
+            .iter()
+            .map(|group| {
+                let id = group.event_id();
+                groups.entry(id.clone()).or_insert_with(|| group.clone());
+                id
+            })
+            .collect();
+        lookup.insert(row.event_id.clone(), ids);
+    }
+    for (id, group) in &groups {
+        event_rows.push(EventRow {
+            event_id: id.clone(),
+            term_canonical: group.label(),
+            umls_cui: None,
+            umls_name: None,
+        });
+    }
+    std::fs::create_dir_all(settings.join_data("clean"))?;
+    smq::write_groups(&groups.into_values().collect::<Vec<_>>(), settings)?;
+    Ok(lookup)
+}
+
+/// Write imported `(case, drug, event)` rows in the raw schema, one `{stem}_{quarter}.csv` per quarter.
+///
+/// Rows already in a quarter's file are kept unless the batch carries the same case, whose
+/// newer version then replaces them, so batches accumulate and re-imports are idempotent.
+pub fn write_raw_quarters(
+    dir: &Path,
+    stem: &str,
+    by_quarter: &BTreeMap<String, Vec<[String; 3]>>,
+) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let mut outputs = Vec::new();
+    for (quarter, rows) in by_quarter {
+        let path = dir.join(format!("{stem}_{quarter}.csv"));
+        let cases: HashSet<&str> = rows.iter().map(|[case, _, _]| case.as_str()).collect();
+        let mut kept = Vec::new();
+        if path.exists() {
+            for record in csv::Reader::from_path(&path)?.records() {
+                let record = record?;
+                let field = |idx: usize| record.get(idx).unwrap_or_default().to_string();
+                if !cases.contains(field(0).as_str()) {
+                    kept.push([field(0), field(1), field(2)]);
+                }
+            }
+        }
+
+        // Written beside the target and renamed, so a failed write keeps earlier batches.
+        let partial = path.with_extension("csv.part");
+        let mut writer = csv::Writer::from_path(&partial)?;
+        writer.write_record(["CASEID", "DRUGNAME", "PT", "YEAR_QUARTER"])?;
+        for [case, drug, event] in kept.iter().chain(rows) {
+            writer.write_record([
+                case.as_str(),
+                drug.as_str(),
+                event.as_str(),
+                quarter.as_str(),
+            ])?;
+        }
+        writer.flush()?;
+        drop(writer);
+        std::fs::rename(&partial, &path)?;
+        info!(
+            path = %path.display(),
+            rows = rows.len(),
+            kept = kept.len(),
+            "wrote raw case rows"
+        );
+        outputs.push(path);
+    }
+    Ok(outputs)
+}
+
+/// Raw rows of every source directory, and a fingerprint per quarter of the files holding its rows.
+///
+/// A file's fingerprint covers its path, size, modification time and content hash.
+fn load_faers_rows(
+    settings: &Settings,
+    sources: &[&str],
+) -> Result<(Vec<FaersRawRow>, BTreeMap<String, String>)> {
+    let mut paths = Vec::new();
+    for source in sources {
+        let root = settings.join_data(source);
+        if !root.exists() {
+            continue;
+        }
+        for entry in std::fs::read_dir(root)? {
+            let path = entry?.path();
+            if path.extension().and_then(|s| s.to_str()) == Some("csv") {
+                paths.push(path);
+            }
+        }
+    }
+    // One file per quarter and source, so files are parsed in parallel.
+    let bar = progress::counter("parsing raw FAERS files", "rows");
+    let per_file = paths
+        .par_iter()
+        .map(|path| -> Result<(Vec<FaersRawRow>, String)> {
+            let bytes = std::fs::read(path)?;
+            let modified = std::fs::metadata(path)?
+                .modified()?
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default();
+            let fingerprint = format!(
+                "{} {} {} {:x}",
+                path.display(),
+                bytes.len(),
+                modified.as_nanos(),
+                Sha256::digest(&bytes)
+            );
+            let rows = csv::Reader::from_reader(bytes.as_slice())
+                .deserialize()
+                .inspect(|_| bar.inc(1))
+                .collect::<Result<Vec<FaersRawRow>, _>>()?;
+            Ok((rows, fingerprint))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    bar.finish_and_clear();
+    let mut files: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (rows, fingerprint) in &per_file {
+        let quarters: BTreeSet<&str> = rows.iter().map(|r| r.quarter.as_str()).collect();
+        for quarter in quarters {
+            files.entry(quarter).or_default().push(fingerprint);
+        }
+    }
+    let fingerprints = files
+        .into_iter()
+        .map(|(quarter, mut files)| {
+            files.sort_unstable();
+            let hash = Sha256::digest(files.join("\n"));
+            (quarter.to_string(), format!("{hash:x}"))
+        })
+        .collect();
+    let rows: Vec<FaersRawRow> = per_file.into_iter().flat_map(|(rows, _)| rows).collect();
+    info!(
+        rows = rows.len(),
+        files = paths.len(),
+        "loaded faers raw rows"
+    );
+    Ok((rows, fingerprints))
 }
 
 fn collect_unique<I>(iter: I) -> Vec<String>
@@ -148,66 +1028,314 @@ where
     set.into_keys().collect()
 }
 
-async fn build_drug_map(names: &[String], client: &Client) -> HashMap<String, String> {
+/// Resolve raw drug names: user dictionary, seed map, DrugBank, RxNorm, then lowercase passthrough.
+///
+/// Combination products are split so each ingredient is resolved on its own, and components
+/// that miss the dictionaries are retried after stripping strengths, salts and dosage forms,
+/// then spelling-corrected against the dictionaries before falling back to RxNorm.
+/// Also returns how each name was resolved (its weakest component for combinations).
+async fn build_drug_map(
+    names: &[String],
+    user: &HashMap<String, String>,
+    vocabulary: &HashMap<String, String>,
+    rules: &DrugNameRules,
+    rxnorm: &mut RxNormResolver,
+) -> (HashMap<String, Vec<String>>, HashMap<String, MatchSource>) {
     let seed: HashMap<_, _> = DRUG_SEED_MAP
         .iter()
         .map(|(raw, canon)| ((*raw).to_string(), (*canon).to_string()))
         .collect();
+    let dictionaries = [user, &seed, vocabulary];
+    let dictionary_sources = [MatchSource::User, MatchSource::Seed, MatchSource::DrugBank];
+    let speller = DrugSpeller::new(dictionaries.iter().flat_map(|d| {
+        d.iter().flat_map(|(raw, canon)| {
+            [
+                (raw.as_str(), canon.as_str()),
+                (canon.as_str(), canon.as_str()),
+            ]
+        })
+    }));
+    let mut corrected = 0usize;
     let mut mapping = HashMap::new();
+    let mut sources = HashMap::new();
     for name in names {
         let seed_key = name.trim().to_ascii_uppercase();
-        if let Some(canon) = seed.get(&seed_key) {
-            mapping.insert(name.clone(), canon.clone());
-            continue;
-        }
-        if let Some(rx) = rxnorm_lookup(name, client).await {
-            mapping.insert(name.clone(), rx);
+        // A combination listed in a dictionary as a whole keeps that mapping.
+        let components = if dictionaries.iter().any(|d| d.contains_key(&seed_key)) {
+            vec![seed_key]
         } else {
-            mapping.insert(name.clone(), name.to_lowercase());
+            split_combination(name)
+        };
+        let mut ingredients = Vec::new();
+        let mut name_source = MatchSource::User;
+        for component in components {
+            let stripped = rules.apply(&component);
+            let dictionary_hit = [&component, &stripped].into_iter().find_map(|key| {
+                dictionaries
+                    .iter()
+                    .zip(dictionary_sources)
+                    .find_map(|(d, source)| d.get(key).map(|canon| (canon.clone(), source)))
+            });
+            let resolved = match dictionary_hit {
+                Some(hit) => Some(hit),
+                None => match speller.correct(&stripped) {
+                    Some(canon) => {
+                        debug!(raw = %component, %canon, "spelling-corrected drug name");
+                        corrected += 1;
+                        Some((canon.to_string(), MatchSource::Spelling))
+                    }
+                    None => {
+                        // The full name goes to RxNorm too, in case the rules stripped too much.
+                        let mut canon = rxnorm.resolve(&stripped).await;
+                        if canon.is_none() && !stripped.eq_ignore_ascii_case(component.trim()) {
+                            canon = rxnorm.resolve(&component).await;
+                        }
+                        canon.map(|canon| (canon, MatchSource::RxNorm))
+                    }
+                },
+            };
+            let (canon, source) =
+                resolved.unwrap_or_else(|| (stripped.to_lowercase(), MatchSource::Passthrough));
+            name_source = name_source.max(source);
+            if !ingredients.contains(&canon) {
+                ingredients.push(canon);
+            }
         }
+        mapping.insert(name.clone(), ingredients);
+        sources.insert(name.clone(), name_source);
     }
-    mapping
+    info!(corrected, "spelling-corrected drug names");
+    (mapping, sources)
 }
 
-async fn rxnorm_lookup(name: &str, client: &Client) -> Option<String> {
-    let url = format!(
-        "https://rxnav.nlm.nih.gov/REST/drugs.json?name={}",
-        urlencoding::encode(name)
-    );
-    let resp = client.get(url).send().await.ok()?;
-    if !resp.status().is_success() {
-        return None;
-    }
-    let payload: serde_json::Value = resp.json().await.ok()?;
-    payload
-        .pointer("/drugGroup/conceptGroup/0/conceptProperties/0/name")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_lowercase())
+/// Canonical ingredients of each raw drug name, resolved as `normalize` does.
+pub async fn resolve_drug_names(
+    settings: &Settings,
+    names: &[String],
+) -> Result<HashMap<String, Vec<String>>> {
+    let user_drugs = dictionary::load_drug_dictionary(settings)?;
+    let name_rules = DrugNameRules::load(settings)?;
+    let drugbank_synonyms = drugbank::load_synonyms(settings)?;
+    let mut rxnorm = RxNormResolver::new(settings)?;
+    let (mapping, _) = build_drug_map(
+        names,
+        &user_drugs,
+        &drugbank_synonyms,
+        &name_rules,
+        &mut rxnorm,
+    )
+    .await;
+    Ok(mapping)
 }
 
-fn build_event_map(names: &[String]) -> HashMap<String, String> {
-    let dictionary: Vec<String> = SIDER_TERMS.iter().map(|s| s.to_string()).collect();
+/// Map raw event terms onto the dictionary, returning how each term was resolved.
+fn build_event_map(
+    names: &[String],
+    dictionary: &EventDictionary,
+    threshold: Option<f64>,
+) -> (HashMap<String, String>, HashMap<String, MatchSource>) {
     let mut mapping = HashMap::new();
+    let mut sources = HashMap::new();
+    let mut misses = Vec::new();
     for name in names {
-        let target = name.trim().to_lowercase();
-        let mut best = (0.0f64, target.clone());
-        for candidate in &dictionary {
-            let score = jaro_winkler(&target, candidate);
-            if score > best.0 {
-                best = (score, candidate.clone());
+        match dictionary.canonical(name) {
+            Some(canonical) => {
+                mapping.insert(name.clone(), canonical.to_string());
+                sources.insert(name.clone(), MatchSource::Exact);
+            }
+            None => misses.push(name),
+        }
+    }
+
+    // Fuzzy matching is opt-in: Jaro-Winkler also joins unrelated terms that share letters.
+    // Only the terms without an exact or normalized match go through it, spread across threads.
+    let fuzzy_index = threshold.map(|threshold| (FuzzyIndex::new(dictionary.entries()), threshold));
+    let fuzzy: Vec<_> = misses
+        .par_iter()
+        .map(|name| {
+            let target = name.trim().to_lowercase();
+            let canonical = fuzzy_index.as_ref().and_then(|(index, threshold)| {
+                index
+                    .nearest(&target)
+                    .filter(|(_, score)| score > threshold)
+            });
+            (*name, target, canonical)
+        })
+        .collect();
+    for (name, target, canonical) in fuzzy {
+        match canonical {
+            Some((canonical, score)) => {
+                info!(raw = %name, %canonical, score, "matched event term fuzzily");
+                mapping.insert(name.clone(), canonical.to_string());
+                sources.insert(name.clone(), MatchSource::Fuzzy);
+            }
+            None => {
+                mapping.insert(name.clone(), target);
+                sources.insert(name.clone(), MatchSource::Passthrough);
             }
         }
-        let canonical = if best.0 > 0.82 { best.1 } else { target };
-        mapping.insert(name.clone(), canonical);
     }
-    mapping
+    debug!(
+        exact = names.len() - misses.len(),
+        fuzzy_checked = misses.len(),
+        "mapped event terms"
+    );
+    (mapping, sources)
+}
+
+/// Dictionary surfaces, lower-cased and grouped by first character, for Jaro-Winkler lookups.
+///
+/// Comparing every term with every entry is quadratic, so a term is only compared with entries
+/// that share its first character and are between half and twice its length. Others could
+/// only score high through a long shared prefix, which the first character already rules out.
+struct FuzzyIndex<'a> {
+    /// `(length in chars, surface, canonical)` by first character, shortest first.
+    by_initial: HashMap<char, Vec<(usize, String, &'a str)>>,
+}
+
+impl<'a> FuzzyIndex<'a> {
+    fn new(entries: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut by_initial: HashMap<char, Vec<_>> = HashMap::new();
+        for (surface, canonical) in entries {
+            let surface = surface.trim().to_lowercase();
+            if let Some(initial) = surface.chars().next() {
+                let length = surface.chars().count();
+                by_initial
+                    .entry(initial)
+                    .or_default()
+                    .push((length, surface, canonical));
+            }
+        }
+        for bucket in by_initial.values_mut() {
+            bucket.sort_by_key(|(length, _, _)| *length);
+        }
+        Self { by_initial }
+    }
+
+    /// Canonical value and score of the entry closest to lower-cased `target`.
+    fn nearest(&self, target: &str) -> Option<(&'a str, f64)> {
+        let bucket = self.by_initial.get(&target.chars().next()?)?;
+        let length = target.chars().count();
+        let start = bucket.partition_point(|(other, _, _)| other * 2 < length);
+        let end = bucket.partition_point(|(other, _, _)| *other <= length * 2);
+        let mut best: Option<(&str, f64)> = None;
+        for (_, surface, canonical) in &bucket[start..end] {
+            let score = jaro_winkler(target, surface);
+            if !matches!(best, Some((_, top)) if top >= score) {
+                best = Some((canonical, score));
+            }
+        }
+        best
+    }
+}
+
+/// Look up the UMLS concept of each raw event term.
+///
+/// The canonical terms are left alone, as SMQ membership and labels are keyed on MedDRA PTs.
+/// Returns canonical term → concept, taking the first concept found for each canonical term.
+async fn link_umls(
+    names: &[String],
+    mapping: &HashMap<String, String>,
+    umls: &mut UmlsNormalizer,
+) -> HashMap<String, UmlsConcept> {
+    let mut concepts = HashMap::new();
+    let mut matched = 0usize;
+    for name in names {
+        let Some(canonical) = mapping.get(name) else {
+            continue;
+        };
+        if let Some(concept) = umls.resolve(name).await {
+            concepts.entry(canonical.clone()).or_insert(concept);
+            matched += 1;
+        }
+    }
+    info!(
+        matched,
+        total = names.len(),
+        "mapped events to UMLS concepts"
+    );
+    concepts
 }
 
-fn materialise_drugs(map: &HashMap<String, String>) -> (Vec<DrugRow>, HashMap<String, String>) {
+#[derive(Debug, Serialize)]
+struct UnmappedTermRow<'a> {
+    kind: &'static str,
+    raw_term: &'a str,
+    frequency: usize,
+    suggestion: Option<&'a str>,
+    similarity: Option<f64>,
+}
+
+/// Write `normalize_report.csv`: passthrough drug/event strings by row frequency, with the
+/// closest dictionary entry as a curation hint.
+fn write_normalize_report(
+    rows: &[FaersRawRow],
+    unmapped_drugs: &HashSet<String>,
+    drug_candidates: &FuzzyIndex,
+    unmapped_events: &HashSet<String>,
+    event_dictionary: &EventDictionary,
+    path: PathBuf,
+) -> Result<()> {
+    let mut drug_counts: HashMap<String, usize> = HashMap::new();
+    let mut event_counts: HashMap<String, usize> = HashMap::new();
+    for row in rows {
+        *drug_counts
+            .entry(row.drugname.trim().to_ascii_uppercase())
+            .or_default() += 1;
+        *event_counts
+            .entry(row.event.trim().to_ascii_uppercase())
+            .or_default() += 1;
+    }
+
+    let event_candidates = FuzzyIndex::new(event_dictionary.entries());
+    let mut report = Vec::new();
+    for (kind, unmapped, counts) in [
+        ("drug", unmapped_drugs, &drug_counts),
+        ("event", unmapped_events, &event_counts),
+    ] {
+        let mut terms: Vec<&String> = unmapped.iter().filter(|t| !t.is_empty()).collect();
+        terms.sort_by(|a, b| counts.get(*b).cmp(&counts.get(*a)).then(a.cmp(b)));
+        for term in terms {
+            let target = term.to_lowercase();
+            let suggestion = if kind == "drug" {
+                drug_candidates.nearest(&target)
+            } else {
+                event_candidates.nearest(&target)
+            };
+            report.push(UnmappedTermRow {
+                kind,
+                raw_term: term,
+                frequency: counts.get(term).copied().unwrap_or(0),
+                suggestion: suggestion.map(|(canonical, _)| canonical),
+                similarity: suggestion.map(|(_, score)| (score * 1000.0).round() / 1000.0),
+            });
+        }
+    }
+
+    let mut writer = csv::Writer::from_path(&path)?;
+    if report.is_empty() {
+        writer.write_record(["kind", "raw_term", "frequency", "suggestion", "similarity"])?;
+    }
+    for row in &report {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+    info!(
+        drugs = unmapped_drugs.len(),
+        events = unmapped_events.len(),
+        path = %path.display(),
+        "wrote unmapped-terms report"
+    );
+    Ok(())
+}
+
+fn materialise_drugs(
+    map: &HashMap<String, Vec<String>>,
+) -> (Vec<DrugRow>, HashMap<String, Vec<String>>) {
     let mut canonical = IndexMap::new();
-    for value in map.values() {
+    for value in map.values().flatten() {
         if !canonical.contains_key(value) {
-            let id = format!("D{:04}", canonical.len() + 1);
+            let id = stable_id('D', value);
             canonical.insert(value.clone(), id);
         }
     }
@@ -218,20 +1346,26 @@ fn materialise_drugs(map: &HashMap<String, String>) -> (Vec<DrugRow>, HashMap<St
             name_canonical: name.clone(),
         });
     }
+    rows.sort_by(|a, b| a.name_canonical.cmp(&b.name_canonical));
     let mut lookup = HashMap::new();
-    for (raw, canon) in map {
-        if let Some(id) = canonical.get(canon) {
-            lookup.insert(raw.clone(), id.clone());
-        }
+    for (raw, ingredients) in map {
+        let ids = ingredients
+            .iter()
+            .filter_map(|canon| canonical.get(canon).cloned())
+            .collect();
+        lookup.insert(raw.clone(), ids);
     }
     (rows, lookup)
 }
 
-fn materialise_events(map: &HashMap<String, String>) -> (Vec<EventRow>, HashMap<String, String>) {
+fn materialise_events(
+    map: &HashMap<String, String>,
+    concepts: &HashMap<String, UmlsConcept>,
+) -> (Vec<EventRow>, HashMap<String, String>) {
     let mut canonical = IndexMap::new();
     for value in map.values() {
         if !canonical.contains_key(value) {
-            let id = format!("E{:04}", canonical.len() + 1);
+            let id = stable_id('E', value);
             canonical.insert(value.clone(), id);
         }
     }
@@ -240,8 +1374,11 @@ fn materialise_events(map: &HashMap<String, String>) -> (Vec<EventRow>, HashMap<
         rows.push(EventRow {
             event_id: id.clone(),
             term_canonical: name.clone(),
+            umls_cui: concepts.get(name).map(|c| c.cui.clone()),
+            umls_name: concepts.get(name).map(|c| c.name.clone()),
         });
     }
+    rows.sort_by(|a, b| a.term_canonical.cmp(&b.term_canonical));
     let mut lookup = HashMap::new();
     for (raw, canon) in map {
         if let Some(id) = canonical.get(canon) {
@@ -251,72 +1388,150 @@ fn materialise_events(map: &HashMap<String, String>) -> (Vec<EventRow>, HashMap<
     (rows, lookup)
 }
 
+/// Count per-quarter 2x2 tables at case level.
+///
+/// Ids are interned to `u32` codes so case sets and co-occurrence counts avoid per-row `String`
+/// clones, and quarters are counted in parallel.
 fn build_contingency(
     rows: &[FaersRawRow],
-    drug_lookup: &HashMap<String, String>,
+    drug_lookup: &HashMap<String, Vec<String>>,
     event_lookup: &HashMap<String, String>,
+    smq_lookup: &HashMap<String, Vec<String>>,
+    drug_roles: &[DrugRole],
 ) -> Vec<FaersNormRow> {
-    #[derive(Default)]
-    struct CaseSummary {
-        drugs: HashSet<String>,
-        events: HashSet<String>,
-    }
-
-    let mut quarters: HashMap<String, HashMap<String, CaseSummary>> = HashMap::new();
+    let mut drug_ids: IndexSet<&str> = IndexSet::new();
+    let mut event_ids: IndexSet<&str> = IndexSet::new();
+    let drug_codes: HashMap<&str, Vec<u32>> = drug_lookup
+        .iter()
+        .map(|(raw, ids)| {
+            let codes = ids
+                .iter()
+                .map(|id| drug_ids.insert_full(id.as_str()).0 as u32)
+                .collect();
+            (raw.as_str(), codes)
+        })
+        .collect();
+    let event_codes: HashMap<&str, Vec<u32>> = event_lookup
+        .iter()
+        .map(|(raw, id)| {
+            let mut codes = vec![event_ids.insert_full(id.as_str()).0 as u32];
+            for smq_id in smq_lookup.get(id).into_iter().flatten() {
+                codes.push(event_ids.insert_full(smq_id.as_str()).0 as u32);
+            }
+            (raw.as_str(), codes)
+        })
+        .collect();
 
+    let mut by_quarter: HashMap<&str, Vec<&FaersRawRow>> = HashMap::new();
     for row in rows {
-        let case_entry = quarters
-            .entry(row.quarter.clone())
+        by_quarter
+            .entry(row.quarter.as_str())
             .or_default()
-            .entry(row.caseid.clone())
-            .or_default();
-        if let Some(drug_id) = drug_lookup.get(&row.drugname.trim().to_ascii_uppercase()) {
-            case_entry.drugs.insert(drug_id.clone());
-        }
-        if let Some(event_id) = event_lookup.get(&row.event.trim().to_ascii_uppercase()) {
-            case_entry.events.insert(event_id.clone());
-        }
+            .push(row);
     }
 
-    let mut results = Vec::new();
-    for (quarter, cases) in quarters {
-        let case_values: Vec<_> = cases.values().collect();
-        let total_cases = case_values.len() as i64;
-        let mut drug_totals: HashMap<String, i64> = HashMap::new();
-        let mut event_totals: HashMap<String, i64> = HashMap::new();
-        let mut co_counts: HashMap<(String, String), i64> = HashMap::new();
-
-        for summary in &case_values {
-            for drug in &summary.drugs {
-                *drug_totals.entry(drug.clone()).or_insert(0) += 1;
-            }
-            for event in &summary.events {
-                *event_totals.entry(event.clone()).or_insert(0) += 1;
+    by_quarter
+        .into_par_iter()
+        .flat_map_iter(|(quarter, rows)| {
+            let mut cases: HashMap<&str, (Vec<u32>, Vec<u32>)> = HashMap::new();
+            for row in rows {
+                let (drugs, events) = cases.entry(row.caseid.as_str()).or_default();
+                // Excluded roles still contribute the case and its events, just not the drug exposure.
+                if role_counted(row, drug_roles) {
+                    if let Some(codes) =
+                        drug_codes.get(row.drugname.trim().to_ascii_uppercase().as_str())
+                    {
+                        drugs.extend_from_slice(codes);
+                    }
+                }
+                if let Some(codes) = event_codes.get(row.event.trim().to_ascii_uppercase().as_str())
+                {
+                    events.extend_from_slice(codes);
+                }
             }
-            for drug in &summary.drugs {
-                for event in &summary.events {
-                    *co_counts.entry((drug.clone(), event.clone())).or_insert(0) += 1;
+
+            let total_cases = cases.len() as i64;
+            let mut drug_totals: HashMap<u32, i64> = HashMap::new();
+            let mut event_totals: HashMap<u32, i64> = HashMap::new();
+            let mut co_counts: HashMap<(u32, u32), i64> = HashMap::new();
+            for (drugs, events) in cases.values_mut() {
+                drugs.sort_unstable();
+                drugs.dedup();
+                events.sort_unstable();
+                events.dedup();
+                for drug in drugs.iter() {
+                    *drug_totals.entry(*drug).or_insert(0) += 1;
+                }
+                for event in events.iter() {
+                    *event_totals.entry(*event).or_insert(0) += 1;
+                }
+                for drug in drugs.iter() {
+                    for event in events.iter() {
+                        *co_counts.entry((*drug, *event)).or_insert(0) += 1;
+                    }
                 }
             }
-        }
 
-        for ((drug, event), a) in co_counts.iter() {
-            let b = drug_totals.get(drug).cloned().unwrap_or(0) - a;
-            let c = event_totals.get(event).cloned().unwrap_or(0) - a;
-            let d = total_cases - (a + b + c);
-            results.push(FaersNormRow {
-                drug_id: drug.clone(),
-                event_id: event.clone(),
-                year_quarter: quarter.clone(),
-                a: *a,
-                b,
-                c,
-                d,
-            });
+            let drug_ids = &drug_ids;
+            let event_ids = &event_ids;
+            co_counts.into_iter().map(move |((drug, event), a)| {
+                let b = drug_totals.get(&drug).copied().unwrap_or(0) - a;
+                let c = event_totals.get(&event).copied().unwrap_or(0) - a;
+                FaersNormRow {
+                    drug_id: drug_ids[drug as usize].to_string(),
+                    event_id: event_ids[event as usize].to_string(),
+                    year_quarter: quarter.to_string(),
+                    period: None,
+                    a,
+                    b,
+                    c,
+                    d: total_cases - (a + b + c),
+                }
+            })
+        })
+        .collect()
+}
+
+fn write_known_associations(
+    norm_rows: &[FaersNormRow],
+    drug_rows: &[DrugRow],
+    event_rows: &[EventRow],
+    vocab: &SiderVocabulary,
+    path: PathBuf,
+) -> Result<()> {
+    // Written even when empty, so pairs flagged by an earlier SIDER download do not linger.
+    let drug_names: HashMap<&str, &str> = drug_rows
+        .iter()
+        .map(|r| (r.drug_id.as_str(), r.name_canonical.as_str()))
+        .collect();
+    let event_terms: HashMap<&str, &str> = event_rows
+        .iter()
+        .map(|r| (r.event_id.as_str(), r.term_canonical.as_str()))
+        .collect();
+    let mut pairs = HashSet::new();
+    for row in norm_rows {
+        if let (Some(drug), Some(event)) = (
+            drug_names.get(row.drug_id.as_str()),
+            event_terms.get(row.event_id.as_str()),
+        ) {
+            if vocab.is_known(drug, event) {
+                pairs.insert((row.drug_id.clone(), row.event_id.clone()));
+            }
         }
     }
-
-    results
+    let (drug_ids, event_ids): (Vec<String>, Vec<String>) = pairs.into_iter().unzip();
+    let rows = drug_ids.len();
+    let mut df = DataFrame::new(vec![
+        Series::new("drug_id".into(), drug_ids),
+        Series::new("event_id".into(), event_ids),
+    ])?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = File::create(&path)?;
+    ParquetWriter::new(file).finish(&mut df)?;
+    info!(path = %path.display(), rows, "wrote SIDER known associations");
+    Ok(())
 }
 
 fn write_drugs(rows: &[DrugRow], path: PathBuf) -> Result<()> {
@@ -347,9 +1562,13 @@ fn write_events(rows: &[EventRow], path: PathBuf) -> Result<()> {
     }
     let ids: Vec<String> = rows.iter().map(|r| r.event_id.clone()).collect();
     let names: Vec<String> = rows.iter().map(|r| r.term_canonical.clone()).collect();
+    let cuis: Vec<Option<String>> = rows.iter().map(|r| r.umls_cui.clone()).collect();
+    let umls_names: Vec<Option<String>> = rows.iter().map(|r| r.umls_name.clone()).collect();
     let mut df = DataFrame::new(vec![
         Series::new("event_id".into(), ids),
         Series::new("term_canonical".into(), names),
+        Series::new("umls_cui".into(), cuis),
+        Series::new("umls_name".into(), umls_names),
     ])?;
     let file = File::create(&path)?;
     ParquetWriter::new(file).finish(&mut df)?;
@@ -367,6 +1586,7 @@ fn write_norm(rows: &[FaersNormRow], path: PathBuf) -> Result<()> {
     let drug_ids: Vec<String> = rows.iter().map(|r| r.drug_id.clone()).collect();
     let event_ids: Vec<String> = rows.iter().map(|r| r.event_id.clone()).collect();
     let quarters: Vec<String> = rows.iter().map(|r| r.year_quarter.clone()).collect();
+    let periods: Vec<Option<String>> = rows.iter().map(|r| r.period.clone()).collect();
     let a: Vec<i64> = rows.iter().map(|r| r.a).collect();
     let b: Vec<i64> = rows.iter().map(|r| r.b).collect();
     let c: Vec<i64> = rows.iter().map(|r| r.c).collect();
@@ -375,6 +1595,7 @@ fn write_norm(rows: &[FaersNormRow], path: PathBuf) -> Result<()> {
         Series::new("drug_id".into(), drug_ids),
         Series::new("event_id".into(), event_ids),
         Series::new("year_quarter".into(), quarters),
+        Series::new("period".into(), periods),
         Series::new("a".into(), a),
         Series::new("b".into(), b),
         Series::new("c".into(), c),
@@ -0,0 +1,227 @@
+//! PubMed Central open-access full-text retrieval.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::{
+    config::Settings,
+    data::pubmed::{self, PubRecord},
+    net,
+};
+
+const EUTILS_BASE: &str = "https://eutils.ncbi.nlm.nih.gov/entrez/eutils";
+/// Section types and titles kept from full text; methods and introductions add little signal.
+const KEY_SECTIONS: &[&str] = &["result", "conclusion", "discussion"];
+
+/// Attach open-access PMC results/conclusions/discussion text to records that have it.
+///
+/// Returns the number of records that gained full text. Articles outside the PMC open-access
+/// subset return only front matter and are left untouched.
+pub async fn attach_full_text(records: &mut [PubRecord], settings: &Settings) -> Result<usize> {
+    let pmids: Vec<String> = records
+        .iter()
+        .filter(|r| r.full_text.is_none())
+        .map(|r| r.pmid.clone())
+        .collect();
+    if pmids.is_empty() {
+        return Ok(0);
+    }
+    let client = net::client(settings)?;
+
+    let mut pmc_ids = Vec::new();
+    for chunk in pmids.chunks(100) {
+        let ids: String = chunk.iter().map(|id| format!("&id={id}")).collect();
+        let url = format!(
+            "{EUTILS_BASE}/elink.fcgi?dbfrom=pubmed&db=pmc&linkname=pubmed_pmc&retmode=json{ids}&tool={tool}&email={email}{key}",
+            tool = settings.pubmed_tool,
+            email = settings.pubmed_email,
+            key = pubmed::api_key_param(settings)
+        );
+        // Errors carry the request URL, which holds the api key.
+        let payload: ELinkResponse = client
+            .get(&url)
+            .await?
+            .error_for_status()
+            .map_err(net::redact)?
+            .json()
+            .await
+            .map_err(net::redact)?;
+        pmc_ids.extend(
+            payload
+                .linksets
+                .into_iter()
+                .flat_map(|set| set.linksetdbs)
+                .flat_map(|db| db.links),
+        );
+    }
+    if pmc_ids.is_empty() {
+        return Ok(0);
+    }
+    info!(articles = pmc_ids.len(), "fetching PMC full text");
+
+    let mut texts: HashMap<String, String> = HashMap::new();
+    for chunk in pmc_ids.chunks(20) {
+        let url = format!(
+            "{EUTILS_BASE}/efetch.fcgi?db=pmc&retmode=xml&id={ids}&tool={tool}&email={email}{key}",
+            ids = chunk.join(","),
+            tool = settings.pubmed_tool,
+            email = settings.pubmed_email,
+            key = pubmed::api_key_param(settings)
+        );
+        let xml = client
+            .get(&url)
+            .await?
+            .error_for_status()
+            .map_err(net::redact)?
+            .text()
+            .await
+            .map_err(net::redact)?;
+        match key_sections(&xml) {
+            Ok(articles) => texts.extend(articles),
+            Err(err) => warn!(%err, "failed to parse PMC batch"),
+        }
+    }
+
+    let mut attached = 0;
+    for record in records.iter_mut() {
+        if let Some(text) = texts.remove(&record.pmid) {
+            record.full_text = Some(text);
+            attached += 1;
+        }
+    }
+    Ok(attached)
+}
+
+/// Extract `(pmid, text)` for every article in a PMC efetch batch, keeping only results,
+/// conclusions and discussion sections. Articles without such sections are omitted.
+pub fn key_sections(xml: &str) -> Result<Vec<(String, String)>> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut articles = Vec::new();
+    let mut pmid: Option<String> = None;
+    let mut text = String::new();
+    let mut in_pmid = false;
+    // One flag per open <sec>, true when it (or an ancestor) is a key section.
+    let mut sections: Vec<bool> = Vec::new();
+    // Whether the current <title> belongs directly to the innermost <sec>.
+    let mut in_sec_title = false;
+    let mut depth_in_sec: Vec<usize> = Vec::new();
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                match e.local_name().as_ref() {
+                    b"article" => {
+                        pmid = None;
+                        text.clear();
+                        sections.clear();
+                        depth_in_sec.clear();
+                    }
+                    b"article-id" => in_pmid = is_pmid_id(&e)?,
+                    b"sec" => {
+                        let inherited = sections.last().copied().unwrap_or(false);
+                        sections.push(inherited || is_key_sec_type(&e)?);
+                        depth_in_sec.push(0);
+                        continue;
+                    }
+                    b"title" => in_sec_title = depth_in_sec.last() == Some(&0),
+                    _ => {}
+                }
+                if let Some(depth) = depth_in_sec.last_mut() {
+                    *depth += 1;
+                }
+            }
+            Event::End(e) => match e.local_name().as_ref() {
+                b"sec" => {
+                    sections.pop();
+                    depth_in_sec.pop();
+                    text.push('\n');
+                }
+                b"article" => {
+                    let body = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                    if let (Some(id), false) = (pmid.take(), body.is_empty()) {
+                        articles.push((id, body));
+                    }
+                }
+                name => {
+                    if name == b"article-id" {
+                        in_pmid = false;
+                    } else if name == b"title" {
+                        in_sec_title = false;
+                    }
+                    if let Some(depth) = depth_in_sec.last_mut() {
+                        *depth = depth.saturating_sub(1);
+                    }
+                }
+            },
+            Event::Text(t) => {
+                let value = t.unescape()?;
+                if in_pmid {
+                    pmid = Some(value.trim().to_string());
+                    continue;
+                }
+                if in_sec_title && is_key_title(&value) {
+                    if let Some(flag) = sections.last_mut() {
+                        *flag = true;
+                    }
+                }
+                if sections.last() == Some(&true) {
+                    text.push_str(&value);
+                    text.push(' ');
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(articles)
+}
+
+fn is_pmid_id(e: &BytesStart<'_>) -> Result<bool> {
+    for attribute in e.attributes() {
+        let attribute = attribute?;
+        if attribute.key.local_name().as_ref() == b"pub-id-type" {
+            return Ok(attribute.unescape_value()? == "pmid");
+        }
+    }
+    Ok(false)
+}
+
+fn is_key_sec_type(e: &BytesStart<'_>) -> Result<bool> {
+    for attribute in e.attributes() {
+        let attribute = attribute?;
+        if attribute.key.local_name().as_ref() == b"sec-type" {
+            return Ok(is_key_title(&attribute.unescape_value()?));
+        }
+    }
+    Ok(false)
+}
+
+fn is_key_title(title: &str) -> bool {
+    let lower = title.to_lowercase();
+    KEY_SECTIONS.iter().any(|key| lower.contains(key))
+}
+
+#[derive(Debug, Deserialize)]
+struct ELinkResponse {
+    #[serde(default)]
+    linksets: Vec<LinkSet>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LinkSet {
+    #[serde(default)]
+    linksetdbs: Vec<LinkSetDb>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LinkSetDb {
+    #[serde(default)]
+    links: Vec<String>,
+}
@@ -1,17 +1,21 @@
 //! PubMed ingestion utilities leveraging E-utilities.
 
-use std::{fs::File, io::Write, path::PathBuf};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+};
 
 use anyhow::{Context, Result};
 use quick_xml::de::from_str;
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use tracing::info;
+use tracing::{info, warn};
 use urlencoding::encode;
 
-use crate::config::Settings;
+use crate::{config::Settings, net};
 
-const EUTILS_BASE: &str = "https://eutils.ncbi.nlm.nih.gov/entrez/eutils";
+pub(crate) const EUTILS_BASE: &str = "https://eutils.ncbi.nlm.nih.gov/entrez/eutils";
 
 /// Normalised PubMed record persisted to JSONL.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,28 +23,301 @@ pub struct PubRecord {
     pub pmid: String,
     pub title: String,
     pub abstract_text: String,
+    /// Labelled paragraphs of a structured abstract, in order; empty for unstructured ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub abstract_sections: Vec<AbstractParagraph>,
     pub journal: Option<String>,
     pub authors: Vec<String>,
     pub year: Option<i32>,
+    /// Key full-text sections from PubMed Central, when the article is open access.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub full_text: Option<String>,
+    /// MeSH descriptors with their qualifiers (e.g., "chemically induced").
+    #[serde(default)]
+    pub mesh_headings: Vec<MeshHeading>,
+    /// Substance names from the MEDLINE chemical list.
+    #[serde(default)]
+    pub chemicals: Vec<String>,
+    /// PubMed publication types ("Randomized Controlled Trial", "Case Reports").
+    #[serde(default)]
+    pub publication_types: Vec<String>,
+}
+
+/// A MeSH descriptor and the qualifiers attached to it on a citation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MeshHeading {
+    pub descriptor: String,
+    #[serde(default)]
+    pub qualifiers: Vec<String>,
+}
+
+/// Structured-abstract section, from the `NlmCategory` (or `Label`) of an `AbstractText`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AbstractSection {
+    Background,
+    Objective,
+    Methods,
+    Results,
+    Conclusions,
+    /// Unstructured abstracts, unassigned labels and full text.
+    #[default]
+    Unlabelled,
+}
+
+impl AbstractSection {
+    /// Classify a paragraph by its NLM category, falling back to the author's label
+    /// ("FINDINGS", "Interpretation") when PubMed left it unassigned.
+    pub fn classify(category: Option<&str>, label: Option<&str>) -> Self {
+        let from = |name: &str| match name.trim().to_ascii_uppercase().as_str() {
+            "BACKGROUND" | "INTRODUCTION" | "CONTEXT" => Self::Background,
+            "OBJECTIVE" | "OBJECTIVES" | "AIM" | "AIMS" | "PURPOSE" => Self::Objective,
+            "METHODS" | "DESIGN" | "MATERIALS AND METHODS" | "PATIENTS AND METHODS" | "SETTING" => {
+                Self::Methods
+            }
+            "RESULTS" | "FINDINGS" | "CASE PRESENTATION" | "CASE REPORT" => Self::Results,
+            "CONCLUSIONS" | "CONCLUSION" | "INTERPRETATION" | "DISCUSSION" => Self::Conclusions,
+            _ => Self::Unlabelled,
+        };
+        match category.map(from) {
+            Some(section) if section != Self::Unlabelled => section,
+            _ => label.map(from).unwrap_or_default(),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Background => "background",
+            Self::Objective => "objective",
+            Self::Methods => "methods",
+            Self::Results => "results",
+            Self::Conclusions => "conclusions",
+            Self::Unlabelled => "unlabelled",
+        }
+    }
+}
+
+/// One `AbstractText` paragraph of a structured abstract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbstractParagraph {
+    pub section: AbstractSection,
+    pub text: String,
+}
+
+/// Default esearch template; `{drug}` is replaced by the drug name.
+pub const DEFAULT_QUERY_TEMPLATE: &str = "{drug} adverse event";
+
+/// PubMed publication types that can restrict a search, and that grade the study design of
+/// literature evidence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum PublicationType {
+    CaseReport,
+    Rct,
+    ClinicalTrial,
+    Review,
+    MetaAnalysis,
+    Observational,
+}
+
+impl PublicationType {
+    /// Strongest study design among a citation's PubMed publication types, if any is graded.
+    pub fn strongest(names: &[String]) -> Option<Self> {
+        names
+            .iter()
+            .filter_map(|name| Self::from_name(name))
+            .max_by_key(|kind| kind.evidence_rank())
+    }
+
+    /// Map a PubMed publication type name ("Randomized Controlled Trial") to a variant.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "case reports" => Some(Self::CaseReport),
+            "randomized controlled trial" => Some(Self::Rct),
+            "clinical trial"
+            | "clinical trial, phase i"
+            | "clinical trial, phase ii"
+            | "clinical trial, phase iii"
+            | "clinical trial, phase iv"
+            | "controlled clinical trial" => Some(Self::ClinicalTrial),
+            "review" | "systematic review" => Some(Self::Review),
+            "meta-analysis" => Some(Self::MetaAnalysis),
+            "observational study" | "comparative study" | "multicenter study" => {
+                Some(Self::Observational)
+            }
+            _ => None,
+        }
+    }
+
+    /// Position in the evidence hierarchy: meta-analysis > RCT > clinical trial >
+    /// observational/cohort > review > case report.
+    fn evidence_rank(self) -> u8 {
+        match self {
+            Self::MetaAnalysis => 5,
+            Self::Rct => 4,
+            Self::ClinicalTrial => 3,
+            Self::Observational => 2,
+            Self::Review => 1,
+            Self::CaseReport => 0,
+        }
+    }
+
+    /// Kebab-case name, as accepted by `--publication-types` and `STUDY_TYPE_WEIGHTS`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::CaseReport => "case-report",
+            Self::Rct => "rct",
+            Self::ClinicalTrial => "clinical-trial",
+            Self::Review => "review",
+            Self::MetaAnalysis => "meta-analysis",
+            Self::Observational => "observational",
+        }
+    }
+
+    fn tag(self) -> &'static str {
+        match self {
+            Self::CaseReport => "Case Reports[pt]",
+            Self::Rct => "Randomized Controlled Trial[pt]",
+            Self::ClinicalTrial => "Clinical Trial[pt]",
+            Self::Review => "Review[pt]",
+            Self::MetaAnalysis => "Meta-Analysis[pt]",
+            Self::Observational => "Observational Study[pt]",
+        }
+    }
+}
+
+/// Search template and filters applied to every per-drug esearch.
+#[derive(Debug, Clone)]
+pub struct PubmedQuery {
+    /// Query text with a `{drug}` placeholder.
+    pub template: String,
+    /// Earliest publication date (`YYYY`, `YYYY/MM` or `YYYY/MM/DD`).
+    pub since: Option<String>,
+    /// Latest publication date, same formats as `since`.
+    pub until: Option<String>,
+    /// Restrict to any of these publication types; empty means no restriction.
+    pub publication_types: Vec<PublicationType>,
+    /// Only return articles added to PubMed on or after this Entrez date (`YYYY/MM/DD`).
+    pub added_since: Option<String>,
+}
+
+impl Default for PubmedQuery {
+    fn default() -> Self {
+        Self {
+            template: DEFAULT_QUERY_TEMPLATE.to_string(),
+            since: None,
+            until: None,
+            publication_types: Vec::new(),
+            added_since: None,
+        }
+    }
+}
+
+impl PubmedQuery {
+    /// Render the esearch `term` for a drug.
+    pub fn term(&self, drug: &str) -> String {
+        let mut term = self.template.replace("{drug}", drug);
+        if !self.publication_types.is_empty() {
+            let types: Vec<&str> = self.publication_types.iter().map(|t| t.tag()).collect();
+            term = format!("({term}) AND ({})", types.join(" OR "));
+        }
+        if self.since.is_some() || self.until.is_some() {
+            // Both bounds are required once either is given.
+            let min = self.since.as_deref().unwrap_or("1800");
+            let max = self.until.as_deref().unwrap_or("3000");
+            term = format!("({term}) AND (\"{min}\"[pdat] : \"{max}\"[pdat])");
+        }
+        term
+    }
+
+    /// Provenance URL for the esearch behind a drug's cache (without credentials).
+    pub fn source_url(&self, drug: &str) -> String {
+        format!(
+            "{EUTILS_BASE}/esearch.fcgi?db=pubmed&term={}",
+            encode(&self.term(drug))
+        )
+    }
+
+    /// Entrez-date parameters for incremental searches, empty when not incremental.
+    fn date_params(&self) -> String {
+        match &self.added_since {
+            Some(since) => format!("&datetype=edat&mindate={}&maxdate=3000", encode(since)),
+            None => String::new(),
+        }
+    }
 }
 
-pub async fn search_pubmed(drug: &str, max: usize, settings: &Settings) -> Result<Vec<String>> {
+/// PMIDs already cached for a drug, stored next to its JSONL as `<drug>.index.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PmidIndex {
+    /// Entrez date (`YYYY/MM/DD`) of the last successful fetch.
+    pub last_fetched: Option<String>,
+    pub pmids: BTreeSet<String>,
+}
+
+impl PmidIndex {
+    /// Load the index for `drug`; empty when missing or when its JSONL cache was removed.
+    pub fn load(drug: &str, settings: &Settings) -> Result<Self> {
+        let path = index_path(drug, settings);
+        if !path.exists() || !records_path(drug, settings).exists() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(&path).with_context(|| format!("read {path:?}"))?;
+        Ok(serde_json::from_str(&text).unwrap_or_else(|err| {
+            warn!(%drug, %err, "corrupt pubmed index; refetching");
+            Self::default()
+        }))
+    }
+
+    pub fn save(&self, drug: &str, settings: &Settings) -> Result<()> {
+        let path = index_path(drug, settings);
+        std::fs::write(&path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("write {path:?}"))
+    }
+}
+
+fn records_path(drug: &str, settings: &Settings) -> PathBuf {
+    settings
+        .join_data("raw/pubmed")
+        .join(format!("{drug}.jsonl"))
+}
+
+fn index_path(drug: &str, settings: &Settings) -> PathBuf {
+    settings
+        .join_data("raw/pubmed")
+        .join(format!("{drug}.index.json"))
+}
+
+pub async fn search_pubmed(
+    drug: &str,
+    max: usize,
+    query: &PubmedQuery,
+    settings: &Settings,
+) -> Result<Vec<String>> {
     if drug.trim().is_empty() {
         return Ok(vec![]);
     }
-    let client = http_client(settings)?;
-    let query = format!("{drug} adverse event");
-    let term = encode(query.as_str());
+    let client = net::client(settings)?;
+    let term = encode(&query.term(drug)).into_owned();
     let url = format!(
-        "{base}/esearch.fcgi?db=pubmed&retmode=json&term={term}&retmax={max}&tool={tool}&email={email}",
+        "{base}/esearch.fcgi?db=pubmed&retmode=json&term={term}&retmax={max}{dates}&tool={tool}&email={email}{key}",
         base = EUTILS_BASE,
         term = term,
         max = max,
+        dates = query.date_params(),
         tool = settings.pubmed_tool,
-        email = settings.pubmed_email
+        email = settings.pubmed_email,
+        key = api_key_param(settings)
     );
-    let resp = client.get(url).send().await?;
-    let payload: ESearchResponse = resp.json().await?;
+    // Errors carry the request URL, which holds the api key.
+    let payload: ESearchResponse = client
+        .get(&url)
+        .await?
+        .error_for_status()
+        .map_err(net::redact)?
+        .json()
+        .await
+        .map_err(net::redact)?;
     Ok(payload.esearchresult.idlist)
 }
 
@@ -48,18 +325,26 @@ pub async fn fetch_pubmed(pmids: &[String], settings: &Settings) -> Result<Vec<P
     if pmids.is_empty() {
         return Ok(Vec::new());
     }
-    let client = http_client(settings)?;
+    let client = net::client(settings)?;
     let mut output = Vec::new();
     for chunk in pmids.chunks(200) {
         let ids = chunk.join(",");
         let url = format!(
-            "{base}/efetch.fcgi?db=pubmed&rettype=abstract&retmode=xml&id={ids}&tool={tool}&email={email}",
+            "{base}/efetch.fcgi?db=pubmed&rettype=abstract&retmode=xml&id={ids}&tool={tool}&email={email}{key}",
             base = EUTILS_BASE,
             ids = ids,
             tool = settings.pubmed_tool,
-            email = settings.pubmed_email
+            email = settings.pubmed_email,
+            key = api_key_param(settings)
         );
-        let xml = client.get(&url).send().await?.text().await?;
+        let xml = client
+            .get(&url)
+            .await?
+            .error_for_status()
+            .map_err(net::redact)?
+            .text()
+            .await
+            .map_err(net::redact)?;
         let article_set: PubmedArticleSet = from_str(&xml).unwrap_or_default();
         for article in article_set.articles {
             if let Some(record) = article.into_record() {
@@ -70,29 +355,85 @@ pub async fn fetch_pubmed(pmids: &[String], settings: &Settings) -> Result<Vec<P
     Ok(output)
 }
 
-pub fn persist_records(drug: &str, records: &[PubRecord], settings: &Settings) -> Result<PathBuf> {
-    let path = settings
-        .join_data("raw/pubmed")
-        .join(format!("{drug}.jsonl"));
+/// Write records to `raw/pubmed/<drug>.jsonl`, appending to the cache when `append` is set.
+pub fn persist_records(
+    drug: &str,
+    records: &[PubRecord],
+    append: bool,
+    settings: &Settings,
+) -> Result<PathBuf> {
+    let path = records_path(drug, settings);
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
     }
-    let mut file = File::create(&path).with_context(|| format!("create {path:?}"))?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(&path)
+        .with_context(|| format!("open {path:?}"))?;
     for record in records {
         let line = serde_json::to_string(record)?;
         file.write_all(line.as_bytes())?;
         file.write_all(b"\n")?;
     }
-    info!(path = %path.display(), count = records.len(), "saved pubmed records");
+    info!(path = %path.display(), count = records.len(), append, "saved pubmed records");
     Ok(path)
 }
 
-fn http_client(settings: &Settings) -> Result<Client> {
-    Ok(Client::builder()
-        .user_agent(format!("rwe-assistant/0.1 (+{})", settings.pubmed_email))
-        .gzip(true)
-        .brotli(true)
-        .build()?)
+/// Cached records for `pmids` from every `raw/pubmed/*.jsonl`, keyed by PMID. PMIDs that
+/// were never fetched are missing from the map.
+pub fn load_records(
+    pmids: &HashSet<&str>,
+    settings: &Settings,
+) -> Result<HashMap<String, PubRecord>> {
+    /// Just enough of a record to decide whether to parse the rest.
+    #[derive(Deserialize)]
+    struct Pmid<'a> {
+        #[serde(borrow)]
+        pmid: std::borrow::Cow<'a, str>,
+    }
+
+    let mut records = HashMap::new();
+    let root = settings.join_data("raw/pubmed");
+    if pmids.is_empty() || !root.exists() {
+        return Ok(records);
+    }
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(&root)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    paths.retain(|path| path.extension().and_then(|s| s.to_str()) == Some("jsonl"));
+    paths.sort();
+    for path in paths {
+        let reader = BufReader::new(File::open(&path).with_context(|| format!("open {path:?}"))?);
+        for line in reader.lines() {
+            let line = line.with_context(|| format!("read {path:?}"))?;
+            let Ok(Pmid { pmid }) = serde_json::from_str::<Pmid>(&line) else {
+                continue;
+            };
+            if !pmids.contains(pmid.as_ref()) || records.contains_key(pmid.as_ref()) {
+                continue;
+            }
+            match serde_json::from_str::<PubRecord>(&line) {
+                Ok(record) => {
+                    records.insert(record.pmid.clone(), record);
+                }
+                Err(err) => {
+                    warn!(path = %path.display(), %pmid, %err, "skipping bad pubmed record")
+                }
+            }
+        }
+    }
+    Ok(records)
+}
+
+pub(crate) fn api_key_param(settings: &Settings) -> String {
+    settings
+        .pubmed_api_key
+        .as_deref()
+        .map(|key| format!("&api_key={}", encode(key)))
+        .unwrap_or_default()
 }
 
 #[derive(Debug, Deserialize)]
@@ -107,7 +448,7 @@ struct ESearchResult {
     idlist: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Default)]
 struct PubmedArticleSet {
     #[serde(rename = "PubmedArticle", default)]
     articles: Vec<PubmedArticle>,
@@ -124,17 +465,27 @@ impl PubmedArticle {
         let pmid = self.citation.pmid.value;
         let article = self.citation.article;
         let title = article.title.value;
-        let abstract_text = article
+        let paragraphs: Vec<&AbstractText> = article
             .abstract_section
             .as_ref()
-            .map(|abs| {
-                abs.text
-                    .iter()
-                    .filter_map(|t| t.value.clone())
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            })
+            .map(|abs| abs.text.iter().filter(|t| t.value.is_some()).collect())
             .unwrap_or_default();
+        let abstract_text = paragraphs
+            .iter()
+            .filter_map(|t| t.value.clone())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let abstract_sections = if paragraphs.iter().any(|t| t.label.is_some()) {
+            paragraphs
+                .iter()
+                .map(|t| AbstractParagraph {
+                    section: AbstractSection::classify(t.category.as_deref(), t.label.as_deref()),
+                    text: t.value.clone().unwrap_or_default(),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
         let journal = article.journal.and_then(|j| j.title.map(|t| t.value));
         let authors = article
             .author_list
@@ -146,14 +497,41 @@ impl PubmedArticle {
             })
             .unwrap_or_default();
         let year = self.citation.article_date.and_then(|d| d.year());
+        let mesh_headings = self
+            .citation
+            .mesh_heading_list
+            .map(|list| {
+                list.headings
+                    .into_iter()
+                    .map(|h| MeshHeading {
+                        descriptor: h.descriptor.value,
+                        qualifiers: h.qualifiers.into_iter().map(|q| q.value).collect(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let chemicals = self
+            .citation
+            .chemical_list
+            .map(|list| list.chemicals.into_iter().map(|c| c.name.value).collect())
+            .unwrap_or_default();
+        let publication_types = article
+            .publication_type_list
+            .map(|list| list.types.into_iter().map(|t| t.value).collect())
+            .unwrap_or_default();
 
         Some(PubRecord {
             pmid,
             title,
             abstract_text,
+            abstract_sections,
             journal,
             authors,
             year,
+            full_text: None,
+            mesh_headings,
+            chemicals,
+            publication_types,
         })
     }
 }
@@ -166,6 +544,36 @@ struct MedlineCitation {
     article: Article,
     #[serde(rename = "ArticleDate")]
     article_date: Option<ArticleDate>,
+    #[serde(rename = "MeshHeadingList")]
+    mesh_heading_list: Option<MeshHeadingList>,
+    #[serde(rename = "ChemicalList")]
+    chemical_list: Option<ChemicalList>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MeshHeadingList {
+    #[serde(rename = "MeshHeading", default)]
+    headings: Vec<MeshHeadingNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MeshHeadingNode {
+    #[serde(rename = "DescriptorName")]
+    descriptor: TextNode,
+    #[serde(rename = "QualifierName", default)]
+    qualifiers: Vec<TextNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChemicalList {
+    #[serde(rename = "Chemical", default)]
+    chemicals: Vec<Chemical>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chemical {
+    #[serde(rename = "NameOfSubstance")]
+    name: TextNode,
 }
 
 #[derive(Debug, Deserialize)]
@@ -178,6 +586,14 @@ struct Article {
     journal: Option<Journal>,
     #[serde(rename = "AuthorList")]
     author_list: Option<AuthorList>,
+    #[serde(rename = "PublicationTypeList")]
+    publication_type_list: Option<PublicationTypeList>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PublicationTypeList {
+    #[serde(rename = "PublicationType", default)]
+    types: Vec<TextNode>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -188,6 +604,10 @@ struct Abstract {
 
 #[derive(Debug, Deserialize)]
 struct AbstractText {
+    #[serde(rename = "@Label")]
+    label: Option<String>,
+    #[serde(rename = "@NlmCategory")]
+    category: Option<String>,
     #[serde(rename = "$text")] // raw text content
     value: Option<String>,
 }
@@ -242,11 +662,3 @@ struct TextNode {
     #[serde(rename = "$text")]
     value: String,
 }
-
-impl Default for PubmedArticleSet {
-    fn default() -> Self {
-        Self {
-            articles: Vec::new(),
-        }
-    }
-}
@@ -0,0 +1,266 @@
+//! Selective removal of cached artefacts, keeping the manifest and indexes consistent.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use tracing::{debug, info};
+
+use crate::{
+    cli::schedule,
+    config::Settings,
+    data::{manifest, quality},
+    nlp::embedding_cache,
+};
+
+/// Directories under `data/raw` that `fetch` downloads and can download again. Licensed or
+/// hand-supplied inputs (MedDRA, RxNorm, UMLS, DrugBank) and imported cases are kept.
+const RAW_DOWNLOADS: &[&str] = &["faers", "pubmed", "sider", "dailymed", "device"];
+
+/// Outputs that runs append to and that cannot be rebuilt once removed.
+const KEPT_OUTPUTS: &[&str] = &[quality::HISTORY_FILE, schedule::HISTORY_FILE];
+
+/// A class of cached artefacts that can be purged independently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// Downloaded source files under `data/raw`.
+    Raw,
+    /// Normalised tables under `data/clean`.
+    Clean,
+    /// Ranked signals, summaries and reports under the outputs directory, except run
+    /// histories.
+    Outputs,
+    /// Model weights under `data/models` and the embedding cache.
+    Models,
+}
+
+impl Target {
+    const ALL: [Target; 4] = [Self::Raw, Self::Clean, Self::Outputs, Self::Models];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Raw => "raw",
+            Self::Clean => "clean",
+            Self::Outputs => "outputs",
+            Self::Models => "models",
+        }
+    }
+
+    fn roots(self, settings: &Settings) -> Vec<PathBuf> {
+        match self {
+            Self::Raw => RAW_DOWNLOADS
+                .iter()
+                .map(|dir| settings.join_data("raw").join(dir))
+                .collect(),
+            Self::Clean => vec![settings.join_data("clean")],
+            Self::Outputs => vec![settings.outputs_dir.clone()],
+            Self::Models => vec![
+                settings.join_data("models"),
+                settings.join_data(embedding_cache::CACHE_FILE),
+            ],
+        }
+    }
+}
+
+/// What a purge removed, or would remove on a dry run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PurgeReport {
+    /// Files removed, or that would be removed.
+    pub paths: Vec<PathBuf>,
+    pub files: usize,
+    pub bytes: u64,
+    /// Manifest entries dropped because their file is gone.
+    pub manifest_entries: usize,
+}
+
+/// Remove the files of `targets`, only those last modified more than `older_than` ago when
+/// given. State such as watchlists, annotations and the manifest itself is never touched.
+pub fn purge(
+    settings: &Settings,
+    targets: &[Target],
+    older_than: Option<Duration>,
+    dry_run: bool,
+) -> Result<PurgeReport> {
+    let cutoff = match older_than {
+        Some(age) => Some(
+            SystemTime::now()
+                .checked_sub(age)
+                .context("--older-than reaches before the epoch")?,
+        ),
+        None => None,
+    };
+    check_roots(settings, targets)?;
+    let kept: Vec<PathBuf> = KEPT_OUTPUTS
+        .iter()
+        .map(|name| settings.join_output(name))
+        .collect();
+    let mut report = PurgeReport::default();
+    for target in targets {
+        for root in target.roots(settings) {
+            let mut files = Vec::new();
+            collect_files(&root, &mut files)?;
+            files.retain(|path| !kept.contains(path));
+            // PubMed indexes are bookkeeping for their JSONL and go only once it has.
+            let (indexes, files): (Vec<_>, Vec<_>) =
+                files.into_iter().partition(|path| is_index(path));
+            let mut removed = HashSet::new();
+            for path in files {
+                let meta = path.metadata().with_context(|| format!("stat {path:?}"))?;
+                if cutoff.is_some_and(|cutoff| meta.modified().is_ok_and(|time| time > cutoff)) {
+                    continue;
+                }
+                remove(&path, meta.len(), dry_run, &mut report)?;
+                removed.insert(path);
+            }
+            for path in indexes {
+                let records = records_of(&path);
+                if records.exists() && !removed.contains(&records) {
+                    continue;
+                }
+                let size = path.metadata().map(|meta| meta.len()).unwrap_or(0);
+                remove(&path, size, dry_run, &mut report)?;
+            }
+            if !dry_run && root.is_dir() {
+                remove_empty_dirs(&root)?;
+            }
+        }
+        info!(target = target.name(), "purged");
+    }
+    if !dry_run {
+        report.manifest_entries = manifest::prune(settings)?;
+    }
+    Ok(report)
+}
+
+/// Parse an age such as `90d`, `12h`, `2w` or `30m`; a bare number counts days.
+pub fn parse_age(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let Ok(number) = number.parse::<u64>() else {
+        bail!("invalid age {text:?}; expected e.g. 90d, 12h or 2w");
+    };
+    let seconds = match unit {
+        "m" => 60,
+        "h" => 60 * 60,
+        "" | "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => bail!("invalid age unit {unit:?} in {text:?}; expected m, h, d or w"),
+    };
+    let Some(seconds) = number.checked_mul(seconds) else {
+        bail!("age {text:?} is too large");
+    };
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Refuse roots that would take more than their own artefacts with them: the working
+/// directory, a filesystem root, one holding the data directory, or one nested in another
+/// target's root.
+fn check_roots(settings: &Settings, targets: &[Target]) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let data_dir = std::path::absolute(&settings.data_dir)?;
+    let mut all = Vec::new();
+    for target in Target::ALL {
+        for root in target.roots(settings) {
+            all.push((target, std::path::absolute(&root)?));
+        }
+    }
+    for target in targets {
+        for root in target.roots(settings) {
+            let absolute = std::path::absolute(&root)?;
+            if absolute == cwd || absolute.parent().is_none() {
+                bail!(
+                    "refusing to purge {}: the {} root is the working directory or a filesystem root",
+                    root.display(),
+                    target.name()
+                );
+            }
+            if data_dir.starts_with(&absolute) {
+                bail!(
+                    "refusing to purge {}: the {} root contains the data directory",
+                    root.display(),
+                    target.name()
+                );
+            }
+            for (other, other_root) in &all {
+                if *other_root != absolute
+                    && (other_root.starts_with(&absolute) || absolute.starts_with(other_root))
+                {
+                    bail!(
+                        "refusing to purge {}: the {} root overlaps the {} root {}",
+                        root.display(),
+                        target.name(),
+                        other.name(),
+                        other_root.display()
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn remove(path: &Path, size: u64, dry_run: bool, report: &mut PurgeReport) -> Result<()> {
+    if !dry_run {
+        std::fs::remove_file(path).with_context(|| format!("remove {path:?}"))?;
+        debug!(path = %path.display(), bytes = size, "removed");
+    }
+    report.paths.push(path.to_path_buf());
+    report.files += 1;
+    report.bytes += size;
+    Ok(())
+}
+
+/// Regular files at or below `path`. Symbolic links are skipped rather than followed, so
+/// nothing outside the root is removed.
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let Ok(meta) = std::fs::symlink_metadata(path) else {
+        return Ok(());
+    };
+    if meta.is_file() {
+        files.push(path.to_path_buf());
+    } else if meta.is_dir() {
+        for entry in std::fs::read_dir(path).with_context(|| format!("read {path:?}"))? {
+            collect_files(&entry?.path(), files)?;
+        }
+    } else if meta.is_symlink() {
+        debug!(path = %path.display(), "skipping symbolic link");
+    }
+    Ok(())
+}
+
+/// Remove directories left empty below `root`, keeping `root` itself.
+fn remove_empty_dirs(root: &Path) -> Result<()> {
+    for entry in std::fs::read_dir(root).with_context(|| format!("read {root:?}"))? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            remove_empty_dirs(&path)?;
+            if std::fs::read_dir(&path)?.next().is_none() {
+                std::fs::remove_dir(&path).with_context(|| format!("remove {path:?}"))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_index(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(".index.json"))
+}
+
+/// `<drug>.jsonl` next to `<drug>.index.json`.
+fn records_of(index: &Path) -> PathBuf {
+    let name = index
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    index.with_file_name(format!("{}.jsonl", name.trim_end_matches(".index.json")))
+}
@@ -0,0 +1,116 @@
+//! Normalization quality metrics tracked run over run.
+
+use std::{
+    collections::BTreeMap,
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Write},
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::config::Settings;
+
+const LATEST_FILE: &str = "normalize_metrics.json";
+pub(crate) const HISTORY_FILE: &str = "normalize_metrics_history.jsonl";
+
+/// How raw mentions of one kind (drug or event) were resolved.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MappingCoverage {
+    /// Raw rows carrying a mention.
+    pub mentions: usize,
+    /// Mentions per resolution source (e.g. `seed`, `rxnorm`, `fuzzy`, `passthrough`).
+    pub by_source: BTreeMap<String, usize>,
+    /// Share of mentions resolved by anything other than lowercase passthrough.
+    pub mapped_fraction: f64,
+}
+
+impl MappingCoverage {
+    /// Tally `mentions` rows per source label.
+    pub fn from_counts(counts: impl IntoIterator<Item = (&'static str, usize)>) -> Self {
+        let mut by_source = BTreeMap::new();
+        for (source, count) in counts {
+            *by_source.entry(source.to_string()).or_insert(0) += count;
+        }
+        let mentions: usize = by_source.values().sum();
+        let passthrough = by_source.get("passthrough").copied().unwrap_or(0);
+        let mapped_fraction = if mentions == 0 {
+            0.0
+        } else {
+            (mentions - passthrough) as f64 / mentions as f64
+        };
+        Self {
+            mentions,
+            by_source,
+            mapped_fraction,
+        }
+    }
+}
+
+/// Coverage and case accounting for one `normalize` run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizeMetrics {
+    pub generated_at: DateTime<Utc>,
+    pub raw_rows: usize,
+    /// Distinct cases read from the raw files.
+    pub cases_read: usize,
+    /// Older versions of followed-up cases dropped in cumulative mode.
+    pub cases_dropped_followups: usize,
+    /// Cases whose latest version has no drug in a counted role; they still contribute to
+    /// event totals.
+    pub cases_without_exposure: usize,
+    pub drugs: MappingCoverage,
+    pub events: MappingCoverage,
+}
+
+impl NormalizeMetrics {
+    /// Write `outputs/normalize_metrics.json` and append to the run history.
+    pub fn persist(&self, settings: &Settings) -> Result<()> {
+        let latest = settings.join_output(LATEST_FILE);
+        std::fs::write(&latest, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("write {latest:?}"))?;
+        let history = settings.join_output(HISTORY_FILE);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&history)
+            .with_context(|| format!("open {history:?}"))?;
+        writeln!(file, "{}", serde_json::to_string(self)?)?;
+        info!(
+            drug_mapped = self.drugs.mapped_fraction,
+            event_mapped = self.events.mapped_fraction,
+            path = %latest.display(),
+            "wrote normalization metrics"
+        );
+        Ok(())
+    }
+}
+
+/// Metrics of the most recent `normalize` run, if any.
+pub fn load_latest(settings: &Settings) -> Result<Option<NormalizeMetrics>> {
+    let path = settings.join_output(LATEST_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(&path).with_context(|| format!("read {path:?}"))?;
+    Ok(Some(serde_json::from_str(&text)?))
+}
+
+/// Every recorded run, oldest first.
+pub fn load_history(settings: &Settings) -> Result<Vec<NormalizeMetrics>> {
+    let path = settings.join_output(HISTORY_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file = std::fs::File::open(&path).with_context(|| format!("open {path:?}"))?;
+    let mut runs = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            runs.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(runs)
+}
@@ -0,0 +1,361 @@
+//! RxNorm drug name resolution via local RRF files or the RxNav REST API.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Lines, Write},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    config::Settings,
+    net::{self, HttpClient},
+};
+
+pub(crate) const RXNAV_BASE: &str = "https://rxnav.nlm.nih.gov/REST";
+
+/// Age after which a cached RxNav miss is looked up again, as RxNorm adds names monthly.
+const MISS_TTL_DAYS: i64 = 30;
+
+/// Term types whose concept is itself an ingredient.
+const INGREDIENT_TTYS: &[&str] = &["IN", "PIN", "MIN"];
+/// RXNREL relationships linking brands, forms and precise ingredients to ingredients.
+const INGREDIENT_RELAS: &[&str] = &[
+    "tradename_of",
+    "has_tradename",
+    "form_of",
+    "has_form",
+    "precise_ingredient_of",
+    "has_precise_ingredient",
+];
+
+/// Offline name → ingredient index built from RxNorm RRF release files.
+#[derive(Debug, Clone, Default)]
+pub struct RxNormIndex {
+    names: HashMap<String, String>,
+    /// Lower-cased ingredient name → ingredient RxCUI.
+    ingredient_rxcuis: HashMap<String, String>,
+}
+
+impl RxNormIndex {
+    /// Resolve a raw drug string (case-insensitive) to a lower-cased ingredient name.
+    pub fn resolve(&self, name: &str) -> Option<String> {
+        self.names.get(&name.trim().to_ascii_uppercase()).cloned()
+    }
+
+    /// RxCUI of an ingredient name as returned by [`RxNormIndex::resolve`].
+    pub fn rxcui(&self, ingredient: &str) -> Option<String> {
+        self.ingredient_rxcuis
+            .get(&ingredient.trim().to_lowercase())
+            .cloned()
+    }
+}
+
+/// Load `data/raw/rxnorm/RXNCONSO.RRF` (plus `RXNREL.RRF` when present).
+///
+/// Returns `None` when the RRF files are not installed, in which case callers fall back to RxNav.
+pub fn load_local(settings: &Settings) -> Result<Option<RxNormIndex>> {
+    let root = settings.join_data("raw/rxnorm");
+    let conso_path = root.join("RXNCONSO.RRF");
+    if !conso_path.exists() {
+        return Ok(None);
+    }
+
+    // RXCUI|LAT|TS|LUI|STT|SUI|ISPREF|RXAUI|SAUI|SCUI|SDUI|SAB|TTY|CODE|STR|SRL|SUPPRESS|CVF|
+    let mut strings: Vec<(String, String)> = Vec::new();
+    let mut ingredient_names: HashMap<String, String> = HashMap::new();
+    for line in open_lines(&conso_path)? {
+        let line = line?;
+        let cols: Vec<&str> = line.split('|').collect();
+        if cols.len() < 17 || cols[1] != "ENG" || cols[11] != "RXNORM" || cols[16] == "O" {
+            continue;
+        }
+        let (rxcui, tty, text) = (cols[0], cols[12], cols[14]);
+        if INGREDIENT_TTYS.contains(&tty) {
+            ingredient_names
+                .entry(rxcui.to_string())
+                .or_insert_with(|| text.to_lowercase());
+        }
+        strings.push((text.to_ascii_uppercase(), rxcui.to_string()));
+    }
+
+    let mut to_ingredient: HashMap<String, String> = HashMap::new();
+    let rel_path = root.join("RXNREL.RRF");
+    if rel_path.exists() {
+        // RXCUI1|RXAUI1|STYPE1|REL|RXCUI2|RXAUI2|STYPE2|RELA|...
+        let ingredients: HashSet<&String> = ingredient_names.keys().collect();
+        for line in open_lines(&rel_path)? {
+            let line = line?;
+            let cols: Vec<&str> = line.split('|').collect();
+            if cols.len() < 8 || !INGREDIENT_RELAS.contains(&cols[7]) {
+                continue;
+            }
+            let (left, right) = (cols[0].to_string(), cols[4].to_string());
+            match (ingredients.contains(&left), ingredients.contains(&right)) {
+                (true, false) => {
+                    to_ingredient.entry(right).or_insert(left);
+                }
+                (false, true) => {
+                    to_ingredient.entry(left).or_insert(right);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut names = HashMap::new();
+    for (text, rxcui) in strings {
+        let ingredient = ingredient_names.get(&rxcui).or_else(|| {
+            to_ingredient
+                .get(&rxcui)
+                .and_then(|target| ingredient_names.get(target))
+        });
+        if let Some(ingredient) = ingredient {
+            names.entry(text).or_insert_with(|| ingredient.clone());
+        }
+    }
+    let mut ingredient_rxcuis = HashMap::new();
+    for (rxcui, name) in ingredient_names {
+        ingredient_rxcuis.entry(name).or_insert(rxcui);
+    }
+    info!(names = names.len(), "loaded local RxNorm index");
+    Ok(Some(RxNormIndex {
+        names,
+        ingredient_rxcuis,
+    }))
+}
+
+fn open_lines(path: &Path) -> Result<Lines<BufReader<File>>> {
+    let file = File::open(path).with_context(|| format!("open {}", path.display()))?;
+    Ok(BufReader::new(file).lines())
+}
+
+/// Resolves drug names via the local RRF index when installed, otherwise RxNav with an on-disk cache.
+///
+/// In offline mode only the RRF index and previously cached RxNav answers are consulted.
+pub struct RxNormResolver {
+    local: Option<RxNormIndex>,
+    cache: RestCache,
+    rxcui_cache: RestCache,
+    client: &'static HttpClient,
+    approx_min_score: f64,
+    offline: bool,
+}
+
+impl RxNormResolver {
+    pub fn new(settings: &Settings) -> Result<Self> {
+        Ok(Self {
+            local: load_local(settings)?,
+            cache: RestCache::open(settings, "lookups.jsonl")?,
+            rxcui_cache: RestCache::open(settings, "rxcuis.jsonl")?,
+            client: net::client(settings)?,
+            approx_min_score: settings.rxnorm_approx_min_score,
+            offline: settings.offline,
+        })
+    }
+
+    /// Resolve a raw drug name to a lower-cased RxNorm name, if any source knows it.
+    pub async fn resolve(&mut self, name: &str) -> Option<String> {
+        if let Some(index) = &self.local {
+            return index.resolve(name);
+        }
+        // Approximate matches depend on the accepted score, so it is part of the key.
+        let key = format!(
+            "{}|{}",
+            name.trim().to_ascii_uppercase(),
+            self.approx_min_score
+        );
+        if let Some(cached) = self.cache.get(&key) {
+            return cached.clone();
+        }
+        if self.offline {
+            return None;
+        }
+        let result = match lookup_rest(name, self.client).await {
+            Ok(None) => lookup_approximate(name, self.approx_min_score, self.client).await,
+            other => other,
+        };
+        match result {
+            Ok(resolved) => {
+                if let Err(err) = self.cache.insert(key, resolved.clone()) {
+                    warn!(%err, "failed to append rxnorm cache entry");
+                }
+                resolved
+            }
+            Err(err) => {
+                warn!(%name, %err, "rxnav lookup failed; not caching");
+                None
+            }
+        }
+    }
+
+    /// RxCUI of a canonical ingredient name, from the RRF index or RxNav (cached).
+    pub async fn rxcui(&mut self, ingredient: &str) -> Option<String> {
+        if let Some(index) = &self.local {
+            return index.rxcui(ingredient);
+        }
+        let key = ingredient.trim().to_ascii_uppercase();
+        if let Some(cached) = self.rxcui_cache.get(&key) {
+            return cached.clone();
+        }
+        if self.offline {
+            return None;
+        }
+        match lookup_rxcui(ingredient, self.client).await {
+            Ok(rxcui) => {
+                if let Err(err) = self.rxcui_cache.insert(key, rxcui.clone()) {
+                    warn!(%err, "failed to append rxcui cache entry");
+                }
+                rxcui
+            }
+            Err(err) => {
+                warn!(%ingredient, %err, "rxcui lookup failed; not caching");
+                None
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    name: String,
+    resolved: Option<String>,
+    /// When RxNav answered; misses without it predate expiry and are looked up again.
+    #[serde(default)]
+    checked_at: Option<DateTime<Utc>>,
+}
+
+impl CacheEntry {
+    fn is_fresh(&self, now: DateTime<Utc>) -> bool {
+        self.resolved.is_some()
+            || self
+                .checked_at
+                .is_some_and(|checked| now - checked < Duration::days(MISS_TTL_DAYS))
+    }
+}
+
+/// Append-only JSONL cache of RxNav answers keyed by upper-cased name. Later lines win, and
+/// misses expire after [`MISS_TTL_DAYS`].
+struct RestCache {
+    entries: HashMap<String, CacheEntry>,
+    file: File,
+}
+
+impl RestCache {
+    fn open(settings: &Settings, file_name: &str) -> Result<Self> {
+        let dir = settings.join_data("cache/rxnorm");
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(file_name);
+        let mut entries = HashMap::new();
+        if path.exists() {
+            for line in open_lines(&path)? {
+                let line = line?;
+                match serde_json::from_str::<CacheEntry>(&line) {
+                    Ok(entry) => {
+                        entries.insert(entry.name.clone(), entry);
+                    }
+                    Err(err) => warn!(%err, "skipping corrupt rxnorm cache line"),
+                }
+            }
+            info!(entries = entries.len(), "loaded rxnorm lookup cache");
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("open {}", path.display()))?;
+        Ok(Self { entries, file })
+    }
+
+    /// The cached answer, unless it is an expired miss.
+    fn get(&self, key: &str) -> Option<&Option<String>> {
+        self.entries
+            .get(key)
+            .filter(|entry| entry.is_fresh(Utc::now()))
+            .map(|entry| &entry.resolved)
+    }
+
+    fn insert(&mut self, name: String, resolved: Option<String>) -> Result<()> {
+        let entry = CacheEntry {
+            name,
+            resolved,
+            checked_at: Some(Utc::now()),
+        };
+        let line = serde_json::to_string(&entry)?;
+        self.file.write_all(line.as_bytes())?;
+        self.file.write_all(b"\n")?;
+        self.entries.insert(entry.name.clone(), entry);
+        Ok(())
+    }
+}
+
+/// Resolve a drug name through the RxNav `drugs.json` endpoint.
+///
+/// `Ok(None)` means RxNav answered without a match; transport and HTTP failures are errors.
+pub async fn lookup_rest(name: &str, client: &HttpClient) -> Result<Option<String>> {
+    let url = format!("{RXNAV_BASE}/drugs.json?name={}", urlencoding::encode(name));
+    let payload: serde_json::Value = client.get(&url).await?.error_for_status()?.json().await?;
+    Ok(payload
+        .pointer("/drugGroup/conceptGroup/0/conceptProperties/0/name")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_lowercase()))
+}
+
+/// Look up the RxCUI of an exact RxNorm name through the RxNav `rxcui.json` endpoint.
+pub async fn lookup_rxcui(name: &str, client: &HttpClient) -> Result<Option<String>> {
+    let url = format!(
+        "{RXNAV_BASE}/rxcui.json?name={}&search=2",
+        urlencoding::encode(name)
+    );
+    let payload: serde_json::Value = client.get(&url).await?.error_for_status()?.json().await?;
+    Ok(payload
+        .pointer("/idGroup/rxnormId/0")
+        .and_then(|v| v.as_str())
+        .map(str::to_string))
+}
+
+/// Resolve a misspelt or free-text drug name through RxNav `approximateTerm`.
+///
+/// The best candidate is accepted only when its score reaches `min_score`.
+pub async fn lookup_approximate(
+    name: &str,
+    min_score: f64,
+    client: &HttpClient,
+) -> Result<Option<String>> {
+    let url = format!(
+        "{RXNAV_BASE}/approximateTerm.json?term={}&maxEntries=1",
+        urlencoding::encode(name)
+    );
+    let payload: serde_json::Value = client.get(&url).await?.error_for_status()?.json().await?;
+    let Some(candidate) = payload.pointer("/approximateGroup/candidate/0") else {
+        return Ok(None);
+    };
+    let score = candidate
+        .get("score")
+        .and_then(|v| v.as_str().and_then(|s| s.parse().ok()).or(v.as_f64()))
+        .unwrap_or(0.0);
+    if score < min_score {
+        return Ok(None);
+    }
+    if let Some(candidate_name) = candidate.get("name").and_then(|v| v.as_str()) {
+        return Ok(Some(candidate_name.to_lowercase()));
+    }
+    let Some(rxcui) = candidate.get("rxcui").and_then(|v| v.as_str()) else {
+        return Ok(None);
+    };
+    let properties: serde_json::Value = client
+        .get(&format!("{RXNAV_BASE}/rxcui/{rxcui}/properties.json"))
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(properties
+        .pointer("/properties/name")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_lowercase()))
+}
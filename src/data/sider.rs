@@ -0,0 +1,148 @@
+//! SIDER side-effect resource ingestion.
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use tracing::{info, warn};
+
+use crate::{
+    config::Settings,
+    data::{manifest, read_string_pairs},
+    net,
+};
+
+const SIDER_BASE: &str = "https://sideeffects.embl.de/media/download";
+const SIDER_FILES: &[&str] = &["meddra_all_se.tsv", "drug_names.tsv"];
+
+/// Event terms used when the SIDER download is not available locally.
+const FALLBACK_TERMS: &[&str] = &[
+    "hepatotoxicity",
+    "rash",
+    "diarrhoea",
+    "neutropenia",
+    "fatigue",
+    "nausea",
+    "fever",
+    "cardiotoxicity",
+    "anemia",
+    "thrombocytopenia",
+    "headache",
+];
+
+/// Side-effect vocabulary and known drug–event associations derived from SIDER.
+#[derive(Debug, Clone, Default)]
+pub struct SiderVocabulary {
+    /// Lower-cased MedDRA preferred terms.
+    pub terms: Vec<String>,
+    /// Lower-cased `(drug name, preferred term)` pairs listed on SIDER labels.
+    pub associations: HashSet<(String, String)>,
+}
+
+impl SiderVocabulary {
+    /// Whether SIDER lists `event` as a side effect of `drug`.
+    pub fn is_known(&self, drug: &str, event: &str) -> bool {
+        self.associations
+            .contains(&(drug.to_lowercase(), event.to_lowercase()))
+    }
+}
+
+/// Download the SIDER side-effect and drug-name tables into `data/raw/sider`.
+pub async fn fetch_sider(settings: &Settings) -> Result<Vec<PathBuf>> {
+    let client = net::client(settings)?;
+    let dest_root = settings.join_data("raw/sider");
+    std::fs::create_dir_all(&dest_root)?;
+
+    let mut outputs = Vec::new();
+    for name in SIDER_FILES {
+        let path = dest_root.join(name);
+        if path.exists() {
+            info!(file = %name, "using cached SIDER file");
+            outputs.push(path);
+            continue;
+        }
+        net::ensure_online(settings, &format!("SIDER file {}", path.display()))?;
+        let url = format!("{SIDER_BASE}/{name}.gz");
+        info!(%url, "downloading SIDER file");
+        let bytes = client.get(&url).await?.error_for_status()?.bytes().await?;
+        let mut text = String::new();
+        GzDecoder::new(bytes.as_ref())
+            .read_to_string(&mut text)
+            .with_context(|| format!("decompress {url}"))?;
+        std::fs::write(&path, text).with_context(|| format!("write {path:?}"))?;
+        manifest::record(settings, &path, &url, &[], &[])?;
+        outputs.push(path);
+    }
+    Ok(outputs)
+}
+
+/// Load the SIDER vocabulary from `data/raw/sider`, falling back to a small built-in term list.
+pub fn load_vocabulary(settings: &Settings) -> Result<SiderVocabulary> {
+    let root = settings.join_data("raw/sider");
+    let se_path = root.join("meddra_all_se.tsv");
+    if !se_path.exists() {
+        warn!("SIDER meddra_all_se.tsv missing; using built-in event terms (run fetch --sider)");
+        return Ok(SiderVocabulary {
+            terms: FALLBACK_TERMS.iter().map(|s| s.to_string()).collect(),
+            associations: HashSet::new(),
+        });
+    }
+
+    let names_path = root.join("drug_names.tsv");
+    let mut drug_names: HashMap<String, String> = HashMap::new();
+    if names_path.exists() {
+        for line in BufReader::new(File::open(&names_path)?).lines() {
+            let line = line?;
+            let mut cols = line.split('\t');
+            if let (Some(cid), Some(name)) = (cols.next(), cols.next()) {
+                drug_names.insert(cid.trim().to_string(), name.trim().to_lowercase());
+            }
+        }
+    } else {
+        warn!("SIDER drug_names.tsv missing; known-association flags disabled");
+    }
+
+    // Columns: STITCH flat id, STITCH stereo id, label CUI, MedDRA type, MedDRA CUI, side effect name.
+    let mut terms = BTreeSet::new();
+    let mut associations = HashSet::new();
+    for line in BufReader::new(File::open(&se_path)?).lines() {
+        let line = line?;
+        let cols: Vec<&str> = line.split('\t').collect();
+        if cols.len() < 6 || !cols[3].eq_ignore_ascii_case("PT") {
+            continue;
+        }
+        let term = cols[5].trim().to_lowercase();
+        if term.is_empty() {
+            continue;
+        }
+        if let Some(drug) = drug_names.get(cols[0].trim()) {
+            associations.insert((drug.clone(), term.clone()));
+        }
+        terms.insert(term);
+    }
+    info!(
+        terms = terms.len(),
+        associations = associations.len(),
+        "loaded SIDER vocabulary"
+    );
+    Ok(SiderVocabulary {
+        terms: terms.into_iter().collect(),
+        associations,
+    })
+}
+
+/// Load `(drug_id, event_id)` pairs that SIDER lists as known side effects.
+pub fn load_known_pairs(settings: &Settings) -> Result<HashSet<(String, String)>> {
+    let path = settings.join_data("clean/known_associations.parquet");
+    if !path.exists() {
+        return Ok(HashSet::new());
+    }
+    Ok(read_string_pairs(&path, "drug_id", "event_id")?
+        .into_iter()
+        .collect())
+}
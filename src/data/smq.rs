@@ -0,0 +1,213 @@
+//! Standardised MedDRA Query (SMQ) grouping from licensed MedDRA ASCII files.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use anyhow::Result;
+use polars::prelude::{DataFrame, NamedFrom, ParquetWriter, Series};
+use tracing::{info, warn};
+
+use crate::{config::Settings, data::read_string_pairs};
+
+/// MedDRA hierarchy level code for preferred terms in `smq_content.asc`.
+const PT_LEVEL: &str = "4";
+/// Level code of `smq_content.asc` rows whose term is a child SMQ.
+const SMQ_LEVEL: &str = "0";
+
+/// Search scope of an SMQ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SmqScope {
+    Narrow,
+    Broad,
+}
+
+impl SmqScope {
+    fn suffix(self) -> char {
+        match self {
+            Self::Narrow => 'N',
+            Self::Broad => 'B',
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Narrow => "narrow",
+            Self::Broad => "broad",
+        }
+    }
+}
+
+/// A single SMQ at a given scope, used as a pseudo-event in contingency tables.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SmqGroup {
+    pub code: String,
+    pub name: String,
+    pub scope: SmqScope,
+}
+
+impl SmqGroup {
+    /// Event id used for SMQ-level rows, e.g. `SMQ20000006N`.
+    pub fn event_id(&self) -> String {
+        format!("SMQ{}{}", self.code, self.scope.suffix())
+    }
+
+    /// Human-readable label, e.g. `Drug related hepatic disorders (narrow)`.
+    pub fn label(&self) -> String {
+        format!("{} ({})", self.name, self.scope.label())
+    }
+}
+
+/// Preferred-term → SMQ membership index.
+#[derive(Debug, Clone, Default)]
+pub struct SmqIndex {
+    memberships: HashMap<String, Vec<SmqGroup>>,
+}
+
+impl SmqIndex {
+    /// SMQs (both scopes) containing the lower-cased preferred term.
+    pub fn groups_for(&self, term: &str) -> &[SmqGroup] {
+        self.memberships
+            .get(&term.trim().to_lowercase())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+/// Load SMQ memberships from `data/raw/meddra/{pt,smq_list,smq_content}.asc`.
+///
+/// Narrow-scope terms belong to both the narrow and broad search; broad-scope terms to the
+/// broad search only. An SMQ also contains every term of its child SMQs, at the scope the
+/// term has there. Returns `None` when the MedDRA files are not present.
+pub fn load_index(settings: &Settings) -> Result<Option<SmqIndex>> {
+    let root = settings.join_data("raw/meddra");
+    let files = ["pt.asc", "smq_list.asc", "smq_content.asc"].map(|f| root.join(f));
+    if let Some(missing) = files.iter().find(|p| !p.exists()) {
+        warn!(path = %missing.display(), "MedDRA SMQ file missing; SMQ grouping disabled");
+        return Ok(None);
+    }
+
+    let pt_names: HashMap<String, String> = read_asc(&files[0])?
+        .into_iter()
+        .filter(|cols| cols.len() > 1)
+        .map(|cols| (cols[0].clone(), cols[1].to_lowercase()))
+        .collect();
+    let smq_names: HashMap<String, String> = read_asc(&files[1])?
+        .into_iter()
+        .filter(|cols| cols.len() > 1)
+        .map(|cols| (cols[0].clone(), cols[1].clone()))
+        .collect();
+
+    // smq_code $ term_code $ term_level $ term_scope $ term_category $ term_weight $ term_status ...
+    let mut terms: HashMap<String, Vec<(String, &[SmqScope])>> = HashMap::new();
+    let mut children: HashMap<String, Vec<String>> = HashMap::new();
+    for cols in read_asc(&files[2])? {
+        if cols.len() < 7 || cols[6] != "A" {
+            continue;
+        }
+        if cols[2] == SMQ_LEVEL {
+            children
+                .entry(cols[0].clone())
+                .or_default()
+                .push(cols[1].clone());
+            continue;
+        }
+        if cols[2] != PT_LEVEL {
+            continue;
+        }
+        let Some(term) = pt_names.get(&cols[1]) else {
+            continue;
+        };
+        let scopes: &[SmqScope] = match cols[3].as_str() {
+            "2" => &[SmqScope::Narrow, SmqScope::Broad],
+            "1" => &[SmqScope::Broad],
+            _ => continue,
+        };
+        terms
+            .entry(cols[0].clone())
+            .or_default()
+            .push((term.clone(), scopes));
+    }
+
+    let mut memberships: HashMap<String, Vec<SmqGroup>> = HashMap::new();
+    for (code, name) in &smq_names {
+        // Depth-first over the child SMQs; `seen` also guards against cycles.
+        let mut pending = vec![code.as_str()];
+        let mut seen = HashSet::new();
+        let mut groups = HashSet::new();
+        while let Some(smq) = pending.pop() {
+            if !seen.insert(smq) {
+                continue;
+            }
+            pending.extend(children.get(smq).into_iter().flatten().map(String::as_str));
+            for (term, scopes) in terms.get(smq).into_iter().flatten() {
+                for scope in *scopes {
+                    groups.insert((term, *scope));
+                }
+            }
+        }
+        for (term, scope) in groups {
+            memberships.entry(term.clone()).or_default().push(SmqGroup {
+                code: code.clone(),
+                name: name.clone(),
+                scope,
+            });
+        }
+    }
+    for groups in memberships.values_mut() {
+        groups.sort_by(|a, b| (&a.code, a.scope.suffix()).cmp(&(&b.code, b.scope.suffix())));
+    }
+    info!(terms = memberships.len(), "loaded SMQ memberships");
+    Ok(Some(SmqIndex { memberships }))
+}
+
+/// Lower-cased MedDRA preferred term → PT code from `data/raw/meddra/pt.asc`, empty when the
+/// file is not installed.
+pub fn load_pt_codes(settings: &Settings) -> Result<HashMap<String, String>> {
+    let path = settings.join_data("raw/meddra/pt.asc");
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    Ok(read_asc(&path)?
+        .into_iter()
+        .filter(|cols| cols.len() > 1)
+        .map(|cols| (cols[1].to_lowercase(), cols[0].clone()))
+        .collect())
+}
+
+fn read_asc(path: &Path) -> Result<Vec<Vec<String>>> {
+    let mut rows = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        rows.push(line.split('$').map(|c| c.trim().to_string()).collect());
+    }
+    Ok(rows)
+}
+
+/// Persist SMQ pseudo-event labels so `rank` can populate the `smq` column.
+pub fn write_groups(groups: &[SmqGroup], settings: &Settings) -> Result<()> {
+    let path = settings.join_data("clean/smq_groups.parquet");
+    let ids: Vec<String> = groups.iter().map(SmqGroup::event_id).collect();
+    let labels: Vec<String> = groups.iter().map(SmqGroup::label).collect();
+    let mut df = DataFrame::new(vec![
+        Series::new("event_id".into(), ids),
+        Series::new("smq".into(), labels),
+    ])?;
+    ParquetWriter::new(File::create(&path)?).finish(&mut df)?;
+    info!(path = %path.display(), rows = groups.len(), "wrote SMQ groups");
+    Ok(())
+}
+
+/// Load SMQ pseudo-event id → label, empty when SMQ grouping was not run.
+pub fn load_labels(settings: &Settings) -> Result<HashMap<String, String>> {
+    let path = settings.join_data("clean/smq_groups.parquet");
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    Ok(read_string_pairs(&path, "event_id", "smq")?
+        .into_iter()
+        .collect())
+}
@@ -0,0 +1,75 @@
+//! Edit-distance spelling correction for raw drug names.
+
+use std::collections::HashMap;
+
+use strsim::osa_distance;
+
+/// Corrects misspelled drug names (`IMATANIB`, `KEYTRDA`) against a known vocabulary.
+#[derive(Debug, Clone, Default)]
+pub struct DrugSpeller {
+    /// Upper-cased surface forms bucketed by length, each with its canonical ingredient.
+    by_len: HashMap<usize, Vec<(String, String)>>,
+}
+
+impl DrugSpeller {
+    /// Build from `(surface form, canonical ingredient)` pairs.
+    pub fn new<'a>(entries: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut by_len: HashMap<usize, Vec<(String, String)>> = HashMap::new();
+        for (surface, canonical) in entries {
+            let surface = surface.trim().to_ascii_uppercase();
+            if max_edits(surface.len()) == 0 || surface.chars().any(|c| c.is_ascii_digit()) {
+                continue;
+            }
+            by_len
+                .entry(surface.len())
+                .or_default()
+                .push((surface, canonical.to_string()));
+        }
+        Self { by_len }
+    }
+
+    /// Canonical ingredient of the unique closest vocabulary entry within the edit budget.
+    ///
+    /// Names of four characters or fewer, or containing digits, are never corrected. A name
+    /// whose closest entries map to different ingredients is left alone.
+    pub fn correct(&self, name: &str) -> Option<&str> {
+        let name = name.trim().to_ascii_uppercase();
+        let budget = max_edits(name.len());
+        if budget == 0 || name.chars().any(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let mut best: Option<(usize, &str)> = None;
+        let mut tied = false;
+        for len in name.len().saturating_sub(budget)..=name.len() + budget {
+            for (surface, canonical) in self.by_len.get(&len).into_iter().flatten() {
+                let distance = osa_distance(&name, surface);
+                if distance > budget {
+                    continue;
+                }
+                match best {
+                    Some((top, current)) if distance == top => {
+                        tied |= current != canonical.as_str();
+                    }
+                    Some((top, _)) if distance > top => {}
+                    _ => {
+                        best = Some((distance, canonical.as_str()));
+                        tied = false;
+                    }
+                }
+            }
+        }
+        match best {
+            Some((_, canonical)) if !tied => Some(canonical),
+            _ => None,
+        }
+    }
+}
+
+/// Edits tolerated for a name of `len` characters.
+fn max_edits(len: usize) -> usize {
+    match len {
+        0..=4 => 0,
+        5..=8 => 1,
+        _ => 2,
+    }
+}
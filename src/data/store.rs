@@ -2,13 +2,20 @@
 
 //! Lightweight helpers for DuckDB-backed analytical storage.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
-use duckdb::Connection;
-use tracing::info;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration};
+use duckdb::{
+    types::{TimeUnit, Value},
+    Connection,
+};
+use tracing::{debug, info};
 
-use crate::config::Settings;
+use crate::{
+    config::Settings,
+    data::export::{Cell, Table},
+};
 
 /// Wrapper around a DuckDB connection tied to the configured data directory.
 pub struct DuckStore {
@@ -31,4 +38,114 @@ impl DuckStore {
         self.conn.execute("LOAD httpfs;", [])?;
         Ok(())
     }
+
+    /// (Re)create a view per parquet file in `data/clean/`, named after the file
+    /// (`faers_norm`, `signal_metrics`, ...), and `signals` over `signals.csv`. The views read
+    /// the files at query time, so they always reflect the latest pipeline run. Returns the
+    /// view names.
+    pub fn register_views(&self, settings: &Settings) -> Result<Vec<String>> {
+        let clean = settings.join_data("clean");
+        let mut sources = Vec::new();
+        if clean.is_dir() {
+            for entry in std::fs::read_dir(&clean).with_context(|| format!("read {clean:?}"))? {
+                let path = entry?.path();
+                if path.extension().and_then(|ext| ext.to_str()) != Some("parquet") {
+                    continue;
+                }
+                if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    sources.push((name.to_string(), "read_parquet", path.clone()));
+                }
+            }
+        }
+        let signals = settings.join_output("signals.csv");
+        if signals.is_file() {
+            sources.push(("signals".to_string(), "read_csv_auto", signals));
+        }
+        sources.sort();
+
+        let mut views = Vec::with_capacity(sources.len());
+        for (name, reader, path) in sources {
+            let sql = format!(
+                "CREATE OR REPLACE VIEW {} AS SELECT * FROM {reader}({})",
+                quote_identifier(&name),
+                quote_path(&path)
+            );
+            self.conn
+                .execute_batch(&sql)
+                .with_context(|| format!("register view {name} over {path:?}"))?;
+            debug!(view = %name, path = %path.display(), "registered view");
+            views.push(name);
+        }
+        Ok(views)
+    }
+
+    /// Run `sql` and collect its result.
+    pub fn query(&self, sql: &str) -> Result<Table> {
+        let mut statement = self.conn.prepare(sql)?;
+        let mut rows = statement.query([])?;
+        // Columns are only known once the statement has run.
+        let columns = rows
+            .as_ref()
+            .map(|statement| statement.column_names())
+            .unwrap_or_default();
+        let mut table = Table {
+            columns,
+            rows: Vec::new(),
+        };
+        while let Some(row) = rows.next()? {
+            let cells = (0..table.columns.len())
+                .map(|index| row.get::<_, Value>(index).map(cell))
+                .collect::<duckdb::Result<_>>()?;
+            table.rows.push(cells);
+        }
+        Ok(table)
+    }
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn quote_path(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', "''"))
+}
+
+fn cell(value: Value) -> Cell {
+    match value {
+        Value::Null => Cell::Null,
+        Value::Boolean(flag) => Cell::Bool(flag),
+        Value::TinyInt(n) => Cell::Int(n.into()),
+        Value::SmallInt(n) => Cell::Int(n.into()),
+        Value::Int(n) => Cell::Int(n.into()),
+        Value::BigInt(n) => Cell::Int(n),
+        Value::HugeInt(n) => i64::try_from(n).map_or(Cell::Float(n as f64), Cell::Int),
+        Value::UTinyInt(n) => Cell::Int(n.into()),
+        Value::USmallInt(n) => Cell::Int(n.into()),
+        Value::UInt(n) => Cell::Int(n.into()),
+        Value::UBigInt(n) => i64::try_from(n).map_or(Cell::Float(n as f64), Cell::Int),
+        Value::Float(n) => Cell::Float(n.into()),
+        Value::Double(n) => Cell::Float(n),
+        Value::Decimal(n) => n
+            .to_string()
+            .parse()
+            .map_or_else(|_| Cell::Text(n.to_string()), Cell::Float),
+        Value::Text(text) => Cell::Text(text),
+        Value::Date32(days) => Cell::Text(
+            (DateTime::UNIX_EPOCH.date_naive() + Duration::days(days.into()))
+                .format("%Y-%m-%d")
+                .to_string(),
+        ),
+        Value::Timestamp(unit, n) => {
+            let micros = match unit {
+                TimeUnit::Second => n.saturating_mul(1_000_000),
+                TimeUnit::Millisecond => n.saturating_mul(1_000),
+                TimeUnit::Microsecond => n,
+                TimeUnit::Nanosecond => n / 1_000,
+            };
+            DateTime::from_timestamp_micros(micros).map_or(Cell::Int(n), |time| {
+                Cell::Text(time.format("%Y-%m-%d %H:%M:%S").to_string())
+            })
+        }
+        other => Cell::Text(format!("{other:?}")),
+    }
 }
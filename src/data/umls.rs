@@ -0,0 +1,144 @@
+//! UMLS concept lookup for adverse event normalization.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+use anyhow::{anyhow, Context, Result};
+use tracing::{info, warn};
+
+use crate::{
+    config::Settings,
+    net::{self, HttpClient},
+};
+
+const UTS_SEARCH_URL: &str = "https://uts-ws.nlm.nih.gov/rest/search/current";
+
+/// A UMLS concept with its preferred English name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UmlsConcept {
+    pub cui: String,
+    pub name: String,
+}
+
+/// Maps free-text reaction terms to UMLS CUIs from a local MRCONSO subset or the UTS API.
+pub enum UmlsNormalizer {
+    /// Upper-cased string → concept index built from `data/raw/umls/MRCONSO.RRF`.
+    Local(HashMap<String, UmlsConcept>),
+    /// Exact-match search against the UTS REST API.
+    Remote {
+        client: &'static HttpClient,
+        api_key: String,
+        cache: HashMap<String, Option<UmlsConcept>>,
+    },
+}
+
+impl UmlsNormalizer {
+    /// Prefer a local MRCONSO subset, falling back to UTS when `UMLS_API_KEY` is configured.
+    pub fn load(settings: &Settings) -> Result<Self> {
+        let path = settings.join_data("raw/umls/MRCONSO.RRF");
+        if path.exists() {
+            return Ok(Self::Local(load_mrconso(&path)?));
+        }
+        net::ensure_online(settings, "data/raw/umls/MRCONSO.RRF")?;
+        let api_key = settings.umls_api_key.clone().ok_or_else(|| {
+            anyhow!("UMLS backend needs data/raw/umls/MRCONSO.RRF or UMLS_API_KEY")
+        })?;
+        let client = net::client(settings)?;
+        Ok(Self::Remote {
+            client,
+            api_key,
+            cache: HashMap::new(),
+        })
+    }
+
+    /// Look up the concept for a reaction term; `None` when UMLS has no exact match.
+    pub async fn resolve(&mut self, term: &str) -> Option<UmlsConcept> {
+        let key = term.trim().to_ascii_uppercase();
+        match self {
+            Self::Local(index) => index.get(&key).cloned(),
+            Self::Remote {
+                client,
+                api_key,
+                cache,
+            } => {
+                if let Some(hit) = cache.get(&key) {
+                    return hit.clone();
+                }
+                match search_uts(client, api_key, term).await {
+                    Ok(concept) => {
+                        cache.insert(key, concept.clone());
+                        concept
+                    }
+                    Err(err) => {
+                        warn!(%term, %err, "UTS search failed");
+                        None
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Build a string index from MRCONSO, naming each CUI by its preferred English term.
+fn load_mrconso(path: &std::path::Path) -> Result<HashMap<String, UmlsConcept>> {
+    // CUI|LAT|TS|LUI|STT|SUI|ISPREF|AUI|SAUI|SCUI|SDUI|SAB|TTY|CODE|STR|SRL|SUPPRESS|CVF|
+    let file = File::open(path).with_context(|| format!("open {}", path.display()))?;
+    let mut preferred: HashMap<String, String> = HashMap::new();
+    let mut strings: Vec<(String, String)> = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let cols: Vec<&str> = line.split('|').collect();
+        if cols.len() < 17 || cols[1] != "ENG" || cols[16] == "O" {
+            continue;
+        }
+        let (cui, text) = (cols[0], cols[14]);
+        if cols[2] == "P" && cols[4] == "PF" && cols[6] == "Y" {
+            preferred
+                .entry(cui.to_string())
+                .or_insert_with(|| text.to_lowercase());
+        }
+        strings.push((text.to_ascii_uppercase(), cui.to_string()));
+    }
+    let mut index = HashMap::new();
+    for (text, cui) in strings {
+        if let Some(name) = preferred.get(&cui) {
+            index.entry(text).or_insert_with(|| UmlsConcept {
+                cui,
+                name: name.clone(),
+            });
+        }
+    }
+    info!(strings = index.len(), "loaded local UMLS index");
+    Ok(index)
+}
+
+async fn search_uts(client: &HttpClient, api_key: &str, term: &str) -> Result<Option<UmlsConcept>> {
+    let url = format!(
+        "{UTS_SEARCH_URL}?string={}&searchType=exact&returnIdType=concept&pageSize=1&apiKey={api_key}",
+        urlencoding::encode(term)
+    );
+    // The key travels in the query string, so errors are redacted before they can be logged.
+    let payload: serde_json::Value = client
+        .get(&url)
+        .await?
+        .error_for_status()
+        .map_err(net::redact)?
+        .json()
+        .await
+        .map_err(net::redact)?;
+    let Some(first) = payload.pointer("/result/results/0") else {
+        return Ok(None);
+    };
+    let cui = first.get("ui").and_then(|v| v.as_str());
+    let name = first.get("name").and_then(|v| v.as_str());
+    Ok(match (cui, name) {
+        (Some(cui), Some(name)) if cui != "NONE" => Some(UmlsConcept {
+            cui: cui.to_string(),
+            name: name.to_lowercase(),
+        }),
+        _ => None,
+    })
+}
@@ -0,0 +1,387 @@
+//! Invariant checks over the pipeline artefacts, run by `validate`.
+//!
+//! Each check reports how many rows it looked at, how many broke the invariant and the first
+//! few offenders. A check whose artefact does not exist yet is skipped, not failed.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use polars::prelude::{DataFrame, ParquetReader, SerReader};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Settings, signals::trend};
+
+/// Offending rows kept per check.
+const MAX_EXAMPLES: usize = 10;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub check: &'static str,
+    pub artefact: &'static str,
+    pub rows_checked: usize,
+    pub violations: usize,
+    /// The first offending rows, described.
+    pub examples: Vec<String>,
+    /// Why the check did not run, e.g. a missing artefact.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+}
+
+impl CheckResult {
+    fn new(check: &'static str, artefact: &'static str) -> Self {
+        Self {
+            check,
+            artefact,
+            rows_checked: 0,
+            violations: 0,
+            examples: Vec::new(),
+            skipped: None,
+        }
+    }
+
+    fn skipped(check: &'static str, artefact: &'static str, reason: impl Into<String>) -> Self {
+        Self {
+            skipped: Some(reason.into()),
+            ..Self::new(check, artefact)
+        }
+    }
+
+    fn row(&mut self, ok: bool, describe: impl FnOnce() -> String) {
+        self.rows_checked += 1;
+        if !ok {
+            self.violations += 1;
+            if self.examples.len() < MAX_EXAMPLES {
+                self.examples.push(describe());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationReport {
+    pub generated_at: DateTime<Utc>,
+    /// No check found a violation.
+    pub passed: bool,
+    pub violations: usize,
+    pub checks: Vec<CheckResult>,
+}
+
+/// Run every check over the artefacts under `settings`.
+pub fn validate(settings: &Settings) -> Result<ValidationReport> {
+    let mut checks = Vec::new();
+    check_contingency_tables(settings, &mut checks)?;
+    let vocab = Vocab::load(settings)?;
+    check_metrics(settings, vocab.as_ref(), &mut checks)?;
+    check_ranked(settings, vocab.as_ref(), &mut checks)?;
+    let violations = checks.iter().map(|check| check.violations).sum();
+    Ok(ValidationReport {
+        generated_at: Utc::now(),
+        passed: violations == 0,
+        violations,
+        checks,
+    })
+}
+
+/// `YYYYQn` with n in 1..=4.
+fn valid_quarter(quarter: &str) -> bool {
+    quarter.as_bytes().get(4) == Some(&b'Q')
+        && trend::parse_quarter(quarter).is_some_and(|(_, q)| (1..=4).contains(&q))
+}
+
+/// `low <= value <= high`, with NaN failing.
+fn ordered(low: f64, value: f64, high: f64) -> bool {
+    low <= value && value <= high
+}
+
+fn read_parquet(path: &std::path::Path) -> Result<DataFrame> {
+    ParquetReader::new(File::open(path).with_context(|| format!("open {path:?}"))?)
+        .finish()
+        .with_context(|| format!("read {path:?}"))
+}
+
+/// Cells of `faers_norm.parquet` are non-negative, and within a quarter every row's
+/// `a + b + c + d` is the quarter's case total, `a + b` the drug's and `a + c` the event's.
+fn check_contingency_tables(settings: &Settings, checks: &mut Vec<CheckResult>) -> Result<()> {
+    const ARTEFACT: &str = "clean/faers_norm.parquet";
+    let path = settings.join_data(ARTEFACT);
+    if !path.exists() {
+        for check in ["non_negative_cells", "contingency_totals", "quarter_format"] {
+            checks.push(CheckResult::skipped(
+                check,
+                ARTEFACT,
+                "missing; run normalize",
+            ));
+        }
+        return Ok(());
+    }
+    let df = read_parquet(&path)?;
+    let drugs = df.column("drug_id")?.str()?;
+    let events = df.column("event_id")?.str()?;
+    let quarters = df.column("year_quarter")?.str()?;
+    let cells = [
+        df.column("a")?.i64()?,
+        df.column("b")?.i64()?,
+        df.column("c")?.i64()?,
+        df.column("d")?.i64()?,
+    ];
+    let rows: Vec<_> = (0..df.height())
+        .map(|idx| {
+            (
+                drugs.get(idx).unwrap_or_default(),
+                events.get(idx).unwrap_or_default(),
+                quarters.get(idx).unwrap_or_default(),
+                cells.map(|col| col.get(idx)),
+            )
+        })
+        .collect();
+
+    let mut non_negative = CheckResult::new("non_negative_cells", ARTEFACT);
+    for (drug, event, quarter, cells) in &rows {
+        non_negative.row(
+            cells.iter().all(|cell| cell.is_some_and(|n| n >= 0)),
+            || format!("{drug}/{event} {quarter}: a, b, c, d = {cells:?}"),
+        );
+    }
+
+    // The most common total per quarter (and margin per drug or event) is taken as the truth.
+    let mut case_totals: HashMap<&str, HashMap<i64, usize>> = HashMap::new();
+    let mut drug_totals: HashMap<(&str, &str), HashMap<i64, usize>> = HashMap::new();
+    let mut event_totals: HashMap<(&str, &str), HashMap<i64, usize>> = HashMap::new();
+    let complete: Vec<_> = rows
+        .iter()
+        .filter_map(|&(drug, event, quarter, cells)| match cells {
+            [Some(a), Some(b), Some(c), Some(d)] => Some((drug, event, quarter, [a, b, c, d])),
+            _ => None,
+        })
+        .collect();
+    for &(drug, event, quarter, [a, b, c, d]) in &complete {
+        *case_totals
+            .entry(quarter)
+            .or_default()
+            .entry(a + b + c + d)
+            .or_default() += 1;
+        *drug_totals
+            .entry((drug, quarter))
+            .or_default()
+            .entry(a + b)
+            .or_default() += 1;
+        *event_totals
+            .entry((event, quarter))
+            .or_default()
+            .entry(a + c)
+            .or_default() += 1;
+    }
+    let mode = |counts: &HashMap<i64, usize>| {
+        counts
+            .iter()
+            .max_by_key(|&(value, count)| (count, std::cmp::Reverse(*value)))
+            .map(|(value, _)| *value)
+    };
+    let mut totals = CheckResult::new("contingency_totals", ARTEFACT);
+    for &(drug, event, quarter, [a, b, c, d]) in &complete {
+        let expected = (
+            mode(&case_totals[quarter]),
+            mode(&drug_totals[&(drug, quarter)]),
+            mode(&event_totals[&(event, quarter)]),
+        );
+        let actual = (Some(a + b + c + d), Some(a + b), Some(a + c));
+        totals.row(actual == expected, || {
+            format!(
+                "{drug}/{event} {quarter}: a+b+c+d={}, a+b={}, a+c={}; quarter has {} cases, \
+                 drug {}, event {}",
+                a + b + c + d,
+                a + b,
+                a + c,
+                expected.0.unwrap_or_default(),
+                expected.1.unwrap_or_default(),
+                expected.2.unwrap_or_default(),
+            )
+        });
+    }
+
+    let mut quarter_format = CheckResult::new("quarter_format", ARTEFACT);
+    for (drug, event, quarter, _) in &rows {
+        quarter_format.row(valid_quarter(quarter), || {
+            format!("{drug}/{event}: year_quarter {quarter:?}")
+        });
+    }
+    checks.extend([non_negative, totals, quarter_format]);
+    Ok(())
+}
+
+/// Known drug and event ids from `drugs.parquet` and `events.parquet`.
+struct Vocab {
+    drugs: HashSet<String>,
+    events: HashSet<String>,
+}
+
+impl Vocab {
+    /// `None` until `normalize` has written both files.
+    fn load(settings: &Settings) -> Result<Option<Self>> {
+        let drugs_path = settings.join_data("clean/drugs.parquet");
+        let events_path = settings.join_data("clean/events.parquet");
+        if !drugs_path.exists() || !events_path.exists() {
+            return Ok(None);
+        }
+        let ids = |path: &std::path::Path, column: &str| -> Result<HashSet<String>> {
+            Ok(read_parquet(path)?
+                .column(column)?
+                .str()?
+                .into_iter()
+                .flatten()
+                .map(str::to_string)
+                .collect())
+        };
+        Ok(Some(Self {
+            drugs: ids(&drugs_path, "drug_id")?,
+            events: ids(&events_path, "event_id")?,
+        }))
+    }
+
+    fn check(vocab: Option<&Self>, artefact: &'static str, pairs: &[(&str, &str)]) -> CheckResult {
+        const CHECK: &str = "ids_in_vocabulary";
+        let Some(vocab) = vocab else {
+            return CheckResult::skipped(
+                CHECK,
+                artefact,
+                "drugs.parquet or events.parquet missing; run normalize",
+            );
+        };
+        let mut result = CheckResult::new(CHECK, artefact);
+        for &(drug, event) in pairs {
+            let (drug_known, event_known) =
+                (vocab.drugs.contains(drug), vocab.events.contains(event));
+            result.row(drug_known && event_known, || {
+                match (drug_known, event_known) {
+                    (false, false) => format!("unknown drug {drug} and event {event}"),
+                    (false, true) => format!("unknown drug {drug} (event {event})"),
+                    _ => format!("unknown event {event} (drug {drug})"),
+                }
+            });
+        }
+        result
+    }
+}
+
+/// Quarters, vocabulary ids and interval ordering of `signal_metrics.parquet`.
+fn check_metrics(
+    settings: &Settings,
+    vocab: Option<&Vocab>,
+    checks: &mut Vec<CheckResult>,
+) -> Result<()> {
+    const ARTEFACT: &str = "clean/signal_metrics.parquet";
+    let path = settings.join_data(ARTEFACT);
+    if !path.exists() {
+        for check in ["quarter_format", "ids_in_vocabulary", "ci_ordered"] {
+            checks.push(CheckResult::skipped(check, ARTEFACT, "missing; run signal"));
+        }
+        return Ok(());
+    }
+    let df = read_parquet(&path)?;
+    let drugs = df.column("drug_id")?.str()?;
+    let events = df.column("event_id")?.str()?;
+    let quarters = df.column("year_quarter")?.str()?;
+    let raw_cols = [
+        df.column("ci_low")?.f64()?,
+        df.column("ror")?.f64()?,
+        df.column("ci_high")?.f64()?,
+    ];
+    let shrunk_cols = [
+        df.column("shrunk_ci_low")?.f64()?,
+        df.column("ror_shrunk")?.f64()?,
+        df.column("shrunk_ci_high")?.f64()?,
+    ];
+
+    let pairs: Vec<(&str, &str)> = (0..df.height())
+        .map(|idx| {
+            (
+                drugs.get(idx).unwrap_or_default(),
+                events.get(idx).unwrap_or_default(),
+            )
+        })
+        .collect();
+    let mut quarter_format = CheckResult::new("quarter_format", ARTEFACT);
+    let mut intervals = CheckResult::new("ci_ordered", ARTEFACT);
+    for (idx, (drug, event)) in pairs.iter().enumerate() {
+        let quarter = quarters.get(idx).unwrap_or_default();
+        quarter_format.row(valid_quarter(quarter), || {
+            format!("{drug}/{event}: year_quarter {quarter:?}")
+        });
+        let [low, ror, high] = raw_cols.map(|col| col.get(idx).unwrap_or(f64::NAN));
+        let [s_low, s_ror, s_high] = shrunk_cols.map(|col| col.get(idx).unwrap_or(f64::NAN));
+        intervals.row(
+            ordered(low, ror, high) && ordered(s_low, s_ror, s_high),
+            || {
+                format!(
+                    "{drug}/{event} {quarter}: ror {ror} in [{low}, {high}], \
+                     shrunk {s_ror} in [{s_low}, {s_high}]"
+                )
+            },
+        );
+    }
+    checks.extend([
+        quarter_format,
+        Vocab::check(vocab, ARTEFACT, &pairs),
+        intervals,
+    ]);
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct RankedRow {
+    drug_id: String,
+    event_id: String,
+    year_quarter: String,
+    recent_ror: f64,
+    ci_low: f64,
+    ci_high: f64,
+}
+
+/// The same checks over `signals.csv`.
+fn check_ranked(
+    settings: &Settings,
+    vocab: Option<&Vocab>,
+    checks: &mut Vec<CheckResult>,
+) -> Result<()> {
+    const ARTEFACT: &str = "outputs/signals.csv";
+    let path = settings.join_output("signals.csv");
+    if !path.exists() {
+        for check in ["quarter_format", "ids_in_vocabulary", "ci_ordered"] {
+            checks.push(CheckResult::skipped(check, ARTEFACT, "missing; run rank"));
+        }
+        return Ok(());
+    }
+    let rows: Vec<RankedRow> = csv::Reader::from_path(&path)
+        .with_context(|| format!("open {path:?}"))?
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .with_context(|| format!("read {path:?}"))?;
+    let mut quarter_format = CheckResult::new("quarter_format", ARTEFACT);
+    let mut intervals = CheckResult::new("ci_ordered", ARTEFACT);
+    for row in &rows {
+        let pair = format!("{}/{}", row.drug_id, row.event_id);
+        quarter_format.row(valid_quarter(&row.year_quarter), || {
+            format!("{pair}: year_quarter {:?}", row.year_quarter)
+        });
+        intervals.row(ordered(row.ci_low, row.recent_ror, row.ci_high), || {
+            format!(
+                "{pair}: recent_ror {} in [{}, {}]",
+                row.recent_ror, row.ci_low, row.ci_high
+            )
+        });
+    }
+    let pairs: Vec<(&str, &str)> = rows
+        .iter()
+        .map(|row| (row.drug_id.as_str(), row.event_id.as_str()))
+        .collect();
+    checks.extend([
+        quarter_format,
+        Vocab::check(vocab, ARTEFACT, &pairs),
+        intervals,
+    ]);
+    Ok(())
+}
@@ -0,0 +1,139 @@
+//! WHO VigiBase custom extract import.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use tracing::{info, warn};
+
+use crate::{
+    config::Settings,
+    data::{faers::detect_delimiter, normalize},
+    signals::trend,
+};
+
+const CASE_HEADERS: &[&str] = &["CASEID", "CASE_ID", "UMCREPORTID", "SAFETYREPORTID"];
+const DRUG_HEADERS: &[&str] = &[
+    "DRUG",
+    "DRUGNAME",
+    "DRUG_NAME",
+    "SUBSTANCE",
+    "ACTIVE_INGREDIENT",
+];
+const PT_HEADERS: &[&str] = &["PT", "MEDDRA_PT", "REACTION", "REACTION_PT", "ADR"];
+const DATE_HEADERS: &[&str] = &[
+    "DATE",
+    "REPORT_DATE",
+    "FIRSTDATEDATABASE",
+    "RECEIVED",
+    "INITIAL_DATE",
+];
+
+/// Prefix applied to VigiBase case ids so they never collide with FAERS ids.
+pub const CASE_PREFIX: &str = "VB";
+
+/// Import a VigiBase extract file (or directory of files) into the raw case schema.
+///
+/// Each extract must carry case id, drug and MedDRA PT columns; an ISO report date column is
+/// used to assign quarters, falling back to `default_quarter` when absent.
+pub fn import_extract(
+    source: &Path,
+    default_quarter: Option<&str>,
+    settings: &Settings,
+) -> Result<Vec<PathBuf>> {
+    let files = extract_files(source)?;
+    if files.is_empty() {
+        warn!(path = %source.display(), "no vigibase extract files found");
+        return Ok(Vec::new());
+    }
+
+    let mut by_quarter: BTreeMap<String, Vec<[String; 3]>> = BTreeMap::new();
+    for file in &files {
+        info!(file = %file.display(), "reading vigibase extract");
+        read_extract(file, default_quarter, &mut by_quarter)
+            .with_context(|| format!("parse vigibase extract {file:?}"))?;
+    }
+
+    normalize::write_raw_quarters(&settings.join_data("raw/vigibase"), "vigibase", &by_quarter)
+}
+
+fn extract_files(source: &Path) -> Result<Vec<PathBuf>> {
+    if source.is_file() {
+        return Ok(vec![source.to_path_buf()]);
+    }
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(source).with_context(|| format!("read dir {source:?}"))? {
+        let path = entry?.path();
+        let ext = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_ascii_lowercase());
+        if matches!(ext.as_deref(), Some("txt" | "csv" | "tsv")) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn read_extract(
+    path: &Path,
+    default_quarter: Option<&str>,
+    sink: &mut BTreeMap<String, Vec<[String; 3]>>,
+) -> Result<()> {
+    let mut header_line = String::new();
+    BufReader::new(File::open(path)?).read_line(&mut header_line)?;
+    let delimiter = detect_delimiter(&header_line) as u8;
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_path(path)?;
+    let headers = reader.headers()?.clone();
+    let find = |aliases: &[&str]| {
+        headers
+            .iter()
+            .position(|h| aliases.iter().any(|a| h.trim().eq_ignore_ascii_case(a)))
+    };
+    let case_idx = find(CASE_HEADERS).ok_or_else(|| anyhow!("missing case id column"))?;
+    let drug_idx = find(DRUG_HEADERS).ok_or_else(|| anyhow!("missing drug column"))?;
+    let pt_idx = find(PT_HEADERS).ok_or_else(|| anyhow!("missing MedDRA PT column"))?;
+    let date_idx = find(DATE_HEADERS);
+
+    let mut skipped = 0u64;
+    for (line, record) in (2..).zip(reader.records()) {
+        let record = record?;
+        let field = |idx: usize| record.get(idx).map(str::trim).unwrap_or_default();
+        let (case, drug, pt) = (field(case_idx), field(drug_idx), field(pt_idx));
+        if case.is_empty() || drug.is_empty() || pt.is_empty() {
+            skipped += 1;
+            continue;
+        }
+        let quarter = match date_idx.map(field).filter(|date| !date.is_empty()) {
+            Some(date) => Some(trend::quarter_from_date(date).ok_or_else(|| {
+                anyhow!("line {line}: report date {date:?} is not YYYY-MM-DD or YYYYMMDD")
+            })?),
+            None => default_quarter.map(str::to_string),
+        };
+        let Some(quarter) = quarter else {
+            skipped += 1;
+            continue;
+        };
+        sink.entry(quarter).or_default().push([
+            format!("{CASE_PREFIX}{case}"),
+            drug.to_string(),
+            pt.to_string(),
+        ]);
+    }
+    if skipped > 0 {
+        warn!(
+            skipped,
+            "skipped vigibase rows missing case, drug, PT or date"
+        );
+    }
+    Ok(())
+}
@@ -3,6 +3,8 @@ pub mod cli;
 pub mod config;
 pub mod data;
 pub mod logging;
+pub mod net;
 pub mod nlp;
+pub mod progress;
 pub mod signals;
 pub mod ui;
@@ -1,32 +1,263 @@
 //! Structured logging bootstrap using `tracing`.
 
-use anyhow::Result;
-use tracing::Level;
-use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Write as _},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use anyhow::{Context as _, Result};
+use clap::ValueEnum;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Level, Subscriber,
+};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{
+    filter::LevelFilter,
+    fmt::{self, MakeWriter},
+    layer::Context,
+    prelude::*,
+    registry::LookupSpan,
+    EnvFilter, Layer,
+};
+
+use crate::progress::LogWriter;
+
+/// Span field marking work done for an API job; events inside such a span are kept for
+/// `GET /jobs/:id`.
+pub const JOB_SPAN_FIELD: &str = "job_id";
+
+/// Lines kept per job; later events are dropped.
+const MAX_JOB_LOG_LINES: usize = 2000;
+
+static JOB_LOGS: Lazy<Mutex<HashMap<u64, Vec<String>>>> = Lazy::new(Default::default);
+
+/// Layout of log lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines with a timestamp, level, target and source location.
+    #[default]
+    Pretty,
+    /// One JSON object per line, with the event fields at the top level.
+    Json,
+}
+
+/// How often the log file is rolled over to a new, date-suffixed file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Minutely,
+    Hourly,
+    #[default]
+    Daily,
+    /// A single file that grows without rolling over.
+    Never,
+}
+
+impl From<LogRotation> for Rotation {
+    fn from(rotation: LogRotation) -> Self {
+        match rotation {
+            LogRotation::Minutely => Self::MINUTELY,
+            LogRotation::Hourly => Self::HOURLY,
+            LogRotation::Daily => Self::DAILY,
+            LogRotation::Never => Self::NEVER,
+        }
+    }
+}
+
+/// Where and how log lines are written, from settings and CLI flags.
+#[derive(Debug, Clone, Default)]
+pub struct LogOptions {
+    pub format: LogFormat,
+    /// Also append logs to this file, rolled over per `rotation`.
+    pub file: Option<PathBuf>,
+    pub rotation: LogRotation,
+    /// `-v` count minus `-q` count; anything but 0 replaces `RUST_LOG`.
+    pub verbosity: i8,
+}
 
 /// Install a global tracing subscriber with sensible defaults.
-pub fn init_tracing() -> Result<()> {
+pub fn init_tracing(options: &LogOptions) -> Result<()> {
     if tracing::dispatcher::has_been_set() {
         return Ok(());
     }
 
-    let env_filter = EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new("info"))?;
+    let file_layer = match &options.file {
+        Some(path) => {
+            let dir = path
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map_or_else(|| PathBuf::from("."), PathBuf::from);
+            let name = path
+                .file_name()
+                .with_context(|| format!("log file {path:?} has no file name"))?;
+            std::fs::create_dir_all(&dir).with_context(|| format!("create {dir:?}"))?;
+            let appender = RollingFileAppender::new(options.rotation.into(), dir, name);
+            Some(fmt_layer(
+                options.format,
+                appender,
+                false,
+                env_filter(options.verbosity)?,
+            ))
+        }
+        None => None,
+    };
+
+    let registry = tracing_subscriber::registry()
+        .with(fmt_layer(
+            options.format,
+            LogWriter,
+            true,
+            env_filter(options.verbosity)?,
+        ))
+        .with(file_layer)
+        .with(JobLogLayer.with_filter(LevelFilter::INFO));
+    registry.init();
+
+    tracing::debug!(level = ?Level::INFO, "tracing initialised");
+    Ok(())
+}
 
-    let timer = fmt::time::UtcTime::rfc_3339();
+/// `RUST_LOG`, else `info`, unless `-v` or `-q` chose a level.
+fn env_filter(verbosity: i8) -> Result<EnvFilter> {
+    match verbosity_directives(verbosity) {
+        Some(directives) => Ok(EnvFilter::try_new(directives)?),
+        None => Ok(EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new("info"))?),
+    }
+}
 
-    let fmt_layer = fmt::layer()
+/// Filter for a verbosity: `-v` adds this crate's debug events, `-vv` its trace events and
+/// dependencies' debug events, `-vvv` everything; `-q` keeps warnings, `-qq` errors and
+/// `-qqq` nothing.
+pub fn verbosity_directives(verbosity: i8) -> Option<&'static str> {
+    match verbosity {
+        i8::MIN..=-3 => Some("off"),
+        -2 => Some("error"),
+        -1 => Some("warn"),
+        0 => None,
+        1 => Some("info,rwe_assistant=debug"),
+        2 => Some("debug,rwe_assistant=trace"),
+        3..=i8::MAX => Some("trace"),
+    }
+}
+
+/// Formatting layer writing to `writer` in `format`, with ANSI colours only where a person
+/// reads the output.
+fn fmt_layer<S, W>(
+    format: LogFormat,
+    writer: W,
+    ansi: bool,
+    filter: EnvFilter,
+) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + 'static,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = fmt::layer()
+        .with_writer(writer)
+        .with_ansi(ansi)
         .with_target(true)
-        .with_timer(timer)
+        .with_timer(fmt::time::UtcTime::rfc_3339())
         .with_level(true)
         .with_line_number(true)
         .with_file(true)
         .with_thread_ids(false)
-        .with_thread_names(false)
-        .with_filter(env_filter);
+        .with_thread_names(false);
+    match format {
+        LogFormat::Pretty => layer.with_filter(filter).boxed(),
+        LogFormat::Json => layer
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .with_filter(filter)
+            .boxed(),
+    }
+}
 
-    let registry = tracing_subscriber::registry().with(fmt_layer);
-    registry.init();
+/// Log lines captured so far for `job`, oldest first.
+pub fn job_log(job: u64) -> Vec<String> {
+    JOB_LOGS
+        .lock()
+        .map(|logs| logs.get(&job).cloned().unwrap_or_default())
+        .unwrap_or_default()
+}
 
-    tracing::debug!(level = ?Level::INFO, "tracing initialised");
-    Ok(())
+/// Job id recorded on a span's extensions.
+struct JobId(u64);
+
+/// Copies events emitted inside a `job_id` span into `JOB_LOGS`.
+struct JobLogLayer;
+
+impl<S> Layer<S> for JobLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let mut visitor = JobIdVisitor(None);
+        attrs.record(&mut visitor);
+        if let (Some(job), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(JobId(job));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(job) = ctx.event_scope(event).and_then(|mut scope| {
+            scope.find_map(|span| span.extensions().get::<JobId>().map(|job| job.0))
+        }) else {
+            return;
+        };
+        let mut fields = FieldsVisitor(String::new());
+        event.record(&mut fields);
+        let line = format!(
+            "{} {} {}",
+            chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            event.metadata().level(),
+            fields.0.trim_start()
+        );
+        if let Ok(mut logs) = JOB_LOGS.lock() {
+            let lines = logs.entry(job).or_default();
+            if lines.len() < MAX_JOB_LOG_LINES {
+                lines.push(line);
+            }
+        }
+    }
+}
+
+struct JobIdVisitor(Option<u64>);
+
+impl Visit for JobIdVisitor {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if field.name() == JOB_SPAN_FIELD {
+            self.0 = Some(value);
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn Debug) {}
+}
+
+/// Renders the message followed by `key=value` pairs, like the console output.
+struct FieldsVisitor(String);
+
+impl Visit for FieldsVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            let _ = write!(self.0, " {value}");
+        } else {
+            let _ = write!(self.0, " {}={value}", field.name());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, " {value:?}");
+        } else {
+            let _ = write!(self.0, " {}={value:?}", field.name());
+        }
+    }
 }
@@ -1,25 +1,29 @@
 //! Entry point wiring CLI dispatch to pipeline modules.
 
-mod api;
-mod cli;
-mod config;
-mod data;
-mod logging;
-mod nlp;
-mod signals;
-mod ui;
-
 use anyhow::Result;
-use cli::Cli;
-use config::Settings;
-use tracing::{info, instrument};
+use rwe_assistant::{
+    cli::{self, Cli},
+    config::Settings,
+    logging, progress,
+};
+use tracing::{debug, info, instrument};
 
 #[tokio::main]
 #[instrument]
 async fn main() -> Result<()> {
-    logging::init_tracing()?;
-    let settings = Settings::load()?;
     let cli = Cli::parse();
+    if let Some(shell) = cli.completion_shell() {
+        return cli::completions::print(shell);
+    }
+    // Settings come first, as they configure logging.
+    let settings = Settings::load(cli.config())?;
+    logging::init_tracing(&cli.log_options(&settings))?;
+    if cli.quiet() {
+        progress::hide();
+    }
+    if let Some(path) = &settings.config_file {
+        debug!(path = %path.display(), "layered config file");
+    }
 
     info!(?cli, "starting command");
     cli.dispatch(settings).await
@@ -0,0 +1,229 @@
+//! Shared HTTP client with retries, exponential backoff and per-host rate limits.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Result};
+use once_cell::sync::Lazy;
+use rand::Rng;
+use reqwest::{header::RETRY_AFTER, Client, Method, RequestBuilder, Response, StatusCode, Url};
+use tracing::warn;
+
+use crate::config::Settings;
+
+/// Attempts after the first before a transient failure is returned to the caller.
+const MAX_RETRIES: u32 = 4;
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// Statuses that signal a temporary condition worth retrying.
+const RETRY_STATUSES: &[StatusCode] = &[
+    StatusCode::TOO_MANY_REQUESTS,
+    StatusCode::INTERNAL_SERVER_ERROR,
+    StatusCode::BAD_GATEWAY,
+    StatusCode::SERVICE_UNAVAILABLE,
+    StatusCode::GATEWAY_TIMEOUT,
+];
+
+/// Query parameters that carry credentials: the UTS `apiKey` and the NCBI and openFDA
+/// `api_key`.
+const SECRET_PARAMS: &[&str] = &["apiKey", "api_key"];
+
+/// One client per distinct [`ClientKey`]; a server with several datasets or jobs may see
+/// settings that differ in these fields.
+static SHARED: Lazy<Mutex<HashMap<ClientKey, &'static HttpClient>>> = Lazy::new(Default::default);
+
+/// Earliest instant the next request to each host may be sent, shared by every client so
+/// they stay within a host's limit together.
+static NEXT_SLOT: Lazy<Mutex<HashMap<String, Instant>>> = Lazy::new(Default::default);
+
+/// The settings a client is built from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ClientKey {
+    offline: bool,
+    contact: String,
+    pubmed_api_key: bool,
+}
+
+impl ClientKey {
+    fn new(settings: &Settings) -> Self {
+        Self {
+            offline: settings.offline,
+            contact: settings.pubmed_email.clone(),
+            pubmed_api_key: settings.pubmed_api_key.is_some(),
+        }
+    }
+}
+
+/// Process-wide client for the offline flag, contact address and PubMed API key of `settings`.
+pub fn client(settings: &Settings) -> Result<&'static HttpClient> {
+    let key = ClientKey::new(settings);
+    let mut clients = SHARED.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(client) = clients.get(&key) {
+        return Ok(client);
+    }
+    // Leaked on purpose: there are only as many clients as distinct keys, and they live for
+    // the rest of the process.
+    let client: &'static HttpClient = Box::leak(Box::new(HttpClient::new(settings)?));
+    clients.insert(key, client);
+    Ok(client)
+}
+
+/// Fail with a clear message when `what` is missing from the cache in offline mode.
+pub fn ensure_online(settings: &Settings, what: &str) -> Result<()> {
+    if settings.offline {
+        bail!("{what} is not cached and offline mode is enabled");
+    }
+    Ok(())
+}
+
+/// `url` with credential query values masked, fit for logs and error messages.
+pub fn redact_url(url: &Url) -> Url {
+    let is_secret = |name: &str| SECRET_PARAMS.contains(&name);
+    let mut redacted = url.clone();
+    if url.query_pairs().any(|(name, _)| is_secret(&name)) {
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(name, value)| {
+                let value = if is_secret(&name) {
+                    "REDACTED".to_string()
+                } else {
+                    value.into_owned()
+                };
+                (name.into_owned(), value)
+            })
+            .collect();
+        redacted.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    redacted
+}
+
+/// Mask credentials in the URL a reqwest error carries. Errors from `error_for_status` and
+/// body decoding name the request URL, so callers map them through this as well.
+pub fn redact(mut err: reqwest::Error) -> reqwest::Error {
+    if let Some(url) = err.url_mut() {
+        *url = redact_url(url);
+    }
+    err
+}
+
+/// reqwest client wrapper that spaces requests per host and retries 429/5xx and timeouts.
+pub struct HttpClient {
+    client: Client,
+    offline: bool,
+    /// Minimum spacing between requests to a host, from each service's published limits.
+    host_intervals: HashMap<&'static str, Duration>,
+}
+
+impl HttpClient {
+    pub fn new(settings: &Settings) -> Result<Self> {
+        let client = Client::builder()
+            .user_agent(format!("rwe-assistant/0.1 (+{})", settings.pubmed_email))
+            .gzip(true)
+            .brotli(true)
+            .connect_timeout(Duration::from_secs(30))
+            .read_timeout(Duration::from_secs(120))
+            .build()?;
+        // NCBI allows 3 requests/s, or 10 with an API key.
+        let eutils = if settings.pubmed_api_key.is_some() {
+            Duration::from_millis(100)
+        } else {
+            Duration::from_millis(340)
+        };
+        let host_intervals = HashMap::from([
+            ("eutils.ncbi.nlm.nih.gov", eutils),
+            ("rxnav.nlm.nih.gov", Duration::from_millis(50)),
+            ("uts-ws.nlm.nih.gov", Duration::from_millis(50)),
+            ("dailymed.nlm.nih.gov", Duration::from_millis(100)),
+            // openFDA allows 240 requests per minute.
+            ("api.fda.gov", Duration::from_millis(250)),
+        ]);
+        Ok(Self {
+            client,
+            offline: settings.offline,
+            host_intervals,
+        })
+    }
+
+    /// Start a request; send it with [`HttpClient::send`] to get retries and throttling.
+    pub fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        self.client.request(method, url)
+    }
+
+    pub async fn get(&self, url: &str) -> Result<Response> {
+        self.send(self.request(Method::GET, url)).await
+    }
+
+    pub async fn head(&self, url: &str) -> Result<Response> {
+        self.send(self.request(Method::HEAD, url)).await
+    }
+
+    /// Send a request, retrying transient failures with exponential backoff.
+    ///
+    /// Non-retryable statuses are returned as-is; callers decide via `error_for_status`.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let request = request.build()?;
+        if self.offline {
+            bail!(
+                "offline mode: refusing network request to {}",
+                redact_url(request.url())
+            );
+        }
+        let host = request.url().host_str().unwrap_or_default().to_string();
+        let mut attempt = 0;
+        loop {
+            self.throttle(&host).await;
+            let attempt_request = request
+                .try_clone()
+                .ok_or_else(|| anyhow!("request body cannot be retried"))?;
+            let outcome = self.client.execute(attempt_request).await;
+            let transient = match &outcome {
+                Ok(resp) if RETRY_STATUSES.contains(&resp.status()) => Some(retry_after(resp)),
+                Err(err) if err.is_timeout() || err.is_connect() => Some(None),
+                _ => None,
+            };
+            match transient {
+                Some(hint) if attempt < MAX_RETRIES => {
+                    let delay = hint.unwrap_or_else(|| backoff(attempt));
+                    warn!(%host, attempt = attempt + 1, ?delay, "transient HTTP failure; retrying");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                _ => return Ok(outcome.map_err(redact)?),
+            }
+        }
+    }
+
+    /// Wait for the next free slot for `host` so concurrent tasks share its rate limit.
+    async fn throttle(&self, host: &str) {
+        let Some(interval) = self.host_intervals.get(host) else {
+            return;
+        };
+        let wait = {
+            let mut slots = NEXT_SLOT.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            let slot = slots.get(host).map_or(now, |at| (*at).max(now));
+            slots.insert(host.to_string(), slot + *interval);
+            slot - now
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Delay requested by a `Retry-After: <seconds>` header, capped at [`MAX_BACKOFF`].
+fn retry_after(resp: &Response) -> Option<Duration> {
+    resp.headers()
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(|secs| Duration::from_secs(secs).min(MAX_BACKOFF))
+}
+
+fn backoff(attempt: u32) -> Duration {
+    let jitter = Duration::from_millis(rand::thread_rng().gen_range(0..250));
+    (BASE_BACKOFF * 2u32.pow(attempt) + jitter).min(MAX_BACKOFF)
+}
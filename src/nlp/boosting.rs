@@ -0,0 +1,190 @@
+//! Gradient-boosted regression trees for binary relation classification.
+
+use ndarray::{Array2, ArrayView1};
+
+/// Training knobs for [`GradientBoostedTrees`].
+#[derive(Debug, Clone, Copy)]
+pub struct BoostingParams {
+    pub rounds: usize,
+    pub learning_rate: f64,
+    pub max_depth: usize,
+    pub min_samples_leaf: usize,
+}
+
+impl Default for BoostingParams {
+    fn default() -> Self {
+        Self {
+            rounds: 100,
+            learning_rate: 0.1,
+            max_depth: 3,
+            min_samples_leaf: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Leaf(f64),
+    Split {
+        feature: usize,
+        threshold: f64,
+        left: Box<Node>,
+        right: Box<Node>,
+    },
+}
+
+impl Node {
+    fn value(&self, row: ArrayView1<f64>) -> f64 {
+        match self {
+            Self::Leaf(value) => *value,
+            Self::Split {
+                feature,
+                threshold,
+                left,
+                right,
+            } => {
+                if row[*feature] <= *threshold {
+                    left.value(row)
+                } else {
+                    right.value(row)
+                }
+            }
+        }
+    }
+}
+
+/// Additive model of shallow regression trees fitted to the logistic-loss gradient.
+#[derive(Debug, Clone)]
+pub struct GradientBoostedTrees {
+    base: f64,
+    learning_rate: f64,
+    trees: Vec<Node>,
+}
+
+impl GradientBoostedTrees {
+    /// Fit on rows of `x` with 0/1 `labels`.
+    pub fn fit(x: &Array2<f64>, labels: &[i32], params: BoostingParams) -> Self {
+        let targets: Vec<f64> = labels
+            .iter()
+            .map(|&label| if label > 0 { 1.0 } else { 0.0 })
+            .collect();
+        let prior =
+            (targets.iter().sum::<f64>() / targets.len().max(1) as f64).clamp(1e-6, 1.0 - 1e-6);
+        let base = (prior / (1.0 - prior)).ln();
+        let mut scores = vec![base; targets.len()];
+        let mut trees = Vec::with_capacity(params.rounds);
+        let rows: Vec<usize> = (0..targets.len()).collect();
+        for _ in 0..params.rounds {
+            let probabilities: Vec<f64> = scores.iter().map(|&s| sigmoid(s)).collect();
+            let residuals: Vec<f64> = targets
+                .iter()
+                .zip(&probabilities)
+                .map(|(y, p)| y - p)
+                .collect();
+            let hessians: Vec<f64> = probabilities.iter().map(|p| p * (1.0 - p)).collect();
+            let tree = grow(x, &residuals, &hessians, &rows, 0, &params);
+            for (idx, score) in scores.iter_mut().enumerate() {
+                *score += params.learning_rate * tree.value(x.row(idx));
+            }
+            trees.push(tree);
+        }
+        Self {
+            base,
+            learning_rate: params.learning_rate,
+            trees,
+        }
+    }
+
+    /// Log-odds of the positive class for each row.
+    pub fn decision_function(&self, x: &Array2<f64>) -> Vec<f64> {
+        x.rows()
+            .into_iter()
+            .map(|row| {
+                self.base
+                    + self.learning_rate
+                        * self.trees.iter().map(|tree| tree.value(row)).sum::<f64>()
+            })
+            .collect()
+    }
+
+    pub fn predict(&self, x: &Array2<f64>) -> Vec<i32> {
+        self.decision_function(x)
+            .into_iter()
+            .map(|score| i32::from(score > 0.0))
+            .collect()
+    }
+}
+
+fn sigmoid(value: f64) -> f64 {
+    1.0 / (1.0 + (-value).exp())
+}
+
+/// Grow a tree on `rows`, splitting to minimise the squared error of the residuals.
+/// Leaves hold a Newton step, `sum(residual) / sum(hessian)`.
+fn grow(
+    x: &Array2<f64>,
+    residuals: &[f64],
+    hessians: &[f64],
+    rows: &[usize],
+    depth: usize,
+    params: &BoostingParams,
+) -> Node {
+    let leaf = || {
+        let gradient: f64 = rows.iter().map(|&idx| residuals[idx]).sum();
+        let hessian: f64 = rows.iter().map(|&idx| hessians[idx]).sum();
+        Node::Leaf(gradient / (hessian + 1e-6))
+    };
+    if depth >= params.max_depth || rows.len() < 2 * params.min_samples_leaf {
+        return leaf();
+    }
+    let Some((feature, threshold)) = best_split(x, residuals, rows, params.min_samples_leaf) else {
+        return leaf();
+    };
+    let (left, right): (Vec<usize>, Vec<usize>) = rows
+        .iter()
+        .partition(|&&idx| x[[idx, feature]] <= threshold);
+    Node::Split {
+        feature,
+        threshold,
+        left: Box::new(grow(x, residuals, hessians, &left, depth + 1, params)),
+        right: Box::new(grow(x, residuals, hessians, &right, depth + 1, params)),
+    }
+}
+
+/// Feature and threshold with the largest reduction in residual squared error, if any.
+fn best_split(
+    x: &Array2<f64>,
+    residuals: &[f64],
+    rows: &[usize],
+    min_samples_leaf: usize,
+) -> Option<(usize, f64)> {
+    let total: f64 = rows.iter().map(|&idx| residuals[idx]).sum();
+    let count = rows.len() as f64;
+    let parent = total * total / count;
+    let mut best: Option<(f64, usize, f64)> = None;
+    let mut order = rows.to_vec();
+    for feature in 0..x.ncols() {
+        order.sort_by(|&a, &b| x[[a, feature]].total_cmp(&x[[b, feature]]));
+        let mut left_sum = 0.0;
+        for (position, &idx) in order.iter().enumerate().take(order.len() - 1) {
+            left_sum += residuals[idx];
+            let left_count = position + 1;
+            let value = x[[idx, feature]];
+            let next = x[[order[position + 1], feature]];
+            if value == next
+                || left_count < min_samples_leaf
+                || order.len() - left_count < min_samples_leaf
+            {
+                continue;
+            }
+            let right_sum = total - left_sum;
+            let gain = left_sum * left_sum / left_count as f64
+                + right_sum * right_sum / (count - left_count as f64)
+                - parent;
+            if gain > 1e-12 && !matches!(best, Some((top, _, _)) if top >= gain) {
+                best = Some((gain, feature, (value + next) / 2.0));
+            }
+        }
+    }
+    best.map(|(_, feature, threshold)| (feature, threshold))
+}
@@ -0,0 +1,103 @@
+//! Platt scaling of classifier decision scores into relation probabilities.
+
+/// Sigmoid `P(relation | score) = 1 / (1 + exp(a * score + b))` fitted by Platt's method.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlattScaler {
+    pub a: f64,
+    pub b: f64,
+}
+
+impl Default for PlattScaler {
+    /// Plain logistic link, for scores that are already log-odds.
+    fn default() -> Self {
+        Self { a: -1.0, b: 0.0 }
+    }
+}
+
+impl PlattScaler {
+    /// Fit on decision scores and 0/1 labels with the Newton method of Lin, Lin & Weng (2007),
+    /// including Platt's smoothed targets. Falls back to the plain logistic link when the
+    /// labels hold a single class.
+    pub fn fit(scores: &[f64], labels: &[i32]) -> Self {
+        let positives = labels.iter().filter(|&&label| label > 0).count() as f64;
+        let negatives = labels.len() as f64 - positives;
+        if positives == 0.0 || negatives == 0.0 {
+            return Self::default();
+        }
+        let high = (positives + 1.0) / (positives + 2.0);
+        let low = 1.0 / (negatives + 2.0);
+        let targets: Vec<f64> = labels
+            .iter()
+            .map(|&label| if label > 0 { high } else { low })
+            .collect();
+
+        let mut a = 0.0;
+        let mut b = ((negatives + 1.0) / (positives + 1.0)).ln();
+        let mut loss = log_loss(scores, &targets, a, b);
+        for _ in 0..100 {
+            let (mut h11, mut h22, mut h21) = (1e-12, 1e-12, 0.0);
+            let (mut g1, mut g2) = (0.0, 0.0);
+            for (&score, &target) in scores.iter().zip(&targets) {
+                let p = positive_probability(score * a + b);
+                let d2 = p * (1.0 - p);
+                h11 += score * score * d2;
+                h22 += d2;
+                h21 += score * d2;
+                let d1 = target - p;
+                g1 += score * d1;
+                g2 += d1;
+            }
+            if g1.abs() < 1e-5 && g2.abs() < 1e-5 {
+                break;
+            }
+            let det = h11 * h22 - h21 * h21;
+            let da = -(h22 * g1 - h21 * g2) / det;
+            let db = -(-h21 * g1 + h11 * g2) / det;
+            let slope = g1 * da + g2 * db;
+            let mut step = 1.0;
+            while step >= 1e-10 {
+                let (next_a, next_b) = (a + step * da, b + step * db);
+                let next_loss = log_loss(scores, &targets, next_a, next_b);
+                if next_loss < loss + 1e-4 * step * slope {
+                    (a, b, loss) = (next_a, next_b, next_loss);
+                    break;
+                }
+                step /= 2.0;
+            }
+            if step < 1e-10 {
+                break;
+            }
+        }
+        Self { a, b }
+    }
+
+    /// Calibrated probability that `score` belongs to a true relation.
+    pub fn probability(&self, score: f64) -> f64 {
+        positive_probability(score * self.a + self.b)
+    }
+}
+
+/// `1 / (1 + exp(x))`, evaluated without overflow.
+fn positive_probability(x: f64) -> f64 {
+    if x >= 0.0 {
+        (-x).exp() / (1.0 + (-x).exp())
+    } else {
+        1.0 / (1.0 + x.exp())
+    }
+}
+
+/// Cross-entropy of the smoothed targets under the sigmoid `(a, b)`.
+fn log_loss(scores: &[f64], targets: &[f64], a: f64, b: f64) -> f64 {
+    scores
+        .iter()
+        .zip(targets)
+        .map(|(&score, &target)| {
+            let x = score * a + b;
+            if x >= 0.0 {
+                target * x + (1.0 + (-x).exp()).ln()
+            } else {
+                (target - 1.0) * x + (1.0 + x.exp()).ln()
+            }
+        })
+        .sum()
+}
@@ -0,0 +1,396 @@
+//! Clustering of term embeddings for near-duplicate detection.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+
+/// Algorithm used by `embed` to group near-duplicate terms.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ClusterAlgorithm {
+    /// First-fit assignment to the first centroid within the threshold (input-order dependent).
+    #[default]
+    Greedy,
+    /// Average-linkage agglomerative clustering, cut at the similarity threshold.
+    Average,
+    /// HDBSCAN over cosine distance; unclustered terms stay on their own.
+    Hdbscan,
+}
+
+/// Clustering knobs shared by the algorithms.
+#[derive(Debug, Clone, Copy)]
+pub struct ClusterParams {
+    pub algorithm: ClusterAlgorithm,
+    /// Cosine similarity at or above which terms are merged (greedy and average linkage).
+    pub threshold: f32,
+    /// Smallest group HDBSCAN reports as a cluster.
+    pub min_cluster_size: usize,
+    /// Cosine similarity at or above which drug names are merged.
+    pub drug_threshold: f32,
+}
+
+impl Default for ClusterParams {
+    fn default() -> Self {
+        Self {
+            algorithm: ClusterAlgorithm::Greedy,
+            threshold: 0.85,
+            min_cluster_size: 2,
+            drug_threshold: 0.97,
+        }
+    }
+}
+
+impl ClusterParams {
+    /// Parameters for drug names. Distinct drugs of one class (imatinib, nilotinib) embed
+    /// nearly as close as event synonyms, so drugs merge only at `drug_threshold`, and
+    /// HDBSCAN, which has no similarity floor, gives way to average linkage.
+    pub fn for_drugs(&self) -> Self {
+        let algorithm = match self.algorithm {
+            ClusterAlgorithm::Hdbscan => ClusterAlgorithm::Average,
+            other => other,
+        };
+        Self {
+            algorithm,
+            threshold: self.drug_threshold,
+            ..*self
+        }
+    }
+}
+
+/// Most terms `average` and `hdbscan` accept. Both hold every pairwise similarity in a
+/// dense n×n `f32` matrix, about 900 MB at this size.
+pub const MAX_DENSE_TERMS: usize = 15_000;
+
+/// Cluster id per embedding, numbered by first appearance so ids are stable across runs.
+/// Fails when a dense algorithm is given more than [`MAX_DENSE_TERMS`] embeddings.
+pub fn cluster(embeddings: &[Vec<f32>], params: &ClusterParams) -> Result<Vec<usize>> {
+    if embeddings.is_empty() {
+        return Ok(Vec::new());
+    }
+    if params.algorithm != ClusterAlgorithm::Greedy && embeddings.len() > MAX_DENSE_TERMS {
+        bail!(
+            "{:?} clustering of {} terms needs a dense {n}x{n} similarity matrix; it is limited \
+             to {MAX_DENSE_TERMS} terms, use --algorithm greedy for larger vocabularies",
+            params.algorithm,
+            embeddings.len(),
+            n = embeddings.len(),
+        );
+    }
+    let assignments = match params.algorithm {
+        ClusterAlgorithm::Greedy => greedy(embeddings, params.threshold),
+        ClusterAlgorithm::Average => average_linkage(embeddings, params.threshold),
+        ClusterAlgorithm::Hdbscan => hdbscan(embeddings, params.min_cluster_size.max(2)),
+    };
+    Ok(renumber(&assignments))
+}
+
+/// Index of the member representing each cluster: the one with the highest `frequency`,
+/// ties going to the alphabetically first name, so the choice does not depend on input order.
+pub fn representatives(
+    clusters: &[usize],
+    names: &[String],
+    frequency: &[u64],
+) -> HashMap<usize, usize> {
+    let mut reps: HashMap<usize, usize> = HashMap::new();
+    for (idx, &cluster) in clusters.iter().enumerate() {
+        let rep = reps.entry(cluster).or_insert(idx);
+        let better = frequency[idx]
+            .cmp(&frequency[*rep])
+            .then_with(|| names[*rep].cmp(&names[idx]));
+        if better.is_gt() {
+            *rep = idx;
+        }
+    }
+    reps
+}
+
+fn renumber(assignments: &[usize]) -> Vec<usize> {
+    let mut ids = HashMap::new();
+    assignments
+        .iter()
+        .map(|&label| {
+            let next = ids.len();
+            *ids.entry(label).or_insert(next)
+        })
+        .collect()
+}
+
+fn greedy(embeddings: &[Vec<f32>], threshold: f32) -> Vec<usize> {
+    let mut clusters: Vec<&[f32]> = Vec::new();
+    let mut assignments = Vec::new();
+    for vector in embeddings {
+        if let Some(idx) = clusters
+            .iter()
+            .position(|centroid| cosine(vector, centroid) >= threshold)
+        {
+            assignments.push(idx);
+        } else {
+            clusters.push(vector);
+            assignments.push(clusters.len() - 1);
+        }
+    }
+    assignments
+}
+
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot = a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+    let norm_a = a.iter().map(|v| v * v).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// Pairwise cosine similarities as a dense row-major matrix.
+fn similarity_matrix(embeddings: &[Vec<f32>]) -> Vec<f32> {
+    let n = embeddings.len();
+    let mut sim = vec![0.0; n * n];
+    for i in 0..n {
+        sim[i * n + i] = 1.0;
+        for j in i + 1..n {
+            let value = cosine(&embeddings[i], &embeddings[j]);
+            sim[i * n + j] = value;
+            sim[j * n + i] = value;
+        }
+    }
+    sim
+}
+
+/// Union-find over point indices.
+struct DisjointSet {
+    parent: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+        }
+    }
+
+    fn find(&mut self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        x
+    }
+
+    /// Join two sets under the smaller root, keeping results independent of merge order.
+    fn union(&mut self, a: usize, b: usize) -> usize {
+        let (a, b) = (self.find(a), self.find(b));
+        let (keep, drop) = (a.min(b), a.max(b));
+        self.parent[drop] = keep;
+        keep
+    }
+}
+
+/// Average-linkage (UPGMA) clustering by the nearest-neighbour chain algorithm, in O(n²)
+/// time and memory. Average linkage is reducible, so the flat clusters at `threshold` are
+/// exactly the unions made by merges whose average similarity reaches it.
+fn average_linkage(embeddings: &[Vec<f32>], threshold: f32) -> Vec<usize> {
+    let n = embeddings.len();
+    let mut sim = similarity_matrix(embeddings);
+    let mut size = vec![1usize; n];
+    let mut active = vec![true; n];
+    let mut remaining = n;
+    let mut chain: Vec<usize> = Vec::new();
+    let mut merges: Vec<(usize, usize, f32)> = Vec::with_capacity(n.saturating_sub(1));
+    while remaining > 1 {
+        if chain.is_empty() {
+            chain.push(active.iter().position(|&a| a).unwrap_or_default());
+        }
+        let a = *chain.last().unwrap_or(&0);
+        let previous = chain.len().checked_sub(2).map(|idx| chain[idx]);
+        // Most similar active cluster; ties go to the chain predecessor, then the lowest index.
+        let mut best = previous;
+        let mut best_sim = previous.map_or(f32::NEG_INFINITY, |p| sim[a * n + p]);
+        for b in (0..n).filter(|&b| active[b] && b != a) {
+            if sim[a * n + b] > best_sim {
+                best = Some(b);
+                best_sim = sim[a * n + b];
+            }
+        }
+        let Some(b) = best else {
+            break;
+        };
+        if Some(b) != previous {
+            chain.push(b);
+            continue;
+        }
+        chain.truncate(chain.len() - 2);
+        merges.push((a, b, best_sim));
+        let (keep, drop) = (a.min(b), a.max(b));
+        let total = (size[keep] + size[drop]) as f32;
+        for k in (0..n).filter(|&k| active[k] && k != keep && k != drop) {
+            let merged = (size[keep] as f32 * sim[keep * n + k]
+                + size[drop] as f32 * sim[drop * n + k])
+                / total;
+            sim[keep * n + k] = merged;
+            sim[k * n + keep] = merged;
+        }
+        size[keep] += size[drop];
+        active[drop] = false;
+        remaining -= 1;
+    }
+    let mut sets = DisjointSet::new(n);
+    for (a, b, similarity) in merges {
+        if similarity >= threshold {
+            sets.union(a, b);
+        }
+    }
+    (0..n).map(|idx| sets.find(idx)).collect()
+}
+
+/// Neighbours used for the HDBSCAN core distance (the point itself counts as the first).
+const MIN_SAMPLES: usize = 2;
+
+/// Smallest distance used for density (`lambda = 1 / distance`), so exact duplicates stay
+/// finite.
+const MIN_DISTANCE: f32 = 1e-6;
+
+/// HDBSCAN (Campello, Moulavi & Sander 2013) over cosine distance with excess-of-mass
+/// cluster selection. Noise points become singleton clusters.
+fn hdbscan(embeddings: &[Vec<f32>], min_cluster_size: usize) -> Vec<usize> {
+    let n = embeddings.len();
+    if n < min_cluster_size {
+        return (0..n).collect();
+    }
+    let sim = similarity_matrix(embeddings);
+    let distance = |i: usize, j: usize| (1.0 - sim[i * n + j]).max(0.0);
+
+    let core: Vec<f32> = (0..n)
+        .map(|i| {
+            let mut others: Vec<f32> = (0..n).filter(|&j| j != i).map(|j| distance(i, j)).collect();
+            others.sort_by(f32::total_cmp);
+            others
+                .get(MIN_SAMPLES - 2)
+                .or(others.last())
+                .copied()
+                .unwrap_or(0.0)
+        })
+        .collect();
+    let reachability = |i: usize, j: usize| distance(i, j).max(core[i]).max(core[j]);
+
+    // Minimum spanning tree of the mutual reachability graph (Prim, dense).
+    let mut in_tree = vec![false; n];
+    let mut best = vec![(f32::INFINITY, 0usize); n];
+    let mut edges: Vec<(usize, usize, f32)> = Vec::with_capacity(n - 1);
+    let mut current = 0;
+    in_tree[0] = true;
+    for _ in 1..n {
+        for j in (0..n).filter(|&j| !in_tree[j]) {
+            let weight = reachability(current, j);
+            if weight < best[j].0 {
+                best[j] = (weight, current);
+            }
+        }
+        let next = (0..n)
+            .filter(|&j| !in_tree[j])
+            .min_by(|&a, &b| best[a].0.total_cmp(&best[b].0).then(a.cmp(&b)))
+            .unwrap_or_default();
+        edges.push((best[next].1, next, best[next].0));
+        in_tree[next] = true;
+        current = next;
+    }
+    edges.sort_by(|a, b| a.2.total_cmp(&b.2).then((a.0, a.1).cmp(&(b.0, b.1))));
+
+    // Single-linkage dendrogram: nodes 0..n are points, n.. are merges.
+    let mut sets = DisjointSet::new(n);
+    let mut node_of_root: Vec<usize> = (0..n).collect();
+    let mut children: Vec<(usize, usize, f32)> = Vec::with_capacity(n - 1);
+    let mut node_size = vec![1usize; n];
+    for (a, b, weight) in edges {
+        let (ra, rb) = (sets.find(a), sets.find(b));
+        let node = n + children.len();
+        children.push((node_of_root[ra], node_of_root[rb], weight));
+        node_size.push(node_size[node_of_root[ra]] + node_size[node_of_root[rb]]);
+        let root = sets.union(ra, rb);
+        node_of_root[root] = node;
+    }
+    let leaves = |node: usize| -> Vec<usize> {
+        let mut stack = vec![node];
+        let mut points = Vec::new();
+        while let Some(top) = stack.pop() {
+            if top < n {
+                points.push(top);
+            } else {
+                let (left, right, _) = children[top - n];
+                stack.extend([left, right]);
+            }
+        }
+        points
+    };
+
+    // Condense the dendrogram: a split yields two clusters only when both sides are large
+    // enough; otherwise the small side's points fall out of the current cluster.
+    let mut parent: Vec<Option<usize>> = vec![None];
+    let mut birth: Vec<f32> = vec![0.0];
+    let mut stability: Vec<f32> = vec![0.0];
+    let mut fell_from = vec![0usize; n];
+    let mut stack = vec![(2 * n - 2, 0usize)];
+    while let Some((node, label)) = stack.pop() {
+        let (left, right, weight) = children[node - n];
+        let lambda = 1.0 / weight.max(MIN_DISTANCE);
+        let large = |child: usize| node_size[child] >= min_cluster_size;
+        if large(left) && large(right) {
+            for child in [left, right] {
+                let new_label = parent.len();
+                parent.push(Some(label));
+                birth.push(lambda);
+                stability.push(0.0);
+                stability[label] += (lambda - birth[label]) * node_size[child] as f32;
+                stack.push((child, new_label));
+            }
+            continue;
+        }
+        for child in [left, right] {
+            if large(child) {
+                stack.push((child, label));
+            } else {
+                for point in leaves(child) {
+                    stability[label] += lambda - birth[label];
+                    fell_from[point] = label;
+                }
+            }
+        }
+    }
+
+    // Excess-of-mass selection, children before parents; the root is never selected.
+    let clusters = parent.len();
+    let mut child_labels: Vec<Vec<usize>> = vec![Vec::new(); clusters];
+    for (label, p) in parent.iter().enumerate().skip(1) {
+        if let Some(p) = *p {
+            child_labels[p].push(label);
+        }
+    }
+    let mut selected = vec![false; clusters];
+    let mut subtree = stability.clone();
+    for label in (1..clusters).rev() {
+        let below: f32 = child_labels[label].iter().map(|&c| subtree[c]).sum();
+        if child_labels[label].is_empty() || stability[label] >= below {
+            selected[label] = true;
+            let mut descendants = child_labels[label].clone();
+            while let Some(d) = descendants.pop() {
+                selected[d] = false;
+                descendants.extend(child_labels[d].iter().copied());
+            }
+        } else {
+            subtree[label] = below;
+        }
+    }
+
+    (0..n)
+        .map(|point| {
+            let mut label = Some(fell_from[point]);
+            while let Some(current) = label {
+                if selected[current] {
+                    return n + current;
+                }
+                label = parent[current];
+            }
+            point
+        })
+        .collect()
+}
@@ -0,0 +1,157 @@
+//! On-disk cache of text embeddings, keyed by model and text hash.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{ensure, Context, Result};
+use polars::prelude::{
+    DataFrame, IntoSeries, ListChunked, NamedFrom, ParquetReader, ParquetWriter, SerReader, Series,
+};
+use sha2::{Digest, Sha256};
+use tracing::{debug, info};
+
+/// Cache file under the data directory, shared by every model.
+pub const CACHE_FILE: &str = "cache/embeddings.parquet";
+
+/// Embeddings previously computed for `(model, text)` pairs, stored in a parquet file with
+/// `key`, `model` and `vector` columns. Entries of other models are kept untouched.
+#[derive(Debug)]
+pub struct EmbeddingCache {
+    path: PathBuf,
+    model: String,
+    entries: HashMap<String, (String, Vec<f32>)>,
+    added: usize,
+}
+
+impl EmbeddingCache {
+    /// Load the cache at `path` (empty when missing) for lookups with `model`.
+    pub fn open(path: PathBuf, model: &str) -> Result<Self> {
+        let entries = if path.exists() {
+            read_entries(&path)?
+        } else {
+            HashMap::new()
+        };
+        debug!(path = %path.display(), entries = entries.len(), "opened embedding cache");
+        Ok(Self {
+            path,
+            model: model.to_string(),
+            entries,
+            added: 0,
+        })
+    }
+
+    /// SHA-256 of the model name and text, hex encoded.
+    pub fn key(model: &str, text: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(model.as_bytes());
+        hasher.update([0]);
+        hasher.update(text.as_bytes());
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    pub fn get(&self, text: &str) -> Option<&[f32]> {
+        self.entries
+            .get(&Self::key(&self.model, text))
+            .map(|(_, vector)| vector.as_slice())
+    }
+
+    pub fn insert(&mut self, text: &str, vector: Vec<f32>) {
+        self.entries
+            .insert(Self::key(&self.model, text), (self.model.clone(), vector));
+        self.added += 1;
+    }
+
+    /// Embeddings of `texts` in order, calling `compute` once with only the texts that are
+    /// not cached yet.
+    pub fn embed_with<F>(&mut self, texts: &[String], compute: F) -> Result<Vec<Vec<f32>>>
+    where
+        F: FnOnce(Vec<&str>) -> Result<Vec<Vec<f32>>>,
+    {
+        let mut missing: Vec<&str> = texts
+            .iter()
+            .map(String::as_str)
+            .filter(|text| self.get(text).is_none())
+            .collect();
+        missing.sort_unstable();
+        missing.dedup();
+        info!(
+            cached = texts.len() - missing.len(),
+            computed = missing.len(),
+            model = %self.model,
+            "embedding texts"
+        );
+        if !missing.is_empty() {
+            let vectors = compute(missing.clone())?;
+            ensure!(
+                vectors.len() == missing.len(),
+                "embedder returned {} vectors for {} texts",
+                vectors.len(),
+                missing.len()
+            );
+            for (text, vector) in missing.into_iter().zip(vectors) {
+                self.insert(text, vector);
+            }
+        }
+        Ok(texts
+            .iter()
+            .map(|text| self.get(text).map(<[f32]>::to_vec).unwrap_or_default())
+            .collect())
+    }
+
+    /// Write the cache back when entries were added.
+    pub fn persist(&self) -> Result<()> {
+        if self.added == 0 {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut keys = Vec::with_capacity(self.entries.len());
+        let mut models = Vec::with_capacity(self.entries.len());
+        let mut vectors = Vec::with_capacity(self.entries.len());
+        for (key, (model, vector)) in &self.entries {
+            keys.push(key.as_str());
+            models.push(model.as_str());
+            vectors.push(Series::new("".into(), vector.as_slice()));
+        }
+        let vectors: ListChunked = vectors.into_iter().collect();
+        let mut df = DataFrame::new(vec![
+            Series::new("key".into(), keys),
+            Series::new("model".into(), models),
+            vectors.into_series().with_name("vector".into()),
+        ])?;
+        let file = File::create(&self.path).with_context(|| format!("create {:?}", self.path))?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        info!(
+            path = %self.path.display(),
+            entries = df.height(),
+            added = self.added,
+            "wrote embedding cache"
+        );
+        Ok(())
+    }
+}
+
+fn read_entries(path: &Path) -> Result<HashMap<String, (String, Vec<f32>)>> {
+    let file = File::open(path).with_context(|| format!("open {path:?}"))?;
+    let df = ParquetReader::new(file).finish()?;
+    let keys = df.column("key")?.str()?;
+    let models = df.column("model")?.str()?;
+    let vectors = df.column("vector")?.list()?;
+    let mut entries = HashMap::with_capacity(df.height());
+    for ((key, model), vector) in keys.into_iter().zip(models).zip(vectors) {
+        let (Some(key), Some(model), Some(vector)) = (key, model, vector) else {
+            continue;
+        };
+        let vector: Vec<f32> = vector.f32()?.into_no_null_iter().collect();
+        entries.insert(key.to_string(), (model.to_string(), vector));
+    }
+    Ok(entries)
+}
@@ -1,92 +1,205 @@
 //! Embedding and clustering utilities built on fastembed.
 
-use std::fs::File;
+use std::{collections::HashMap, fs::File};
 
 use anyhow::Result;
 use polars::prelude::{DataFrame, NamedFrom, ParquetReader, ParquetWriter, SerReader, Series};
+use serde::Deserialize;
 use tracing::{info, warn};
 
 #[cfg(feature = "embeddings")]
-use fastembed::TextEmbedding;
+use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+#[cfg(feature = "embeddings")]
+use ort_providers::execution_providers::{
+    CPUExecutionProvider, CUDAExecutionProvider, CoreMLExecutionProvider, ExecutionProviderDispatch,
+};
+
+use crate::{
+    config::Settings,
+    nlp::clustering::{self, ClusterParams},
+};
+
+#[cfg(feature = "embeddings")]
+use crate::nlp::embedding_cache::{self, EmbeddingCache};
+
+/// Sentence-transformer behind every embedding (fastembed's default), and the cache key
+/// prefix for its vectors.
+pub const EMBEDDING_MODEL: &str = "bge-small-en-v1.5";
+
+/// ONNX Runtime execution provider for embedding. A provider that is unavailable at run
+/// time (no GPU, or a build without `embeddings-cuda`/`embeddings-coreml`) is skipped with
+/// a warning and the next one is tried; the CPU is always the last resort.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingProvider {
+    Cpu,
+    Cuda,
+    #[value(name = "coreml")]
+    #[serde(rename = "coreml")]
+    CoreMl,
+}
+
+#[cfg(feature = "embeddings")]
+impl EmbeddingProvider {
+    fn dispatch(self) -> ExecutionProviderDispatch {
+        match self {
+            Self::Cpu => CPUExecutionProvider::default().build(),
+            Self::Cuda => CUDAExecutionProvider::default().build(),
+            Self::CoreMl => CoreMLExecutionProvider::default().build(),
+        }
+    }
+}
 
-use crate::config::Settings;
+/// Load the embedding model on the configured execution providers.
+#[cfg(feature = "embeddings")]
+fn load_embedder(settings: &Settings) -> Result<TextEmbedding> {
+    let providers = settings
+        .embedding_providers
+        .iter()
+        .map(|provider| provider.dispatch())
+        .collect();
+    info!(providers = ?settings.embedding_providers, "loading embedding model");
+    Ok(TextEmbedding::try_new(
+        InitOptions::new(EmbeddingModel::BGESmallENV15).with_execution_providers(providers),
+    )?)
+}
+
+/// Embed `texts`, reusing vectors cached in `data/cache/embeddings.parquet` and loading the
+/// model only when some text has not been embedded before.
+#[cfg(feature = "embeddings")]
+pub fn embed_cached(settings: &Settings, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+    let mut cache = EmbeddingCache::open(
+        settings.join_data(embedding_cache::CACHE_FILE),
+        EMBEDDING_MODEL,
+    )?;
+    let embeddings = cache.embed_with(texts, |missing| {
+        let embedder = load_embedder(settings)?;
+        Ok(embedder.embed(missing, Some(settings.embedding_batch_size))?)
+    })?;
+    cache.persist()?;
+    Ok(embeddings)
+}
+
+/// A vocabulary clustered by `embed` and the parquet its clusters are written to.
+struct ClusterSpec {
+    kind: &'static str,
+    source: &'static str,
+    id_column: &'static str,
+    name_column: &'static str,
+    output: &'static str,
+    rep_column: &'static str,
+}
 
-/// Compute MiniLM embeddings for canonical event terms and cluster near-duplicates.
-pub async fn build_event_clusters(settings: &Settings) -> Result<()> {
-    let events_path = settings.join_data("clean/events.parquet");
-    if !events_path.exists() {
-        warn!("event parquet missing; run normalize first");
+const EVENT_CLUSTERS: ClusterSpec = ClusterSpec {
+    kind: "event",
+    source: "clean/events.parquet",
+    id_column: "event_id",
+    name_column: "term_canonical",
+    output: "clean/event_clusters.parquet",
+    rep_column: "rep_term",
+};
+
+const DRUG_CLUSTERS: ClusterSpec = ClusterSpec {
+    kind: "drug",
+    source: "clean/drugs.parquet",
+    id_column: "drug_id",
+    name_column: "name_canonical",
+    output: "clean/drug_clusters.parquet",
+    rep_column: "rep_name",
+};
+
+/// Compute embeddings for canonical event terms and cluster near-duplicates.
+pub async fn build_event_clusters(settings: &Settings, params: &ClusterParams) -> Result<()> {
+    build_clusters(settings, params, &EVENT_CLUSTERS)
+}
+
+/// Cluster canonical drug names, catching brand/generic pairs and misspellings that
+/// survived normalization, with the stricter [`ClusterParams::for_drugs`].
+pub async fn build_drug_clusters(settings: &Settings, params: &ClusterParams) -> Result<()> {
+    build_clusters(settings, &params.for_drugs(), &DRUG_CLUSTERS)
+}
+
+fn build_clusters(settings: &Settings, params: &ClusterParams, spec: &ClusterSpec) -> Result<()> {
+    let source_path = settings.join_data(spec.source);
+    if !source_path.exists() {
+        warn!(kind = spec.kind, path = %source_path.display(), "parquet missing; run normalize first");
         return Ok(());
     }
-    let df = ParquetReader::new(File::open(&events_path)?).finish()?;
-    let event_ids: Vec<String> = df
-        .column("event_id")?
+    let df = ParquetReader::new(File::open(&source_path)?).finish()?;
+    let ids: Vec<String> = df
+        .column(spec.id_column)?
         .str()?
         .into_no_null_iter()
         .map(|s| s.to_string())
         .collect();
-    let terms: Vec<String> = df
-        .column("term_canonical")?
+    let names: Vec<String> = df
+        .column(spec.name_column)?
         .str()?
         .into_no_null_iter()
         .map(|s| s.to_string())
         .collect();
-    if terms.is_empty() {
+    if names.is_empty() {
         return Ok(());
     }
 
     #[cfg(feature = "embeddings")]
     let clusters = {
-        let embedder = TextEmbedding::try_new(Default::default())?;
-        let documents: Vec<&str> = terms.iter().map(String::as_str).collect();
-        let embeddings = embedder.embed(documents, None)?;
-        cluster_embeddings(&embeddings, 0.85)
+        let embeddings = embed_cached(settings, &names)?;
+        clustering::cluster(&embeddings, params)?
     };
 
     #[cfg(not(feature = "embeddings"))]
-    let clusters = (0..terms.len()).collect::<Vec<_>>();
-    let mut reps = std::collections::HashMap::new();
-    for (idx, &cluster_id) in clusters.iter().enumerate() {
-        reps.entry(cluster_id).or_insert_with(|| terms[idx].clone());
-    }
-
-    let cluster_ids: Vec<i64> = clusters.iter().map(|c| *c as i64).collect();
-    let rep_terms: Vec<String> = clusters
+    let clusters = (0..names.len()).collect::<Vec<_>>();
+    let counts = report_counts(settings, spec.id_column)?;
+    let frequency: Vec<u64> = ids
         .iter()
-        .map(|c| reps.get(c).cloned().unwrap_or_else(|| "unknown".into()))
+        .map(|id| counts.get(id).copied().unwrap_or(0))
         .collect();
+    let reps = clustering::representatives(&clusters, &names, &frequency);
+
+    let cluster_ids: Vec<i64> = clusters.iter().map(|c| *c as i64).collect();
+    let rep_ids: Vec<String> = clusters.iter().map(|c| ids[reps[c]].clone()).collect();
+    let rep_names: Vec<String> = clusters.iter().map(|c| names[reps[c]].clone()).collect();
     let mut df = DataFrame::new(vec![
-        Series::new("event_id".into(), event_ids),
+        Series::new(spec.id_column.into(), ids),
         Series::new("cluster_id".into(), cluster_ids),
-        Series::new("rep_term".into(), rep_terms),
+        Series::new("rep_id".into(), rep_ids),
+        Series::new(spec.rep_column.into(), rep_names),
     ])?;
-    let out_path = settings.join_data("clean/event_clusters.parquet");
+    let out_path = settings.join_data(spec.output);
     if let Some(parent) = out_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
     let file = std::fs::File::create(&out_path)?;
     ParquetWriter::new(file).finish(&mut df)?;
     let unique_clusters = reps.len();
-    info!(path = %out_path.display(), clusters = unique_clusters, "wrote event clusters");
+    info!(
+        kind = spec.kind,
+        path = %out_path.display(),
+        clusters = unique_clusters,
+        algorithm = ?params.algorithm,
+        "wrote clusters"
+    );
     Ok(())
 }
 
-fn cluster_embeddings(embeddings: &[Vec<f32>], threshold: f32) -> Vec<usize> {
-    let mut clusters: Vec<Vec<f32>> = Vec::new();
-    let mut assignments = Vec::new();
-    for vector in embeddings {
-        if let Some((idx, _)) = clusters
-            .iter()
-            .enumerate()
-            .find(|(_, centroid)| cosine(vector, centroid) >= threshold)
-        {
-            assignments.push(idx);
-        } else {
-            clusters.push(vector.clone());
-            assignments.push(clusters.len() - 1);
-        }
+/// Exposed cases per drug or event id in `faers_norm.parquet`, empty before `normalize`.
+fn report_counts(settings: &Settings, id_column: &str) -> Result<HashMap<String, u64>> {
+    let path = settings.join_data("clean/faers_norm.parquet");
+    if !path.exists() {
+        return Ok(HashMap::new());
     }
-    assignments
+    let df = ParquetReader::new(File::open(&path)?).finish()?;
+    let mut counts = HashMap::new();
+    for (id, a) in df
+        .column(id_column)?
+        .str()?
+        .into_no_null_iter()
+        .zip(df.column("a")?.i64()?.into_no_null_iter())
+    {
+        *counts.entry(id.to_string()).or_insert(0) += a.max(0) as u64;
+    }
+    Ok(counts)
 }
 
 /// Expose clustering for integration tests.
@@ -96,62 +209,14 @@ pub fn cluster_preview(embeddings: &[Vec<f32>], _threshold: f32) -> Vec<usize> {
     }
     #[cfg(feature = "embeddings")]
     {
-        cluster_embeddings(embeddings, _threshold)
+        let params = ClusterParams {
+            threshold: _threshold,
+            ..ClusterParams::default()
+        };
+        clustering::cluster(embeddings, &params).expect("greedy clustering has no size limit")
     }
     #[cfg(not(feature = "embeddings"))]
     {
         (0..embeddings.len()).collect()
     }
 }
-
-fn cosine(a: &[f32], b: &[f32]) -> f32 {
-    let dot = a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
-    let norm_a = a.iter().map(|v| v * v).sum::<f32>().sqrt();
-    let norm_b = b.iter().map(|v| v * v).sum::<f32>().sqrt();
-    if norm_a == 0.0 || norm_b == 0.0 {
-        0.0
-    } else {
-        dot / (norm_a * norm_b)
-    }
-}
-
-/// Summaries fallback when llama.cpp is not enabled.
-#[cfg(not(feature = "summaries"))]
-pub async fn summaries(
-    _settings: &Settings,
-    drug: &str,
-    event: &str,
-    topk: usize,
-) -> Result<String> {
-    Ok(format!(
-        "Summary unavailable (LLM disabled). {drug} and {event} flagged in top {topk} supporting sentences. Cite relevant PMIDs from relations file."
-    ))
-}
-
-/// Summaries using llama.cpp if feature enabled.
-#[cfg(feature = "summaries")]
-pub async fn summaries(
-    settings: &Settings,
-    drug: &str,
-    event: &str,
-    topk: usize,
-) -> Result<String> {
-    use llama_cpp_rs::{LLama, LLamaContextParams, LLamaModel, TokenId};
-
-    let model_path = settings.join_data("models/llama-tiny.gguf");
-    if !model_path.exists() {
-        return Ok(format!(
-            "Summary disabled – expected model {} not found.",
-            model_path.display()
-        ));
-    }
-    let model = LLamaModel::load_from_file(&model_path, Default::default())?;
-    let ctx_params = LLamaContextParams::default();
-    let ctx = LLama::new(model, ctx_params)?;
-    let prompt = format!(
-        "Summarise evidence for {drug} causing {event}. Include PMID references. Limit to {topk} sentences."
-    );
-    let tokens: Vec<TokenId> = ctx.model().tokenize(&prompt, true)?;
-    let response = ctx.evaluate(&tokens, None, 256, None)?;
-    Ok(response)
-}
@@ -0,0 +1,235 @@
+//! Cross-validated evaluation of the relation classifier.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use ndarray::{Array1, Array2, Axis};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::config::Settings;
+
+const METRICS_FILE: &str = "extract_metrics.json";
+
+/// Binary confusion counts with the relation (label 1) as the positive class.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfusionMatrix {
+    pub true_positive: usize,
+    pub false_positive: usize,
+    pub true_negative: usize,
+    pub false_negative: usize,
+}
+
+impl ConfusionMatrix {
+    pub fn from_predictions(truth: &[i32], predicted: &[i32]) -> Self {
+        let mut matrix = Self::default();
+        for (&actual, &guess) in truth.iter().zip(predicted) {
+            match (actual > 0, guess > 0) {
+                (true, true) => matrix.true_positive += 1,
+                (false, true) => matrix.false_positive += 1,
+                (false, false) => matrix.true_negative += 1,
+                (true, false) => matrix.false_negative += 1,
+            }
+        }
+        matrix
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        self.true_positive += other.true_positive;
+        self.false_positive += other.false_positive;
+        self.true_negative += other.true_negative;
+        self.false_negative += other.false_negative;
+    }
+
+    pub fn total(&self) -> usize {
+        self.true_positive + self.false_positive + self.true_negative + self.false_negative
+    }
+
+    pub fn precision(&self) -> f64 {
+        ratio(self.true_positive, self.true_positive + self.false_positive)
+    }
+
+    pub fn recall(&self) -> f64 {
+        ratio(self.true_positive, self.true_positive + self.false_negative)
+    }
+
+    pub fn f1(&self) -> f64 {
+        let (precision, recall) = (self.precision(), self.recall());
+        if precision + recall == 0.0 {
+            0.0
+        } else {
+            2.0 * precision * recall / (precision + recall)
+        }
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        ratio(self.true_positive + self.true_negative, self.total())
+    }
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+/// Scores of one held-out fold.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FoldMetrics {
+    pub fold: usize,
+    pub train_examples: usize,
+    pub test_examples: usize,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    pub confusion: ConfusionMatrix,
+}
+
+/// Cross-validation report written to `outputs/extract_metrics.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractMetrics {
+    pub generated_at: DateTime<Utc>,
+    pub mode: String,
+    pub classifier: String,
+    /// Where the reference labels came from (`weak` heuristics or `gold` annotations).
+    pub label_source: String,
+    pub folds: usize,
+    pub examples: usize,
+    pub positives: usize,
+    /// Micro-averaged over every held-out prediction.
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    pub accuracy: f64,
+    pub confusion: ConfusionMatrix,
+    pub per_fold: Vec<FoldMetrics>,
+}
+
+impl ExtractMetrics {
+    pub fn from_folds(
+        mode: &str,
+        classifier: &str,
+        label_source: &str,
+        labels: &[i32],
+        per_fold: Vec<FoldMetrics>,
+    ) -> Self {
+        let mut confusion = ConfusionMatrix::default();
+        for fold in &per_fold {
+            confusion.merge(&fold.confusion);
+        }
+        Self {
+            generated_at: Utc::now(),
+            mode: mode.to_string(),
+            classifier: classifier.to_string(),
+            label_source: label_source.to_string(),
+            folds: per_fold.len(),
+            examples: labels.len(),
+            positives: labels.iter().filter(|&&label| label > 0).count(),
+            precision: confusion.precision(),
+            recall: confusion.recall(),
+            f1: confusion.f1(),
+            accuracy: confusion.accuracy(),
+            confusion,
+            per_fold,
+        }
+    }
+
+    pub fn persist(&self, settings: &Settings) -> Result<()> {
+        let path = settings.join_output(METRICS_FILE);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("write {path:?}"))?;
+        info!(
+            folds = self.folds,
+            precision = self.precision,
+            recall = self.recall,
+            f1 = self.f1,
+            path = %path.display(),
+            "wrote extraction metrics"
+        );
+        Ok(())
+    }
+}
+
+/// Assign examples to `k` folds by PubMed id, so sentences of one abstract never straddle
+/// the train/test split. Abstracts are dealt round-robin in first-seen order.
+pub fn fold_assignments(pmids: &[&str], k: usize) -> Vec<usize> {
+    let mut groups: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut next = 0;
+    for pmid in pmids {
+        groups.entry(pmid).or_insert_with(|| {
+            let group = next;
+            next += 1;
+            group
+        });
+    }
+    pmids.iter().map(|pmid| groups[pmid] % k.max(1)).collect()
+}
+
+/// Held-out results of [`cross_validate`].
+#[derive(Debug, Clone, Default)]
+pub struct CrossValidation {
+    pub per_fold: Vec<FoldMetrics>,
+    /// Out-of-fold decision score per example, `None` for examples in skipped folds.
+    pub scores: Vec<Option<f64>>,
+}
+
+/// Fit on all folds but one and score the held-out fold, for each fold in turn.
+///
+/// `fit_score` returns decision scores for the test rows; a positive score predicts a
+/// relation. Folds whose training split holds a single class are skipped, since a binary
+/// classifier cannot be fitted on them.
+pub fn cross_validate<F>(
+    x: &Array2<f64>,
+    labels: &[i32],
+    folds: &[usize],
+    k: usize,
+    mut fit_score: F,
+) -> Result<CrossValidation>
+where
+    F: FnMut(Array2<f64>, Array1<i32>, Array2<f64>) -> Result<Vec<f64>>,
+{
+    let mut result = CrossValidation {
+        per_fold: Vec::new(),
+        scores: vec![None; labels.len()],
+    };
+    for fold in 0..k {
+        let (test, train): (Vec<usize>, Vec<usize>) =
+            (0..labels.len()).partition(|&idx| folds[idx] == fold);
+        if test.is_empty() {
+            continue;
+        }
+        let train_labels: Array1<i32> = train.iter().map(|&idx| labels[idx]).collect();
+        let positives = train_labels.iter().filter(|&&label| label > 0).count();
+        if positives == 0 || positives == train_labels.len() {
+            warn!(fold, "training split has a single class; skipping fold");
+            continue;
+        }
+        let scores = fit_score(
+            x.select(Axis(0), &train),
+            train_labels,
+            x.select(Axis(0), &test),
+        )?;
+        let predicted: Vec<i32> = scores.iter().map(|&score| i32::from(score > 0.0)).collect();
+        for (&idx, &score) in test.iter().zip(&scores) {
+            result.scores[idx] = Some(score);
+        }
+        let truth: Vec<i32> = test.iter().map(|&idx| labels[idx]).collect();
+        let confusion = ConfusionMatrix::from_predictions(&truth, &predicted);
+        result.per_fold.push(FoldMetrics {
+            fold,
+            train_examples: train.len(),
+            test_examples: test.len(),
+            precision: confusion.precision(),
+            recall: confusion.recall(),
+            f1: confusion.f1(),
+            confusion,
+        });
+    }
+    Ok(result)
+}
@@ -1,9 +1,15 @@
 //! Lightweight sentence feature engineering for relation extraction.
 
+use std::collections::{HashMap, HashSet};
+
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use tracing::debug;
 
+use crate::data::pubmed::{AbstractSection, MeshHeading, PublicationType};
+
 /// Representation of a sentence mentioning a drug and event.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SentenceContext {
@@ -12,6 +18,21 @@ pub struct SentenceContext {
     pub drug: String,
     pub event: String,
     pub text: String,
+    /// Sentences in the source abstract (and full text), for the relative position feature.
+    #[serde(default)]
+    pub sentence_count: usize,
+    /// MeSH headings of the source citation.
+    #[serde(default)]
+    pub mesh_headings: Vec<MeshHeading>,
+    /// Structured-abstract section holding the sentence.
+    #[serde(default)]
+    pub section: AbstractSection,
+    /// Strongest study design among the citation's publication types.
+    #[serde(default)]
+    pub study_type: Option<PublicationType>,
+    /// Detected ISO 639-3 language of the abstract; `None` when undetermined.
+    #[serde(default)]
+    pub language: Option<String>,
 }
 
 /// Numerical features used by the logistic relation classifier.
@@ -24,39 +45,423 @@ pub struct FeatureVector {
     pub token_distance: f32,
     pub has_cue_word: f32,
     pub negation_flag: f32,
+    /// 1.0 when a hedging cue ("may", "could not be excluded") scopes over the event.
+    #[serde(default)]
+    pub speculation_flag: f32,
     pub co_mention_count: f32,
-    pub tfidf_like: f32,
+    /// Mean TF-IDF of the words between the mentions, with document frequencies taken over
+    /// the hydrated sentence corpus.
+    pub tfidf: f32,
+    pub mesh_qualifier: f32,
+    /// Tokens between the drug and event mentions (sentence length when either is missing).
+    #[serde(default)]
+    pub between_tokens: f32,
+    /// Hashed unigram and bigram frequencies of the tokens between the two mentions.
+    #[serde(default)]
+    pub between_ngrams: Vec<f32>,
+    /// 1.0 when a causal verb ("caused", "induced", "led to") sits between the mentions.
+    #[serde(default)]
+    pub causal_verb: f32,
+    /// 1.0 for "<drug>-induced <event>" or "<event> after/following/due to <drug>" patterns.
+    #[serde(default)]
+    pub preposition_pattern: f32,
+    /// Relative position of the sentence in its abstract, 0.0 (first) to 1.0 (last).
+    #[serde(default)]
+    pub sentence_position: f32,
+    /// 1.0 when the drug is mentioned before the event.
+    #[serde(default)]
+    pub drug_first: f32,
+    /// 1.0 for sentences in the RESULTS section of a structured abstract.
+    #[serde(default)]
+    pub results_section: f32,
+    /// 1.0 for sentences in the CONCLUSIONS section of a structured abstract.
+    #[serde(default)]
+    pub conclusions_section: f32,
 }
 
-/// Split abstract text into coarse sentences.
+impl FeatureVector {
+    /// Classifier inputs in a fixed column order.
+    pub fn to_row(&self) -> Vec<f64> {
+        let mut row = vec![
+            self.token_distance as f64,
+            self.has_cue_word as f64,
+            self.negation_flag as f64,
+            self.speculation_flag as f64,
+            self.co_mention_count as f64,
+            self.tfidf as f64,
+            self.mesh_qualifier as f64,
+            self.between_tokens as f64,
+            self.causal_verb as f64,
+            self.preposition_pattern as f64,
+            self.sentence_position as f64,
+            self.drug_first as f64,
+            self.results_section as f64,
+            self.conclusions_section as f64,
+        ];
+        row.extend(self.between_ngrams.iter().map(|&v| v as f64));
+        row.resize(FEATURE_WIDTH, 0.0);
+        row
+    }
+}
+
+/// Hash buckets for between-mention n-grams.
+const NGRAM_BUCKETS: usize = 16;
+
+/// Number of columns produced by [`FeatureVector::to_row`].
+pub const FEATURE_WIDTH: usize = 14 + NGRAM_BUCKETS;
+
+/// Column of [`FeatureVector::conclusions_section`] in [`FeatureVector::to_row`].
+pub const CONCLUSIONS_COLUMN: usize = 13;
+
+/// Period-terminated abbreviations that do not end a sentence (lower-cased, without the period).
+const ABBREVIATIONS: &[&str] = &[
+    "vs", "fig", "figs", "al", "approx", "ca", "cf", "no", "nos", "vol", "ref", "refs", "tab",
+    "eq", "resp", "incl", "max", "min", "mo", "yr", "yrs", "wk", "wks", "pt", "pts", "sp", "spp",
+    "subsp", "var", "dr", "prof", "inc", "ltd",
+];
+
+/// Split abstract text into sentences.
+///
+/// A `.`, `!` or `?` (optionally followed by closing brackets or quotes) ends a sentence only
+/// when whitespace and an upper-case letter, digit or opening bracket follow. Periods after
+/// known abbreviations (`vs.`, `Fig. 2`) or dotted letters (`e.g.`, `i.v.`) never split, and
+/// decimals (`0.5 mg`) and species names (`E. coli`) stay intact because no whitespace or an
+/// upper-case letter follows their period. A period after a number (`P = 0.03.`) does split.
 pub fn split_sentences(text: &str) -> Vec<String> {
-    static PATTERN: once_cell::sync::Lazy<Regex> =
-        once_cell::sync::Lazy::new(|| Regex::new(r"(?m)(?<=[.!?])\s+").expect("valid regex"));
-    PATTERN
-        .split(text)
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty())
-        .collect()
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    for (idx, &(pos, c)) in chars.iter().enumerate() {
+        if !matches!(c, '.' | '!' | '?') {
+            continue;
+        }
+        let mut end = idx + 1;
+        while chars
+            .get(end)
+            .is_some_and(|&(_, c)| matches!(c, ')' | ']' | '"' | '\''))
+        {
+            end += 1;
+        }
+        let mut next = end;
+        while chars.get(next).is_some_and(|&(_, c)| c.is_whitespace()) {
+            next += 1;
+        }
+        let Some(&(next_pos, next_char)) = chars.get(next) else {
+            continue;
+        };
+        if next == end || !opens_sentence(next_char) {
+            continue;
+        }
+        if c == '.' && ends_with_abbreviation(&text[start..pos]) {
+            continue;
+        }
+        push_sentence(&mut sentences, &text[start..chars[end].0]);
+        start = next_pos;
+    }
+    push_sentence(&mut sentences, &text[start..]);
+    sentences
 }
 
-/// Convert sentences into feature vectors.
+fn opens_sentence(c: char) -> bool {
+    c.is_uppercase() || c.is_ascii_digit() || matches!(c, '(' | '[' | '"')
+}
+
+fn ends_with_abbreviation(before: &str) -> bool {
+    let token = before
+        .rsplit(char::is_whitespace)
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches(['(', '[', '"']);
+    // Numbers (`0.03`, `45.2)`) end sentences; only letters can precede an abbreviation's period.
+    if !token.chars().last().is_some_and(char::is_alphabetic) {
+        return false;
+    }
+    let dotted = token.contains('.')
+        && token
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(char::is_alphabetic));
+    dotted || ABBREVIATIONS.contains(&token.to_lowercase().as_str())
+}
+
+fn push_sentence(sentences: &mut Vec<String>, sentence: &str) {
+    let sentence = sentence.trim();
+    if !sentence.is_empty() {
+        sentences.push(sentence.to_string());
+    }
+}
+
+/// Sentence document frequencies for TF-IDF weighting, fitted on the whole candidate corpus.
+#[derive(Debug, Clone, Default)]
+pub struct CorpusStats {
+    documents: usize,
+    document_frequency: HashMap<String, usize>,
+}
+
+impl CorpusStats {
+    /// Count each distinct `(pmid, sent_idx)` sentence once, however many pairs it yields.
+    pub fn fit(sentences: &[SentenceContext]) -> Self {
+        let mut seen = HashSet::new();
+        let distinct: Vec<&SentenceContext> = sentences
+            .iter()
+            .filter(|ctx| seen.insert((ctx.pmid.as_str(), ctx.sent_idx)))
+            .collect();
+        let vocabularies: Vec<HashSet<String>> = distinct
+            .par_iter()
+            .map(|ctx| words(&ctx.text).collect())
+            .collect();
+        let mut stats = Self {
+            documents: distinct.len(),
+            ..Self::default()
+        };
+        for word in vocabularies.into_iter().flatten() {
+            *stats.document_frequency.entry(word).or_insert(0) += 1;
+        }
+        debug!(
+            documents = stats.documents,
+            vocabulary = stats.document_frequency.len(),
+            "fitted corpus statistics"
+        );
+        stats
+    }
+
+    /// Smoothed inverse document frequency, `ln((1 + N) / (1 + df)) + 1`.
+    pub fn idf(&self, word: &str) -> f32 {
+        let df = self.document_frequency.get(word).copied().unwrap_or(0);
+        ((1.0 + self.documents as f32) / (1.0 + df as f32)).ln() + 1.0
+    }
+}
+
+/// Convert sentences into feature vectors, fitting corpus statistics on the same sentences.
 pub fn featurise(sentences: &[SentenceContext]) -> Vec<FeatureVector> {
+    featurise_with(sentences, &CorpusStats::fit(sentences))
+}
+
+/// Convert sentences into feature vectors against previously fitted corpus statistics.
+/// Sentences are featurised in parallel; the output keeps their order.
+pub fn featurise_with(sentences: &[SentenceContext], corpus: &CorpusStats) -> Vec<FeatureVector> {
     sentences
-        .iter()
-        .map(|ctx| FeatureVector {
-            pmid: ctx.pmid.clone(),
-            sent_idx: ctx.sent_idx,
-            drug: ctx.drug.clone(),
-            event: ctx.event.clone(),
-            token_distance: token_distance(ctx),
-            has_cue_word: cue_word(ctx),
-            negation_flag: negation(ctx),
-            co_mention_count: co_mentions(ctx),
-            tfidf_like: tfidf_like(ctx),
+        .par_iter()
+        .map(|ctx| {
+            let context = context_features(ctx, corpus);
+            FeatureVector {
+                pmid: ctx.pmid.clone(),
+                sent_idx: ctx.sent_idx,
+                drug: ctx.drug.clone(),
+                event: ctx.event.clone(),
+                token_distance: token_distance(ctx),
+                has_cue_word: cue_word(ctx),
+                negation_flag: negation(ctx),
+                speculation_flag: speculation(ctx),
+                co_mention_count: co_mentions(ctx),
+                tfidf: context.tfidf,
+                mesh_qualifier: mesh_qualifier(ctx),
+                between_tokens: context.between_tokens,
+                between_ngrams: context.between_ngrams,
+                causal_verb: context.causal_verb,
+                preposition_pattern: context.preposition_pattern,
+                sentence_position: context.sentence_position,
+                drug_first: context.drug_first,
+                results_section: flag(ctx.section == AbstractSection::Results),
+                conclusions_section: flag(ctx.section == AbstractSection::Conclusions),
+            }
         })
         .collect()
 }
 
+/// Between-mention, ordering and position features.
+struct ContextFeatures {
+    tfidf: f32,
+    between_tokens: f32,
+    between_ngrams: Vec<f32>,
+    causal_verb: f32,
+    preposition_pattern: f32,
+    sentence_position: f32,
+    drug_first: f32,
+}
+
+fn context_features(ctx: &SentenceContext, corpus: &CorpusStats) -> ContextFeatures {
+    let tokens = scope_tokens(&ctx.text);
+    let drug = entity_span(&tokens, &ctx.drug);
+    let event = entity_span(&tokens, &ctx.event);
+    let between: &[String] = match (drug, event) {
+        (Some(d), Some(e)) if d.1 <= e.0 => &tokens[d.1..e.0],
+        (Some(d), Some(e)) if e.1 <= d.0 => &tokens[e.1..d.0],
+        _ => &[],
+    };
+    let sentence_position = if ctx.sentence_count > 1 {
+        ctx.sent_idx as f32 / (ctx.sentence_count - 1) as f32
+    } else {
+        0.0
+    };
+    // Fall back to the whole sentence when the mentions are adjacent or not both found.
+    let context = if between.is_empty() {
+        &tokens[..]
+    } else {
+        between
+    };
+    ContextFeatures {
+        tfidf: tfidf(&tokens, context, corpus),
+        between_tokens: match (drug, event) {
+            (Some(_), Some(_)) => between.len() as f32,
+            _ => tokens.len() as f32,
+        },
+        between_ngrams: hashed_ngrams(between, corpus),
+        causal_verb: flag(
+            between
+                .iter()
+                .any(|token| CAUSAL_VERBS.iter().any(|verb| token.starts_with(verb))),
+        ),
+        preposition_pattern: flag(causal_pattern(&tokens, &ctx.drug, drug, event, between)),
+        sentence_position,
+        drug_first: flag(matches!((drug, event), (Some(d), Some(e)) if d.0 < e.0)),
+    }
+}
+
+/// Verb stems that state a causal link between the mentions.
+const CAUSAL_VERBS: &[&str] = &[
+    "caus",
+    "induc",
+    "develop",
+    "led",
+    "lead",
+    "result",
+    "precipitat",
+    "trigger",
+    "provok",
+    "attribut",
+    "associat",
+    "elicit",
+    "produc",
+];
+
+/// Prepositions that introduce the suspected cause after an event mention.
+const CAUSAL_PREPOSITIONS: &[&str] = &[
+    "after",
+    "following",
+    "during",
+    "from",
+    "on",
+    "with",
+    "due to",
+    "secondary to",
+    "related to",
+    "attributed to",
+];
+
+/// Adjectival suffixes on a drug token (`imatinib-induced`).
+const DRUG_SUFFIXES: &[&str] = &[
+    "-induced",
+    "-associated",
+    "-related",
+    "-mediated",
+    "-caused",
+];
+
+fn causal_pattern(
+    tokens: &[String],
+    drug_name: &str,
+    drug: Option<(usize, usize)>,
+    event: Option<(usize, usize)>,
+    between: &[String],
+) -> bool {
+    let drug_name = drug_name.to_lowercase();
+    if tokens.iter().any(|token| {
+        DRUG_SUFFIXES
+            .iter()
+            .any(|suffix| *token == format!("{drug_name}{suffix}"))
+    }) {
+        return true;
+    }
+    let event_first = matches!((drug, event), (Some(d), Some(e)) if e.0 < d.0);
+    event_first
+        && CAUSAL_PREPOSITIONS
+            .iter()
+            .any(|prep| !mention_positions(between, prep).is_empty())
+}
+
+/// First token range of an entity, also matching hyphenated forms such as `imatinib-induced`.
+fn entity_span(tokens: &[String], name: &str) -> Option<(usize, usize)> {
+    if let Some(&span) = mention_positions(tokens, name).first() {
+        return Some(span);
+    }
+    let words = scope_tokens(name);
+    let [word] = words.as_slice() else {
+        return None;
+    };
+    let prefix = format!("{word}-");
+    tokens
+        .iter()
+        .position(|token| token.starts_with(&prefix))
+        .map(|idx| (idx, idx + 1))
+}
+
+/// Unigram and bigram IDF weights hashed into `NGRAM_BUCKETS`, normalised to sum to 1.
+///
+/// A bigram is weighted by the mean IDF of its two words.
+fn hashed_ngrams(tokens: &[String], corpus: &CorpusStats) -> Vec<f32> {
+    let mut buckets = vec![0.0f32; NGRAM_BUCKETS];
+    let unigrams = tokens
+        .iter()
+        .map(|token| (token.clone(), corpus.idf(token)));
+    let bigrams = tokens.windows(2).map(|pair| {
+        (
+            format!("{} {}", pair[0], pair[1]),
+            (corpus.idf(&pair[0]) + corpus.idf(&pair[1])) / 2.0,
+        )
+    });
+    let mut total = 0.0;
+    for (gram, weight) in unigrams.chain(bigrams) {
+        buckets[fnv1a(&gram) as usize % NGRAM_BUCKETS] += weight;
+        total += weight;
+    }
+    if total > 0.0 {
+        buckets.iter_mut().for_each(|value| *value /= total);
+    }
+    buckets
+}
+
+/// Mean TF-IDF of the distinct words in `context`, with term frequency taken over `sentence`.
+fn tfidf(sentence: &[String], context: &[String], corpus: &CorpusStats) -> f32 {
+    let sentence_words: Vec<&String> = sentence.iter().filter(|t| is_word(t)).collect();
+    let distinct: HashSet<&String> = context.iter().filter(|t| is_word(t)).collect();
+    if sentence_words.is_empty() || distinct.is_empty() {
+        return 0.0;
+    }
+    let total: f32 = distinct
+        .iter()
+        .map(|word| {
+            let tf = sentence_words.iter().filter(|t| *t == word).count() as f32
+                / sentence_words.len() as f32;
+            tf * corpus.idf(word)
+        })
+        .sum();
+    total / distinct.len() as f32
+}
+
+/// Lower-cased words of `text`, without clause punctuation.
+fn words(text: &str) -> impl Iterator<Item = String> {
+    scope_tokens(text).into_iter().filter(|t| is_word(t))
+}
+
+fn is_word(token: &str) -> bool {
+    token.chars().any(char::is_alphanumeric)
+}
+
+/// FNV-1a, stable across runs and platforms unlike `DefaultHasher`.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn flag(value: bool) -> f32 {
+    if value {
+        1.0
+    } else {
+        0.0
+    }
+}
+
 fn token_distance(ctx: &SentenceContext) -> f32 {
     let tokens: Vec<&str> = ctx.text.split_whitespace().collect();
     let drug_idx = tokens
@@ -81,25 +486,232 @@ fn cue_word(ctx: &SentenceContext) -> f32 {
     }
 }
 
+/// NegEx-style cue lists: pre-cues negate the words after them, post-cues the words before
+/// them, and pseudo-cues look like negations but are not (`no increase`, `not only`).
+struct ScopeCues {
+    pre: &'static [&'static str],
+    post: &'static [&'static str],
+    pseudo: &'static [&'static str],
+}
+
+const NEGATION_CUES: ScopeCues = ScopeCues {
+    pre: &[
+        "no",
+        "not",
+        "without",
+        "neither",
+        "nor",
+        "never",
+        "absence of",
+        "no evidence of",
+        "no signs of",
+        "negative for",
+        "free of",
+        "lack of",
+        "ruled out",
+        "denied",
+    ],
+    post: &[
+        "ruled out",
+        "was excluded",
+        "were excluded",
+        "unlikely",
+        "absent",
+        "not observed",
+        "not seen",
+        "not detected",
+        "not reported",
+        "not found",
+        "did not occur",
+    ],
+    pseudo: &[
+        "no increase",
+        "no change",
+        "no significant change",
+        "no further",
+        "not only",
+        "not necessarily",
+        "without difficulty",
+        "gram negative",
+        "not be ruled out",
+        "not be excluded",
+    ],
+};
+
+const HEDGING_CUES: ScopeCues = ScopeCues {
+    pre: &[
+        "may",
+        "might",
+        "could",
+        "possibly",
+        "possible",
+        "probably",
+        "probable",
+        "likely",
+        "suggest",
+        "suggests",
+        "suggested",
+        "suggesting",
+        "suspected",
+        "potential",
+        "potentially",
+        "putative",
+        "appears to",
+        "seems to",
+        "whether",
+    ],
+    post: &[
+        "could not be excluded",
+        "cannot be excluded",
+        "could not be ruled out",
+        "cannot be ruled out",
+        "is possible",
+        "was suspected",
+        "remains unclear",
+        "is unclear",
+    ],
+    pseudo: &["may be excluded", "likely not"],
+};
+
+/// Tokens that close a cue's scope.
+const SCOPE_TERMINATORS: &[&str] = &[
+    ";", ":", ".", "but", "however", "although", "though", "whereas", "yet", "except", "which",
+    "while",
+];
+
+/// Maximum number of tokens a cue reaches.
+const SCOPE_WINDOW: usize = 6;
+
+/// 1.0 when a negation cue's scope covers the event mention.
+///
+/// As in NegEx, only the finding is tested: "no dose adjustment of imatinib was needed;
+/// hepatotoxicity was observed" is not negated.
 fn negation(ctx: &SentenceContext) -> f32 {
-    const NEGATIONS: &[&str] = &["no", "not", "without", "neither"];
-    let text = ctx.text.to_lowercase();
-    if NEGATIONS.iter().any(|cue| text.contains(cue)) {
+    if in_cue_scope(ctx, &NEGATION_CUES) {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// 1.0 when a hedging cue's scope covers the event mention.
+fn speculation(ctx: &SentenceContext) -> f32 {
+    if in_cue_scope(ctx, &HEDGING_CUES) {
         1.0
     } else {
         0.0
     }
 }
 
+fn in_cue_scope(ctx: &SentenceContext, cues: &ScopeCues) -> bool {
+    let tokens = scope_tokens(&ctx.text);
+    let mentions = mention_positions(&tokens, &ctx.event);
+    if mentions.is_empty() {
+        return false;
+    }
+    let pseudo: Vec<(usize, usize)> = cues
+        .pseudo
+        .iter()
+        .flat_map(|cue| mention_positions(&tokens, cue))
+        .collect();
+    let is_pseudo = |start: usize, end: usize| pseudo.iter().any(|&(s, e)| start < e && s < end);
+    let covered = |from: usize, to: usize| {
+        mentions
+            .iter()
+            .any(|&(start, end)| start >= from && end <= to)
+    };
+    for cue in cues.pre {
+        for (start, end) in mention_positions(&tokens, cue) {
+            if is_pseudo(start, end) {
+                continue;
+            }
+            let mut scope_end = end;
+            while scope_end < tokens.len()
+                && scope_end < end + SCOPE_WINDOW
+                && !SCOPE_TERMINATORS.contains(&tokens[scope_end].as_str())
+            {
+                scope_end += 1;
+            }
+            if covered(end, scope_end) {
+                return true;
+            }
+        }
+    }
+    for cue in cues.post {
+        for (start, end) in mention_positions(&tokens, cue) {
+            if is_pseudo(start, end) {
+                continue;
+            }
+            let mut scope_start = start;
+            while scope_start > 0
+                && scope_start + SCOPE_WINDOW > start
+                && !SCOPE_TERMINATORS.contains(&tokens[scope_start - 1].as_str())
+            {
+                scope_start -= 1;
+            }
+            if covered(scope_start, start) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Lower-cased word and clause-punctuation tokens, with `n't` expanded to `not`.
+fn scope_tokens(text: &str) -> Vec<String> {
+    static TOKEN: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"[a-z0-9]+(?:['-][a-z0-9]+)*|[;:.]").expect("valid regex"));
+    let text = text.to_lowercase().replace("n't", " not");
+    TOKEN
+        .find_iter(&text)
+        .map(|m| m.as_str().to_string())
+        .collect()
+}
+
+/// Token ranges `[start, end)` where the words of `phrase` occur in order.
+fn mention_positions(tokens: &[String], phrase: &str) -> Vec<(usize, usize)> {
+    let words = scope_tokens(phrase);
+    if words.is_empty() || words.len() > tokens.len() {
+        return Vec::new();
+    }
+    tokens
+        .windows(words.len())
+        .enumerate()
+        .filter(|(_, window)| *window == words.as_slice())
+        .map(|(start, _)| (start, start + words.len()))
+        .collect()
+}
+
 fn co_mentions(ctx: &SentenceContext) -> f32 {
     let lower = ctx.text.to_lowercase();
     let event = ctx.event.to_lowercase();
     lower.matches(&event).count() as f32
 }
 
-fn tfidf_like(ctx: &SentenceContext) -> f32 {
-    let token_count = ctx.text.split_whitespace().count() as f32 + 1e-6;
-    (ctx.event.len() as f32 / token_count).min(5.0)
+/// Citation-level MeSH evidence: 1.0 when the event heading is qualified as "chemically
+/// induced", 0.5 when only the drug heading carries "adverse effects" or "toxicity".
+fn mesh_qualifier(ctx: &SentenceContext) -> f32 {
+    let event = ctx.event.to_lowercase();
+    let drug = ctx.drug.to_lowercase();
+    let qualified = |heading: &MeshHeading, wanted: &[&str]| {
+        heading
+            .qualifiers
+            .iter()
+            .any(|q| wanted.iter().any(|w| q.eq_ignore_ascii_case(w)))
+    };
+    let mut score: f32 = 0.0;
+    for heading in &ctx.mesh_headings {
+        let descriptor = heading.descriptor.to_lowercase();
+        if (descriptor.contains(&event) || event.contains(&descriptor))
+            && qualified(heading, &["chemically induced"])
+        {
+            return 1.0;
+        }
+        if descriptor.contains(&drug) && qualified(heading, &["adverse effects", "toxicity"]) {
+            score = 0.5;
+        }
+    }
+    score
 }
 
 /// Convenience helper for instrumentation.
@@ -0,0 +1,139 @@
+//! Gold relation annotations for supervised extraction, and candidate export for labelling.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{config::Settings, nlp::features::SentenceContext};
+
+const CANDIDATES_FILE: &str = "annotation_candidates.csv";
+
+/// Annotated candidate, keyed like a [`crate::nlp::features::FeatureVector`].
+pub type LabelKey = (String, usize, String, String);
+
+#[derive(Debug, Deserialize)]
+struct LabelRecord {
+    pmid: String,
+    sent_idx: usize,
+    drug: String,
+    event: String,
+    label: String,
+}
+
+/// Key of a candidate sentence: PubMed id, sentence index and lower-cased drug and event.
+pub fn label_key(pmid: &str, sent_idx: usize, drug: &str, event: &str) -> LabelKey {
+    (
+        pmid.trim().to_string(),
+        sent_idx,
+        drug.trim().to_lowercase(),
+        event.trim().to_lowercase(),
+    )
+}
+
+/// Read a `pmid,sent_idx,drug,event,label` CSV of gold annotations.
+///
+/// Labels are `1`/`0`, `true`/`false` or `yes`/`no`; rows with a blank label (not yet
+/// annotated) are skipped, and extra columns are ignored. When a candidate is annotated
+/// twice, the last annotation wins.
+pub fn read_gold_labels(path: &Path) -> Result<HashMap<LabelKey, i32>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .with_context(|| format!("open gold labels {}", path.display()))?;
+    let mut labels = HashMap::new();
+    for record in reader.deserialize() {
+        let record: LabelRecord = record.with_context(|| format!("parse {}", path.display()))?;
+        let label = match record.label.to_ascii_lowercase().as_str() {
+            "" => continue,
+            "1" | "true" | "yes" => 1,
+            "0" | "false" | "no" => 0,
+            other => bail!(
+                "{}: label {other:?} for pmid {} is not 1/0, true/false or yes/no",
+                path.display(),
+                record.pmid
+            ),
+        };
+        labels.insert(
+            label_key(&record.pmid, record.sent_idx, &record.drug, &record.event),
+            label,
+        );
+    }
+    info!(labels = labels.len(), path = %path.display(), "loaded gold labels");
+    Ok(labels)
+}
+
+/// Row of the annotation CSV; `label` is left blank for the annotator.
+#[derive(Debug, Serialize)]
+struct CandidateRow<'a> {
+    pmid: &'a str,
+    sent_idx: usize,
+    drug: &'a str,
+    event: &'a str,
+    label: &'a str,
+    probability: f64,
+    text: &'a str,
+}
+
+/// Write the `n` candidates whose relation probability is nearest 0.5 to
+/// `outputs/annotation_candidates.csv`, skipping those already in `known`.
+///
+/// The file has the gold-label columns, so once annotated it can be passed to
+/// `extract --mode supervised --labels`.
+pub fn export_uncertain(
+    settings: &Settings,
+    sentences: &[SentenceContext],
+    probabilities: &[f64],
+    n: usize,
+    known: &HashMap<LabelKey, i32>,
+) -> Result<PathBuf> {
+    let mut candidates: Vec<(&SentenceContext, f64)> = sentences
+        .iter()
+        .zip(probabilities.iter().copied())
+        .filter(|(ctx, _)| {
+            !known.contains_key(&label_key(&ctx.pmid, ctx.sent_idx, &ctx.drug, &ctx.event))
+        })
+        .collect();
+    candidates.sort_by(|a, b| (a.1 - 0.5).abs().total_cmp(&(b.1 - 0.5).abs()));
+    candidates.truncate(n);
+
+    let path = settings.join_output(CANDIDATES_FILE);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut writer =
+        csv::Writer::from_path(&path).with_context(|| format!("create {}", path.display()))?;
+    if candidates.is_empty() {
+        writer.write_record([
+            "pmid",
+            "sent_idx",
+            "drug",
+            "event",
+            "label",
+            "probability",
+            "text",
+        ])?;
+    }
+    for (ctx, probability) in &candidates {
+        writer.serialize(CandidateRow {
+            pmid: &ctx.pmid,
+            sent_idx: ctx.sent_idx,
+            drug: &ctx.drug,
+            event: &ctx.event,
+            label: "",
+            probability: *probability,
+            text: &ctx.text,
+        })?;
+    }
+    writer.flush()?;
+    info!(
+        rows = candidates.len(),
+        path = %path.display(),
+        "wrote uncertain candidates for annotation"
+    );
+    Ok(path)
+}
@@ -0,0 +1,22 @@
+//! Abstract language detection, so non-English text stays out of the English-only features.
+
+use whatlang::Lang;
+
+/// Shortest text (in characters) whose detected language is trusted.
+const MIN_DETECTION_CHARS: usize = 40;
+
+/// ISO 639-3 code of the language of `text` (`"eng"`, `"deu"`), or `None` when the text is
+/// too short or the detection is unreliable.
+pub fn detect(text: &str) -> Option<&'static str> {
+    if text.chars().filter(|c| c.is_alphabetic()).count() < MIN_DETECTION_CHARS {
+        return None;
+    }
+    whatlang::detect(text)
+        .filter(|info| info.is_reliable())
+        .map(|info| info.lang().code())
+}
+
+/// False only for a detected language other than English; undetermined text is kept.
+pub fn is_english(language: Option<&str>) -> bool {
+    language.is_none_or(|code| code == Lang::Eng.code())
+}
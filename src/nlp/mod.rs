@@ -1,35 +1,68 @@
 //! Natural language processing orchestration layer.
 
+pub mod boosting;
+pub mod calibration;
+pub mod clustering;
+pub mod embedding_cache;
 pub mod embeddings;
+pub mod evaluation;
 pub mod features;
+pub mod labels;
+pub mod language;
 pub mod ner;
 pub mod relclf;
+pub mod summaries;
 
 use anyhow::Result;
-use tracing::info;
+use tracing::{info, warn};
 
-use crate::{cli::ExtractMode, config::Settings};
+use crate::config::Settings;
+
+pub use relclf::{Classifier, ExtractOptions};
 
 /// Run the end-to-end relation extraction pipeline.
-pub async fn extract_relations(settings: &Settings, mode: ExtractMode) -> Result<()> {
-    info!(?mode, "starting relation extraction");
+pub async fn extract_relations(settings: &Settings, options: &ExtractOptions) -> Result<()> {
+    info!(
+        mode = ?options.mode,
+        classifier = options.classifier.label(),
+        folds = options.folds,
+        "starting relation extraction"
+    );
     let ner = ner::load_model(settings).await?;
-    let sentences = relclf::hydrate_sentences(settings).await?;
-    let features = features::featurise(&sentences);
-    relclf::train_and_predict(settings, ner.as_ref(), features, mode).await
+    let sentences = relclf::hydrate_sentences(settings, options.keep_non_english).await?;
+    let corpus = features::CorpusStats::fit(&sentences);
+    let features = features::featurise_with(&sentences, &corpus);
+    let confidences =
+        relclf::train_and_predict(settings, ner.as_ref(), &sentences, features, options).await?;
+    if let Some(n) = options.select_uncertain {
+        if !options.mode.is_training() {
+            warn!("--select-uncertain needs a trained classifier; skipping candidate export");
+        } else if !confidences.is_empty() {
+            let known = match &options.labels {
+                Some(path) => labels::read_gold_labels(path)?,
+                None => Default::default(),
+            };
+            labels::export_uncertain(settings, &sentences, &confidences, n, &known)?;
+        }
+    }
+    Ok(())
 }
 
-/// Build embeddings for event deduplication.
-pub async fn build_embeddings(settings: &Settings) -> Result<()> {
-    embeddings::build_event_clusters(settings).await
+/// Build embeddings for event and drug deduplication.
+pub async fn build_embeddings(
+    settings: &Settings,
+    params: &clustering::ClusterParams,
+) -> Result<()> {
+    embeddings::build_event_clusters(settings, params).await?;
+    embeddings::build_drug_clusters(settings, params).await
 }
 
-/// Produce optional local summary text.
+/// Produce a summary with the configured language model backend.
 pub async fn summarize(
     settings: &Settings,
     drug: &str,
     event: &str,
     topk: usize,
-) -> Result<String> {
-    embeddings::summaries(settings, drug, event, topk).await
+) -> Result<summaries::Summary> {
+    summaries::summarize(settings, drug, event, topk).await
 }
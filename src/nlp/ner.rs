@@ -1,11 +1,13 @@
-//! Lightweight dictionary-based NER fallback. Swap with rust-bert when enabled.
+//! Lightweight dictionary-based NER over the normalized vocabularies. Swap with rust-bert when enabled.
 
-use std::sync::Arc;
+use std::{collections::HashSet, fs::File, path::PathBuf, sync::Arc};
 
-use anyhow::Result;
-use once_cell::sync::Lazy;
+use aho_corasick::AhoCorasick;
+use anyhow::{Context, Result};
+use polars::prelude::{ParquetReader, SerReader};
+use tracing::{info, warn};
 
-use crate::config::Settings;
+use crate::{config::Settings, data::dictionary};
 
 /// Extracted entity span with offsets relative to the source text.
 #[derive(Debug, Clone)]
@@ -22,73 +24,133 @@ pub trait Ner: Send + Sync {
     fn extract(&self, text: &str) -> Vec<Span>;
 }
 
-static DRUG_TERMS: Lazy<Vec<&'static str>> = Lazy::new(|| {
-    vec![
-        "imatinib",
-        "gleevec",
-        "dasatinib",
-        "sprycel",
-        "nilotinib",
-        "tasigna",
-        "nivolumab",
-        "opdivo",
-        "pembrolizumab",
-        "keytruda",
-        "ipilimumab",
-        "yervoy",
-    ]
-});
+/// Dictionary matcher over the drug and event vocabularies produced by `normalize`.
+///
+/// All terms share one Aho-Corasick automaton, so a sentence is scanned once however large
+/// the vocabularies are.
+pub struct DictionaryNer {
+    automaton: AhoCorasick,
+    /// Entity label of each automaton pattern, in pattern order.
+    labels: Vec<&'static str>,
+}
+
+impl DictionaryNer {
+    /// Build from term lists; terms are matched case-insensitively and deduplicated.
+    pub fn new(drugs: Vec<String>, events: Vec<String>) -> Result<Self> {
+        let drugs = dedup_terms(drugs);
+        let events = dedup_terms(events);
+        let labels = std::iter::repeat_n("DRUG", drugs.len())
+            .chain(std::iter::repeat_n("EVENT", events.len()))
+            .collect();
+        let automaton = AhoCorasick::builder()
+            .ascii_case_insensitive(true)
+            .build(drugs.iter().chain(&events))
+            .context("build NER automaton")?;
+        Ok(Self { automaton, labels })
+    }
 
-static EVENT_TERMS: Lazy<Vec<&'static str>> = Lazy::new(|| {
-    vec![
-        "hepatotoxicity",
-        "rash",
-        "diarrhoea",
-        "neutropenia",
-        "fatigue",
-        "nausea",
-        "fever",
-        "cardiotoxicity",
-        "anemia",
-        "thrombocytopenia",
-        "headache",
-    ]
-});
+    /// Canonical names from `clean/drugs.parquet` and `clean/events.parquet`, plus the raw names
+    /// in `DRUG_DICTIONARY` and the synonyms in `EVENT_DICTIONARY`.
+    pub fn load(settings: &Settings) -> Result<Self> {
+        let mut drugs = read_terms(
+            settings.join_data("clean/drugs.parquet"),
+            "drug_id",
+            "name_canonical",
+        )?;
+        drugs.extend(
+            dictionary::load_drug_dictionary(settings)?
+                .into_keys()
+                .map(|raw| raw.to_ascii_lowercase()),
+        );
 
-struct DictionaryNer;
+        let mut events = read_terms(
+            settings.join_data("clean/events.parquet"),
+            "event_id",
+            "term_canonical",
+        )?;
+        if settings.event_dictionary.is_some() {
+            let user_events = dictionary::load_event_dictionary(settings, &[])?;
+            events.extend(
+                user_events
+                    .entries()
+                    .map(|(surface, _)| surface.to_string()),
+            );
+        }
+        if drugs.is_empty() || events.is_empty() {
+            warn!("NER vocabulary is empty; run normalize before extract");
+        }
+        info!(
+            drugs = drugs.len(),
+            events = events.len(),
+            "loaded NER dictionaries"
+        );
+        Self::new(drugs, events)
+    }
+}
 
 impl Ner for DictionaryNer {
     fn extract(&self, text: &str) -> Vec<Span> {
-        let mut spans = Vec::new();
-        spans.extend(find_terms(text, &DRUG_TERMS, "DRUG"));
-        spans.extend(find_terms(text, &EVENT_TERMS, "EVENT"));
-        spans
+        find_terms(text, &self.automaton, &self.labels)
     }
 }
 
-fn find_terms(text: &str, terms: &[&str], label: &str) -> Vec<Span> {
-    let lower = text.to_lowercase();
-    let mut spans = Vec::new();
-    for term in terms {
-        let term_lower = term.to_lowercase();
-        let mut start_pos = 0;
-        while let Some(pos) = lower[start_pos..].find(&term_lower) {
-            let start = start_pos + pos;
-            let end = start + term_lower.len();
-            spans.push(Span {
-                start,
-                end,
-                label: label.to_string(),
-                text: text[start..end].to_string(),
-                score: 0.8,
-            });
-            start_pos = end;
-        }
+fn dedup_terms(terms: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    terms
+        .into_iter()
+        .map(|t| t.trim().to_ascii_lowercase())
+        .filter(|t| !t.is_empty() && seen.insert(t.clone()))
+        .collect()
+}
+
+/// Read the name column of a normalized parquet, skipping SMQ pseudo-events.
+///
+/// Empty when the file is missing.
+fn read_terms(path: PathBuf, id_column: &str, name_column: &str) -> Result<Vec<String>> {
+    if !path.exists() {
+        return Ok(Vec::new());
     }
-    spans
+    let df = ParquetReader::new(File::open(&path)?).finish()?;
+    let ids = df.column(id_column)?.str()?;
+    let names = df.column(name_column)?.str()?;
+    Ok(ids
+        .into_iter()
+        .zip(names)
+        .filter_map(|(id, name)| match (id, name) {
+            (Some(id), Some(name)) if !id.starts_with("SMQ") => Some(name.to_string()),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Whole-word matches of every pattern, dropping matches nested inside a longer match of the
+/// same label (`liver` within `liver injury`).
+fn find_terms(text: &str, automaton: &AhoCorasick, labels: &[&str]) -> Vec<Span> {
+    let bytes = text.as_bytes();
+    let is_word = |idx: usize| bytes.get(idx).is_some_and(|b| b.is_ascii_alphanumeric());
+    let matches: Vec<(usize, usize, &str)> = automaton
+        .find_overlapping_iter(text)
+        .filter(|m| (m.start() == 0 || !is_word(m.start() - 1)) && !is_word(m.end()))
+        .map(|m| (m.start(), m.end(), labels[m.pattern().as_usize()]))
+        .collect();
+    matches
+        .iter()
+        .filter(|&&(start, end, label)| {
+            !matches
+                .iter()
+                .any(|&(s, e, l)| l == label && s <= start && end <= e && (s, e) != (start, end))
+        })
+        .map(|&(start, end, label)| Span {
+            start,
+            end,
+            label: label.to_string(),
+            text: text[start..end].to_string(),
+            score: 0.8,
+        })
+        .collect()
 }
 
 /// Load a dictionary-backed NER implementation.
-pub async fn load_model(_settings: &Settings) -> Result<Arc<dyn Ner>> {
-    Ok(Arc::new(DictionaryNer) as Arc<dyn Ner>)
+pub async fn load_model(settings: &Settings) -> Result<Arc<dyn Ner>> {
+    Ok(Arc::new(DictionaryNer::load(settings)?) as Arc<dyn Ner>)
 }
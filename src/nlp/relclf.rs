@@ -1,39 +1,82 @@
 //! Weak supervision and relation classification routines.
 
-use std::{collections::HashMap, fs::File, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use indicatif::ProgressBar;
 use linfa::{
     dataset::DatasetBase,
     prelude::{Fit, Predict},
 };
 use linfa_logistic::LogisticRegression;
-use ndarray::{Array1, Array2};
+use linfa_svm::Svm;
+use ndarray::{Array1, Array2, Axis};
 use polars::prelude::{DataFrame, NamedFrom, ParquetReader, ParquetWriter, SerReader, Series};
+use rayon::prelude::*;
 use serde::Serialize;
 use tracing::{info, warn};
 
 use crate::{
     cli::ExtractMode,
     config::Settings,
-    data::pubmed::PubRecord,
+    data::{
+        dictionary::{self, EventDictionary},
+        pubmed::{AbstractSection, PubRecord, PublicationType},
+        rxnorm::RxNormResolver,
+        sider, smq,
+    },
     nlp::features::{self, FeatureVector, SentenceContext},
     nlp::ner::Ner,
+    nlp::{
+        boosting::{BoostingParams, GradientBoostedTrees},
+        calibration::PlattScaler,
+        evaluation, labels, language,
+    },
+    progress,
 };
 
-const EVENT_DICTIONARY: &[&str] = &[
-    "hepatotoxicity",
-    "rash",
-    "diarrhoea",
-    "neutropenia",
-    "fatigue",
-    "nausea",
-    "fever",
-    "cardiotoxicity",
-    "anemia",
-    "thrombocytopenia",
-    "headache",
-];
+/// Options for `extract`.
+#[derive(Debug, Clone)]
+pub struct ExtractOptions {
+    pub mode: ExtractMode,
+    /// Cross-validation folds for `outputs/extract_metrics.json`; fewer than 2 disables it.
+    pub folds: usize,
+    /// Gold annotations, required by `--mode supervised`.
+    pub labels: Option<PathBuf>,
+    pub classifier: Classifier,
+    /// Export this many least certain candidates for annotation.
+    pub select_uncertain: Option<usize>,
+    /// Hydrate abstracts detected as non-English rather than dropping them.
+    pub keep_non_english: bool,
+}
+
+/// Relation classifier backends.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Classifier {
+    /// Logistic regression.
+    #[default]
+    Logistic,
+    /// Support vector machine with a Gaussian kernel over standardised features.
+    Svm,
+    /// Gradient-boosted regression trees.
+    Gbt,
+}
+
+impl Classifier {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Logistic => "logistic",
+            Self::Svm => "svm",
+            Self::Gbt => "gbt",
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize)]
 struct RelationRow {
@@ -42,69 +85,206 @@ struct RelationRow {
     pmid: String,
     sent_idx: i64,
     confidence: f64,
+    speculative: bool,
+    rxcui: Option<String>,
+    umls_cui: Option<String>,
+    meddra_pt_code: Option<String>,
+    event_cluster_id: Option<i64>,
+    drug_cluster_id: Option<i64>,
+    section: &'static str,
+    study_type: Option<&'static str>,
+    language: Option<String>,
+    sentence: String,
+    drug_span: Option<(i64, i64)>,
+    event_span: Option<(i64, i64)>,
 }
 
+/// Records parsed and scanned together; bounds memory while keeping every core busy.
+const HYDRATE_BATCH: usize = 1024;
+
 /// Load PubMed JSONL cache and generate candidate sentences.
-pub async fn hydrate_sentences(settings: &Settings) -> Result<Vec<SentenceContext>> {
-    let mut contexts = Vec::new();
+///
+/// Files are hydrated in parallel and streamed line by line, with each batch of records
+/// split across threads. Output follows file name then record order, so runs are repeatable.
+/// Records whose title and abstract are detected as non-English are dropped unless
+/// `keep_non_english` is set.
+pub async fn hydrate_sentences(
+    settings: &Settings,
+    keep_non_english: bool,
+) -> Result<Vec<SentenceContext>> {
     let root = settings.join_data("raw/pubmed");
     if !root.exists() {
-        return Ok(contexts);
+        return Ok(Vec::new());
     }
+    let sider_terms = sider::load_vocabulary(settings)?.terms;
+    let events = dictionary::load_event_dictionary(settings, &sider_terms)?;
+    let mut paths = Vec::new();
     for entry in std::fs::read_dir(root)? {
-        let entry = entry?;
-        if entry.path().extension().and_then(|s| s.to_str()) != Some("jsonl") {
+        let path = entry?.path();
+        if path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    let filter = LanguageFilter {
+        keep_non_english,
+        dropped: AtomicUsize::new(0),
+    };
+    let bar = progress::counter("splitting abstracts into sentences", "abstracts");
+    let per_file = paths
+        .par_iter()
+        .map(|path| hydrate_file(path, &events, &filter, &bar))
+        .collect::<Result<Vec<_>>>()?;
+    bar.finish_and_clear();
+    let contexts: Vec<SentenceContext> = per_file.into_iter().flatten().collect();
+    let dropped = filter.dropped.into_inner();
+    if dropped > 0 {
+        info!(
+            abstracts = dropped,
+            "dropped non-English abstracts (pass --keep-non-english to keep them)"
+        );
+    }
+    info!(
+        count = contexts.len(),
+        files = paths.len(),
+        "built sentence contexts"
+    );
+    Ok(contexts)
+}
+
+/// Whether non-English records are hydrated, and how many were dropped.
+struct LanguageFilter {
+    keep_non_english: bool,
+    dropped: AtomicUsize,
+}
+
+/// Candidate sentences of one `raw/pubmed/<drug>.jsonl` cache.
+fn hydrate_file(
+    path: &Path,
+    events: &EventDictionary,
+    filter: &LanguageFilter,
+    bar: &ProgressBar,
+) -> Result<Vec<SentenceContext>> {
+    let drug = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("unknown")
+        .to_lowercase();
+    let reader = BufReader::new(File::open(path).with_context(|| format!("open {path:?}"))?);
+    let mut contexts = Vec::new();
+    let mut batch = Vec::with_capacity(HYDRATE_BATCH);
+    for line in reader.lines() {
+        let line = line.with_context(|| format!("read {path:?}"))?;
+        if line.trim().is_empty() {
             continue;
         }
-        let drug = entry
-            .path()
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("unknown")
-            .to_lowercase();
-        let file = std::fs::read_to_string(entry.path())?;
-        for line in file.lines() {
+        batch.push(line);
+        if batch.len() == HYDRATE_BATCH {
+            contexts.extend(hydrate_batch(&batch, &drug, events, filter)?);
+            bar.inc(batch.len() as u64);
+            batch.clear();
+        }
+    }
+    contexts.extend(hydrate_batch(&batch, &drug, events, filter)?);
+    bar.inc(batch.len() as u64);
+    Ok(contexts)
+}
+
+fn hydrate_batch(
+    lines: &[String],
+    drug: &str,
+    events: &EventDictionary,
+    filter: &LanguageFilter,
+) -> Result<Vec<SentenceContext>> {
+    let per_record = lines
+        .par_iter()
+        .map(|line| -> Result<Vec<SentenceContext>> {
             let record: PubRecord = serde_json::from_str(line)?;
-            for (sent_idx, sentence) in features::split_sentences(&record.abstract_text)
-                .into_iter()
-                .enumerate()
-            {
-                let sentence_lower = sentence.to_lowercase();
-                if !sentence_lower.contains(&drug) {
-                    continue;
-                }
-                if let Some(event) = EVENT_DICTIONARY
-                    .iter()
-                    .find(|term| sentence_lower.contains(*term))
-                {
-                    contexts.push(SentenceContext {
-                        pmid: record.pmid.clone(),
-                        sent_idx,
-                        drug: drug.clone(),
-                        event: (*event).to_string(),
-                        text: sentence,
-                    });
-                }
+            let detected = language::detect(&format!("{}\n{}", record.title, record.abstract_text));
+            if !language::is_english(detected) && !filter.keep_non_english {
+                filter.dropped.fetch_add(1, Ordering::Relaxed);
+                return Ok(Vec::new());
             }
+            Ok(record_contexts(&record, drug, events, detected))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(per_record.into_iter().flatten().collect())
+}
+
+/// Sentences of a record that mention `drug` and a dictionary event.
+fn record_contexts(
+    record: &PubRecord,
+    drug: &str,
+    events: &EventDictionary,
+    language: Option<&str>,
+) -> Vec<SentenceContext> {
+    let study_type = PublicationType::strongest(&record.publication_types);
+    let sentences = record_sentences(record);
+    let sentence_count = sentences.len();
+    let mut contexts = Vec::new();
+    for (sent_idx, (section, sentence)) in sentences.into_iter().enumerate() {
+        let sentence_lower = sentence.to_lowercase();
+        if !dictionary::mentions_term(&sentence_lower, drug) {
+            continue;
+        }
+        if let Some(event) = events.find_in(&sentence_lower) {
+            contexts.push(SentenceContext {
+                pmid: record.pmid.clone(),
+                sent_idx,
+                drug: drug.to_string(),
+                event: event.to_string(),
+                text: sentence,
+                sentence_count,
+                mesh_headings: record.mesh_headings.clone(),
+                section,
+                study_type,
+                language: language.map(str::to_string),
+            });
         }
     }
-    info!(count = contexts.len(), "built sentence contexts");
-    Ok(contexts)
+    contexts
+}
+
+/// Sentences of a record with their abstract section, numbered across the abstract and
+/// then the full text.
+fn record_sentences(record: &PubRecord) -> Vec<(AbstractSection, String)> {
+    let mut paragraphs: Vec<(AbstractSection, &str)> = if record.abstract_sections.is_empty() {
+        vec![(AbstractSection::Unlabelled, record.abstract_text.as_str())]
+    } else {
+        record
+            .abstract_sections
+            .iter()
+            .map(|paragraph| (paragraph.section, paragraph.text.as_str()))
+            .collect()
+    };
+    if let Some(full_text) = &record.full_text {
+        paragraphs.push((AbstractSection::Unlabelled, full_text));
+    }
+    paragraphs
+        .into_iter()
+        .flat_map(|(section, text)| {
+            features::split_sentences(text)
+                .into_iter()
+                .map(move |sentence| (section, sentence))
+        })
+        .collect()
 }
 
-/// Train a logistic classifier (optionally) and persist predictions.
+/// Train a relation classifier (optionally), evaluate it by cross-validation and persist
+/// Platt-calibrated relation probabilities. Returns the confidence of each feature row.
 pub async fn train_and_predict(
     settings: &Settings,
-    _ner: &dyn Ner,
+    ner: &dyn Ner,
+    sentences: &[SentenceContext],
     features: Vec<FeatureVector>,
-    mode: ExtractMode,
-) -> Result<()> {
+    options: &ExtractOptions,
+) -> Result<Vec<f64>> {
     if features.is_empty() {
         warn!("no features generated; skipping relation extraction");
-        return Ok(());
+        return Ok(Vec::new());
     }
 
-    let labels: Vec<i32> = features
+    let weak_labels: Vec<i32> = features
         .iter()
         .map(|f| {
             if f.has_cue_word > 0.5 && f.negation_flag < 0.5 {
@@ -114,43 +294,243 @@ pub async fn train_and_predict(
             }
         })
         .collect();
-    let matrix: Vec<f64> = features
-        .iter()
-        .flat_map(|f| {
-            [
-                f.token_distance as f64,
-                f.has_cue_word as f64,
-                f.negation_flag as f64,
-                f.co_mention_count as f64,
-                f.tfidf_like as f64,
-            ]
-        })
-        .collect();
+    let matrix: Vec<f64> = features.iter().flat_map(FeatureVector::to_row).collect();
     let rows = features.len();
-    let x = Array2::from_shape_vec((rows, 5), matrix)?;
-    let y = Array1::from(labels.clone());
-    let dataset: DatasetBase<_, _> = DatasetBase::new(x.clone(), y.clone());
-
-    let confidences: Vec<f64> = if mode.is_training() {
-        let model = LogisticRegression::default().max_iterations(150);
-        let fitted = model.fit(&dataset)?;
-        fitted
-            .predict(&dataset)
+    let x = Array2::from_shape_vec((rows, features::FEATURE_WIDTH), matrix)?;
+
+    let confidences: Vec<f64> = if options.mode.is_training() {
+        let (train_rows, labels, label_source) = training_labels(&features, weak_labels, options)?;
+        let train_x = x.select(Axis(0), &train_rows);
+        let train_features: Vec<&FeatureVector> =
+            train_rows.iter().map(|&idx| &features[idx]).collect();
+        let held_out = if options.folds >= 2 {
+            evaluate(
+                settings,
+                &train_features,
+                &train_x,
+                &labels,
+                label_source,
+                options,
+            )?
+        } else {
+            Vec::new()
+        };
+        let scores = fit_scores(
+            options.classifier,
+            train_x,
+            Array1::from(labels.clone()),
+            &x,
+        )?;
+        // Prefer out-of-fold scores: in-sample scores are overconfident, flexible models most.
+        let (calibration_scores, calibration_labels): (Vec<f64>, Vec<i32>) =
+            if held_out.iter().any(Option::is_some) {
+                held_out
+                    .iter()
+                    .zip(&labels)
+                    .filter_map(|(score, &label)| score.map(|score| (score, label)))
+                    .unzip()
+            } else {
+                train_rows
+                    .iter()
+                    .map(|&idx| scores[idx])
+                    .zip(labels)
+                    .unzip()
+            };
+        let scaler = PlattScaler::fit(&calibration_scores, &calibration_labels);
+        info!(
+            a = scaler.a,
+            b = scaler.b,
+            "calibrated relation probabilities"
+        );
+        scores
             .into_iter()
-            .map(|value| value as f64)
+            .map(|score| scaler.probability(score))
             .collect()
     } else {
-        labels.into_iter().map(|value| value as f64).collect()
+        weak_labels.into_iter().map(|value| value as f64).collect()
     };
 
-    persist_relations(settings, &features, confidences)?;
-    Ok(())
+    persist_relations(settings, ner, sentences, &features, &confidences).await?;
+    Ok(confidences)
+}
+
+/// Rows to train on with their labels and label source: every row with the heuristic weak
+/// labels, or only the gold-annotated rows in supervised mode.
+fn training_labels(
+    features: &[FeatureVector],
+    weak_labels: Vec<i32>,
+    options: &ExtractOptions,
+) -> Result<(Vec<usize>, Vec<i32>, &'static str)> {
+    if !matches!(options.mode, ExtractMode::Supervised) {
+        return Ok(((0..features.len()).collect(), weak_labels, "weak"));
+    }
+    let Some(path) = &options.labels else {
+        bail!("--mode supervised needs a gold labels file (--labels)");
+    };
+    let gold = labels::read_gold_labels(path)?;
+    let (rows, targets): (Vec<usize>, Vec<i32>) = features
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, f)| {
+            gold.get(&labels::label_key(&f.pmid, f.sent_idx, &f.drug, &f.event))
+                .map(|&label| (idx, label))
+        })
+        .unzip();
+    if rows.is_empty() {
+        bail!(
+            "none of the {} gold labels in {} match an extracted candidate sentence",
+            gold.len(),
+            path.display()
+        );
+    }
+    info!(
+        matched = rows.len(),
+        annotated = gold.len(),
+        "training on gold labels"
+    );
+    Ok((rows, targets, "gold"))
 }
 
-fn persist_relations(
+/// K-fold (grouped by PubMed id) evaluation against the training labels, returning the
+/// out-of-fold decision score of each training row.
+fn evaluate(
     settings: &Settings,
+    features: &[&FeatureVector],
+    x: &Array2<f64>,
+    labels: &[i32],
+    label_source: &str,
+    options: &ExtractOptions,
+) -> Result<Vec<Option<f64>>> {
+    let pmids: Vec<&str> = features.iter().map(|f| f.pmid.as_str()).collect();
+    let folds = evaluation::fold_assignments(&pmids, options.folds);
+    let cv = evaluation::cross_validate(x, labels, &folds, options.folds, |x, y, test| {
+        fit_scores(options.classifier, x, y, &test)
+    })?;
+    if cv.per_fold.is_empty() {
+        warn!("no fold could be evaluated; skipping extraction metrics");
+        return Ok(cv.scores);
+    }
+    let mode = format!("{:?}", options.mode);
+    evaluation::ExtractMetrics::from_folds(
+        &mode,
+        options.classifier.label(),
+        label_source,
+        labels,
+        cv.per_fold,
+    )
+    .persist(settings)?;
+    Ok(cv.scores)
+}
+
+/// Fit the chosen classifier on `(x, labels)` and return decision scores for `test`; a
+/// positive score predicts a relation.
+fn fit_scores(
+    classifier: Classifier,
+    x: Array2<f64>,
+    labels: Array1<i32>,
+    test: &Array2<f64>,
+) -> Result<Vec<f64>> {
+    let (x, labels) = emphasise_conclusions(x, labels);
+    match classifier {
+        Classifier::Logistic => {
+            let fitted = LogisticRegression::default()
+                .max_iterations(150)
+                .fit(&DatasetBase::new(x, labels))?;
+            let predicted = fitted.predict(test);
+            // `predict_probabilities` reports linfa's internal positive class; the predicted
+            // class always holds the larger share, which orients it towards label 1.
+            Ok(fitted
+                .predict_probabilities(test)
+                .iter()
+                .zip(predicted.iter())
+                .map(|(&p, &label)| {
+                    let relation = if label > 0 {
+                        p.max(1.0 - p)
+                    } else {
+                        p.min(1.0 - p)
+                    };
+                    let relation = relation.clamp(1e-9, 1.0 - 1e-9);
+                    (relation / (1.0 - relation)).ln()
+                })
+                .collect())
+        }
+        Classifier::Svm => {
+            let scaler = Standardiser::fit(&x);
+            let targets = labels.mapv(|label| label > 0);
+            let width = x.ncols() as f64;
+            let fitted = Svm::<f64, bool>::params()
+                .gaussian_kernel(width)
+                .fit(&DatasetBase::new(scaler.apply(&x), targets))?;
+            Ok(scaler
+                .apply(test)
+                .rows()
+                .into_iter()
+                .map(|row| fitted.weighted_sum(&row) - fitted.rho)
+                .collect())
+        }
+        Classifier::Gbt => {
+            let labels = labels.to_vec();
+            Ok(
+                GradientBoostedTrees::fit(&x, &labels, BoostingParams::default())
+                    .decision_function(test),
+            )
+        }
+    }
+}
+
+/// Times each CONCLUSIONS-section row appears in a training set: authors state their
+/// causal judgement there, so those sentences count double in every classifier.
+const CONCLUSIONS_TRAINING_COPIES: usize = 2;
+
+/// Repeat CONCLUSIONS-section rows, weighting them in classifiers without sample weights.
+fn emphasise_conclusions(x: Array2<f64>, labels: Array1<i32>) -> (Array2<f64>, Array1<i32>) {
+    let rows: Vec<usize> = (0..x.nrows())
+        .flat_map(|idx| {
+            let copies = if x[[idx, features::CONCLUSIONS_COLUMN]] > 0.5 {
+                CONCLUSIONS_TRAINING_COPIES
+            } else {
+                1
+            };
+            std::iter::repeat_n(idx, copies)
+        })
+        .collect();
+    if rows.len() == x.nrows() {
+        return (x, labels);
+    }
+    (x.select(Axis(0), &rows), labels.select(Axis(0), &rows))
+}
+
+/// Per-column z-scores fitted on the training rows; kernel distances need comparable scales.
+struct Standardiser {
+    mean: Array1<f64>,
+    std: Array1<f64>,
+}
+
+impl Standardiser {
+    fn fit(x: &Array2<f64>) -> Self {
+        let mean = x
+            .mean_axis(Axis(0))
+            .unwrap_or_else(|| Array1::zeros(x.ncols()));
+        let std = x
+            .std_axis(Axis(0), 0.0)
+            .mapv(|s| if s > 1e-12 { s } else { 1.0 });
+        Self { mean, std }
+    }
+
+    fn apply(&self, x: &Array2<f64>) -> Array2<f64> {
+        (x - &self.mean) / &self.std
+    }
+}
+
+/// Write `relations.parquet`, linking each relation to external identifiers: the drug RxCUI,
+/// the event UMLS CUI and MedDRA PT code, and the `embed` clusters of the event and drug. The evidence
+/// sentence is stored with the character offsets of both mentions.
+async fn persist_relations(
+    settings: &Settings,
+    ner: &dyn Ner,
+    sentences: &[SentenceContext],
     features: &[FeatureVector],
-    confidences: Vec<f64>,
+    confidences: &[f64],
 ) -> Result<()> {
     let drug_lookup = parquet_lookup(
         settings.join_data("clean/drugs.parquet"),
@@ -162,9 +542,19 @@ fn persist_relations(
         "term_canonical",
         "event_id",
     )?;
+    let umls_lookup = parquet_lookup(
+        settings.join_data("clean/events.parquet"),
+        "term_canonical",
+        "umls_cui",
+    )?;
+    let pt_codes = smq::load_pt_codes(settings)?;
+    let cluster_lookup = cluster_ids(settings, "event_clusters.parquet", "event_id")?;
+    let drug_cluster_lookup = cluster_ids(settings, "drug_clusters.parquet", "drug_id")?;
+    let mut rxnorm = RxNormResolver::new(settings)?;
+    let mut rxcuis: HashMap<String, Option<String>> = HashMap::new();
 
     let mut rows = Vec::new();
-    for (feat, conf) in features.iter().zip(confidences) {
+    for ((feat, ctx), &conf) in features.iter().zip(sentences).zip(confidences) {
         let drug_key = feat.drug.to_lowercase();
         let event_key = feat.event.to_lowercase();
         let Some(drug_id) = drug_lookup.get(&drug_key) else {
@@ -173,12 +563,28 @@ fn persist_relations(
         let Some(event_id) = event_lookup.get(&event_key) else {
             continue;
         };
+        if !rxcuis.contains_key(&drug_key) {
+            let rxcui = rxnorm.rxcui(&drug_key).await;
+            rxcuis.insert(drug_key.clone(), rxcui);
+        }
         rows.push(RelationRow {
             drug_id: drug_id.clone(),
             event_id: event_id.clone(),
             pmid: feat.pmid.clone(),
             sent_idx: feat.sent_idx as i64,
             confidence: conf,
+            speculative: feat.speculation_flag > 0.5,
+            rxcui: rxcuis[&drug_key].clone(),
+            umls_cui: umls_lookup.get(&event_key).cloned(),
+            meddra_pt_code: pt_codes.get(&event_key).cloned(),
+            event_cluster_id: cluster_lookup.get(event_id).copied(),
+            drug_cluster_id: drug_cluster_lookup.get(drug_id).copied(),
+            section: ctx.section.as_str(),
+            study_type: ctx.study_type.map(PublicationType::name),
+            language: ctx.language.clone(),
+            sentence: ctx.text.clone(),
+            drug_span: mention_span(ner, &ctx.text, &ctx.drug, "DRUG"),
+            event_span: mention_span(ner, &ctx.text, &ctx.event, "EVENT"),
         });
     }
 
@@ -192,6 +598,22 @@ fn persist_relations(
     let pmids: Vec<String> = rows.iter().map(|r| r.pmid.clone()).collect();
     let sent_idx: Vec<i64> = rows.iter().map(|r| r.sent_idx).collect();
     let confidences: Vec<f64> = rows.iter().map(|r| r.confidence).collect();
+    let speculative: Vec<bool> = rows.iter().map(|r| r.speculative).collect();
+    let rxcui: Vec<Option<String>> = rows.iter().map(|r| r.rxcui.clone()).collect();
+    let umls_cui: Vec<Option<String>> = rows.iter().map(|r| r.umls_cui.clone()).collect();
+    let meddra_pt_code: Vec<Option<String>> =
+        rows.iter().map(|r| r.meddra_pt_code.clone()).collect();
+    let event_cluster_id: Vec<Option<i64>> = rows.iter().map(|r| r.event_cluster_id).collect();
+    let drug_cluster_id: Vec<Option<i64>> = rows.iter().map(|r| r.drug_cluster_id).collect();
+    let section: Vec<&str> = rows.iter().map(|r| r.section).collect();
+    let study_type: Vec<Option<&str>> = rows.iter().map(|r| r.study_type).collect();
+    let language: Vec<Option<String>> = rows.iter().map(|r| r.language.clone()).collect();
+    let sentence: Vec<String> = rows.iter().map(|r| r.sentence.clone()).collect();
+    let span_column = |span: fn(&RelationRow) -> Option<(i64, i64)>, end: bool| {
+        rows.iter()
+            .map(|r| span(r).map(|(s, e)| if end { e } else { s }))
+            .collect::<Vec<Option<i64>>>()
+    };
 
     let mut df = DataFrame::new(vec![
         Series::new("drug_id".into(), drug_ids),
@@ -199,6 +621,20 @@ fn persist_relations(
         Series::new("pmid".into(), pmids),
         Series::new("sent_idx".into(), sent_idx),
         Series::new("confidence".into(), confidences),
+        Series::new("speculative".into(), speculative),
+        Series::new("rxcui".into(), rxcui),
+        Series::new("umls_cui".into(), umls_cui),
+        Series::new("meddra_pt_code".into(), meddra_pt_code),
+        Series::new("event_cluster_id".into(), event_cluster_id),
+        Series::new("drug_cluster_id".into(), drug_cluster_id),
+        Series::new("section".into(), section),
+        Series::new("study_type".into(), study_type),
+        Series::new("language".into(), language),
+        Series::new("sentence".into(), sentence),
+        Series::new("drug_start".into(), span_column(|r| r.drug_span, false)),
+        Series::new("drug_end".into(), span_column(|r| r.drug_span, true)),
+        Series::new("event_start".into(), span_column(|r| r.event_span, false)),
+        Series::new("event_end".into(), span_column(|r| r.event_span, true)),
     ])?;
     let path = settings.join_data("clean/relations.parquet");
     if let Some(parent) = path.parent() {
@@ -210,7 +646,28 @@ fn persist_relations(
     Ok(())
 }
 
-fn parquet_lookup(path: PathBuf, key: &str, value: &str) -> Result<HashMap<String, String>> {
+/// Character offsets `[start, end)` of `name` in `text` (case-insensitive), falling back to
+/// the first NER span with `label` when the sentence uses a synonym.
+fn mention_span(ner: &dyn Ner, text: &str, name: &str, label: &str) -> Option<(i64, i64)> {
+    let (start, end) = match text.to_ascii_lowercase().find(&name.to_ascii_lowercase()) {
+        Some(start) => (start, start + name.len()),
+        None => {
+            let span = ner
+                .extract(text)
+                .into_iter()
+                .find(|span| span.label == label)?;
+            (span.start, span.end)
+        }
+    };
+    let chars = |byte: usize| text.get(..byte).map_or(0, |prefix| prefix.chars().count()) as i64;
+    Some((chars(start), chars(end)))
+}
+
+pub(crate) fn parquet_lookup(
+    path: PathBuf,
+    key: &str,
+    value: &str,
+) -> Result<HashMap<String, String>> {
     if !path.exists() {
         return Ok(HashMap::new());
     }
@@ -218,12 +675,26 @@ fn parquet_lookup(path: PathBuf, key: &str, value: &str) -> Result<HashMap<Strin
     let key_col = df.column(key)?;
     let val_col = df.column(value)?;
     let mut map = HashMap::new();
-    for (k, v) in key_col
-        .str()?
-        .into_no_null_iter()
-        .zip(val_col.str()?.into_no_null_iter())
-    {
-        map.insert(k.to_string(), v.to_string());
+    for (k, v) in key_col.str()?.into_iter().zip(val_col.str()?) {
+        if let (Some(k), Some(v)) = (k, v) {
+            map.insert(k.to_string(), v.to_string());
+        }
     }
     Ok(map)
 }
+
+/// Id → `embed` cluster id from `clean/<file>`, empty when `embed` has not been run.
+fn cluster_ids(settings: &Settings, file: &str, id_column: &str) -> Result<HashMap<String, i64>> {
+    let path = settings.join_data("clean").join(file);
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let df = ParquetReader::new(File::open(&path)?).finish()?;
+    let ids = df.column(id_column)?.str()?;
+    let clusters = df.column("cluster_id")?.i64()?;
+    Ok(ids
+        .into_iter()
+        .zip(clusters)
+        .filter_map(|(id, cluster)| Some((id?.to_string(), cluster?)))
+        .collect())
+}
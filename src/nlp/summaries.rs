@@ -0,0 +1,604 @@
+//! Evidence summaries for a drug-event pair from a local or OpenAI-compatible language model.
+//!
+//! The prompt is grounded in the highest-confidence sentences for the pair from
+//! `relations.parquet`. The model answers with JSON claims, and every PMID it cites is checked
+//! against the relations for the pair so that invented citations are flagged.
+
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    fs::File,
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use polars::prelude::{ParquetReader, SerReader};
+use reqwest::{header::AUTHORIZATION, Method};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{config::Settings, net, nlp::relclf::parquet_lookup};
+
+/// Language model behind `summarize`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SummaryBackend {
+    /// Local GGUF model through llama.cpp (the `summaries` feature).
+    #[default]
+    Llama,
+    /// Any `/v1/chat/completions` endpoint: vLLM, ollama, llama.cpp server or a hosted API.
+    Openai,
+}
+
+/// Sampling parameters shared by both backends.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct SamplingParams {
+    pub temperature: f32,
+    /// Nucleus sampling: keep the smallest token set whose probability reaches `top_p`.
+    pub top_p: f32,
+    /// Candidate tokens considered at each step (llama.cpp only).
+    pub top_k: i32,
+    /// Tokens the model may generate for one summary.
+    pub max_tokens: usize,
+    /// Sampling seed, so repeated runs give the same summary.
+    pub seed: u32,
+}
+
+impl Default for SamplingParams {
+    fn default() -> Self {
+        Self {
+            temperature: 0.2,
+            top_p: 0.9,
+            top_k: 40,
+            max_tokens: 512,
+            seed: 42,
+        }
+    }
+}
+
+/// One supporting sentence from `relations.parquet`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Evidence {
+    pub pmid: String,
+    pub sent_idx: i64,
+    pub confidence: f64,
+    /// Structured-abstract section, when `extract` recorded one.
+    pub section: Option<String>,
+    pub sentence: String,
+}
+
+/// Every sentence relating `drug` to `event`, most confident first.
+///
+/// Names are matched case-insensitively against the canonical names in `drugs.parquet` and
+/// `events.parquet`; ids are accepted as well.
+pub fn pair_evidence(settings: &Settings, drug: &str, event: &str) -> Result<Vec<Evidence>> {
+    Ok(evidence_by_pair(settings)?
+        .remove(&resolve_pair(settings, drug, event)?)
+        .unwrap_or_default())
+}
+
+/// Drug and event ids for canonical names (case-insensitive); ids pass through unchanged.
+pub fn resolve_pair(settings: &Settings, drug: &str, event: &str) -> Result<(String, String)> {
+    let drug_id = parquet_lookup(
+        settings.join_data("clean/drugs.parquet"),
+        "name_canonical",
+        "drug_id",
+    )?
+    .remove(&drug.to_lowercase())
+    .unwrap_or_else(|| drug.to_string());
+    let event_id = parquet_lookup(
+        settings.join_data("clean/events.parquet"),
+        "term_canonical",
+        "event_id",
+    )?
+    .remove(&event.to_lowercase())
+    .unwrap_or_else(|| event.to_string());
+    Ok((drug_id, event_id))
+}
+
+/// Evidence sentences of every pair in `relations.parquet`, keyed by `(drug_id, event_id)`
+/// and most confident first. Rows without sentence text (relations written before evidence
+/// sentences were stored) are skipped.
+pub fn evidence_by_pair(settings: &Settings) -> Result<HashMap<(String, String), Vec<Evidence>>> {
+    let path = settings.join_data("clean/relations.parquet");
+    if !path.exists() {
+        warn!(path = %path.display(), "no relations yet; run extract first");
+        return Ok(HashMap::new());
+    }
+    let df = ParquetReader::new(File::open(&path)?).finish()?;
+    let Ok(sentences) = df.column("sentence") else {
+        warn!("relations.parquet has no sentence column; rerun extract to store evidence text");
+        return Ok(HashMap::new());
+    };
+    let sentences = sentences.str()?;
+    let drugs = df.column("drug_id")?.str()?;
+    let events = df.column("event_id")?.str()?;
+    let pmids = df.column("pmid")?.str()?;
+    let sent_idx = df.column("sent_idx")?.i64()?;
+    let confidences = df.column("confidence")?.f64()?;
+    let sections: Vec<Option<String>> = match df.column("section") {
+        Ok(column) => column
+            .str()?
+            .into_iter()
+            .map(|section| section.map(str::to_string))
+            .collect(),
+        Err(_) => vec![None; df.height()],
+    };
+
+    let mut pairs: HashMap<(String, String), Vec<Evidence>> = HashMap::new();
+    for (row, section) in sections.into_iter().enumerate() {
+        let (Some(drug), Some(event), Some(pmid), Some(sentence)) = (
+            drugs.get(row),
+            events.get(row),
+            pmids.get(row),
+            sentences.get(row),
+        ) else {
+            continue;
+        };
+        pairs
+            .entry((drug.to_string(), event.to_string()))
+            .or_default()
+            .push(Evidence {
+                pmid: pmid.to_string(),
+                sent_idx: sent_idx.get(row).unwrap_or_default(),
+                confidence: confidences.get(row).unwrap_or_default(),
+                section,
+                sentence: sentence.to_string(),
+            });
+    }
+    for evidence in pairs.values_mut() {
+        evidence.sort_by(|a, b| {
+            b.confidence
+                .partial_cmp(&a.confidence)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| (&a.pmid, a.sent_idx).cmp(&(&b.pmid, b.sent_idx)))
+        });
+        // The same sentence can pair the drug and event in several mention combinations.
+        let mut seen = HashSet::new();
+        evidence.retain(|item| seen.insert((item.pmid.clone(), item.sent_idx)));
+    }
+    Ok(pairs)
+}
+
+/// Prompt shared by every backend: the instruction, the numbered evidence and the JSON
+/// shape the answer must take.
+pub fn prompt(drug: &str, event: &str, topk: usize, evidence: &[Evidence]) -> String {
+    let mut prompt = format!(
+        "Summarise evidence for {drug} causing {event}. Include PMID references. Limit to {topk} claims.\n\
+         Use only the evidence below and cite the PMID of every sentence you rely on. \
+         If the evidence does not support a causal link, say so.\n\nEvidence:\n"
+    );
+    for (idx, item) in evidence.iter().enumerate() {
+        let _ = writeln!(
+            prompt,
+            "{}. [PMID {}] {}",
+            idx + 1,
+            item.pmid,
+            item.sentence
+        );
+    }
+    prompt.push_str(
+        "\nAnswer with JSON only, in this form:\n\
+         {\"claims\": [{\"claim\": \"one-sentence finding\", \
+         \"strength\": \"strong|moderate|weak|insufficient\", \
+         \"pmids\": [\"PMIDs supporting the claim\"], \
+         \"quotes\": [\"verbatim excerpts from the evidence\"]}]}\n",
+    );
+    prompt
+}
+
+/// How well the cited evidence supports a claim, as judged by the model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClaimStrength {
+    Strong,
+    Moderate,
+    Weak,
+    Insufficient,
+}
+
+/// One finding of a summary with the citations the model gave for it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Claim {
+    pub claim: String,
+    pub strength: ClaimStrength,
+    #[serde(default)]
+    pub pmids: Vec<String>,
+    #[serde(default)]
+    pub quotes: Vec<String>,
+    /// Cited PMIDs with no relation for the pair in `relations.parquet`, i.e. invented.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub unverified_pmids: Vec<String>,
+    /// Quotes that appear in none of the evidence sentences.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub unverified_quotes: Vec<String>,
+}
+
+/// Structured summary of the evidence for one drug-event pair.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub drug: String,
+    pub event: String,
+    #[serde(default)]
+    pub drug_id: String,
+    #[serde(default)]
+    pub event_id: String,
+    pub claims: Vec<Claim>,
+    /// Whether every cited PMID and quote was found in the relations for the pair; null when
+    /// no model ran, so there was nothing to check.
+    #[serde(default)]
+    pub verified: Option<bool>,
+    /// Sentences given to the model, best first.
+    pub evidence: Vec<Evidence>,
+    /// Why there are no claims, when no model ran.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl Summary {
+    /// True when citations were checked and some were not found.
+    pub fn has_unverified_citations(&self) -> bool {
+        self.verified == Some(false)
+    }
+}
+
+/// Saved summary of a pair under the outputs directory.
+fn summary_path(settings: &Settings, drug_id: &str, event_id: &str) -> PathBuf {
+    settings.join_output(format!("summaries/{drug_id}_{event_id}.json"))
+}
+
+/// Keep `summary` for reports, replacing any earlier summary of the pair.
+pub fn save_summary(settings: &Settings, summary: &Summary) -> Result<PathBuf> {
+    let path = summary_path(settings, &summary.drug_id, &summary.event_id);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, serde_json::to_vec_pretty(summary)?)
+        .with_context(|| format!("write {path:?}"))?;
+    Ok(path)
+}
+
+/// The saved summary of a pair, with its citations checked again against `known_pmids` (the
+/// pair's current relations), since `extract` may have rewritten them since.
+pub fn load_summary(
+    settings: &Settings,
+    drug_id: &str,
+    event_id: &str,
+    known_pmids: &HashSet<&str>,
+) -> Result<Option<Summary>> {
+    let path = summary_path(settings, drug_id, event_id);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(&path).with_context(|| format!("read {path:?}"))?;
+    let mut summary: Summary =
+        serde_json::from_str(&text).with_context(|| format!("parse {path:?}"))?;
+    summary.verified = Some(verify_claims(
+        &mut summary.claims,
+        known_pmids,
+        &summary.evidence,
+    ));
+    Ok(Some(summary))
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelAnswer {
+    claims: Vec<Claim>,
+}
+
+/// Parse the model's JSON answer, tolerating text or code fences around the object.
+pub fn parse_claims(answer: &str) -> Result<Vec<Claim>> {
+    let start = answer.find('{');
+    let end = answer.rfind('}');
+    let json = match (start, end) {
+        (Some(start), Some(end)) if start < end => &answer[start..=end],
+        _ => bail!("model answer contains no JSON object: {answer:?}"),
+    };
+    let parsed: ModelAnswer = serde_json::from_str(json)
+        .with_context(|| format!("model answer is not a claims object: {json}"))?;
+    Ok(parsed.claims)
+}
+
+/// Flag cited PMIDs missing from `known_pmids` (every PMID with a relation for the pair) and
+/// quotes found in no evidence sentence. PMIDs are normalised to their digits first. Returns
+/// whether everything checked out.
+pub fn verify_claims(
+    claims: &mut [Claim],
+    known_pmids: &HashSet<&str>,
+    evidence: &[Evidence],
+) -> bool {
+    let sentences: Vec<String> = evidence
+        .iter()
+        .map(|item| normalise_quote(&item.sentence))
+        .collect();
+    let mut verified = true;
+    for claim in claims {
+        for pmid in &mut claim.pmids {
+            *pmid = pmid.chars().filter(char::is_ascii_digit).collect();
+        }
+        let mut seen = HashSet::new();
+        claim
+            .pmids
+            .retain(|pmid| !pmid.is_empty() && seen.insert(pmid.clone()));
+        claim.unverified_pmids = claim
+            .pmids
+            .iter()
+            .filter(|pmid| !known_pmids.contains(pmid.as_str()))
+            .cloned()
+            .collect();
+        claim.unverified_quotes = claim
+            .quotes
+            .iter()
+            .filter(|quote| {
+                let quote = normalise_quote(quote);
+                !sentences.iter().any(|sentence| sentence.contains(&quote))
+            })
+            .cloned()
+            .collect();
+        verified &= claim.unverified_pmids.is_empty() && claim.unverified_quotes.is_empty();
+    }
+    verified
+}
+
+/// Lowercase with runs of whitespace collapsed and surrounding quotes/ellipses trimmed.
+fn normalise_quote(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches(|c: char| matches!(c, '"' | '\'' | '.' | '…') || c.is_whitespace())
+        .to_lowercase()
+}
+
+/// Summarise the literature evidence for `drug` causing `event` with the configured backend
+/// and check every citation in the answer against `relations.parquet`.
+pub async fn summarize(
+    settings: &Settings,
+    drug: &str,
+    event: &str,
+    topk: usize,
+) -> Result<Summary> {
+    let (drug_id, event_id) = resolve_pair(settings, drug, event)?;
+    let all = evidence_by_pair(settings)?
+        .remove(&(drug_id.clone(), event_id.clone()))
+        .unwrap_or_default();
+    let known_pmids: HashSet<&str> = all.iter().map(|item| item.pmid.as_str()).collect();
+    let evidence: Vec<Evidence> = all.iter().take(topk).cloned().collect();
+    let mut summary = Summary {
+        drug: drug.to_string(),
+        event: event.to_string(),
+        drug_id,
+        event_id,
+        claims: Vec::new(),
+        verified: None,
+        evidence,
+        note: None,
+    };
+    if summary.evidence.is_empty() {
+        summary.note = Some(format!(
+            "No supporting sentences for {drug} and {event} in relations.parquet; nothing to summarise."
+        ));
+        return Ok(summary);
+    }
+    info!(
+        drug,
+        event,
+        sentences = summary.evidence.len(),
+        "summarising evidence"
+    );
+    let prompt = prompt(drug, event, topk, &summary.evidence);
+    let answer = match settings.summary_backend {
+        SummaryBackend::Openai => openai_complete(settings, &prompt).await?,
+        SummaryBackend::Llama => {
+            let (settings, prompt) = (settings.clone(), prompt.clone());
+            match tokio::task::spawn_blocking(move || llama_complete(&settings, &prompt)).await?? {
+                Ok(answer) => answer,
+                Err(note) => {
+                    summary.note = Some(note);
+                    return Ok(summary);
+                }
+            }
+        }
+    };
+    summary.claims = parse_claims(&answer)?;
+    summary.verified = Some(verify_claims(
+        &mut summary.claims,
+        &known_pmids,
+        &summary.evidence,
+    ));
+    for claim in summary
+        .claims
+        .iter()
+        .filter(|claim| !claim.unverified_pmids.is_empty())
+    {
+        warn!(claim = %claim.claim, pmids = ?claim.unverified_pmids, "summary cites PMIDs with no relation for the pair");
+    }
+    Ok(summary)
+}
+
+#[derive(Debug, Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage<'a>>,
+    temperature: f32,
+    top_p: f32,
+    max_tokens: usize,
+    seed: u32,
+    response_format: ResponseFormat,
+}
+
+#[derive(Debug, Serialize)]
+struct ResponseFormat {
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    #[serde(default)]
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: ChatReply,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatReply {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// Send the prompt to `{SUMMARY_API_URL}/chat/completions`.
+async fn openai_complete(settings: &Settings, prompt: &str) -> Result<String> {
+    let model = settings.summary_model.as_deref().ok_or_else(|| {
+        anyhow!(
+            "SUMMARY_MODEL must name the model served at {}",
+            settings.summary_api_url
+        )
+    })?;
+    let url = format!(
+        "{}/chat/completions",
+        settings.summary_api_url.trim_end_matches('/')
+    );
+    let body = ChatRequest {
+        model,
+        messages: vec![ChatMessage {
+            role: "user",
+            content: prompt,
+        }],
+        temperature: settings.summary_sampling.temperature,
+        top_p: settings.summary_sampling.top_p,
+        max_tokens: settings.summary_sampling.max_tokens,
+        seed: settings.summary_sampling.seed,
+        response_format: ResponseFormat {
+            kind: "json_object",
+        },
+    };
+    let client = net::client(settings)?;
+    let mut request = client.request(Method::POST, &url).json(&body);
+    if let Some(key) = &settings.summary_api_key {
+        request = request.header(AUTHORIZATION, format!("Bearer {key}"));
+    }
+    let response: ChatResponse = client
+        .send(request)
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    info!(%url, model, "received summary");
+    response
+        .choices
+        .into_iter()
+        .find_map(|choice| choice.message.content)
+        .ok_or_else(|| anyhow!("{url} returned no completion"))
+}
+
+/// Fallback when llama.cpp is not enabled: a note instead of an answer.
+#[cfg(not(feature = "summaries"))]
+fn llama_complete(_settings: &Settings, _prompt: &str) -> Result<Result<String, String>> {
+    Ok(Err(
+        "Summary unavailable (LLM disabled); the supporting sentences are listed.".to_string(),
+    ))
+}
+
+/// Complete the prompt with llama.cpp, formatted with the model's chat template (or
+/// `LLAMA_CHAT_TEMPLATE`) and sampled with the configured parameters. A missing model file
+/// yields a note instead of an answer.
+#[cfg(feature = "summaries")]
+fn llama_complete(settings: &Settings, prompt: &str) -> Result<Result<String, String>> {
+    use std::num::NonZeroU32;
+
+    use anyhow::{bail, Context};
+    use llama_cpp_2::{
+        context::params::LlamaContextParams,
+        llama_backend::LlamaBackend,
+        llama_batch::LlamaBatch,
+        model::{
+            params::LlamaModelParams, AddBos, LlamaChatMessage, LlamaChatTemplate, LlamaModel,
+            Special,
+        },
+        sampling::LlamaSampler,
+    };
+    use once_cell::sync::OnceCell;
+
+    // llama.cpp may only be initialised once per process.
+    static BACKEND: OnceCell<LlamaBackend> = OnceCell::new();
+
+    let model_path = &settings.llama_model_path;
+    if !model_path.exists() {
+        return Ok(Err(format!(
+            "Summary disabled – expected model {} not found.",
+            model_path.display()
+        )));
+    }
+    let backend = BACKEND.get_or_try_init(LlamaBackend::init)?;
+    let model = LlamaModel::load_from_file(backend, model_path, &LlamaModelParams::default())
+        .with_context(|| format!("load {}", model_path.display()))?;
+    let template = match &settings.llama_chat_template {
+        Some(template) => LlamaChatTemplate::new(template)?,
+        None => model
+            .chat_template(None)
+            .context("model has no chat template; set LLAMA_CHAT_TEMPLATE")?,
+    };
+    let messages = [LlamaChatMessage::new(
+        "user".to_string(),
+        prompt.to_string(),
+    )?];
+    let formatted = model.apply_chat_template(&template, &messages, true)?;
+    let tokens = model.str_to_token(&formatted, AddBos::Always)?;
+
+    let n_ctx = settings.llama_context_length;
+    let sampling = &settings.summary_sampling;
+    let budget = tokens.len() + sampling.max_tokens;
+    if budget > n_ctx as usize {
+        bail!(
+            "prompt of {} tokens plus {} generated tokens exceeds LLAMA_CONTEXT_LENGTH={n_ctx}",
+            tokens.len(),
+            sampling.max_tokens
+        );
+    }
+    // The prompt is decoded as one batch, so the batch size has to cover the whole context.
+    let ctx_params = LlamaContextParams::default()
+        .with_n_ctx(NonZeroU32::new(n_ctx))
+        .with_n_batch(n_ctx);
+    let mut ctx = model.new_context(backend, ctx_params)?;
+
+    let mut batch = LlamaBatch::new(n_ctx as usize, 1);
+    let last = tokens.len() as i32 - 1;
+    for (pos, token) in (0_i32..).zip(tokens) {
+        batch.add(token, pos, &[0], pos == last)?;
+    }
+    ctx.decode(&mut batch)?;
+
+    let mut sampler = LlamaSampler::chain_simple([
+        LlamaSampler::top_k(sampling.top_k),
+        LlamaSampler::top_p(sampling.top_p, 1),
+        LlamaSampler::temp(sampling.temperature),
+        LlamaSampler::dist(sampling.seed),
+    ]);
+    let mut position = batch.n_tokens();
+    // A token can end inside a multi-byte character, so bytes are decoded once at the end.
+    let mut output = Vec::new();
+    for _ in 0..sampling.max_tokens {
+        let token = sampler.sample(&ctx, batch.n_tokens() - 1);
+        sampler.accept(token);
+        if model.is_eog_token(token) {
+            break;
+        }
+        output.extend(model.token_to_bytes(token, Special::Tokenize)?);
+        batch.clear();
+        batch.add(token, position, &[0], true)?;
+        position += 1;
+        ctx.decode(&mut batch)?;
+    }
+    info!(model = %model_path.display(), tokens = position, "generated summary");
+    Ok(Ok(String::from_utf8_lossy(&output).into_owned()))
+}
@@ -0,0 +1,97 @@
+//! Progress bars for long-running stages, drawn on stderr only when it is a terminal so
+//! redirected runs and CI logs stay clean.
+
+use std::{
+    borrow::Cow,
+    io::{self, IsTerminal, Write},
+    time::Duration,
+};
+
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use once_cell::sync::Lazy;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Every bar of the process, stacked so concurrent downloads do not overwrite each other.
+static BARS: Lazy<MultiProgress> = Lazy::new(|| {
+    let target = if io::stderr().is_terminal() {
+        ProgressDrawTarget::stderr()
+    } else {
+        ProgressDrawTarget::hidden()
+    };
+    MultiProgress::with_draw_target(target)
+});
+
+/// Stop drawing bars for the rest of the process, e.g. for `--quiet`.
+pub fn hide() {
+    BARS.set_draw_target(ProgressDrawTarget::hidden());
+}
+
+const TICK: Duration = Duration::from_millis(120);
+
+/// Bar over a download of `total` bytes, or a spinner when the server sent no length.
+pub fn bytes(message: impl Into<Cow<'static, str>>, total: Option<u64>) -> ProgressBar {
+    let bar = match total {
+        Some(total) => ProgressBar::new(total).with_style(style(
+            "{msg} [{bar:30}] {bytes}/{total_bytes} {bytes_per_sec} eta {eta}",
+        )),
+        None => {
+            ProgressBar::new_spinner().with_style(style("{spinner} {msg} {bytes} {bytes_per_sec}"))
+        }
+    };
+    add(bar.with_message(message))
+}
+
+/// Bar over `total` items counted in `unit`, e.g. pairs.
+pub fn items(message: impl Into<Cow<'static, str>>, total: u64, unit: &str) -> ProgressBar {
+    let template = format!(
+        "{{msg}} [{{bar:30}}] {{human_pos}}/{{human_len}} {unit} ({{per_sec}}, eta {{eta}})"
+    );
+    add(ProgressBar::new(total)
+        .with_style(style(&template))
+        .with_message(message))
+}
+
+/// Spinner counting items in `unit` when their total is not known up front.
+pub fn counter(message: impl Into<Cow<'static, str>>, unit: &str) -> ProgressBar {
+    let template = format!("{{spinner}} {{msg}} {{human_pos}} {unit} ({{per_sec}})");
+    add(ProgressBar::new_spinner()
+        .with_style(style(&template))
+        .with_message(message))
+}
+
+fn add(bar: ProgressBar) -> ProgressBar {
+    let bar = BARS.add(bar);
+    if bar.length().is_none() {
+        bar.enable_steady_tick(TICK);
+    }
+    bar
+}
+
+fn style(template: &str) -> ProgressStyle {
+    ProgressStyle::with_template(template)
+        .expect("progress templates are static")
+        .progress_chars("=> ")
+}
+
+/// Log output to stdout that clears the bars while a line is written, so log lines are not
+/// drawn over; used as the tracing writer.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogWriter;
+
+impl<'a> MakeWriter<'a> for LogWriter {
+    type Writer = LogWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        *self
+    }
+}
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        BARS.suspend(|| io::stdout().lock().write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
@@ -7,8 +7,8 @@ pub struct Prior {
     pub var: f64,
 }
 
-impl Prior {
-    pub fn default() -> Self {
+impl Default for Prior {
+    fn default() -> Self {
         Self {
             mean: 0.0,
             var: 0.25,
@@ -0,0 +1,301 @@
+//! Differences between two ranking runs: pairs that appeared or dropped out, score and rank
+//! movements, and threshold crossings. Each `rank` run is archived so runs can be compared.
+
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Settings;
+
+/// Archived `signals.csv` copies under the outputs directory, one per `rank` run.
+pub const RUNS_DIR: &str = "runs";
+
+/// The columns of `signals.csv` a diff compares.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RankedRow {
+    pub drug_id: String,
+    pub event_id: String,
+    pub year_quarter: String,
+    pub recent_ror: f64,
+    pub ci_low: f64,
+    pub score: f64,
+}
+
+/// One pair's score and rank (1 = highest score) in each run; `None` where it is absent.
+#[derive(Debug, Clone, Serialize)]
+pub struct PairChange {
+    pub drug_id: String,
+    pub event_id: String,
+    pub year_quarter: String,
+    pub score_before: Option<f64>,
+    pub score_after: Option<f64>,
+    pub score_delta: Option<f64>,
+    pub rank_before: Option<usize>,
+    pub rank_after: Option<usize>,
+    /// Places gained; negative when the pair fell.
+    pub rank_delta: Option<i64>,
+}
+
+/// Which side of a threshold a pair moved to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Above,
+    Below,
+}
+
+/// A pair in both runs whose `metric` moved across `threshold`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Crossing {
+    pub drug_id: String,
+    pub event_id: String,
+    /// `score` against the chosen threshold, or `ci_low` against 1 (the lower bound of the
+    /// ROR interval clearing 1).
+    pub metric: &'static str,
+    pub threshold: f64,
+    pub before: f64,
+    pub after: f64,
+    pub direction: Direction,
+}
+
+/// Everything that changed from `before` to `after`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SignalDiff {
+    pub before: String,
+    pub after: String,
+    pub score_threshold: f64,
+    pub pairs_before: usize,
+    pub pairs_after: usize,
+    /// Highest score first.
+    pub new_pairs: Vec<PairChange>,
+    /// Highest former score first.
+    pub dropped_pairs: Vec<PairChange>,
+    pub crossings: Vec<Crossing>,
+    /// Pairs in both runs whose score or rank changed, largest rank movement first.
+    pub changed: Vec<PairChange>,
+}
+
+/// Copy a freshly written `signals.csv` to `runs/<UTC timestamp>.csv`.
+pub fn archive_run(settings: &Settings, signals: &Path) -> Result<PathBuf> {
+    let dir = settings.join_output(RUNS_DIR);
+    std::fs::create_dir_all(&dir).with_context(|| format!("create {dir:?}"))?;
+    let path = dir.join(format!("{}.csv", Utc::now().format("%Y%m%dT%H%M%SZ")));
+    std::fs::copy(signals, &path).with_context(|| format!("copy {signals:?} to {path:?}"))?;
+    Ok(path)
+}
+
+/// Archived runs, oldest first.
+pub fn list_runs(settings: &Settings) -> Result<Vec<PathBuf>> {
+    let dir = settings.join_output(RUNS_DIR);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut runs = Vec::new();
+    for entry in std::fs::read_dir(&dir).with_context(|| format!("read {dir:?}"))? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) == Some("csv") {
+            runs.push(path);
+        }
+    }
+    runs.sort();
+    Ok(runs)
+}
+
+/// The `signals.csv` meant by `spec`: `latest` or `previous` archived run, a run id (or a
+/// unique prefix of one), a configured dataset name, a CSV file, or a directory holding
+/// `signals.csv` or `outputs/signals.csv`.
+pub fn resolve_run(settings: &Settings, spec: &str) -> Result<PathBuf> {
+    let runs = list_runs(settings)?;
+    let from_end = match spec {
+        "latest" => Some(1),
+        "previous" => Some(2),
+        _ => None,
+    };
+    if let Some(back) = from_end {
+        return match runs.len().checked_sub(back) {
+            Some(index) => Ok(runs[index].clone()),
+            None => bail!(
+                "only {} archived ranking runs; `{spec}` needs {back} (run rank again)",
+                runs.len()
+            ),
+        };
+    }
+    let path = Path::new(spec);
+    if path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+    if path.is_dir() {
+        for candidate in [path.join("signals.csv"), path.join("outputs/signals.csv")] {
+            if candidate.is_file() {
+                return Ok(candidate);
+            }
+        }
+        bail!("{path:?} holds no signals.csv");
+    }
+    if let Some(root) = settings.datasets.get(spec) {
+        return Ok(root.join("outputs/signals.csv"));
+    }
+    let matches: Vec<&PathBuf> = runs
+        .iter()
+        .filter(|run| {
+            run.file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|stem| stem.starts_with(spec))
+        })
+        .collect();
+    match matches.as_slice() {
+        [run] => Ok((*run).clone()),
+        [] => bail!(
+            "no run, dataset or file {spec:?}; archived runs: {}",
+            run_ids(&runs).join(", ")
+        ),
+        _ => bail!("run id {spec:?} is ambiguous; give more of the timestamp"),
+    }
+}
+
+fn run_ids(runs: &[PathBuf]) -> Vec<String> {
+    runs.iter()
+        .filter_map(|run| run.file_stem().and_then(|stem| stem.to_str()))
+        .map(str::to_string)
+        .collect()
+}
+
+pub fn read_run(path: &Path) -> Result<Vec<RankedRow>> {
+    let mut reader = csv::Reader::from_path(path).with_context(|| format!("open {path:?}"))?;
+    reader
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .with_context(|| format!("read {path:?}"))
+}
+
+/// Compare the runs at two paths.
+pub fn diff_runs(before: &Path, after: &Path, score_threshold: f64) -> Result<SignalDiff> {
+    let mut diff = diff(&read_run(before)?, &read_run(after)?, score_threshold);
+    diff.before = before.display().to_string();
+    diff.after = after.display().to_string();
+    Ok(diff)
+}
+
+pub fn diff(before: &[RankedRow], after: &[RankedRow], score_threshold: f64) -> SignalDiff {
+    let old = ranked(before);
+    let new = ranked(after);
+    let mut result = SignalDiff {
+        before: String::new(),
+        after: String::new(),
+        score_threshold,
+        pairs_before: old.len(),
+        pairs_after: new.len(),
+        new_pairs: Vec::new(),
+        dropped_pairs: Vec::new(),
+        crossings: Vec::new(),
+        changed: Vec::new(),
+    };
+    for (key, (rank, row)) in &new {
+        let Some((old_rank, old_row)) = old.get(key) else {
+            result.new_pairs.push(change(row, None, Some((*rank, row))));
+            continue;
+        };
+        for (metric, threshold, before, after) in [
+            ("score", score_threshold, old_row.score, row.score),
+            ("ci_low", 1.0, old_row.ci_low, row.ci_low),
+        ] {
+            let direction = match (before >= threshold, after >= threshold) {
+                (false, true) => Direction::Above,
+                (true, false) => Direction::Below,
+                _ => continue,
+            };
+            result.crossings.push(Crossing {
+                drug_id: row.drug_id.clone(),
+                event_id: row.event_id.clone(),
+                metric,
+                threshold,
+                before,
+                after,
+                direction,
+            });
+        }
+        let pair = change(row, Some((*old_rank, old_row)), Some((*rank, row)));
+        if pair.rank_delta != Some(0) || pair.score_delta.is_some_and(|d| d.abs() > 1e-9) {
+            result.changed.push(pair);
+        }
+    }
+    for (key, (rank, row)) in &old {
+        if !new.contains_key(key) {
+            result
+                .dropped_pairs
+                .push(change(row, Some((*rank, row)), None));
+        }
+    }
+    result
+        .new_pairs
+        .sort_by(|a, b| by_score(b.score_after, a.score_after));
+    result
+        .dropped_pairs
+        .sort_by(|a, b| by_score(b.score_before, a.score_before));
+    result
+        .crossings
+        .sort_by(|a, b| by_score(Some(b.after), Some(a.after)));
+    result.changed.sort_by(|a, b| {
+        let moved = |p: &PairChange| p.rank_delta.unwrap_or(0).abs();
+        moved(b)
+            .cmp(&moved(a))
+            .then_with(|| by_score(b.score_after, a.score_after))
+    });
+    result
+}
+
+type PairKey = (String, String);
+
+/// Rows by pair with their 1-based rank by descending score; NaN scores rank last.
+fn ranked(rows: &[RankedRow]) -> HashMap<PairKey, (usize, &RankedRow)> {
+    let mut order: Vec<&RankedRow> = rows.iter().collect();
+    order.sort_by(|a, b| {
+        by_score(Some(b.score), Some(a.score))
+            .then_with(|| (&a.drug_id, &a.event_id).cmp(&(&b.drug_id, &b.event_id)))
+    });
+    order
+        .into_iter()
+        .enumerate()
+        .map(|(index, row)| {
+            (
+                (row.drug_id.clone(), row.event_id.clone()),
+                (index + 1, row),
+            )
+        })
+        .collect()
+}
+
+fn by_score(a: Option<f64>, b: Option<f64>) -> Ordering {
+    let key = |v: Option<f64>| v.filter(|v| !v.is_nan()).unwrap_or(f64::NEG_INFINITY);
+    key(a).total_cmp(&key(b))
+}
+
+fn change(
+    row: &RankedRow,
+    before: Option<(usize, &RankedRow)>,
+    after: Option<(usize, &RankedRow)>,
+) -> PairChange {
+    let score_before = before.map(|(_, row)| row.score);
+    let score_after = after.map(|(_, row)| row.score);
+    let rank_before = before.map(|(rank, _)| rank);
+    let rank_after = after.map(|(rank, _)| rank);
+    PairChange {
+        drug_id: row.drug_id.clone(),
+        event_id: row.event_id.clone(),
+        year_quarter: row.year_quarter.clone(),
+        score_before,
+        score_after,
+        score_delta: score_before.zip(score_after).map(|(b, a)| a - b),
+        rank_before,
+        rank_after,
+        rank_delta: rank_before
+            .zip(rank_after)
+            .map(|(b, a)| b as i64 - a as i64),
+    }
+}
@@ -1,18 +1,24 @@
 //! Signal computation and ranking layer.
 
 pub mod bayes;
+pub mod diff;
 pub mod ror;
 pub mod trend;
 
 use std::{collections::HashMap, fs::File};
 
 use anyhow::Result;
+use clap::ValueEnum;
 use polars::prelude::{
     CsvWriter, DataFrame, NamedFrom, ParquetReader, ParquetWriter, SerReader, SerWriter, Series,
 };
 use tracing::{info, warn};
 
-use crate::config::Settings;
+use crate::{
+    config::Settings,
+    data::{dailymed, pubmed::PublicationType, sider, smq},
+    progress,
+};
 
 #[derive(Debug, Clone)]
 struct MetricRow {
@@ -84,7 +90,9 @@ pub async fn compute(settings: &Settings) -> Result<()> {
 
     let mut metrics = Vec::new();
     let mut log_rors = Vec::new();
+    let bar = progress::items("computing disproportionality", rows.len() as u64, "pairs");
     for row in &rows {
+        bar.inc(1);
         let (ror_value, ci_low, ci_high, variance) =
             ror::ror_with_ci(row.a as f64, row.b as f64, row.c as f64, row.d as f64);
         let log_ror = ror_value.ln();
@@ -104,6 +112,7 @@ pub async fn compute(settings: &Settings) -> Result<()> {
             trend_z: 0.0,
         });
     }
+    bar.finish_and_clear();
 
     let prior = bayes::estimate_prior(&log_rors);
     for metric in &mut metrics {
@@ -118,6 +127,9 @@ pub async fn compute(settings: &Settings) -> Result<()> {
     Ok(())
 }
 
+/// Quarter, log ROR, variance, shrunk ROR, shrunk CI bounds and trend z of a pair's latest row.
+type LatestMetrics = (String, f64, f64, f64, f64, f64, f64);
+
 pub async fn rank(settings: &Settings) -> Result<()> {
     let metrics_path = settings.join_data("clean/signal_metrics.parquet");
     if !metrics_path.exists() {
@@ -171,8 +183,7 @@ pub async fn rank(settings: &Settings) -> Result<()> {
         }
     }
 
-    let mut latest: HashMap<(String, String), (String, f64, f64, f64, f64, f64, f64)> =
-        HashMap::new();
+    let mut latest: HashMap<(String, String), LatestMetrics> = HashMap::new();
     for row in rows {
         let key = (row.0.clone(), row.1.clone());
         let order = trend::parse_quarter(&row.2).unwrap_or((0, 0));
@@ -186,13 +197,22 @@ pub async fn rank(settings: &Settings) -> Result<()> {
     }
 
     let lit_counts = literature_support(settings)?;
+    let labeled_pairs = dailymed::load_labeled_pairs(settings)?;
+    let known_pairs = sider::load_known_pairs(settings)?;
+    let smq_labels = smq::load_labels(settings)?;
 
     let mut out_rows = Vec::new();
     for (key, value) in latest {
         let (quarter, log_ror, variance, shrunk, ci_low, ci_high, trend_z) = value;
         let z_recent = ror::z_score(log_ror, variance);
-        let lit_support = lit_counts.get(&key).cloned().unwrap_or(0);
-        let score = z_recent + 0.3 * ((lit_support + 1) as f64).ln() + 0.2 * trend_z;
+        let weighted_support = lit_counts.get(&key).cloned().unwrap_or(0.0);
+        let lit_support = weighted_support.round() as i64;
+        let weights = &settings.score_weights;
+        let score =
+            z_recent + weights.literature * (weighted_support + 1.0).ln() + weights.trend * trend_z;
+        let labeled = labeled_pairs.contains(&key);
+        let sider_known = known_pairs.contains(&key);
+        let smq_label = smq_labels.get(&key.1).cloned().unwrap_or_default();
         out_rows.push((
             key.0,
             key.1,
@@ -203,6 +223,9 @@ pub async fn rank(settings: &Settings) -> Result<()> {
             lit_support,
             trend_z,
             score,
+            labeled,
+            sider_known,
+            smq_label,
         ));
     }
 
@@ -238,7 +261,7 @@ pub async fn rank(settings: &Settings) -> Result<()> {
         ),
         Series::new(
             "lit_support".into(),
-            out_rows.iter().map(|r| r.6 as i64).collect::<Vec<_>>(),
+            out_rows.iter().map(|r| r.6).collect::<Vec<_>>(),
         ),
         Series::new(
             "trend_z".into(),
@@ -248,6 +271,18 @@ pub async fn rank(settings: &Settings) -> Result<()> {
             "score".into(),
             out_rows.iter().map(|r| r.8).collect::<Vec<_>>(),
         ),
+        Series::new(
+            "labeled".into(),
+            out_rows.iter().map(|r| r.9).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "sider_known".into(),
+            out_rows.iter().map(|r| r.10).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "smq".into(),
+            out_rows.iter().map(|r| r.11.clone()).collect::<Vec<_>>(),
+        ),
     ])?;
     let out_path = settings.join_output("signals.csv");
     if let Some(parent) = out_path.parent() {
@@ -256,9 +291,120 @@ pub async fn rank(settings: &Settings) -> Result<()> {
     let mut file = File::create(&out_path)?;
     CsvWriter::new(&mut file).finish(&mut df)?;
     info!(path = %out_path.display(), rows = df.height(), "wrote ranked signals");
+    let run = diff::archive_run(settings, &out_path)?;
+    info!(path = %run.display(), "archived ranking run for diff");
     Ok(())
 }
 
+/// One quarter of `signal_metrics.parquet` for a drug-event pair.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct QuarterMetric {
+    pub year_quarter: String,
+    pub ror: f64,
+    pub ci_low: f64,
+    pub ci_high: f64,
+    pub ror_shrunk: f64,
+    pub shrunk_ci_low: f64,
+    pub shrunk_ci_high: f64,
+    pub trend_z: f64,
+}
+
+/// 2x2 table of one pair in one quarter: `a` cases with drug and event, `b` drug without
+/// event, `c` event without drug, `d` neither.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Contingency {
+    pub a: i64,
+    pub b: i64,
+    pub c: i64,
+    pub d: i64,
+}
+
+/// Counts of every event reported with `drug_id`, keyed by `(event_id, year_quarter)`; empty
+/// before `normalize` has run.
+pub fn drug_contingency(
+    settings: &Settings,
+    drug_id: &str,
+) -> Result<HashMap<(String, String), Contingency>> {
+    let path = settings.join_data("clean/faers_norm.parquet");
+    let mut counts = HashMap::new();
+    if !path.exists() {
+        return Ok(counts);
+    }
+    let df = ParquetReader::new(File::open(&path)?).finish()?;
+    let drug_col = df.column("drug_id")?.str()?;
+    let event_col = df.column("event_id")?.str()?;
+    let quarter_col = df.column("year_quarter")?.str()?;
+    let a_col = df.column("a")?.i64()?;
+    let b_col = df.column("b")?.i64()?;
+    let c_col = df.column("c")?.i64()?;
+    let d_col = df.column("d")?.i64()?;
+    for i in 0..df.height() {
+        let (Some(drug), Some(event), Some(quarter)) =
+            (drug_col.get(i), event_col.get(i), quarter_col.get(i))
+        else {
+            continue;
+        };
+        if !drug.eq_ignore_ascii_case(drug_id) {
+            continue;
+        }
+        if let (Some(a), Some(b), Some(c), Some(d)) =
+            (a_col.get(i), b_col.get(i), c_col.get(i), d_col.get(i))
+        {
+            counts.insert(
+                (event.to_string(), quarter.to_string()),
+                Contingency { a, b, c, d },
+            );
+        }
+    }
+    Ok(counts)
+}
+
+/// Every computed quarter for the pair, oldest first; empty before `signal` has run.
+pub fn pair_history(
+    settings: &Settings,
+    drug_id: &str,
+    event_id: &str,
+) -> Result<Vec<QuarterMetric>> {
+    let path = settings.join_data("clean/signal_metrics.parquet");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let df = ParquetReader::new(File::open(&path)?).finish()?;
+    let drug_col = df.column("drug_id")?.str()?;
+    let event_col = df.column("event_id")?.str()?;
+    let quarter_col = df.column("year_quarter")?.str()?;
+    let ror_col = df.column("ror")?.f64()?;
+    let lo_col = df.column("ci_low")?.f64()?;
+    let hi_col = df.column("ci_high")?.f64()?;
+    let shrunk_col = df.column("ror_shrunk")?.f64()?;
+    let shrunk_lo_col = df.column("shrunk_ci_low")?.f64()?;
+    let shrunk_hi_col = df.column("shrunk_ci_high")?.f64()?;
+    let trend_col = df.column("trend_z")?.f64()?;
+    let mut history = Vec::new();
+    for i in 0..df.height() {
+        let (Some(drug), Some(event), Some(quarter)) =
+            (drug_col.get(i), event_col.get(i), quarter_col.get(i))
+        else {
+            continue;
+        };
+        if !drug.eq_ignore_ascii_case(drug_id) || !event.eq_ignore_ascii_case(event_id) {
+            continue;
+        }
+        history.push(QuarterMetric {
+            year_quarter: quarter.to_string(),
+            ror: ror_col.get(i).unwrap_or(f64::NAN),
+            ci_low: lo_col.get(i).unwrap_or(f64::NAN),
+            ci_high: hi_col.get(i).unwrap_or(f64::NAN),
+            ror_shrunk: shrunk_col.get(i).unwrap_or(f64::NAN),
+            shrunk_ci_low: shrunk_lo_col.get(i).unwrap_or(f64::NAN),
+            shrunk_ci_high: shrunk_hi_col.get(i).unwrap_or(f64::NAN),
+            trend_z: trend_col.get(i).unwrap_or(0.0),
+        });
+    }
+    history.sort_by_key(|metric| trend::parse_quarter(&metric.year_quarter).unwrap_or((0, 0)));
+    Ok(history)
+}
+
 fn apply_trend_scores(metrics: &mut [MetricRow]) {
     let mut grouped: HashMap<(String, String), Vec<usize>> = HashMap::new();
     for (idx, metric) in metrics.iter().enumerate() {
@@ -350,22 +496,68 @@ fn persist_metrics(settings: &Settings, metrics: &[MetricRow]) -> Result<()> {
     Ok(())
 }
 
-fn literature_support(settings: &Settings) -> Result<HashMap<(String, String), i64>> {
+/// Summed relation confidence per drug-event pair; speculative and conclusions sentences
+/// count their configured `score_weights` share of their confidence, and every sentence is
+/// scaled by the configured weight of its study design.
+fn literature_support(settings: &Settings) -> Result<HashMap<(String, String), f64>> {
     let path = settings.join_data("clean/relations.parquet");
     if !path.exists() {
         return Ok(HashMap::new());
     }
     let df = ParquetReader::new(File::open(&path)?).finish()?;
-    let mut counts: HashMap<(String, String), i64> = HashMap::new();
+    let mut support: HashMap<(String, String), f64> = HashMap::new();
     let drug_col = df.column("drug_id")?.str()?;
     let event_col = df.column("event_id")?.str()?;
-    for (drug, event) in drug_col
-        .into_no_null_iter()
-        .zip(event_col.into_no_null_iter())
+    let confidence_col = df.column("confidence")?.f64()?;
+    // Relations written before hedging detection carry no `speculative` column.
+    let speculative: Vec<bool> = match df.column("speculative") {
+        Ok(column) => column
+            .bool()?
+            .into_iter()
+            .map(|flag| flag.unwrap_or(false))
+            .collect(),
+        Err(_) => vec![false; df.height()],
+    };
+    // Likewise for `section`, added with structured-abstract parsing.
+    let conclusions: Vec<bool> = match df.column("section") {
+        Ok(column) => column
+            .str()?
+            .into_iter()
+            .map(|section| section == Some("conclusions"))
+            .collect(),
+        Err(_) => vec![false; df.height()],
+    };
+    let study_types: Vec<Option<PublicationType>> = match df.column("study_type") {
+        Ok(column) => column
+            .str()?
+            .into_iter()
+            .map(|name| name.and_then(|name| PublicationType::from_str(name, true).ok()))
+            .collect(),
+        Err(_) => vec![None; df.height()],
+    };
+    for (((((drug, event), confidence), speculative), conclusions), study_type) in drug_col
+        .into_iter()
+        .zip(event_col)
+        .zip(confidence_col)
+        .zip(speculative)
+        .zip(conclusions)
+        .zip(study_types)
     {
-        *counts
+        let (Some(drug), Some(event)) = (drug, event) else {
+            continue;
+        };
+        let mut weight = if speculative {
+            settings.score_weights.speculative
+        } else {
+            1.0
+        };
+        if conclusions {
+            weight *= settings.score_weights.conclusions;
+        }
+        weight *= settings.study_type_weights.weight(study_type);
+        *support
             .entry((drug.to_string(), event.to_string()))
-            .or_insert(0) += 1;
+            .or_insert(0.0) += weight * confidence.unwrap_or(0.0);
     }
-    Ok(counts)
+    Ok(support)
 }
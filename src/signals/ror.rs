@@ -15,7 +15,7 @@ pub fn ror_with_ci(a: f64, b: f64, c: f64, d: f64) -> (f64, f64, f64, f64) {
 }
 
 fn continuity_correct(a: f64, b: f64, c: f64, d: f64) -> (f64, f64, f64, f64) {
-    if [a, b, c, d].iter().any(|&x| x == 0.0) {
+    if [a, b, c, d].contains(&0.0) {
         (a + 0.5, b + 0.5, c + 0.5, d + 0.5)
     } else {
         (a, b, c, d)
@@ -32,10 +32,49 @@ pub fn rolling_z(values: &[(i32, u8, f64)]) -> f64 {
 
 /// Convert a quarter string like 2024Q1 into sortable tuple.
 pub fn parse_quarter(quarter: &str) -> Option<(i32, u8)> {
-    if quarter.len() != 6 {
+    let bytes = quarter.as_bytes();
+    if bytes.len() != 6 || !bytes.is_ascii() || !bytes[4].eq_ignore_ascii_case(&b'Q') {
         return None;
     }
     let year: i32 = quarter[0..4].parse().ok()?;
     let q: u8 = quarter[5..6].parse().ok()?;
     Some((year, q))
 }
+
+/// Derive a quarter string (e.g., 2024Q1) from an ISO report date such as `20240315`,
+/// `2024-03-15` or `2024-03`.
+///
+/// Day-first and month-first dates (`15/03/2024`, `03/15/2024`) are ambiguous and give `None`.
+pub fn quarter_from_date(date: &str) -> Option<String> {
+    let date = date.trim();
+    let year = date.get(0..4)?;
+    let rest = date.get(4..)?;
+    let month = rest.strip_prefix(['-', '/']).unwrap_or(rest).get(0..2)?;
+    if !year
+        .bytes()
+        .chain(month.bytes())
+        .all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let year: i32 = year.parse().ok()?;
+    let month: u8 = month.parse().ok()?;
+    if !(1..=12).contains(&month) {
+        return None;
+    }
+    Some(format!("{year}Q{}", (month - 1) / 3 + 1))
+}
+
+/// The quarter following `(year, quarter)`.
+pub fn next_quarter((year, quarter): (i32, u8)) -> (i32, u8) {
+    if quarter >= 4 {
+        (year + 1, 1)
+    } else {
+        (year, quarter + 1)
+    }
+}
+
+/// Format a `(year, quarter)` tuple as `2024Q1`.
+pub fn format_quarter((year, quarter): (i32, u8)) -> String {
+    format!("{year}Q{quarter}")
+}
@@ -4,6 +4,13 @@
     if(!res.ok){ throw new Error(`HTTP ${res.status}`); }
     return res.json();
   }
+  // Cells are filled through textContent, so names from imported reports stay text.
+  function cell(text, title){
+    const td = document.createElement('td');
+    td.textContent = String(text);
+    if(title !== undefined){ td.title = String(title); }
+    return td;
+  }
   function render(rows){
     const tbody = document.querySelector('#results');
     if(!tbody){ return; }
@@ -11,22 +18,24 @@
       tbody.innerHTML = '<tr><td colspan="8">No results.</td></tr>';
       return;
     }
-    tbody.innerHTML = rows.map(r => `
-      <tr>
-        <td>${r.drug_id}</td>
-        <td>${r.event_id}</td>
-        <td>${r.year_quarter}</td>
-        <td>${r.recent_ror.toFixed(2)}</td>
-        <td>${r.ci_low.toFixed(2)} – ${r.ci_high.toFixed(2)}</td>
-        <td>${r.lit_support}</td>
-        <td>${r.trend_z.toFixed(2)}</td>
-        <td>${r.score.toFixed(2)}</td>
-      </tr>
-    `).join('');
+    tbody.replaceChildren(...rows.map(r => {
+      const tr = document.createElement('tr');
+      tr.append(
+        cell(r.drug_name ?? r.drug_id, r.drug_id),
+        cell(r.event_term ?? r.event_id, r.event_id),
+        cell(r.year_quarter),
+        cell(r.recent_ror.toFixed(2)),
+        cell(`${r.ci_low.toFixed(2)} – ${r.ci_high.toFixed(2)}`),
+        cell(r.lit_support),
+        cell(r.trend_z.toFixed(2)),
+        cell(r.score.toFixed(2)),
+      );
+      return tr;
+    }));
   }
   async function loadInitial(){
     try{
-      const rows = await request('/signals');
+      const rows = await request('v1/signals');
       render(rows);
     }catch(err){
       console.error(err);
@@ -38,7 +47,7 @@
       event.preventDefault();
       const data = new FormData(form);
       const drug = data.get('drug');
-      const url = drug ? `/signals?drug=${encodeURIComponent(drug)}` : '/signals';
+      const url = drug ? `v1/signals?drug=${encodeURIComponent(drug)}` : 'v1/signals';
       try {
         const rows = await request(url);
         render(rows);
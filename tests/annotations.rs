@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use rwe_assistant::{
+    api::{self, auth::KeyId, datasets, graphql, jobs::Jobs, metrics, AppState},
+    config::Settings,
+};
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn annotations_record_the_key_beside_the_self_reported_author() {
+    let root = tempfile::tempdir().unwrap();
+    let mut settings = Settings::load(None).unwrap().for_dataset(root.path());
+    settings.api_keys = vec!["s3cret".to_string()];
+    settings.rate_limit_per_second = 0;
+    std::fs::create_dir_all(settings.join_data("")).unwrap();
+    let app = api::router(AppState {
+        datasets: Arc::new(datasets::from_settings(&settings)),
+        settings,
+        metrics: metrics::install().unwrap(),
+        jobs: Jobs::start().unwrap(),
+        graphql: graphql::schema(),
+    })
+    .unwrap();
+
+    let request = Request::post("/v1/signals/imatinib/rash/annotations")
+        .header("authorization", "Bearer s3cret")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            r#"{"author": "jdoe", "text": "Listed in label."}"#,
+        ))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(status, StatusCode::CREATED, "{body:?}");
+    let note: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(note["author"], "jdoe");
+    assert_eq!(note["key_id"], KeyId::of("s3cret").0);
+    assert!(!body.windows(6).any(|window| window == b"s3cret"));
+}
@@ -0,0 +1,71 @@
+use anyhow::Context;
+use axum::{
+    body::{to_bytes, Body},
+    http::{header::CONTENT_TYPE, Request, StatusCode},
+    middleware,
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use rwe_assistant::api::error::{self, ApiError};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+async fn body_json(response: axum::response::Response) -> Value {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn errors_carry_code_message_and_detail() {
+    let response = ApiError::not_found("no watchlist hepatic").into_response();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(
+        body_json(response).await,
+        json!({"code": "not_found", "message": "no watchlist hepatic", "detail": null})
+    );
+
+    let err = Err::<(), _>(anyhow::anyhow!("file missing"))
+        .context("read signals.csv")
+        .unwrap_err();
+    let response = ApiError::from(err).into_response();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    // The chain stays in the log; the client only gets a reference to it.
+    let body = body_json(response).await;
+    assert_eq!(body["code"], "internal");
+    assert_eq!(body["message"], "internal error");
+    let detail = body["detail"].as_str().unwrap();
+    assert!(detail.starts_with("reference "), "{detail}");
+    assert!(!detail.contains("signals.csv"), "{detail}");
+}
+
+#[tokio::test]
+async fn plain_text_errors_are_wrapped() {
+    let app = Router::new()
+        .route(
+            "/plain",
+            get(|| async { (StatusCode::BAD_REQUEST, "bad flag") }),
+        )
+        .route("/ok", get(|| async { "fine" }))
+        .layer(middleware::from_fn(error::envelope));
+
+    let request = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request("/plain")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+    assert_eq!(
+        body_json(response).await,
+        json!({"code": "bad_request", "message": "bad flag", "detail": null})
+    );
+
+    let response = app.clone().oneshot(request("/missing")).await.unwrap();
+    assert_eq!(
+        body_json(response).await,
+        json!({"code": "not_found", "message": "Not Found", "detail": null})
+    );
+
+    let response = app.oneshot(request("/ok")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&bytes[..], b"fine");
+}
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    middleware,
+    routing::get,
+    Router,
+};
+use rwe_assistant::{api::auth, config::Settings};
+use tower::ServiceExt;
+
+/// A signals route, the health probe and a stand-in for the static UI fallback behind the
+/// auth layer.
+fn app(keys: &[&str], anonymous_read: bool) -> Router {
+    let mut settings = Settings::load(None).unwrap();
+    settings.api_keys = keys.iter().map(|key| key.to_string()).collect();
+    settings.api_anonymous_read = anonymous_read;
+    Router::new()
+        .route(
+            "/v1/signals",
+            get(|| async { "signals" }).post(|| async { "saved" }),
+        )
+        .route("/healthz", get(|| async { "ok" }))
+        .fallback(|| async { "ui" })
+        .layer(middleware::from_fn_with_state(
+            Arc::new(settings),
+            auth::require_key,
+        ))
+}
+
+async fn status(app: &Router, method: &str, uri: &str, key: Option<&str>) -> StatusCode {
+    let mut request = Request::builder().method(method).uri(uri);
+    if let Some(key) = key {
+        request = request.header("authorization", format!("Bearer {key}"));
+    }
+    let request = request.body(Body::empty()).unwrap();
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn without_keys_every_request_passes() {
+    let app = app(&[], false);
+    assert_eq!(
+        status(&app, "GET", "/v1/signals", None).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status(&app, "POST", "/v1/signals", None).await,
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn configured_keys_guard_the_api_but_not_the_ui_files() {
+    let app = app(&["s3cret"], false);
+    for uri in ["/v1/signals", "/metrics", "/../Cargo.toml"] {
+        assert_eq!(
+            status(&app, "GET", uri, None).await,
+            StatusCode::UNAUTHORIZED,
+            "{uri}"
+        );
+    }
+    assert_eq!(
+        status(&app, "GET", "/v1/signals", Some("wrong")).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        status(&app, "GET", "/v1/signals", Some("s3cret")).await,
+        StatusCode::OK
+    );
+    for uri in ["/", "/index.html", "/htmx.min.js", "/style.css", "/healthz"] {
+        assert_eq!(
+            status(&app, "GET", uri, None).await,
+            StatusCode::OK,
+            "{uri}"
+        );
+    }
+    assert_eq!(
+        status(&app, "POST", "/index.html", None).await,
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[tokio::test]
+async fn anonymous_read_lets_reads_through_without_a_key() {
+    let app = app(&["s3cret"], true);
+    assert_eq!(
+        status(&app, "GET", "/v1/signals", None).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status(&app, "POST", "/v1/signals", None).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        status(&app, "GET", "/v1/signals", Some("wrong")).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        status(&app, "POST", "/v1/signals", Some("s3cret")).await,
+        StatusCode::OK
+    );
+
+    // Job status and logs need a key even for reads.
+    for uri in ["/v1/jobs", "/jobs/1", "/datasets/default/v1/jobs"] {
+        assert_eq!(
+            status(&app, "GET", uri, None).await,
+            StatusCode::UNAUTHORIZED,
+            "{uri}"
+        );
+    }
+    assert_eq!(
+        status(&app, "GET", "/v1/jobs", Some("s3cret")).await,
+        StatusCode::OK
+    );
+}
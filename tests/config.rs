@@ -0,0 +1,48 @@
+use rwe_assistant::config::config_vars;
+
+#[test]
+fn config_file_flattens_to_environment_names() {
+    let vars = config_vars(
+        r#"
+        data_dir = "/srv/data"
+        offline = true
+        embedding_providers = ["cuda", "cpu"]
+
+        [summary]
+        temperature = 0.2
+        max_tokens = 256
+
+        [study_type_weights]
+        rct = 2
+        case-report = 0.25
+        "#,
+    )
+    .unwrap();
+    let get = |key: &str| {
+        vars.iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    };
+    assert_eq!(get("DATA_DIR"), Some("/srv/data"));
+    assert_eq!(get("OFFLINE"), Some("true"));
+    assert_eq!(get("EMBEDDING_PROVIDERS"), Some("cuda,cpu"));
+    assert_eq!(get("SUMMARY_TEMPERATURE"), Some("0.2"));
+    assert_eq!(get("SUMMARY_MAX_TOKENS"), Some("256"));
+    assert_eq!(get("STUDY_TYPE_WEIGHTS"), Some("case-report=0.25,rct=2"));
+}
+
+#[test]
+fn unknown_config_keys_are_rejected() {
+    assert!(config_vars("data_dri = \"/srv/data\"").is_err());
+    assert!(config_vars("[summary]\ntemprature = 0.2").is_err());
+}
+
+#[test]
+fn log_table_sets_logging_variables() {
+    let vars =
+        config_vars("[log]\nformat = \"json\"\nfile = \"logs/rwe.log\"\nrotation = \"hourly\"")
+            .unwrap();
+    assert!(vars.contains(&("LOG_FORMAT".into(), "json".into())));
+    assert!(vars.contains(&("LOG_FILE".into(), "logs/rwe.log".into())));
+    assert!(vars.contains(&("LOG_ROTATION".into(), "hourly".into())));
+}
@@ -0,0 +1,87 @@
+use polars::prelude::*;
+use rwe_assistant::{
+    config::Settings,
+    data::normalize::{self, NormalizeOptions},
+    signals,
+};
+
+#[tokio::test]
+async fn cumulative_tables_rank_under_their_latest_quarter() {
+    let root = tempfile::tempdir().unwrap();
+    std::env::set_var("DATA_DIR", root.path().join("data"));
+    std::env::set_var("OUTPUTS_DIR", root.path().join("outputs"));
+    std::env::set_var("OFFLINE", "true");
+    let settings = Settings::load(None).unwrap();
+    let raw = settings.join_data("raw/faers");
+    std::fs::create_dir_all(&raw).unwrap();
+    // Case 1 is followed up in 2024Q2, so only its later version counts.
+    for (quarter, rows) in [
+        (
+            "2024Q1",
+            "1,IMATINIB,Nausea\n2,IMATINIB,Rash\n3,ASPIRIN,Rash\n",
+        ),
+        ("2024Q2", "1,IMATINIB,Rash\n4,ASPIRIN,Nausea\n"),
+    ] {
+        let body: String = rows
+            .lines()
+            .map(|row| format!("{row},{quarter},PS\n"))
+            .collect();
+        std::fs::write(
+            raw.join(format!("faers_{quarter}.csv")),
+            format!("CASEID,DRUGNAME,PT,YEAR_QUARTER,ROLE_COD\n{body}"),
+        )
+        .unwrap();
+    }
+
+    let options = NormalizeOptions {
+        cumulative: true,
+        ..Default::default()
+    };
+    normalize::canonicalise(&settings, &options).await.unwrap();
+    let norm = ParquetReader::new(
+        std::fs::File::open(settings.join_data("clean/faers_norm.parquet")).unwrap(),
+    )
+    .finish()
+    .unwrap();
+    for (column, expected) in [("year_quarter", "2024Q2"), ("period", "2024Q1-2024Q2")] {
+        let values = norm.column(column).unwrap().str().unwrap();
+        assert!(values.into_iter().all(|v| v == Some(expected)), "{column}");
+    }
+
+    // Four cases once case 1 counts only from 2024Q2; its first version would add a fifth.
+    let column = |name: &str| norm.column(name).unwrap().clone();
+    let (drugs, events) = (column("drug_id"), column("event_id"));
+    let counts: Vec<_> = ["a", "b", "c", "d"].map(column).into_iter().collect();
+    let mut tables = std::collections::BTreeMap::new();
+    for row in 0..norm.height() {
+        let id = |series: &Series| series.str().unwrap().get(row).unwrap().to_string();
+        let cells: Vec<i64> = counts
+            .iter()
+            .map(|series| series.i64().unwrap().get(row).unwrap())
+            .collect();
+        tables.insert((id(&drugs), id(&events)), cells);
+    }
+    let pair = |drug: &str, event: &str| {
+        (
+            normalize::stable_id('D', drug),
+            normalize::stable_id('E', event),
+        )
+    };
+    let expected = std::collections::BTreeMap::from([
+        (pair("imatinib", "rash"), vec![2, 0, 1, 1]),
+        (pair("aspirin", "rash"), vec![1, 1, 2, 0]),
+        (pair("aspirin", "nausea"), vec![1, 1, 0, 2]),
+    ]);
+    assert_eq!(tables, expected);
+
+    signals::compute(&settings).await.unwrap();
+    signals::rank(&settings).await.unwrap();
+    let ranked = CsvReadOptions::default()
+        .try_into_reader_with_file_path(Some(settings.join_output("signals.csv")))
+        .unwrap()
+        .finish()
+        .unwrap();
+    assert_eq!(ranked.height(), norm.height());
+    let quarters = ranked.column("year_quarter").unwrap().str().unwrap();
+    assert!(quarters.into_iter().all(|q| q == Some("2024Q2")));
+}
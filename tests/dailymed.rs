@@ -0,0 +1,23 @@
+use rwe_assistant::{config::Settings, data::dailymed};
+
+#[tokio::test]
+async fn labels_are_cached_under_the_canonical_ingredient() {
+    let root = tempfile::tempdir().unwrap();
+    let mut settings = Settings::load(None).unwrap().for_dataset(root.path());
+    settings.offline = true;
+    std::fs::create_dir_all(settings.join_output("")).unwrap();
+    let labels = settings.join_data("raw/dailymed");
+    std::fs::create_dir_all(&labels).unwrap();
+    std::fs::write(labels.join("imatinib.txt"), "Edema and nausea were common.").unwrap();
+
+    // A brand name finds the section fetched for its ingredient.
+    let paths = dailymed::fetch_labels(&["Gleevec".to_string()], &settings)
+        .await
+        .unwrap();
+    assert_eq!(paths, [labels.join("imatinib.txt")]);
+
+    let err = dailymed::fetch_labels(&["Tasigna".to_string()], &settings)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Tasigna"), "{err:#}");
+}
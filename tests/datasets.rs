@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    Router,
+};
+use rwe_assistant::{
+    api::{self, datasets, graphql, jobs::Jobs, metrics, AppState},
+    config::Settings,
+};
+use tower::ServiceExt;
+
+async fn status(app: &Router, uri: &str, dataset: Option<&str>, key: Option<&str>) -> StatusCode {
+    let mut request = Request::builder().uri(uri);
+    if let Some(dataset) = dataset {
+        request = request.header("x-dataset", dataset);
+    }
+    if let Some(key) = key {
+        request = request.header("authorization", format!("Bearer {key}"));
+    }
+    let request = request.body(Body::empty()).unwrap();
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn dataset_names_are_only_resolved_for_authenticated_requests() {
+    let root = tempfile::tempdir().unwrap();
+    let mut settings = Settings::load(None).unwrap();
+    settings.api_keys = vec!["s3cret".to_string()];
+    settings.rate_limit_per_second = 0;
+    settings
+        .datasets
+        .insert("oncology".to_string(), root.path().to_path_buf());
+    let app = api::router(AppState {
+        datasets: Arc::new(datasets::from_settings(&settings)),
+        settings,
+        metrics: metrics::install().unwrap(),
+        jobs: Jobs::start().unwrap(),
+        graphql: graphql::schema(),
+    })
+    .unwrap();
+
+    // Known and unknown names look the same without a key, UI files included.
+    for name in ["oncology", "nope"] {
+        for uri in [
+            format!("/datasets/{name}/v1/datasets"),
+            format!("/datasets/{name}/"),
+        ] {
+            assert_eq!(
+                status(&app, &uri, None, None).await,
+                StatusCode::UNAUTHORIZED,
+                "{uri}"
+            );
+        }
+        for uri in ["/v1/datasets", "/index.html"] {
+            assert_eq!(
+                status(&app, uri, Some(name), None).await,
+                StatusCode::UNAUTHORIZED,
+                "{uri} for {name}"
+            );
+        }
+    }
+    assert_eq!(
+        status(&app, "/index.html", None, None).await,
+        StatusCode::OK
+    );
+
+    let key = Some("s3cret");
+    assert_eq!(
+        status(&app, "/datasets/oncology/v1/datasets", None, key).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status(&app, "/v1/datasets", Some("oncology"), key).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status(&app, "/datasets/nope/v1/datasets", None, key).await,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        status(&app, "/v1/datasets", Some("nope"), key).await,
+        StatusCode::NOT_FOUND
+    );
+}
@@ -0,0 +1,140 @@
+use polars::prelude::*;
+use rwe_assistant::{
+    config::Settings,
+    data::{
+        device::{quarter_bounds, DeviceReport},
+        normalize::{self, NormalizeOptions},
+    },
+};
+
+#[test]
+fn quarter_bounds_cover_the_whole_quarter() {
+    assert_eq!(
+        quarter_bounds("2024Q1").unwrap(),
+        ("20240101".to_string(), "20240331".to_string())
+    );
+    assert_eq!(
+        quarter_bounds("2023q4").unwrap(),
+        ("20231001".to_string(), "20231231".to_string())
+    );
+    for bad in ["2024Q5", "2024Q0", "2024", "24Q1"] {
+        assert!(quarter_bounds(bad).is_err(), "{bad}");
+    }
+}
+
+fn report(json: &str) -> DeviceReport {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn reports_expand_to_device_and_problem_rows() {
+    let (quarter, rows) = report(
+        r#"{
+            "report_number": "123",
+            "date_received": "20240512",
+            "device": [{"brand_name": " PumpX ", "generic_name": "insulin pump"},
+                       {"generic_name": "infusion set"}],
+            "patient": [{"patient_problems": ["Hypoglycaemia", "Fall"]}],
+            "product_problems": ["Battery Problem"]
+        }"#,
+    )
+    .into_rows("2024Q1")
+    .unwrap();
+    assert_eq!(quarter, "2024Q2");
+    let rows: Vec<[&str; 3]> = rows
+        .iter()
+        .map(|[case, device, problem]| [case.as_str(), device.as_str(), problem.as_str()])
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ["DEV123", "PumpX", "Hypoglycaemia"],
+            ["DEV123", "PumpX", "Fall"],
+            ["DEV123", "infusion set", "Hypoglycaemia"],
+            ["DEV123", "infusion set", "Fall"],
+        ]
+    );
+}
+
+#[test]
+fn reports_fall_back_to_product_problems_key_and_quarter() {
+    let (quarter, rows) = report(
+        r#"{
+            "mdr_report_key": "9",
+            "device": [{"brand_name": "Pacer"}],
+            "patient": [{"patient_problems": []}],
+            "product_problems": ["Battery Problem"]
+        }"#,
+    )
+    .into_rows("2024Q3")
+    .unwrap();
+    assert_eq!(quarter, "2024Q3");
+    assert_eq!(
+        rows,
+        [[
+            "DEV9".to_string(),
+            "Pacer".to_string(),
+            "Battery Problem".to_string()
+        ]]
+    );
+
+    // Without a report number or key there is no case to count.
+    assert!(report(r#"{"device": [{"brand_name": "Pacer"}]}"#)
+        .into_rows("2024Q3")
+        .is_none());
+}
+
+fn write_raw(settings: &Settings, source: &str, row: &str) {
+    let dir = settings.join_data(format!("raw/{source}"));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join(format!("{source}_2024Q1.csv")),
+        format!("CASEID,DRUGNAME,PT,YEAR_QUARTER\n{row},2024Q1\n"),
+    )
+    .unwrap();
+}
+
+/// Cases in the first contingency table (`a + b + c + d`) and the drug names counted.
+fn counted(settings: &Settings) -> (i64, Vec<String>) {
+    let read = |name: &str| {
+        ParquetReader::new(std::fs::File::open(settings.join_data(name)).unwrap())
+            .finish()
+            .unwrap()
+    };
+    let norm = read("clean/faers_norm.parquet");
+    let cases = ["a", "b", "c", "d"]
+        .iter()
+        .map(|name| norm.column(name).unwrap().i64().unwrap().get(0).unwrap())
+        .sum();
+    let drugs = read("clean/drugs.parquet")
+        .column("name_canonical")
+        .unwrap()
+        .str()
+        .unwrap()
+        .into_no_null_iter()
+        .map(str::to_string)
+        .collect();
+    (cases, drugs)
+}
+
+#[tokio::test]
+async fn device_reports_are_only_counted_when_asked_for() {
+    let root = tempfile::tempdir().unwrap();
+    let mut settings = Settings::load(None).unwrap().for_dataset(root.path());
+    settings.offline = true;
+    std::fs::create_dir_all(settings.join_output("")).unwrap();
+    write_raw(&settings, "faers", "1,IMATINIB,Nausea");
+    write_raw(&settings, "device", "DEV1,PumpX,Fall");
+
+    normalize::canonicalise(&settings, &NormalizeOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(counted(&settings), (1, vec!["imatinib".to_string()]));
+
+    let devices = NormalizeOptions {
+        devices: true,
+        ..Default::default()
+    };
+    normalize::canonicalise(&settings, &devices).await.unwrap();
+    assert_eq!(counted(&settings), (1, vec!["pumpx".to_string()]));
+}
@@ -0,0 +1,66 @@
+use rwe_assistant::data::dictionary;
+
+#[test]
+fn drug_dictionary_is_case_insensitive_and_skips_comments() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("drugs.csv");
+    std::fs::write(
+        &path,
+        "# raw,canonical\nXtandi,Enzalutamide\nXTANDI,other\n\nLYNPARZA , olaparib\n",
+    )
+    .unwrap();
+
+    let map = dictionary::read_drug_dictionary(&path).unwrap();
+    assert_eq!(map.len(), 2);
+    assert_eq!(map["XTANDI"], "enzalutamide");
+    assert_eq!(map["LYNPARZA"], "olaparib");
+}
+
+#[test]
+fn event_dictionary_maps_synonyms_and_reads_meddra_pt_lists() {
+    let dir = tempfile::tempdir().unwrap();
+    let custom = dir.path().join("events.txt");
+    std::fs::write(
+        &custom,
+        "# canonical|synonyms\nHepatotoxicity|liver injury|DILI\nrash\n",
+    )
+    .unwrap();
+    let events = dictionary::read_event_dictionary(&custom).unwrap();
+    assert_eq!(events.terms, ["hepatotoxicity", "rash"]);
+    assert_eq!(events.canonical("Liver Injury"), Some("hepatotoxicity"));
+    assert_eq!(
+        events.find_in("grade 3 dili after two cycles"),
+        Some("hepatotoxicity")
+    );
+
+    let pt = dir.path().join("pt.asc");
+    std::fs::write(&pt, "10019851$Hepatotoxicity$$$$$$$$$10019805$$\n").unwrap();
+    let meddra = dictionary::read_event_dictionary(&pt).unwrap();
+    assert_eq!(meddra.terms, ["hepatotoxicity"]);
+}
+
+#[test]
+fn event_terms_match_after_normalization_but_not_fuzzily() {
+    let events = dictionary::EventDictionary::from_terms(vec![
+        "rash pruritic".to_string(),
+        "hepatotoxicity".to_string(),
+    ]);
+    assert_eq!(events.canonical("Rash-Pruritic"), Some("rash pruritic"));
+    assert_eq!(
+        events.canonical("  HEPATOTOXICITY. "),
+        Some("hepatotoxicity")
+    );
+    assert_eq!(events.canonical("cardiotoxicity"), None);
+}
+
+#[test]
+fn event_mentions_need_whole_words() {
+    let events = dictionary::EventDictionary::from_terms(vec!["rash".to_string()]);
+    assert_eq!(
+        events.find_in("imatinib was stopped after a car crash"),
+        None
+    );
+    assert_eq!(events.find_in("a rash appeared on day 3"), Some("rash"));
+    assert_eq!(events.find_in("rash, then fever"), Some("rash"));
+    assert!(!dictionary::mentions_term("nilotinibs", "nilotinib"));
+}
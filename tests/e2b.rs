@@ -0,0 +1,93 @@
+use rwe_assistant::{
+    config::Settings,
+    data::e2b::{import_reports, parse_icsr_xml},
+};
+
+#[test]
+fn parses_r2_safety_reports() {
+    let xml = r#"<ichicsr><safetyreport>
+        <safetyreportid>US-123</safetyreportid>
+        <receiptdate>20240215</receiptdate>
+        <patient>
+          <reaction><primarysourcereaction>liver injury</primarysourcereaction><reactionmeddrapt>Hepatotoxicity</reactionmeddrapt></reaction>
+          <drug><medicinalproduct>GLEEVEC</medicinalproduct></drug>
+        </patient>
+    </safetyreport></ichicsr>"#;
+    let cases = parse_icsr_xml(xml).unwrap();
+    assert_eq!(cases.len(), 1);
+    assert_eq!(cases[0].case_id, "US-123");
+    assert_eq!(cases[0].drugs, vec!["GLEEVEC"]);
+    assert_eq!(cases[0].reactions, vec!["Hepatotoxicity"]);
+}
+
+#[test]
+fn parses_r3_investigation_events() {
+    let xml = r#"<MCCI_IN200100UV01><PORR_IN049016UV><controlActProcess><subject>
+      <investigationEvent>
+        <id root="2.16.840.1.113883.3.989.2.1.3.1" extension="EU-9"/>
+        <availabilityTime value="20231105"/>
+        <component><observation><code code="29"/>
+          <value code="10019851" codeSystem="2.16.840.1.113883.6.163"><originalText>Hepatotoxicity</originalText></value>
+        </observation></component>
+        <component><substanceAdministration><consumable><instanceOfKind><kindOfProduct>
+          <name>Imatinib</name>
+        </kindOfProduct></instanceOfKind></consumable></substanceAdministration></component>
+      </investigationEvent>
+    </subject></controlActProcess></PORR_IN049016UV></MCCI_IN200100UV01>"#;
+    let cases = parse_icsr_xml(xml).unwrap();
+    assert_eq!(cases.len(), 1);
+    assert_eq!(cases[0].case_id, "EU-9");
+    assert_eq!(cases[0].receipt_date.as_deref(), Some("20231105"));
+    assert_eq!(cases[0].reactions, vec!["Hepatotoxicity"]);
+}
+
+fn r2_report(id: &str, date: &str, drug: &str, pt: &str) -> String {
+    format!(
+        "<ichicsr><safetyreport><safetyreportid>{id}</safetyreportid><receiptdate>{date}</receiptdate>\
+         <patient><reaction><reactionmeddrapt>{pt}</reactionmeddrapt></reaction>\
+         <drug><medicinalproduct>{drug}</medicinalproduct></drug></patient></safetyreport></ichicsr>"
+    )
+}
+
+#[test]
+fn later_batches_add_cases_and_replace_follow_ups() {
+    let root = tempfile::tempdir().unwrap();
+    let settings = Settings::load(None).unwrap().for_dataset(root.path());
+    let inbox = root.path().join("inbox");
+    std::fs::create_dir_all(&inbox).unwrap();
+
+    let batch = inbox.join("batch.xml");
+    std::fs::write(&batch, r2_report("US-1", "20240110", "GLEEVEC", "Rash")).unwrap();
+    let written = import_reports(&batch, None, &settings).unwrap();
+    assert_eq!(written, vec![settings.join_data("raw/e2b/e2b_2024Q1.csv")]);
+
+    // A second batch for the quarter adds US-2. US-1 appears twice and batch.xml, read last
+    // in name order, holds its latest version.
+    std::fs::write(
+        inbox.join("a.xml"),
+        r2_report("US-1", "20240110", "GLEEVEC", "Oedema"),
+    )
+    .unwrap();
+    std::fs::write(
+        inbox.join("b.xml"),
+        r2_report("US-2", "20240305", "TASIGNA", "Nausea"),
+    )
+    .unwrap();
+    std::fs::write(
+        &batch,
+        r2_report("US-1", "20240110", "GLEEVEC", "Hepatotoxicity"),
+    )
+    .unwrap();
+    import_reports(&inbox, None, &settings).unwrap();
+
+    let text = std::fs::read_to_string(&written[0]).unwrap();
+    let mut rows: Vec<&str> = text.lines().skip(1).collect();
+    rows.sort();
+    assert_eq!(
+        rows,
+        [
+            "E2BUS-1,GLEEVEC,Hepatotoxicity,2024Q1",
+            "E2BUS-2,TASIGNA,Nausea,2024Q1"
+        ]
+    );
+}
@@ -20,3 +20,166 @@ fn embeddings_stub_returns_identity() {
     let clusters = cluster_preview(&embeddings, 0.85);
     assert_eq!(clusters, vec![0, 1]);
 }
+
+#[test]
+fn linkage_and_density_clustering_ignore_input_order() {
+    use rwe_assistant::nlp::clustering::{cluster, ClusterAlgorithm, ClusterParams};
+
+    let embeddings = vec![
+        vec![1.0, 0.0, 0.0],
+        vec![0.0, 1.0, 0.0],
+        vec![0.99, 0.05, 0.0],
+        vec![0.0, 0.98, 0.1],
+        vec![0.0, 0.0, 1.0],
+        vec![0.97, 0.1, 0.0],
+    ];
+    let reversed: Vec<Vec<f32>> = embeddings.iter().rev().cloned().collect();
+    for algorithm in [ClusterAlgorithm::Average, ClusterAlgorithm::Hdbscan] {
+        let params = ClusterParams {
+            algorithm,
+            ..ClusterParams::default()
+        };
+        let forward = cluster(&embeddings, &params).unwrap();
+        assert_eq!(forward, vec![0, 1, 0, 1, 2, 0], "{algorithm:?}");
+        let mut backward = cluster(&reversed, &params).unwrap();
+        backward.reverse();
+        let pairs: Vec<(usize, usize)> = forward.iter().copied().zip(backward).collect();
+        for (a, b) in &pairs {
+            for (c, d) in &pairs {
+                assert_eq!(a == c, b == d, "{algorithm:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn drugs_of_one_class_stay_separate() {
+    use rwe_assistant::nlp::clustering::{cluster, ClusterAlgorithm, ClusterParams};
+
+    // Cosine 0.95: close enough to merge as events, not as drugs.
+    let imatinib = vec![1.0, 0.0];
+    let nilotinib = vec![0.95, (1.0f32 - 0.95 * 0.95).sqrt()];
+    let gleevec = vec![0.99, (1.0f32 - 0.99 * 0.99).sqrt()];
+    let embeddings = vec![imatinib, nilotinib, gleevec];
+    for algorithm in [
+        ClusterAlgorithm::Greedy,
+        ClusterAlgorithm::Average,
+        ClusterAlgorithm::Hdbscan,
+    ] {
+        let params = ClusterParams {
+            algorithm,
+            ..ClusterParams::default()
+        };
+        assert_eq!(
+            cluster(&embeddings, &params.for_drugs()).unwrap(),
+            vec![0, 1, 0],
+            "{algorithm:?}"
+        );
+    }
+    assert_eq!(
+        cluster(&embeddings, &ClusterParams::default()).unwrap(),
+        vec![0, 0, 0]
+    );
+}
+
+#[test]
+fn dense_clustering_refuses_oversized_vocabularies() {
+    use rwe_assistant::nlp::clustering::{
+        cluster, ClusterAlgorithm, ClusterParams, MAX_DENSE_TERMS,
+    };
+
+    let embeddings = vec![vec![1.0]; MAX_DENSE_TERMS + 1];
+    for algorithm in [ClusterAlgorithm::Average, ClusterAlgorithm::Hdbscan] {
+        let params = ClusterParams {
+            algorithm,
+            ..ClusterParams::default()
+        };
+        let err = cluster(&embeddings, &params).unwrap_err();
+        assert!(err.to_string().contains("--algorithm greedy"), "{err:#}");
+    }
+    let greedy = cluster(&embeddings, &ClusterParams::default()).unwrap();
+    assert!(greedy.iter().all(|&id| id == 0));
+}
+
+#[test]
+fn clusters_are_represented_by_their_most_reported_member() {
+    use rwe_assistant::nlp::clustering::representatives;
+
+    let names: Vec<String> = ["skin rash", "rash", "rashes", "nausea"]
+        .map(String::from)
+        .to_vec();
+    let clusters = [0, 0, 0, 1];
+    let reps = representatives(&clusters, &names, &[3, 40, 3, 0]);
+    assert_eq!(reps[&0], 1);
+    assert_eq!(reps[&1], 3);
+
+    // Ties go to the alphabetically first name, whatever the input order.
+    let reps = representatives(&clusters, &names, &[5, 2, 5, 0]);
+    assert_eq!(names[reps[&0]], "rashes");
+    let reversed: Vec<String> = names.iter().rev().cloned().collect();
+    let reps = representatives(&[1, 0, 0, 0], &reversed, &[0, 5, 2, 5]);
+    assert_eq!(reversed[reps[&0]], "rashes");
+}
+
+#[tokio::test]
+async fn drug_clusters_merge_only_with_their_own_flag() {
+    use polars::prelude::*;
+    use rwe_assistant::{
+        config::Settings,
+        data::normalize::{self, NormalizeOptions},
+    };
+
+    let root = tempfile::tempdir().unwrap();
+    let mut settings = Settings::load(None).unwrap().for_dataset(root.path());
+    settings.offline = true;
+    std::fs::create_dir_all(settings.join_output("")).unwrap();
+    let raw = settings.join_data("raw/faers");
+    std::fs::create_dir_all(&raw).unwrap();
+    std::fs::write(
+        raw.join("faers_2024Q1.csv"),
+        "CASEID,DRUGNAME,PT,YEAR_QUARTER\n1,IMATINIB,Nausea,2024Q1\n2,NILOTINIB,Nausea,2024Q1\n",
+    )
+    .unwrap();
+
+    let ids = [
+        normalize::stable_id('D', "imatinib"),
+        normalize::stable_id('D', "nilotinib"),
+    ];
+    std::fs::create_dir_all(settings.join_data("clean")).unwrap();
+    let mut clusters = DataFrame::new(vec![
+        Series::new("drug_id".into(), ids.to_vec()),
+        Series::new("cluster_id".into(), [0i64, 0]),
+        Series::new("rep_id".into(), [ids[0].clone(), ids[0].clone()]),
+        Series::new("rep_name".into(), ["imatinib", "imatinib"]),
+    ])
+    .unwrap();
+    ParquetWriter::new(
+        std::fs::File::create(settings.join_data("clean/drug_clusters.parquet")).unwrap(),
+    )
+    .finish(&mut clusters)
+    .unwrap();
+
+    let counted_drugs = || {
+        let norm = ParquetReader::new(
+            std::fs::File::open(settings.join_data("clean/faers_norm.parquet")).unwrap(),
+        )
+        .finish()
+        .unwrap();
+        norm.column("drug_id").unwrap().n_unique().unwrap()
+    };
+    let events_only = NormalizeOptions {
+        merge_clusters: true,
+        ..Default::default()
+    };
+    normalize::canonicalise(&settings, &events_only)
+        .await
+        .unwrap();
+    assert_eq!(counted_drugs(), 2);
+
+    let drugs = NormalizeOptions {
+        merge_drug_clusters: true,
+        ..Default::default()
+    };
+    normalize::canonicalise(&settings, &drugs).await.unwrap();
+    assert_eq!(counted_drugs(), 1);
+}
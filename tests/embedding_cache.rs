@@ -0,0 +1,32 @@
+use rwe_assistant::nlp::embedding_cache::EmbeddingCache;
+
+#[test]
+fn cached_embeddings_are_not_recomputed() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("embeddings.parquet");
+    let texts = vec!["hepatotoxicity".to_string(), "rash".to_string()];
+
+    let mut cache = EmbeddingCache::open(path.clone(), "model-a").unwrap();
+    let first = cache
+        .embed_with(&texts, |missing| {
+            assert_eq!(missing, vec!["hepatotoxicity", "rash"]);
+            Ok(vec![vec![1.0, 0.0], vec![0.0, 1.0]])
+        })
+        .unwrap();
+    cache.persist().unwrap();
+
+    let mut cache = EmbeddingCache::open(path.clone(), "model-a").unwrap();
+    let texts = vec!["rash".to_string(), "nausea".to_string()];
+    let second = cache
+        .embed_with(&texts, |missing| {
+            assert_eq!(missing, vec!["nausea"]);
+            Ok(vec![vec![0.5, 0.5]])
+        })
+        .unwrap();
+    assert_eq!(second[0], first[1]);
+    assert_eq!(second[1], vec![0.5, 0.5]);
+
+    // Vectors are keyed by model, so another model recomputes them.
+    let other = EmbeddingCache::open(path, "model-b").unwrap();
+    assert!(other.get("rash").is_none());
+}
@@ -0,0 +1,40 @@
+use axum::http::{header::IF_NONE_MATCH, HeaderMap, HeaderValue, StatusCode};
+use rwe_assistant::api::etag;
+
+fn if_none_match(value: &'static str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(IF_NONE_MATCH, HeaderValue::from_static(value));
+    headers
+}
+
+#[test]
+fn matching_tags_are_not_modified() {
+    let tag = Some("\"abc123\"");
+    for value in ["\"abc123\"", "W/\"abc123\"", "\"other\", \"abc123\"", "*"] {
+        let response = etag::not_modified(&if_none_match(value), tag).expect(value);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()["etag"], "\"abc123\"");
+    }
+}
+
+#[test]
+fn stale_or_missing_tags_fall_through() {
+    let tag = Some("\"abc123\"");
+    assert!(etag::not_modified(&if_none_match("\"old\""), tag).is_none());
+    assert!(etag::not_modified(&HeaderMap::new(), tag).is_none());
+    assert!(etag::not_modified(&if_none_match("*"), None).is_none());
+}
+
+#[test]
+fn fingerprint_follows_file_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("signals.csv");
+    let paths = [path.clone()];
+    let missing = etag::fingerprint(&paths);
+    std::fs::write(&path, "drug_id,event_id\n").unwrap();
+    let written = etag::fingerprint(&paths);
+    assert_ne!(missing, written);
+    assert_eq!(written, etag::fingerprint(&paths));
+    std::fs::write(&path, "drug_id,event_id\nD1,E1\n").unwrap();
+    assert_ne!(written, etag::fingerprint(&paths));
+}
@@ -0,0 +1,100 @@
+use rwe_assistant::{
+    api::{export::ExportFormat, types::SignalDto},
+    data::export::{Cell, Filter, Table},
+};
+
+fn row() -> SignalDto {
+    SignalDto {
+        drug_id: "Dabc".into(),
+        event_id: "Edef".into(),
+        drug_name: Some("imatinib".into()),
+        event_term: None,
+        year_quarter: "2024Q1".into(),
+        recent_ror: 3.5,
+        ci_low: 2.0,
+        ci_high: 6.1,
+        lit_support: 4,
+        trend_z: 1.2,
+        score: 5.0,
+        labeled: false,
+        sider_known: true,
+        smq: String::new(),
+        watched: true,
+    }
+}
+
+#[test]
+fn csv_export_has_header_and_rows() {
+    let bytes = ExportFormat::Csv.encode(&[row()]).unwrap();
+    let text = String::from_utf8(bytes).unwrap();
+    let mut lines = text.lines();
+    assert!(lines
+        .next()
+        .unwrap()
+        .starts_with("drug_id,event_id,drug_name,event_term,"));
+    assert!(lines
+        .next()
+        .unwrap()
+        .starts_with("Dabc,Edef,imatinib,,2024Q1,3.5,"));
+}
+
+#[test]
+fn binary_formats_produce_files() {
+    let xlsx = ExportFormat::Xlsx.encode(&[row()]).unwrap();
+    assert!(xlsx.starts_with(b"PK"));
+    let parquet = ExportFormat::Parquet.encode(&[row()]).unwrap();
+    assert!(parquet.starts_with(b"PAR1"));
+    let json: serde_json::Value =
+        serde_json::from_slice(&ExportFormat::Json.encode(&[row()]).unwrap()).unwrap();
+    assert_eq!(json[0]["drug_name"], "imatinib");
+}
+
+fn table() -> Table {
+    Table {
+        columns: vec!["drug_id".into(), "score".into(), "smq".into()],
+        rows: vec![
+            vec![Cell::infer("Dabc"), Cell::infer("5.5"), Cell::infer("")],
+            vec![
+                Cell::infer("Dxyz"),
+                Cell::infer("1"),
+                Cell::infer("Hepatic"),
+            ],
+        ],
+    }
+}
+
+#[test]
+fn table_filters_compare_numbers_and_text() {
+    let mut high = table();
+    high.retain(&[Filter::parse("score>=2").unwrap()]).unwrap();
+    assert_eq!(high.rows.len(), 1);
+    assert_eq!(high.rows[0][0], Cell::Text("Dabc".into()));
+
+    let mut tagged = table();
+    tagged.retain(&[Filter::parse("smq != ").unwrap()]).unwrap();
+    assert_eq!(tagged.rows[0][0], Cell::Text("Dxyz".into()));
+
+    assert!(table().retain(&[Filter::parse("ror>1").unwrap()]).is_err());
+    assert!(Filter::parse("score").is_err());
+}
+
+#[test]
+fn jsonl_keeps_selected_columns_in_order() {
+    let mut table = table();
+    table.select(&["score".into(), "drug_id".into()]).unwrap();
+    let mut out = Vec::new();
+    table.write_jsonl(&mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    let first = text.lines().next().unwrap();
+    assert_eq!(first, r#"{"score":5.5,"drug_id":"Dabc"}"#);
+}
+
+#[test]
+fn table_csv_leaves_nulls_empty() {
+    let mut out = Vec::new();
+    table().write_csv(&mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "drug_id,score,smq\nDabc,5.5,\nDxyz,1,Hepatic\n"
+    );
+}
@@ -0,0 +1,81 @@
+use ndarray::Array2;
+use rwe_assistant::nlp::{
+    boosting::{BoostingParams, GradientBoostedTrees},
+    calibration::PlattScaler,
+    evaluation::{fold_assignments, ConfusionMatrix},
+    labels::{label_key, read_gold_labels},
+};
+
+#[test]
+fn confusion_matrix_scores_predictions() {
+    let matrix = ConfusionMatrix::from_predictions(&[1, 1, 0, 0, 1], &[1, 0, 1, 0, 1]);
+    assert_eq!(matrix.true_positive, 2);
+    assert_eq!(matrix.false_positive, 1);
+    assert_eq!(matrix.true_negative, 1);
+    assert_eq!(matrix.false_negative, 1);
+    assert!((matrix.precision() - 2.0 / 3.0).abs() < 1e-9);
+    assert!((matrix.recall() - 2.0 / 3.0).abs() < 1e-9);
+    assert!((matrix.f1() - 2.0 / 3.0).abs() < 1e-9);
+    assert!((matrix.accuracy() - 0.6).abs() < 1e-9);
+}
+
+#[test]
+fn folds_keep_sentences_of_one_abstract_together() {
+    let folds = fold_assignments(&["1", "1", "2", "3", "2", "4"], 3);
+    assert_eq!(folds, vec![0, 0, 1, 2, 1, 0]);
+}
+
+#[test]
+fn gold_labels_are_keyed_case_insensitively_and_skip_blank_labels() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("labels.csv");
+    std::fs::write(
+        &path,
+        concat!(
+            "pmid,sent_idx,drug,event,label,text\n",
+            "123,0,Imatinib,Hepatotoxicity,yes,a\n",
+            "123,2,imatinib,rash,0,b\n",
+            "123,3,imatinib,fever,,not yet annotated\n",
+        ),
+    )
+    .unwrap();
+    let labels = read_gold_labels(&path).unwrap();
+    assert_eq!(labels.len(), 2);
+    assert_eq!(
+        labels[&label_key("123", 0, "imatinib", "hepatotoxicity")],
+        1
+    );
+    assert_eq!(labels[&label_key("123", 2, "IMATINIB", "Rash")], 0);
+}
+
+#[test]
+fn unknown_gold_label_values_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("labels.csv");
+    std::fs::write(
+        &path,
+        "pmid,sent_idx,drug,event,label\n123,0,imatinib,rash,maybe\n",
+    )
+    .unwrap();
+    assert!(read_gold_labels(&path).is_err());
+}
+
+#[test]
+fn boosted_trees_separate_a_threshold_rule() {
+    let values: Vec<f64> = (0..40).flat_map(|i| [i as f64, (i % 3) as f64]).collect();
+    let x = Array2::from_shape_vec((40, 2), values).unwrap();
+    let labels: Vec<i32> = (0..40).map(|i| i32::from(i >= 20)).collect();
+    let model = GradientBoostedTrees::fit(&x, &labels, BoostingParams::default());
+    assert_eq!(model.predict(&x), labels);
+}
+
+#[test]
+fn platt_scaling_orders_and_bounds_probabilities() {
+    let scores = [-3.0, -2.0, -1.5, -0.5, 0.2, 0.8, 1.5, 2.5, -0.2, 0.4];
+    let labels = [0, 0, 0, 0, 1, 1, 1, 1, 1, 0];
+    let scaler = PlattScaler::fit(&scores, &labels);
+    let low = scaler.probability(-3.0);
+    let high = scaler.probability(2.5);
+    assert!(low < 0.2 && high > 0.8);
+    assert!(scaler.probability(0.0) > low && scaler.probability(0.0) < high);
+}
@@ -0,0 +1,43 @@
+use std::io::Write;
+
+use rwe_assistant::data::faers;
+
+#[test]
+fn drug_and_reaction_rows_are_joined_on_case_id() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("FAERS_ASCII_2024Q1.zip");
+    let mut archive = zip::ZipWriter::new(std::fs::File::create(&archive_path).unwrap());
+    let options = zip::write::FileOptions::default();
+    archive.start_file("ASCII/REAC24Q1.txt", options).unwrap();
+    archive
+        .write_all(
+            b"primaryid$caseid$pt$drug_rec_act\n1$100$Nausea$\n2$200$Rash$\n3$200$Hepatitis$\n",
+        )
+        .unwrap();
+    archive.start_file("ASCII/DRUG24Q1.txt", options).unwrap();
+    archive
+        .write_all(
+            b"primaryid$caseid$drug_seq$role_cod$drugname\n1$100$1$PS$IMATINIB\n2$200$1$SS$ASPIRIN\n4$400$1$PS$NO REACTIONS\n",
+        )
+        .unwrap();
+    archive.finish().unwrap();
+
+    let dest = dir.path().join("faers_2024Q1.csv");
+    faers::filter_archive(&archive_path, "2024Q1", &dest).unwrap();
+
+    let mut rows: Vec<Vec<String>> = csv::Reader::from_path(&dest)
+        .unwrap()
+        .records()
+        .map(|r| r.unwrap().iter().map(str::to_string).collect())
+        .collect();
+    rows.sort();
+    assert_eq!(
+        rows,
+        [
+            ["100", "IMATINIB", "Nausea", "2024Q1", "PS"],
+            ["200", "ASPIRIN", "Hepatitis", "2024Q1", "SS"],
+            ["200", "ASPIRIN", "Rash", "2024Q1", "SS"],
+        ]
+    );
+    assert!(!dest.with_extension("parts").exists());
+}
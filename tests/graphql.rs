@@ -0,0 +1,38 @@
+use regex::Regex;
+use rwe_assistant::api::graphql;
+
+/// SDL without descriptions and with whitespace collapsed, so each field fits on one line
+/// however the argument list is wrapped.
+fn compact_sdl() -> String {
+    let descriptions = Regex::new(r#"(?s)""".*?"""|"[^"\n]*""#).unwrap();
+    let sdl = descriptions
+        .replace_all(&graphql::schema().sdl(), "")
+        .into_owned();
+    sdl.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("( ", "(")
+        .replace(" )", ")")
+}
+
+#[test]
+fn schema_joins_signals_with_names_and_literature() {
+    let sdl = compact_sdl();
+    for field in [
+        "signals(drug: String, smq: String, limit: Int! = 100): [Signal!]!",
+        "signal(drug: String!, event: String!): Signal",
+        "topPmids(limit: Int! = 5): [String!]!",
+        "relations(limit: Int! = 10): [Relation!]!",
+        "history: [Quarter!]!",
+    ] {
+        assert!(sdl.contains(field), "missing {field}");
+    }
+}
+
+#[tokio::test]
+async fn deep_selections_are_rejected_before_any_artefact_is_read() {
+    let query = "{ signals { drug { signals { event { signals { drug { signals { event { id } } } } } } } } }";
+    let response = graphql::schema().execute(query).await;
+    assert!(response.data == async_graphql::Value::Null);
+    assert!(response.errors[0].message.contains("nested too deep"));
+}
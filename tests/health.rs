@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use rwe_assistant::{
+    api::{self, datasets, graphql, jobs::Jobs, metrics, AppState},
+    config::Settings,
+};
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn health_checks_report_status_without_paths_or_errors() {
+    let root = tempfile::tempdir().unwrap();
+    let mut settings = Settings::load(None).unwrap().for_dataset(root.path());
+    settings.rate_limit_per_second = 0;
+    let app = api::router(AppState {
+        datasets: Arc::new(datasets::from_settings(&settings)),
+        settings,
+        metrics: metrics::install().unwrap(),
+        jobs: Jobs::start().unwrap(),
+        graphql: graphql::schema(),
+    })
+    .unwrap();
+
+    let response = app
+        .oneshot(Request::get("/healthz").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let text = String::from_utf8(body.to_vec()).unwrap();
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE, "{text}");
+    let root = root.path().display().to_string();
+    assert!(!text.contains(&root), "{text}");
+    assert!(!text.contains("No such file"), "{text}");
+
+    let health: serde_json::Value = serde_json::from_str(&text).unwrap();
+    let signals = &health["artefacts"][0];
+    assert_eq!(signals["name"], "signals.csv");
+    assert_eq!(signals["status"], "degraded");
+}
@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use rwe_assistant::{
+    config::Settings,
+    data::{custom, vigibase},
+};
+
+fn settings(root: &Path) -> Settings {
+    Settings::load(None).unwrap().for_dataset(root)
+}
+
+fn mapping() -> custom::ColumnMapping {
+    toml::from_str(
+        r#"
+        case_id = "Case"
+        drug = "Drug"
+        event = "Event"
+        date = "Received"
+        "#,
+    )
+    .unwrap()
+}
+
+fn rows(path: &Path) -> Vec<String> {
+    let text = std::fs::read_to_string(path).unwrap();
+    let mut rows: Vec<String> = text.lines().skip(1).map(str::to_string).collect();
+    rows.sort();
+    rows
+}
+
+#[test]
+fn custom_batches_for_one_quarter_accumulate() {
+    let root = tempfile::tempdir().unwrap();
+    let settings = settings(root.path());
+    let export = root.path().join("export.csv");
+
+    std::fs::write(
+        &export,
+        "Case,Drug,Event,Received\n1,imatinib,rash,2024-01-10\n2,nilotinib,nausea,20240220\n",
+    )
+    .unwrap();
+    let written = custom::import_export(&export, &mapping(), None, &settings).unwrap();
+    assert_eq!(
+        written,
+        vec![settings.join_data("raw/custom/custom_2024Q1.csv")]
+    );
+
+    // A second batch adds case 3 and a follow-up of case 2 that replaces its first version.
+    std::fs::write(
+        &export,
+        "Case,Drug,Event,Received\n3,imatinib,oedema,2024-03-01\n2,nilotinib,vomiting,2024-03-02\n",
+    )
+    .unwrap();
+    custom::import_export(&export, &mapping(), None, &settings).unwrap();
+    let expected = [
+        "CU1,imatinib,rash,2024Q1",
+        "CU2,nilotinib,vomiting,2024Q1",
+        "CU3,imatinib,oedema,2024Q1",
+    ];
+    assert_eq!(rows(&written[0]), expected);
+
+    // Importing the same batch again changes nothing.
+    custom::import_export(&export, &mapping(), None, &settings).unwrap();
+    assert_eq!(rows(&written[0]), expected);
+}
+
+#[test]
+fn custom_import_refuses_non_iso_dates() {
+    let root = tempfile::tempdir().unwrap();
+    let settings = settings(root.path());
+    let export = root.path().join("export.csv");
+    std::fs::write(
+        &export,
+        "Case,Drug,Event,Received\n1,imatinib,rash,2024-01-10\n2,imatinib,rash,15/03/2024\n",
+    )
+    .unwrap();
+
+    let err = custom::import_export(&export, &mapping(), Some("2024Q1"), &settings).unwrap_err();
+    assert!(format!("{err:#}").contains("row 3"), "{err:#}");
+    assert!(!settings.join_data("raw/custom").exists());
+}
+
+#[test]
+fn vigibase_batches_for_one_quarter_both_survive() {
+    let root = tempfile::tempdir().unwrap();
+    let settings = settings(root.path());
+    let first = root.path().join("first.txt");
+    let second = root.path().join("second.txt");
+    std::fs::write(&first, "UMCReportId\tDrug\tPT\n101\timatinib\trash\n").unwrap();
+    std::fs::write(&second, "UMCReportId\tDrug\tPT\n202\tnilotinib\tnausea\n").unwrap();
+
+    let written = vigibase::import_extract(&first, Some("2024Q2"), &settings).unwrap();
+    assert_eq!(
+        written,
+        vec![settings.join_data("raw/vigibase/vigibase_2024Q2.csv")]
+    );
+    vigibase::import_extract(&second, Some("2024Q2"), &settings).unwrap();
+
+    assert_eq!(
+        rows(&written[0]),
+        [
+            "VB101,imatinib,rash,2024Q2",
+            "VB202,nilotinib,nausea,2024Q2"
+        ]
+    );
+}
@@ -0,0 +1,112 @@
+use std::path::Path;
+
+use polars::prelude::*;
+use rwe_assistant::{
+    config::Settings,
+    data::normalize::{self, NormalizeOptions},
+};
+
+fn settings(root: &Path) -> Settings {
+    let mut settings = Settings::load(None).unwrap().for_dataset(root);
+    settings.offline = true;
+    std::fs::create_dir_all(settings.join_output("")).unwrap();
+    settings
+}
+
+fn write_raw(settings: &Settings, source: &str, quarter: &str, rows: &str) {
+    let dir = settings.join_data(format!("raw/{source}"));
+    std::fs::create_dir_all(&dir).unwrap();
+    let body: String = rows
+        .lines()
+        .map(|row| format!("{row},{quarter},PS\n"))
+        .collect();
+    std::fs::write(
+        dir.join(format!("{source}_{quarter}.csv")),
+        format!("CASEID,DRUGNAME,PT,YEAR_QUARTER,ROLE_COD\n{body}"),
+    )
+    .unwrap();
+}
+
+/// Total `a` (exposed cases with the event) per quarter.
+fn exposed_cases(settings: &Settings) -> Vec<(String, i64)> {
+    let norm = ParquetReader::new(
+        std::fs::File::open(settings.join_data("clean/faers_norm.parquet")).unwrap(),
+    )
+    .finish()
+    .unwrap();
+    let mut totals = std::collections::BTreeMap::new();
+    for (quarter, a) in norm
+        .column("year_quarter")
+        .unwrap()
+        .str()
+        .unwrap()
+        .into_no_null_iter()
+        .zip(norm.column("a").unwrap().i64().unwrap().into_no_null_iter())
+    {
+        *totals.entry(quarter.to_string()).or_insert(0) += a;
+    }
+    totals.into_iter().collect()
+}
+
+#[tokio::test]
+async fn incremental_runs_recount_quarters_with_new_imports() {
+    let root = tempfile::tempdir().unwrap();
+    let settings = settings(root.path());
+    write_raw(&settings, "faers", "2024Q1", "1,IMATINIB,Nausea\n");
+    write_raw(&settings, "faers", "2024Q2", "2,IMATINIB,Nausea\n");
+    normalize::canonicalise(&settings, &NormalizeOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        exposed_cases(&settings),
+        [("2024Q1".to_string(), 1), ("2024Q2".to_string(), 1)]
+    );
+
+    // A later import for an already normalized quarter.
+    write_raw(&settings, "custom", "2024Q1", "3,IMATINIB,Nausea\n");
+    let incremental = NormalizeOptions {
+        incremental: true,
+        ..Default::default()
+    };
+    normalize::canonicalise(&settings, &incremental)
+        .await
+        .unwrap();
+    assert_eq!(
+        exposed_cases(&settings),
+        [("2024Q1".to_string(), 2), ("2024Q2".to_string(), 1)]
+    );
+
+    // Nothing changed, so nothing is counted twice.
+    normalize::canonicalise(&settings, &incremental)
+        .await
+        .unwrap();
+    assert_eq!(
+        exposed_cases(&settings),
+        [("2024Q1".to_string(), 2), ("2024Q2".to_string(), 1)]
+    );
+}
+
+#[tokio::test]
+async fn incremental_runs_refuse_to_extend_a_cumulative_table() {
+    let root = tempfile::tempdir().unwrap();
+    let settings = settings(root.path());
+    write_raw(&settings, "faers", "2024Q1", "1,IMATINIB,Nausea\n");
+    write_raw(&settings, "faers", "2024Q2", "2,IMATINIB,Nausea\n");
+    let cumulative = NormalizeOptions {
+        cumulative: true,
+        ..Default::default()
+    };
+    normalize::canonicalise(&settings, &cumulative)
+        .await
+        .unwrap();
+
+    write_raw(&settings, "faers", "2024Q3", "3,IMATINIB,Nausea\n");
+    let incremental = NormalizeOptions {
+        incremental: true,
+        ..Default::default()
+    };
+    let err = normalize::canonicalise(&settings, &incremental)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("cumulative"), "{err:#}");
+}
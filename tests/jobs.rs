@@ -0,0 +1,94 @@
+use std::{
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use axum::http::StatusCode;
+use rwe_assistant::{
+    api::{
+        datasets::{self, DEFAULT_DATASET},
+        jobs::{JobRequest, JobStatus, Jobs},
+    },
+    config::Settings,
+};
+use serde_json::json;
+
+fn request(body: serde_json::Value) -> JobRequest {
+    serde_json::from_value(body).unwrap()
+}
+
+/// Poll `GET /jobs/:id` until the job leaves the queue and finishes.
+fn wait_for(jobs: &Jobs, id: u64) -> JobStatus {
+    let deadline = Instant::now() + Duration::from_secs(30);
+    loop {
+        let status = jobs.get(id).unwrap().status;
+        if !matches!(status, JobStatus::Queued | JobStatus::Running) {
+            return status;
+        }
+        assert!(Instant::now() < deadline, "job {id} still {status:?}");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn jobs_reject_flags_their_stages_do_not_take() {
+    let root = tempfile::tempdir().unwrap();
+    let settings = Settings::load(None).unwrap().for_dataset(root.path());
+    let dataset = datasets::from_settings(&settings)[DEFAULT_DATASET].clone();
+    let jobs = Jobs::start().unwrap();
+    for body in [
+        json!({"stage": "rank", "params": {"quarters": ["2024Q1"]}}),
+        json!({"stage": "rank", "params": {"config": "other.toml"}}),
+        json!({"stage": "normalize", "params": {"offline": true}}),
+        json!({"stage": "signal", "params": {"verbose": 2}}),
+        json!({"stage": "all", "params": {"log_file": "job.log"}}),
+    ] {
+        let err = jobs
+            .submit(request(body.clone()), dataset.clone())
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST, "{body}");
+    }
+    assert!(jobs.list().is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn one_job_runs_at_a_time_and_reports_its_outcome() {
+    let root = tempfile::tempdir().unwrap();
+    let settings = Settings::load(None).unwrap().for_dataset(root.path());
+    let dataset = datasets::from_settings(&settings)[DEFAULT_DATASET].clone();
+    // `rank` blocks opening the metrics pipe until the test writes to it.
+    let metrics = settings.join_data("clean/signal_metrics.parquet");
+    std::fs::create_dir_all(metrics.parent().unwrap()).unwrap();
+    assert!(Command::new("mkfifo")
+        .arg(&metrics)
+        .status()
+        .unwrap()
+        .success());
+
+    let jobs = Jobs::start().unwrap();
+    let rank = || request(json!({"stage": "rank"}));
+    let first = jobs.submit(rank(), dataset.clone()).unwrap();
+    assert_eq!(first.status, JobStatus::Queued);
+    let busy = jobs.submit(rank(), dataset.clone()).unwrap_err();
+    assert_eq!(busy.status, StatusCode::CONFLICT);
+
+    // An empty pipe is not a parquet file.
+    drop(
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(&metrics)
+            .unwrap(),
+    );
+    assert_eq!(wait_for(&jobs, first.id), JobStatus::Failed);
+    // The error chain names the metrics file; only a log reference is returned.
+    let error = jobs.get(first.id).unwrap().error.unwrap();
+    assert!(error.starts_with("job failed; reference "), "{error}");
+    assert!(!error.contains("signal_metrics"), "{error}");
+
+    std::fs::remove_file(&metrics).unwrap();
+    let second = jobs.submit(rank(), dataset).unwrap();
+    assert_eq!(wait_for(&jobs, second.id), JobStatus::Succeeded);
+    let listed: Vec<u64> = jobs.list().iter().map(|job| job.id).collect();
+    assert_eq!(listed, [second.id, first.id]);
+}
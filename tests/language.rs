@@ -0,0 +1,22 @@
+use rwe_assistant::nlp::language::{detect, is_english};
+
+#[test]
+fn non_english_abstracts_are_detected() {
+    let english = detect(
+        "Hepatotoxicity was reported in patients treated with imatinib for chronic myeloid \
+         leukaemia, and liver enzymes returned to normal after the drug was withdrawn.",
+    );
+    assert_eq!(english, Some("eng"));
+    assert!(is_english(english));
+
+    let german = detect(
+        "Bei Patienten, die mit Imatinib wegen einer chronischen myeloischen Leukämie \
+         behandelt wurden, wurde eine Lebertoxizität beobachtet, die nach dem Absetzen \
+         des Medikaments zurückging.",
+    );
+    assert_eq!(german, Some("deu"));
+    assert!(!is_english(german));
+
+    assert_eq!(detect("Imatinib hepatotoxicity."), None);
+    assert!(is_english(None));
+}
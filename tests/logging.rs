@@ -0,0 +1,16 @@
+use rwe_assistant::logging::verbosity_directives;
+use tracing_subscriber::EnvFilter;
+
+#[test]
+fn verbosity_flags_map_to_valid_filters() {
+    assert_eq!(verbosity_directives(0), None);
+    assert_eq!(verbosity_directives(-1), Some("warn"));
+    assert_eq!(verbosity_directives(1), Some("info,rwe_assistant=debug"));
+    assert_eq!(verbosity_directives(i8::MAX), Some("trace"));
+    assert_eq!(verbosity_directives(i8::MIN), Some("off"));
+    for verbosity in -4..=4 {
+        if let Some(directives) = verbosity_directives(verbosity) {
+            assert!(EnvFilter::try_new(directives).is_ok(), "{directives}");
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use rwe_assistant::nlp::ner::{DictionaryNer, Ner};
+
+#[test]
+fn dictionary_ner_matches_whole_words_and_prefers_longer_terms() {
+    let ner = DictionaryNer::new(
+        vec!["imatinib".into()],
+        vec!["liver injury".into(), "Liver".into(), "rash".into()],
+    )
+    .unwrap();
+    let spans =
+        ner.extract("Imatinib caused liver injury and a rash, unlike imatinibum or crashes.");
+    let found: Vec<(&str, &str)> = spans
+        .iter()
+        .map(|span| (span.label.as_str(), span.text.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("DRUG", "Imatinib"),
+            ("EVENT", "liver injury"),
+            ("EVENT", "rash"),
+        ]
+    );
+    let injury = &spans[1];
+    assert_eq!(
+        &"Imatinib caused liver injury"[injury.start..injury.end],
+        "liver injury"
+    );
+}
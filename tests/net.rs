@@ -0,0 +1,17 @@
+use reqwest::Url;
+use rwe_assistant::net;
+
+#[test]
+fn credential_parameters_are_masked_in_urls() {
+    let url = Url::parse(
+        "https://uts-ws.nlm.nih.gov/rest/search/current?string=rash&apiKey=s3cret&pageSize=1",
+    )
+    .unwrap();
+    let redacted = net::redact_url(&url).to_string();
+    assert!(!redacted.contains("s3cret"), "{redacted}");
+    assert!(redacted.contains("apiKey=REDACTED"), "{redacted}");
+    assert!(redacted.contains("string=rash"), "{redacted}");
+
+    let plain = Url::parse("https://rxnav.nlm.nih.gov/REST/rxcui.json?name=imatinib").unwrap();
+    assert_eq!(net::redact_url(&plain), plain);
+}
@@ -5,3 +5,153 @@ fn gleevec_maps_to_imatinib() {
     let canonical = normalize::seed_lookup("Gleevec").unwrap();
     assert_eq!(canonical, "imatinib");
 }
+
+#[test]
+fn combination_products_split_into_ingredients() {
+    assert_eq!(
+        normalize::split_combination("Trimethoprim\\Sulfamethoxazole"),
+        ["TRIMETHOPRIM", "SULFAMETHOXAZOLE"]
+    );
+    assert_eq!(
+        normalize::split_combination("drug A / drug B + drug A"),
+        ["DRUG A", "DRUG B"]
+    );
+    assert_eq!(
+        normalize::split_combination("INSULIN GLARGINE 100 UNITS/ML"),
+        ["INSULIN GLARGINE 100 UNITS/ML"]
+    );
+}
+
+#[test]
+fn drug_name_rules_strip_strength_salt_and_form() {
+    use rwe_assistant::data::drug_rules::DrugNameRules;
+
+    let rules = DrugNameRules::default();
+    assert_eq!(rules.apply("Imatinib Mesylate 400 mg Tablet"), "IMATINIB");
+    assert_eq!(rules.apply("METFORMIN HCL ER 500MG"), "METFORMIN");
+    assert_eq!(rules.apply("calcium carbonate"), "CALCIUM CARBONATE");
+    assert_eq!(rules.apply("TABLET"), "TABLET");
+}
+
+#[test]
+fn drug_name_rules_keep_inorganic_salts_whole() {
+    use rwe_assistant::data::drug_rules::DrugNameRules;
+
+    let rules = DrugNameRules::default();
+    assert_eq!(
+        rules.apply("Sodium Chloride 0.9% Injection"),
+        "SODIUM CHLORIDE"
+    );
+    assert_eq!(
+        rules.apply("POTASSIUM CHLORIDE ER 20 MEQ"),
+        "POTASSIUM CHLORIDE"
+    );
+    assert_eq!(rules.apply("magnesium sulfate"), "MAGNESIUM SULFATE");
+    assert_eq!(rules.apply("FERROUS SULFATE 325 MG TAB"), "FERROUS SULFATE");
+    assert_eq!(rules.apply("Losartan Potassium 50 mg"), "LOSARTAN");
+}
+
+#[test]
+fn stable_ids_depend_only_on_the_canonical_name() {
+    let id = normalize::stable_id('D', "imatinib");
+    assert_eq!(id, normalize::stable_id('D', "imatinib"));
+    assert_eq!(id.len(), 13);
+    assert!(id.starts_with('D'));
+    assert_ne!(id, normalize::stable_id('D', "dasatinib"));
+}
+
+#[test]
+fn misspelled_drug_names_are_corrected_against_the_vocabulary() {
+    use rwe_assistant::data::spelling::DrugSpeller;
+
+    let speller = DrugSpeller::new([
+        ("IMATINIB", "imatinib"),
+        ("GLEEVEC", "imatinib"),
+        ("KEYTRUDA", "pembrolizumab"),
+    ]);
+    assert_eq!(speller.correct("Imatanib"), Some("imatinib"));
+    assert_eq!(speller.correct("KEYTRDA"), Some("pembrolizumab"));
+    assert_eq!(speller.correct("OPDIVO"), None);
+    assert_eq!(speller.correct("ASA"), None);
+}
+
+#[tokio::test]
+async fn unexposed_cases_count_once_by_their_latest_version() {
+    use rwe_assistant::{config::Settings, data::quality};
+
+    let root = tempfile::tempdir().unwrap();
+    let mut settings = Settings::load(None).unwrap().for_dataset(root.path());
+    settings.offline = true;
+    std::fs::create_dir_all(settings.join_output("")).unwrap();
+    let raw = settings.join_data("raw/faers");
+    std::fs::create_dir_all(&raw).unwrap();
+    // Case 1 gains a suspect drug on follow-up; case 2 never has one, in two quarters.
+    for (quarter, rows) in [
+        (
+            "2024Q1",
+            "1,ASPIRIN,Rash,C\n2,ASPIRIN,Nausea,C\n3,IMATINIB,Rash,PS\n",
+        ),
+        ("2024Q2", "1,IMATINIB,Rash,PS\n2,ASPIRIN,Nausea,C\n"),
+    ] {
+        let body: String = rows
+            .lines()
+            .map(|row| {
+                let (head, role) = row.rsplit_once(',').unwrap();
+                format!("{head},{quarter},{role}\n")
+            })
+            .collect();
+        std::fs::write(
+            raw.join(format!("faers_{quarter}.csv")),
+            format!("CASEID,DRUGNAME,PT,YEAR_QUARTER,ROLE_COD\n{body}"),
+        )
+        .unwrap();
+    }
+    let options = normalize::NormalizeOptions {
+        drug_roles: vec![normalize::DrugRole::Ps, normalize::DrugRole::Ss],
+        ..Default::default()
+    };
+    normalize::canonicalise(&settings, &options).await.unwrap();
+    let metrics = quality::load_latest(&settings).unwrap().unwrap();
+    assert_eq!(metrics.cases_without_exposure, 1);
+}
+
+#[tokio::test]
+async fn fuzzy_matches_need_a_shared_initial_and_similar_length() {
+    use rwe_assistant::config::Settings;
+
+    let root = tempfile::tempdir().unwrap();
+    let mut settings = Settings::load(None).unwrap().for_dataset(root.path());
+    settings.offline = true;
+    std::fs::create_dir_all(settings.join_output("")).unwrap();
+    let dictionary = root.path().join("events.txt");
+    std::fs::write(&dictionary, "hepatotoxicity\nrash\n").unwrap();
+    settings.event_dictionary = Some(dictionary);
+    settings.event_match_threshold = Some(0.92);
+    let raw = settings.join_data("raw/faers");
+    std::fs::create_dir_all(&raw).unwrap();
+    std::fs::write(
+        raw.join("faers_2024Q1.csv"),
+        "CASEID,DRUGNAME,PT,YEAR_QUARTER\n1,IMATINIB,Hepatotoxicty,2024Q1\n\
+         2,IMATINIB,Epatotoxicity,2024Q1\n3,IMATINIB,Hep,2024Q1\n",
+    )
+    .unwrap();
+    normalize::canonicalise(&settings, &Default::default())
+        .await
+        .unwrap();
+
+    let mut report = csv::Reader::from_path(settings.join_output("normalize_report.csv")).unwrap();
+    let suggestions: std::collections::BTreeMap<String, String> = report
+        .records()
+        .map(|record| {
+            let record = record.unwrap();
+            (record[1].to_string(), record[3].to_string())
+        })
+        .collect();
+    // The typo matches; a wrong first letter or a fragment is left for curation.
+    assert_eq!(
+        suggestions,
+        [("EPATOTOXICITY", ""), ("HEP", "")]
+            .map(|(raw, suggestion)| (raw.to_string(), suggestion.to_string()))
+            .into()
+    );
+}
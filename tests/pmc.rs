@@ -0,0 +1,58 @@
+use rwe_assistant::data::pmc::key_sections;
+
+fn article(pmid: &str, body: &str) -> String {
+    format!(
+        "<article><front><article-meta>\
+         <article-id pub-id-type=\"pmc\">PMC1</article-id>\
+         <article-id pub-id-type=\"pmid\">{pmid}</article-id>\
+         </article-meta></front><body>{body}</body></article>"
+    )
+}
+
+#[test]
+fn structured_articles_keep_results_and_conclusions() {
+    let xml = format!(
+        "<pmc-articleset>{}</pmc-articleset>",
+        article(
+            "111",
+            "<sec sec-type=\"methods\"><title>Methods</title><p>We enrolled 40 patients.</p></sec>\
+             <sec><title>RESULTS</title><p>Hepatotoxicity occurred in 4 patients.</p>\
+             <sec><title>Subgroups</title><p>All were over 60.</p></sec></sec>\
+             <sec sec-type=\"conclusions\"><title>CONCLUSIONS</title><p>Monitor liver enzymes.</p></sec>",
+        )
+    );
+    let articles = key_sections(&xml).unwrap();
+    assert_eq!(articles.len(), 1);
+    let (pmid, text) = &articles[0];
+    assert_eq!(pmid, "111");
+    assert!(
+        text.contains("Hepatotoxicity occurred in 4 patients."),
+        "{text}"
+    );
+    assert!(text.contains("All were over 60."), "{text}");
+    assert!(text.contains("Monitor liver enzymes."), "{text}");
+    assert!(!text.contains("enrolled"), "{text}");
+}
+
+#[test]
+fn unstructured_articles_are_omitted() {
+    let xml = format!(
+        "<pmc-articleset>{}{}</pmc-articleset>",
+        article(
+            "222",
+            "<p>Imatinib was stopped after a rash appeared; it resolved in a week.</p>"
+        ),
+        article(
+            "333",
+            "<sec><title>Discussion</title><p>Rash is a known effect.</p></sec>"
+        ),
+    );
+    let articles = key_sections(&xml).unwrap();
+    assert_eq!(
+        articles,
+        [(
+            "333".to_string(),
+            "Discussion Rash is a known effect.".to_string()
+        )]
+    );
+}
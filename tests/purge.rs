@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use rwe_assistant::{
+    config::Settings,
+    data::purge::{parse_age, purge, Target},
+};
+
+#[test]
+fn ages_accept_units_and_default_to_days() {
+    assert_eq!(parse_age("90d").unwrap(), Duration::from_secs(90 * 86_400));
+    assert_eq!(parse_age("90").unwrap(), Duration::from_secs(90 * 86_400));
+    assert_eq!(parse_age("12h").unwrap(), Duration::from_secs(12 * 3_600));
+    assert_eq!(parse_age("2w").unwrap(), Duration::from_secs(14 * 86_400));
+    assert_eq!(parse_age("30m").unwrap(), Duration::from_secs(30 * 60));
+}
+
+#[test]
+fn malformed_ages_are_rejected() {
+    for text in ["", "d", "90y", "-5d", "1.5d"] {
+        assert!(parse_age(text).is_err(), "{text}");
+    }
+}
+
+#[test]
+fn dry_runs_report_the_indexes_a_real_run_removes() {
+    let root = tempfile::tempdir().unwrap();
+    std::env::set_var("DATA_DIR", root.path().join("data"));
+    std::env::set_var("OUTPUTS_DIR", root.path().join("outputs"));
+    let settings = Settings::load(None).unwrap();
+    let pubmed = settings.join_data("raw/pubmed");
+    std::fs::create_dir_all(&pubmed).unwrap();
+    for name in ["imatinib.jsonl", "imatinib.index.json"] {
+        std::fs::write(pubmed.join(name), "{}").unwrap();
+    }
+
+    let dry = purge(&settings, &[Target::Raw], None, true).unwrap();
+    assert_eq!(dry.files, 2);
+    assert!(pubmed.join("imatinib.index.json").exists());
+
+    let real = purge(&settings, &[Target::Raw], None, false).unwrap();
+    assert_eq!(real.files, dry.files);
+    assert_eq!(real.paths, dry.paths);
+    assert!(!pubmed.join("imatinib.index.json").exists());
+}
+
+#[test]
+fn oversized_ages_are_rejected() {
+    assert!(parse_age(&format!("{}w", u64::MAX / 2)).is_err());
+}
+
+fn settings(root: &std::path::Path) -> Settings {
+    Settings::load(None).unwrap().for_dataset(root)
+}
+
+#[test]
+fn raw_purges_keep_inputs_that_cannot_be_downloaded_again() {
+    let root = tempfile::tempdir().unwrap();
+    let settings = settings(root.path());
+    let files = [
+        "raw/faers/faers_2024Q1.csv",
+        "raw/meddra/pt.asc",
+        "raw/rxnorm/RXNCONSO.RRF",
+        "raw/custom/custom_2024Q1.csv",
+        "raw/vigibase/vigibase_2024Q1.csv",
+    ];
+    for file in files {
+        let path = settings.join_data(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "x").unwrap();
+    }
+
+    let report = purge(&settings, &[Target::Raw], None, false).unwrap();
+    assert_eq!(report.paths, [settings.join_data(files[0])]);
+    for file in &files[1..] {
+        assert!(settings.join_data(file).exists(), "{file}");
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn purges_do_not_follow_symbolic_links() {
+    let root = tempfile::tempdir().unwrap();
+    let settings = settings(root.path());
+    let elsewhere = root.path().join("elsewhere");
+    std::fs::create_dir_all(&elsewhere).unwrap();
+    std::fs::write(elsewhere.join("keep.csv"), "x").unwrap();
+    let clean = settings.join_data("clean");
+    std::fs::create_dir_all(&clean).unwrap();
+    std::os::unix::fs::symlink(&elsewhere, clean.join("linked")).unwrap();
+
+    let report = purge(&settings, &[Target::Clean], None, false).unwrap();
+    assert_eq!(report.files, 0);
+    assert!(elsewhere.join("keep.csv").exists());
+}
+
+#[test]
+fn output_purges_keep_run_histories() {
+    let root = tempfile::tempdir().unwrap();
+    let settings = settings(root.path());
+    std::fs::create_dir_all(settings.join_output("")).unwrap();
+    for name in [
+        "signals.csv",
+        "normalize_metrics_history.jsonl",
+        "schedule_history.jsonl",
+    ] {
+        std::fs::write(settings.join_output(name), "x").unwrap();
+    }
+
+    let report = purge(&settings, &[Target::Outputs], None, false).unwrap();
+    assert_eq!(report.paths, [settings.join_output("signals.csv")]);
+    assert!(settings.join_output("schedule_history.jsonl").exists());
+    assert!(settings
+        .join_output("normalize_metrics_history.jsonl")
+        .exists());
+}
+
+#[test]
+fn purges_refuse_overlapping_roots() {
+    let root = tempfile::tempdir().unwrap();
+    let mut settings = settings(root.path());
+    settings.outputs_dir = ".".into();
+    assert!(purge(&settings, &[Target::Outputs], None, true).is_err());
+    settings.outputs_dir = settings.data_dir.clone();
+    assert!(purge(&settings, &[Target::Outputs], None, true).is_err());
+    settings.outputs_dir = settings.join_data("clean/outputs");
+    assert!(purge(&settings, &[Target::Clean], None, true).is_err());
+}
@@ -0,0 +1,30 @@
+use rwe_assistant::signals::trend;
+
+#[test]
+fn report_dates_map_to_quarters() {
+    assert_eq!(
+        trend::quarter_from_date("20240315").as_deref(),
+        Some("2024Q1")
+    );
+    assert_eq!(
+        trend::quarter_from_date("2023-11-02").as_deref(),
+        Some("2023Q4")
+    );
+    assert_eq!(
+        trend::quarter_from_date("2024/07").as_deref(),
+        Some("2024Q3")
+    );
+    assert_eq!(trend::quarter_from_date("2023"), None);
+    for ambiguous in ["15/03/2024", "03/15/2024", "01.02.2024", "01022024"] {
+        assert_eq!(trend::quarter_from_date(ambiguous), None, "{ambiguous}");
+    }
+}
+
+#[test]
+fn malformed_quarters_are_rejected() {
+    assert_eq!(trend::parse_quarter("2024Q3"), Some((2024, 3)));
+    assert_eq!(trend::parse_quarter("2024q3"), Some((2024, 3)));
+    for bad in ["2023X1", "2024é", "2024Qé", "2024Q", "24Q1"] {
+        assert_eq!(trend::parse_quarter(bad), None, "{bad}");
+    }
+}
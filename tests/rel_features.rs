@@ -1,17 +1,173 @@
-use rwe_assistant::nlp::features::{featurise, SentenceContext};
+use rwe_assistant::{
+    data::pubmed::{AbstractSection, MeshHeading},
+    nlp::features::{
+        featurise, featurise_with, split_sentences, CorpusStats, SentenceContext, FEATURE_WIDTH,
+    },
+};
 
-#[test]
-fn feature_vector_has_expected_shape() {
-    let ctx = SentenceContext {
+/// A single-sentence imatinib/hepatotoxicity context; tests override fields with struct update.
+fn sentence(text: &str) -> SentenceContext {
+    SentenceContext {
         pmid: "123".into(),
         sent_idx: 0,
         drug: "imatinib".into(),
         event: "hepatotoxicity".into(),
-        text: "Imatinib is associated with hepatotoxicity in rare cases.".into(),
-    };
+        text: text.into(),
+        sentence_count: 1,
+        mesh_headings: Vec::new(),
+        section: AbstractSection::Unlabelled,
+        study_type: None,
+        language: None,
+    }
+}
+
+#[test]
+fn feature_vector_has_expected_shape() {
+    let ctx = sentence("Imatinib is associated with hepatotoxicity in rare cases.");
     let features = featurise(&[ctx]);
     assert_eq!(features.len(), 1);
     let feature = &features[0];
     assert!(feature.has_cue_word >= 1.0);
     assert_eq!(feature.pmid, "123");
 }
+
+#[test]
+fn chemically_induced_mesh_qualifier_is_a_feature() {
+    let ctx = SentenceContext {
+        sent_idx: 2,
+        sentence_count: 3,
+        mesh_headings: vec![
+            MeshHeading {
+                descriptor: "Chemical and Drug Induced Liver Injury".into(),
+                qualifiers: vec!["etiology".into()],
+            },
+            MeshHeading {
+                descriptor: "Hepatotoxicity".into(),
+                qualifiers: vec!["chemically induced".into()],
+            },
+        ],
+        ..sentence("Liver enzymes rose after imatinib.")
+    };
+    let features = featurise(&[ctx]);
+    assert_eq!(features[0].mesh_qualifier, 1.0);
+}
+
+#[test]
+fn sentence_splitter_keeps_abbreviations_decimals_and_species_together() {
+    let text = "Imatinib vs. placebo was compared (Fig. 2). Hepatotoxicity occurred in 2.5% of \
+                patients given i.v. doses. E. coli sepsis, e.g. after neutropenia, was rare.";
+    assert_eq!(
+        split_sentences(text),
+        vec![
+            "Imatinib vs. placebo was compared (Fig. 2).",
+            "Hepatotoxicity occurred in 2.5% of patients given i.v. doses.",
+            "E. coli sepsis, e.g. after neutropenia, was rare.",
+        ]
+    );
+}
+
+#[test]
+fn sentence_splitter_ends_sentences_after_numbers() {
+    assert_eq!(
+        split_sentences("P = 0.03. Rash occurred."),
+        vec!["P = 0.03.", "Rash occurred."]
+    );
+    assert_eq!(
+        split_sentences(
+            "The median was 45.2 (IQR 30.1-45.2). Rash occurred in 12 (0.03). Nausea was rare."
+        ),
+        vec![
+            "The median was 45.2 (IQR 30.1-45.2).",
+            "Rash occurred in 12 (0.03).",
+            "Nausea was rare."
+        ]
+    );
+}
+
+#[test]
+fn negation_only_counts_when_its_scope_covers_the_event() {
+    let features = featurise(&[
+        sentence("No dose adjustment of imatinib was needed; hepatotoxicity was observed."),
+        sentence("Imatinib was not associated with hepatotoxicity."),
+        sentence("Hepatotoxicity was not observed in imatinib-treated patients."),
+        sentence("There was no increase in hepatotoxicity with imatinib."),
+    ]);
+    let flags: Vec<f32> = features.iter().map(|f| f.negation_flag).collect();
+    assert_eq!(flags, vec![0.0, 1.0, 1.0, 0.0]);
+}
+
+#[test]
+fn hedged_event_mentions_are_speculative() {
+    let features = featurise(&[
+        sentence("Imatinib may cause hepatotoxicity."),
+        sentence("A role of imatinib in the hepatotoxicity could not be excluded."),
+        sentence("Imatinib caused hepatotoxicity in two patients."),
+    ]);
+    let flags: Vec<f32> = features.iter().map(|f| f.speculation_flag).collect();
+    assert_eq!(flags, vec![1.0, 1.0, 0.0]);
+    assert_eq!(features[1].negation_flag, 0.0);
+}
+
+#[test]
+fn between_entity_context_features_capture_causal_patterns() {
+    let positioned = |sent_idx: usize, text: &str| SentenceContext {
+        sent_idx,
+        sentence_count: 5,
+        ..sentence(text)
+    };
+    let features = featurise(&[
+        positioned(0, "Imatinib-induced hepatotoxicity was reported."),
+        positioned(4, "Hepatotoxicity developed after imatinib therapy."),
+    ]);
+    assert_eq!(features[0].preposition_pattern, 1.0);
+    assert_eq!(features[0].drug_first, 1.0);
+    assert_eq!(features[0].sentence_position, 0.0);
+    assert_eq!(features[1].causal_verb, 1.0);
+    assert_eq!(features[1].preposition_pattern, 1.0);
+    assert_eq!(features[1].drug_first, 0.0);
+    assert_eq!(features[1].sentence_position, 1.0);
+    assert_eq!(features[1].to_row().len(), FEATURE_WIDTH);
+}
+
+#[test]
+fn corpus_idf_weights_rare_words_above_common_ones() {
+    let numbered = |sent_idx: usize, text: &str| SentenceContext {
+        sent_idx,
+        sentence_count: 3,
+        ..sentence(text)
+    };
+    let sentences = [
+        numbered(0, "Imatinib caused hepatotoxicity."),
+        numbered(1, "Imatinib and hepatotoxicity were reported."),
+        numbered(2, "Imatinib and hepatotoxicity were discussed."),
+    ];
+    let corpus = CorpusStats::fit(&sentences);
+    assert!(corpus.idf("caused") > corpus.idf("and"));
+    assert!(corpus.idf("and") > corpus.idf("imatinib"));
+    let features = featurise_with(&sentences, &corpus);
+    assert!(features[0].tfidf > features[1].tfidf);
+}
+
+#[test]
+fn structured_abstract_sections_become_features() {
+    assert_eq!(
+        AbstractSection::classify(Some("UNASSIGNED"), Some("Interpretation")),
+        AbstractSection::Conclusions
+    );
+    assert_eq!(
+        AbstractSection::classify(Some("RESULTS"), Some("Findings")),
+        AbstractSection::Results
+    );
+    assert_eq!(
+        AbstractSection::classify(None, None),
+        AbstractSection::Unlabelled
+    );
+    let features = featurise(&[SentenceContext {
+        sent_idx: 4,
+        sentence_count: 5,
+        section: AbstractSection::Conclusions,
+        ..sentence("Imatinib may cause hepatotoxicity.")
+    }]);
+    assert_eq!(features[0].conclusions_section, 1.0);
+    assert_eq!(features[0].results_section, 0.0);
+}
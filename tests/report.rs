@@ -0,0 +1,44 @@
+#![cfg(unix)]
+
+use rwe_assistant::{
+    cli::report::{self, Format},
+    config::Settings,
+    data::normalize::{self, NormalizeOptions},
+    signals,
+};
+
+#[tokio::test]
+async fn pdf_command_paths_with_spaces_stay_one_argument() {
+    let root = tempfile::tempdir().unwrap();
+    let mut settings = Settings::load(None)
+        .unwrap()
+        .for_dataset(&root.path().join("my data"));
+    settings.offline = true;
+    settings.report_pdf_command = Some("cp {input} {output}".to_string());
+    std::fs::create_dir_all(settings.join_output("")).unwrap();
+    let raw = settings.join_data("raw/faers");
+    std::fs::create_dir_all(&raw).unwrap();
+    std::fs::write(
+        raw.join("faers_2024Q1.csv"),
+        "CASEID,DRUGNAME,PT,YEAR_QUARTER,ROLE_COD\n\
+         1,IMATINIB,Rash,2024Q1,PS\n2,IMATINIB,Rash,2024Q1,PS\n3,ASPIRIN,Nausea,2024Q1,PS\n",
+    )
+    .unwrap();
+    normalize::canonicalise(&settings, &NormalizeOptions::default())
+        .await
+        .unwrap();
+    signals::compute(&settings).await.unwrap();
+    signals::rank(&settings).await.unwrap();
+
+    let output = settings.join_output("my reports/imatinib.pdf");
+    let args = report::Args {
+        drug: "imatinib".to_string(),
+        format: Format::Pdf,
+        output: Some(output.clone()),
+        summarize: false,
+        topk: 5,
+    };
+    report::run(args, settings).await.unwrap();
+    let printed = std::fs::read_to_string(&output).unwrap();
+    assert!(printed.contains("imatinib"), "{printed}");
+}
@@ -0,0 +1,27 @@
+use rwe_assistant::{config::Settings, data::rxnorm::RxNormResolver};
+
+#[tokio::test]
+async fn cached_rxnav_answers_are_keyed_by_the_minimum_score() {
+    let root = tempfile::tempdir().unwrap();
+    let mut settings = Settings::load(None).unwrap().for_dataset(root.path());
+    settings.offline = true;
+    let cache = settings.join_data("cache/rxnorm");
+    std::fs::create_dir_all(&cache).unwrap();
+    std::fs::write(
+        cache.join("lookups.jsonl"),
+        "{\"name\":\"GLEEVAC|8\",\"resolved\":\"imatinib\",\"checked_at\":\"2026-01-01T00:00:00Z\"}\n",
+    )
+    .unwrap();
+
+    settings.rxnorm_approx_min_score = 8.0;
+    let mut resolver = RxNormResolver::new(&settings).unwrap();
+    assert_eq!(
+        resolver.resolve("Gleevac").await.as_deref(),
+        Some("imatinib")
+    );
+
+    // A stricter score is a different question; offline, it stays unanswered.
+    settings.rxnorm_approx_min_score = 10.0;
+    let mut resolver = RxNormResolver::new(&settings).unwrap();
+    assert_eq!(resolver.resolve("Gleevac").await, None);
+}
@@ -0,0 +1,89 @@
+use chrono::{Datelike, TimeZone, Timelike, Utc, Weekday};
+use rwe_assistant::cli::schedule::{next_after, parse_cron};
+
+#[test]
+fn crontab_expressions_fire_at_the_expected_times() {
+    // 2025-06-04 is a Wednesday.
+    let after = Utc.with_ymd_and_hms(2025, 6, 4, 12, 0, 0).unwrap();
+
+    let weekly = parse_cron("0 3 * * 1").unwrap();
+    let next = next_after(&weekly, after, true).unwrap();
+    assert_eq!(next, Utc.with_ymd_and_hms(2025, 6, 9, 3, 0, 0).unwrap());
+    assert_eq!(next.weekday(), Weekday::Mon);
+
+    // Sunday is 0 or 7, as in crontab.
+    for sunday in ["30 6 * * 0", "30 6 * * 7", "30 6 * * 6-7"] {
+        let next = next_after(&parse_cron(sunday).unwrap(), after, true).unwrap();
+        assert!(
+            matches!(next.weekday(), Weekday::Sat | Weekday::Sun),
+            "{sunday}"
+        );
+        assert_eq!((next.hour(), next.minute()), (6, 30));
+    }
+
+    // 0-7 runs daily rather than on Sundays only.
+    let daily = parse_cron("0 3 * * 0-7").unwrap();
+    let next = next_after(&daily, after, true).unwrap();
+    assert_eq!(next, Utc.with_ymd_and_hms(2025, 6, 5, 3, 0, 0).unwrap());
+
+    let quarterly = parse_cron("0 3 1 1,4,7,10 *").unwrap();
+    let next = next_after(&quarterly, after, true).unwrap();
+    assert_eq!(next, Utc.with_ymd_and_hms(2025, 7, 1, 3, 0, 0).unwrap());
+
+    // Six fields start with seconds.
+    let seconds = parse_cron("15 0 3 * * Mon").unwrap();
+    let next = next_after(&seconds, after, true).unwrap();
+    assert_eq!(next, Utc.with_ymd_and_hms(2025, 6, 9, 3, 0, 15).unwrap());
+
+    for bad in ["", "0 3 * *", "61 3 * * 1", "0 3 * * 8", "every monday"] {
+        assert!(parse_cron(bad).is_err(), "{bad:?}");
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn scheduler_starts_on_a_fresh_deployment() {
+    use std::{
+        process::{Command, Stdio},
+        time::{Duration, Instant},
+    };
+
+    let root = tempfile::tempdir().unwrap();
+    let outputs = root.path().join("fresh/outputs");
+    let mut scheduler = Command::new(assert_cmd::cargo::cargo_bin("rwe-assistant"))
+        .args([
+            "-q",
+            "schedule",
+            "--run-now",
+            "--cron",
+            "0 0 1 1 *",
+            "--",
+            "status",
+        ])
+        .env("DATA_DIR", root.path().join("fresh/data"))
+        .env("OUTPUTS_DIR", &outputs)
+        .env("OFFLINE", "1")
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let history = outputs.join("schedule_history.jsonl");
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while !std::fs::read_to_string(&history).is_ok_and(|text| text.ends_with('\n')) {
+        if let Some(status) = scheduler.try_wait().unwrap() {
+            panic!("scheduler exited with {status}");
+        }
+        assert!(Instant::now() < deadline, "no run recorded");
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    assert!(Command::new("kill")
+        .arg(scheduler.id().to_string())
+        .status()
+        .unwrap()
+        .success());
+    assert!(scheduler.wait().unwrap().success());
+
+    let record: serde_json::Value =
+        serde_json::from_str(std::fs::read_to_string(&history).unwrap().trim()).unwrap();
+    assert_eq!(record["outcome"], "succeeded", "{record}");
+}
@@ -0,0 +1,64 @@
+use rwe_assistant::signals::diff::{diff, Direction, RankedRow};
+
+fn row(drug: &str, event: &str, score: f64, ci_low: f64) -> RankedRow {
+    RankedRow {
+        drug_id: drug.into(),
+        event_id: event.into(),
+        year_quarter: "2024Q4".into(),
+        recent_ror: 2.0,
+        ci_low,
+        score,
+    }
+}
+
+#[test]
+fn reports_new_dropped_moved_and_crossing_pairs() {
+    let before = [
+        row("d1", "e1", 3.0, 1.5),
+        row("d1", "e2", 1.5, 0.8),
+        row("d2", "e1", 1.0, 0.9),
+    ];
+    let after = [
+        row("d1", "e1", 2.5, 1.2),
+        row("d1", "e2", 2.8, 1.1),
+        row("d3", "e3", 4.0, 2.0),
+    ];
+    let delta = diff(&before, &after, 2.0);
+
+    assert_eq!(delta.new_pairs.len(), 1);
+    assert_eq!(delta.new_pairs[0].drug_id, "d3");
+    assert_eq!(delta.new_pairs[0].rank_after, Some(1));
+    assert_eq!(delta.dropped_pairs.len(), 1);
+    assert_eq!(delta.dropped_pairs[0].drug_id, "d2");
+
+    let rose = delta
+        .changed
+        .iter()
+        .find(|change| change.event_id == "e2")
+        .unwrap();
+    assert_eq!((rose.rank_before, rose.rank_after), (Some(2), Some(2)));
+    assert!((rose.score_delta.unwrap() - 1.3).abs() < 1e-9);
+    let fell = delta
+        .changed
+        .iter()
+        .find(|change| change.event_id == "e1")
+        .unwrap();
+    assert_eq!(fell.rank_delta, Some(-2));
+
+    // d1/e2 clears both the score threshold and ci_low > 1; d1/e1 stays above both.
+    assert_eq!(delta.crossings.len(), 2);
+    assert!(delta
+        .crossings
+        .iter()
+        .all(|c| c.event_id == "e2" && c.direction == Direction::Above));
+}
+
+#[test]
+fn identical_runs_have_no_delta() {
+    let rows = [row("d1", "e1", 3.0, 1.5), row("d1", "e2", 1.5, 0.8)];
+    let delta = diff(&rows, &rows, 2.0);
+    assert!(delta.new_pairs.is_empty());
+    assert!(delta.dropped_pairs.is_empty());
+    assert!(delta.changed.is_empty());
+    assert!(delta.crossings.is_empty());
+}
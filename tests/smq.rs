@@ -0,0 +1,52 @@
+use rwe_assistant::{
+    config::Settings,
+    data::smq::{self, SmqScope},
+};
+
+#[test]
+fn smqs_include_the_terms_of_their_child_smqs() {
+    let root = tempfile::tempdir().unwrap();
+    let settings = Settings::load(None).unwrap().for_dataset(root.path());
+    let meddra = settings.join_data("raw/meddra");
+    std::fs::create_dir_all(&meddra).unwrap();
+    std::fs::write(
+        meddra.join("pt.asc"),
+        "10019663$Hepatic failure$\n10023126$Jaundice$\n",
+    )
+    .unwrap();
+    std::fs::write(
+        meddra.join("smq_list.asc"),
+        "20000006$Drug related hepatic disorders$\n20000008$Liver related investigations$\n",
+    )
+    .unwrap();
+    // The parent lists jaundice itself and the hepatic failure SMQ as a child (level 0).
+    std::fs::write(
+        meddra.join("smq_content.asc"),
+        "20000006$10023126$4$1$A$0$A$\n\
+         20000006$20000008$0$0$A$0$A$\n\
+         20000008$10019663$4$2$A$0$A$\n",
+    )
+    .unwrap();
+
+    let index = smq::load_index(&settings).unwrap().unwrap();
+    let groups = |term: &str| -> Vec<(String, SmqScope)> {
+        index
+            .groups_for(term)
+            .iter()
+            .map(|group| (group.code.clone(), group.scope))
+            .collect()
+    };
+    assert_eq!(
+        groups("hepatic failure"),
+        [
+            ("20000006".to_string(), SmqScope::Broad),
+            ("20000006".to_string(), SmqScope::Narrow),
+            ("20000008".to_string(), SmqScope::Broad),
+            ("20000008".to_string(), SmqScope::Narrow),
+        ]
+    );
+    assert_eq!(
+        groups("jaundice"),
+        [("20000006".to_string(), SmqScope::Broad)]
+    );
+}
@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use rwe_assistant::{
+    api::store::{query_signals, SignalCache, SignalFilter, SignalOrder},
+    config::Settings,
+};
+
+#[test]
+fn ties_are_ordered_by_drug_then_event_id() {
+    let root = tempfile::tempdir().unwrap();
+    let settings = Settings::load(None).unwrap().for_dataset(root.path());
+    std::fs::create_dir_all(settings.join_output("")).unwrap();
+    std::fs::write(
+        settings.join_output("signals.csv"),
+        "drug_id,event_id,year_quarter,recent_ror,ci_low,ci_high,lit_support,trend_z,score\n\
+         D2,E1,2024Q1,3.0,1.5,6.0,0,0.0,2.0\n\
+         D1,E2,2024Q1,3.0,1.5,6.0,0,0.0,2.0\n\
+         D3,E9,2024Q1,1.0,0.5,2.0,0,0.0,5.0\n\
+         D1,E1,2024Q1,4.0,1.5,6.0,0,0.0,2.0\n",
+    )
+    .unwrap();
+    let cache = SignalCache::new(Duration::from_secs(30));
+    let pairs = |order| {
+        let filter = SignalFilter {
+            order,
+            ..SignalFilter::default()
+        };
+        query_signals(&settings, &cache, &filter)
+            .unwrap()
+            .into_iter()
+            .map(|signal| format!("{}/{}", signal.drug_id, signal.event_id))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        pairs(SignalOrder::Score),
+        ["D3/E9", "D1/E1", "D1/E2", "D2/E1"]
+    );
+    assert_eq!(
+        pairs(SignalOrder::Ror),
+        ["D1/E1", "D1/E2", "D2/E1", "D3/E9"]
+    );
+}
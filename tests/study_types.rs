@@ -0,0 +1,28 @@
+use rwe_assistant::{config::StudyTypeWeights, data::pubmed::PublicationType};
+
+#[test]
+fn strongest_publication_type_grades_the_citation() {
+    let types = vec![
+        "Journal Article".to_string(),
+        "Case Reports".to_string(),
+        "Randomized Controlled Trial".to_string(),
+    ];
+    assert_eq!(
+        PublicationType::strongest(&types),
+        Some(PublicationType::Rct)
+    );
+    assert_eq!(
+        PublicationType::strongest(&["Journal Article".to_string()]),
+        None
+    );
+}
+
+#[test]
+fn study_type_weights_override_defaults() {
+    let weights = StudyTypeWeights::parse("case-report=0.25, rct=3").unwrap();
+    assert_eq!(weights.weight(Some(PublicationType::CaseReport)), 0.25);
+    assert_eq!(weights.weight(Some(PublicationType::Rct)), 3.0);
+    assert_eq!(weights.weight(Some(PublicationType::MetaAnalysis)), 2.0);
+    assert_eq!(weights.weight(None), 1.0);
+    assert!(StudyTypeWeights::parse("cohort=2").is_err());
+}
@@ -0,0 +1,76 @@
+use std::collections::HashSet;
+
+use rwe_assistant::nlp::summaries::{self, ClaimStrength, Evidence};
+
+fn evidence() -> Vec<Evidence> {
+    vec![
+        Evidence {
+            pmid: "111".into(),
+            sent_idx: 3,
+            confidence: 0.92,
+            section: Some("conclusions".into()),
+            sentence: "Imatinib was associated with hepatotoxicity.".into(),
+        },
+        Evidence {
+            pmid: "222".into(),
+            sent_idx: 0,
+            confidence: 0.4,
+            section: None,
+            sentence: "Liver enzymes rose after imatinib.".into(),
+        },
+    ]
+}
+
+#[test]
+fn prompt_carries_every_sentence_with_its_pmid() {
+    let prompt = summaries::prompt("imatinib", "hepatotoxicity", 5, &evidence());
+    assert!(prompt.starts_with("Summarise evidence for imatinib causing hepatotoxicity."));
+    assert!(prompt.contains("1. [PMID 111] Imatinib was associated with hepatotoxicity."));
+    assert!(prompt.contains("2. [PMID 222] Liver enzymes rose after imatinib."));
+}
+
+#[test]
+fn invented_citations_and_quotes_are_flagged() {
+    let answer = r#"Here is the summary:
+```json
+{"claims": [
+  {"claim": "Imatinib is linked to hepatotoxicity.", "strength": "moderate",
+   "pmids": ["PMID: 111", "999"], "quotes": ["imatinib was  associated with hepatotoxicity"]},
+  {"claim": "Liver enzymes rise on treatment.", "strength": "weak",
+   "pmids": ["222"], "quotes": ["enzymes fell sharply"]}
+]}
+```"#;
+    let mut claims = summaries::parse_claims(answer).unwrap();
+    assert_eq!(claims[0].strength, ClaimStrength::Moderate);
+
+    let known: HashSet<&str> = ["111", "222", "333"].into();
+    let verified = summaries::verify_claims(&mut claims, &known, &evidence());
+    assert!(!verified);
+    assert_eq!(claims[0].pmids, ["111", "999"]);
+    assert_eq!(claims[0].unverified_pmids, ["999"]);
+    assert!(claims[0].unverified_quotes.is_empty());
+    assert!(claims[1].unverified_pmids.is_empty());
+    assert_eq!(claims[1].unverified_quotes, ["enzymes fell sharply"]);
+}
+
+#[test]
+fn answers_without_json_are_rejected() {
+    assert!(summaries::parse_claims("Imatinib probably causes it (PMID 111).").is_err());
+}
+
+#[tokio::test]
+async fn summaries_without_a_model_are_not_marked_verified() {
+    use rwe_assistant::config::Settings;
+
+    let root = tempfile::tempdir().unwrap();
+    let mut settings = Settings::load(None).unwrap().for_dataset(root.path());
+    settings.offline = true;
+    let summary = summaries::summarize(&settings, "imatinib", "rash", 5)
+        .await
+        .unwrap();
+    assert!(summary.claims.is_empty());
+    assert_eq!(summary.verified, None);
+    assert!(!summary.has_unverified_citations());
+    let json = serde_json::to_value(&summary).unwrap();
+    assert!(json["verified"].is_null());
+}
@@ -0,0 +1,44 @@
+use polars::prelude::*;
+use rwe_assistant::{
+    config::Settings,
+    data::normalize::{self, EventBackend, NormalizeOptions},
+};
+
+#[tokio::test]
+async fn umls_concepts_are_linked_without_replacing_the_canonical_term() {
+    let root = tempfile::tempdir().unwrap();
+    std::env::set_var("DATA_DIR", root.path().join("data"));
+    std::env::set_var("OUTPUTS_DIR", root.path().join("outputs"));
+    std::env::set_var("OFFLINE", "true");
+    let settings = Settings::load(None).unwrap();
+    let raw = settings.join_data("raw/faers");
+    std::fs::create_dir_all(&raw).unwrap();
+    std::fs::write(
+        raw.join("faers_2024Q1.csv"),
+        "CASEID,DRUGNAME,PT,YEAR_QUARTER,ROLE_COD\n1,IMATINIB,Rash,2024Q1,PS\n",
+    )
+    .unwrap();
+    let umls = settings.join_data("raw/umls");
+    std::fs::create_dir_all(&umls).unwrap();
+    std::fs::write(
+        umls.join("MRCONSO.RRF"),
+        "C0015230|ENG|P|L1|PF|S1|Y|A1||||MTH|PT|C0015230|Exanthema|0|N||\n\
+         C0015230|ENG|S|L2|VO|S2|N|A2||||MDR|PT|10037844|Rash|0|N||\n",
+    )
+    .unwrap();
+
+    let options = NormalizeOptions {
+        event_backend: EventBackend::Umls,
+        ..Default::default()
+    };
+    normalize::canonicalise(&settings, &options).await.unwrap();
+    let events = ParquetReader::new(
+        std::fs::File::open(settings.join_data("clean/events.parquet")).unwrap(),
+    )
+    .finish()
+    .unwrap();
+    let column = |name: &str| events.column(name).unwrap().str().unwrap().get(0);
+    assert_eq!(column("term_canonical"), Some("rash"));
+    assert_eq!(column("umls_cui"), Some("C0015230"));
+    assert_eq!(column("umls_name"), Some("exanthema"));
+}